@@ -16,6 +16,34 @@ pub struct BenchModel {
     pub name: String,
     pub parallel_safe: bool,
     pub tool_shim: Option<BenchToolShimOpt>,
+    /// Truncation strategy to run with, passed as GOOSE_TRUNCATION_STRATEGY
+    #[serde(default)]
+    pub context_strategy: Option<String>,
+    /// Context limit override, passed as GOOSE_CONTEXT_LIMIT to simulate small context windows
+    #[serde(default)]
+    pub context_limit: Option<usize>,
+}
+
+impl BenchModel {
+    /// Name of the directory results for this model are written to. Tool-shim and context
+    /// settings are part of the name so runs that only differ in those can be compared.
+    pub fn work_dir_name(&self) -> String {
+        let mut name = format!("{}-{}", self.provider, self.name);
+        // only add a shim component if the shim is configured to be used
+        if let Some(shim_opt) = &self.tool_shim {
+            if shim_opt.use_tool_shim {
+                let shim_model = shim_opt.tool_shim_model.as_deref().unwrap_or("default");
+                name.push_str(&format!("-{}-shim-model", shim_model));
+            }
+        }
+        if let Some(strategy) = &self.context_strategy {
+            name.push_str(&format!("-{}", strategy));
+        }
+        if let Some(limit) = self.context_limit {
+            name.push_str(&format!("-ctx{}", limit));
+        }
+        name
+    }
 }
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct BenchEval {
@@ -48,12 +76,16 @@ impl Default for BenchRunConfig {
                         use_tool_shim: false,
                         tool_shim_model: None,
                     }),
+                    context_strategy: None,
+                    context_limit: None,
                 },
                 BenchModel {
                     provider: "databricks".to_string(),
                     name: "goose-claude-3-5-sonnet".to_string(),
                     parallel_safe: true,
                     tool_shim: None,
+                    context_strategy: None,
+                    context_limit: None,
                 },
             ],
             evals: vec![BenchEval {
@@ -0,0 +1,2 @@
+// context management evals
+mod recall_after_truncation;
@@ -0,0 +1,97 @@
+// Remember a code word, flood the context with large tool outputs, then ask for the code word.
+// Run with different `context_strategy` / `context_limit` model settings to compare truncation strategies.
+
+use crate::bench_session::BenchAgent;
+use crate::bench_work_dir::BenchmarkWorkDir;
+use crate::eval_suites::{
+    collect_baseline_metrics, metrics_hashmap_to_vec, EvalMetricValue, Evaluation,
+    ExtensionRequirements,
+};
+use crate::register_evaluation;
+use async_trait::async_trait;
+use mcp_core::role::Role;
+
+const CODE_WORD: &str = "PERIWINKLE-4711";
+const FILLER_ROUNDS: usize = 4;
+
+#[derive(Debug)]
+pub struct ContextRecallAfterTruncation {}
+
+impl ContextRecallAfterTruncation {
+    pub fn new() -> Self {
+        ContextRecallAfterTruncation {}
+    }
+}
+
+#[async_trait]
+impl Evaluation for ContextRecallAfterTruncation {
+    async fn run(
+        &self,
+        agent: &mut BenchAgent,
+        _work_dir: &mut BenchmarkWorkDir,
+    ) -> anyhow::Result<Vec<(String, EvalMetricValue)>> {
+        let mut filler_errors = 0;
+        let _ = agent
+            .prompt(format!(
+                "Remember this code word, I will ask for it later: {}. #important \
+                Reply with 'ok' only.",
+                CODE_WORD
+            ))
+            .await?;
+
+        for round in 0..FILLER_ROUNDS {
+            let prompt = format!(
+                "Run `seq {} {}` in the shell and tell me the last number it printed.",
+                round * 100_000,
+                round * 100_000 + 20_000
+            );
+            if agent.prompt(prompt).await.is_err() {
+                filler_errors += 1;
+            }
+        }
+
+        let (messages, perf_metrics) = collect_baseline_metrics(
+            agent,
+            "What was the code word I asked you to remember? Answer with the code word only."
+                .to_string(),
+        )
+        .await;
+
+        let mut metrics = metrics_hashmap_to_vec(perf_metrics);
+
+        let recalled = messages
+            .iter()
+            .rev()
+            .find(|msg| msg.role == Role::Assistant)
+            .is_some_and(|msg| msg.as_concat_text().contains(CODE_WORD));
+
+        metrics.push((
+            "Recalled code word".to_string(),
+            EvalMetricValue::Boolean(recalled),
+        ));
+        metrics.push((
+            "Filler prompt errors".to_string(),
+            EvalMetricValue::Integer(filler_errors),
+        ));
+        metrics.push((
+            "score".to_string(),
+            EvalMetricValue::Float(if recalled { 1.0 } else { 0.0 }),
+        ));
+
+        Ok(metrics)
+    }
+
+    fn name(&self) -> &str {
+        "context_recall_after_truncation"
+    }
+
+    fn required_extensions(&self) -> ExtensionRequirements {
+        ExtensionRequirements {
+            builtin: vec!["developer".to_string()],
+            external: Vec::new(),
+            remote: Vec::new(),
+        }
+    }
+}
+
+register_evaluation!(ContextRecallAfterTruncation);
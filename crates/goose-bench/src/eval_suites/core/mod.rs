@@ -1,4 +1,5 @@
 mod computercontroller;
+mod context;
 mod developer;
 mod developer_image;
 mod developer_search_replace;
@@ -30,28 +30,8 @@ impl EvalRunner {
             .models
             .first()
             .context("No model specified in configuration")?;
-        let model_name = goose_model.name.clone();
-        let provider_name = goose_model.provider.clone();
-
-        // construct work-dir name to have a shim component only if shim configured to be used
-        let work_dir_name_shim = {
-            let mut shim_name = "".to_string();
-            if let Some(shim_opt) = &goose_model.tool_shim {
-                if shim_opt.use_tool_shim {
-                    let shim_model = if let Some(shim_model) = &shim_opt.tool_shim_model {
-                        shim_model.clone()
-                    } else {
-                        "default".to_string()
-                    };
-                    shim_name = format!("-{}-shim-model", shim_model);
-                }
-            }
-            shim_name
-        };
-
         let include_dir = config.include_dirs.clone();
-        let work_dir_name = format!("{}-{}{}", provider_name, model_name, work_dir_name_shim);
-        let work_dir = BenchmarkWorkDir::new(work_dir_name, include_dir);
+        let work_dir = BenchmarkWorkDir::new(goose_model.work_dir_name(), include_dir);
         Ok(work_dir)
     }
 
@@ -168,13 +148,10 @@ impl EvalRunner {
     }
 
     pub fn path_for_eval(model: &BenchModel, eval: &BenchEval, run_id: String) -> PathBuf {
-        let provider = model.provider.clone();
-        let model = model.name.clone();
         let eval_path = &eval.selector.replace(":", std::path::MAIN_SEPARATOR_STR);
         let eval_results_location = format!(
-            "{}-{}/run-{}{}{}",
-            &provider,
-            model,
+            "{}/run-{}{}{}",
+            model.work_dir_name(),
             run_id,
             std::path::MAIN_SEPARATOR_STR,
             eval_path
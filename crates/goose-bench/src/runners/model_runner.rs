@@ -77,6 +77,12 @@ impl ModelRunner {
         }
         envs.push(("GOOSE_MODEL".to_string(), model.clone().name));
         envs.push(("GOOSE_PROVIDER".to_string(), model.clone().provider));
+        if let Some(strategy) = &model.context_strategy {
+            envs.push(("GOOSE_TRUNCATION_STRATEGY".to_string(), strategy.clone()));
+        }
+        if let Some(limit) = model.context_limit {
+            envs.push(("GOOSE_CONTEXT_LIMIT".to_string(), limit.to_string()));
+        }
 
         // Only run in parallel if the model is parallel_safe
         let run_parallel = model.parallel_safe;
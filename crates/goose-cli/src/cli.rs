@@ -1,8 +1,18 @@
 use anyhow::Result;
 use clap::{Args, Parser, Subcommand};
 
+use goose::agents::check_schema;
+use goose::audit::{AuditFilter, Decision};
 use goose::config::{Config, ExtensionConfig};
-
+use goose::context_mgmt::truncate::TruncationStrategyKind;
+use goose::model::ReasoningConfig;
+use goose::permission::approval_policy::RelayTarget;
+use goose::permission::ApprovalPolicy;
+use goose::session::{SearchQuery, SessionQuery, SortOrder};
+
+use crate::commands::audit::{
+    handle_audit_export, handle_audit_list, handle_audit_verify, parse_decision, parse_since,
+};
 use crate::commands::bench::agent_generator;
 use crate::commands::configure::handle_configure;
 use crate::commands::info::handle_info;
@@ -15,18 +25,22 @@ use crate::commands::schedule::{
     handle_schedule_run_now, handle_schedule_services_status, handle_schedule_services_stop,
     handle_schedule_sessions,
 };
-use crate::commands::session::{handle_session_list, handle_session_remove};
+use crate::commands::session::{
+    cleanup_on_startup, handle_session_cleanup, handle_session_fork, handle_session_import,
+    handle_session_list, handle_session_remove, handle_session_search,
+};
 use crate::logging::setup_logging;
 use crate::recipes::recipe::{explain_recipe_with_parameters, load_recipe_as_template};
 use crate::session;
-use crate::session::{build_session, SessionBuilderConfig};
+use crate::session::{build_session, OutputFormat, RunStatus, SessionBuilderConfig};
 use goose_bench::bench_config::BenchRunConfig;
 use goose_bench::runners::bench_runner::BenchRunner;
 use goose_bench::runners::eval_runner::EvalRunner;
 use goose_bench::runners::metric_aggregator::MetricAggregator;
 use goose_bench::runners::model_runner::ModelRunner;
-use std::io::Read;
-use std::path::PathBuf;
+use serde_json::Value;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(author, version, display_name = "", about, long_about = None)]
@@ -95,6 +109,95 @@ enum SessionCommand {
             long_help = "Sort sessions by date in ascending order (oldest first). Default is descending order (newest first)."
         )]
         ascending: bool,
+
+        #[arg(
+            long,
+            value_name = "DATE",
+            help = "Only sessions active at or after this time (YYYY-MM-DD or RFC 3339)",
+            value_parser = parse_since
+        )]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+
+        #[arg(
+            long,
+            value_name = "DATE",
+            help = "Only sessions last active before this time (YYYY-MM-DD or RFC 3339)",
+            value_parser = parse_since
+        )]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+
+        #[arg(
+            long = "working-dir",
+            value_name = "DIR",
+            help = "Only sessions started in this directory"
+        )]
+        working_dir: Option<PathBuf>,
+
+        #[arg(
+            long = "schedule-id",
+            value_name = "ID",
+            help = "Only sessions created by this schedule"
+        )]
+        schedule_id: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "TEXT",
+            help = "Only sessions whose description contains this text"
+        )]
+        description: Option<String>,
+
+        #[arg(long, help = "Maximum number of sessions to show")]
+        limit: Option<usize>,
+
+        #[arg(long, default_value = "0", help = "Number of sessions to skip")]
+        offset: usize,
+
+        #[arg(long, help = "Show forked sessions under the session they came from")]
+        tree: bool,
+    },
+    #[command(about = "Start a new session from the first messages of an existing one")]
+    Fork {
+        #[arg(short, long, help = "ID of the session to fork")]
+        id: String,
+
+        #[arg(
+            long,
+            value_name = "N",
+            help = "Number of messages to keep from the original session",
+            long_help = "Number of messages to copy from the original session. The new session continues from there; the original is left unchanged."
+        )]
+        at: usize,
+    },
+    #[command(about = "Search the messages of past sessions")]
+    Search {
+        #[arg(help = "Words to search for", required = true, num_args = 1..)]
+        query: Vec<String>,
+
+        #[arg(
+            long = "working-dir",
+            value_name = "DIR",
+            help = "Only search sessions started in this directory"
+        )]
+        working_dir: Option<PathBuf>,
+
+        #[arg(long, default_value = "20", help = "Maximum number of results")]
+        limit: usize,
+
+        #[arg(
+            long,
+            help = "Search by meaning using embeddings instead of matching words",
+            long_help = "Search by meaning using the provider's embeddings instead of matching words. Messages are embedded the first time they are searched, which can take a while for a long history."
+        )]
+        semantic: bool,
+
+        #[arg(
+            short,
+            long,
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
     },
     #[command(about = "Remove sessions. Runs interactively if no ID or regex is provided.")]
     Remove {
@@ -103,7 +206,23 @@ enum SessionCommand {
         #[arg(short, long, help = "Regex for removing matched sessions (optional)")]
         regex: Option<String>,
     },
-    #[command(about = "Export a session to Markdown format")]
+    #[command(about = "Remove and compact old sessions according to the retention policy")]
+    Cleanup {
+        #[arg(
+            long,
+            help = "Show what would be removed or compacted without changing anything"
+        )]
+        dry_run: bool,
+
+        #[arg(
+            short,
+            long,
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+    #[command(about = "Export a session to Markdown, HTML, JSON or a portable bundle")]
     Export {
         #[command(flatten)]
         identifier: Option<Identifier>,
@@ -112,8 +231,135 @@ enum SessionCommand {
             short,
             long,
             help = "Output file path (default: stdout)",
-            long_help = "Path to save the exported Markdown. If not provided, output will be sent to stdout"
+            long_help = "Path to save the exported session. If not provided, output will be sent to stdout"
+        )]
+        output: Option<PathBuf>,
+
+        #[arg(
+            long,
+            help = "Export format (markdown, html, json, jsonl, bundle)",
+            long_help = "Export as readable Markdown, as a standalone HTML page with collapsible tool calls, as JSON with the metadata and every message in full, as JSONL in the session file format with metadata on the first line, or as a bundle that `goose session import` can restore on another machine. Bundles also hold the enabled extensions, without their secrets",
+            default_value = "markdown"
+        )]
+        format: String,
+
+        #[arg(
+            long,
+            value_name = "RECIPE_NAME or FULL_PATH_TO_RECIPE_FILE",
+            help = "Recipe to include in a bundle"
+        )]
+        recipe: Option<String>,
+    },
+    #[command(about = "Import a session from a bundle or an export")]
+    Import {
+        #[arg(
+            help = "Path to a bundle, a JSON export or a session file",
+            value_name = "FILE"
+        )]
+        file: PathBuf,
+
+        #[arg(
+            long = "working-dir",
+            value_name = "DIR",
+            help = "Working directory for the imported session (default: current directory)"
+        )]
+        working_dir: Option<PathBuf>,
+
+        #[arg(
+            long = "with-extensions",
+            help = "Add the bundled extensions you don't have yet, disabled"
+        )]
+        with_extensions: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+struct AuditFilterArgs {
+    #[arg(
+        long,
+        value_name = "SESSION_ID",
+        help = "Only show calls from this session"
+    )]
+    session: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Only show calls to this tool or extension (e.g. 'shell' or 'developer__shell')"
+    )]
+    tool: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "Only show calls at or after this time (YYYY-MM-DD or RFC 3339)",
+        value_parser = parse_since
+    )]
+    since: Option<chrono::DateTime<chrono::Utc>>,
+
+    #[arg(
+        long,
+        value_name = "DECISION",
+        help = "Only show calls with this permission decision (approved, denied, skipped)",
+        value_parser = parse_decision
+    )]
+    decision: Option<Decision>,
+
+    #[arg(long, help = "Only show calls that returned an error")]
+    errors: bool,
+}
+
+impl From<AuditFilterArgs> for AuditFilter {
+    fn from(args: AuditFilterArgs) -> Self {
+        AuditFilter {
+            session_id: args.session,
+            tool: args.tool,
+            since: args.since,
+            decision: args.decision,
+            errors_only: args.errors,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum AuditCommand {
+    #[command(about = "List recorded tool calls, most recent last")]
+    List {
+        #[command(flatten)]
+        filter: AuditFilterArgs,
+
+        #[arg(
+            short,
+            long,
+            default_value = "50",
+            help = "Maximum number of entries to show"
         )]
+        limit: usize,
+
+        #[arg(
+            short,
+            long,
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+    #[command(about = "Check the audit log hash chain for tampering")]
+    Verify {},
+    #[command(about = "Export audit entries for archiving or review")]
+    Export {
+        #[command(flatten)]
+        filter: AuditFilterArgs,
+
+        #[arg(
+            short,
+            long,
+            help = "Export format (jsonl, json, csv)",
+            default_value = "jsonl"
+        )]
+        format: String,
+
+        #[arg(short, long, help = "Output file path (default: stdout)")]
         output: Option<PathBuf>,
     },
 }
@@ -301,6 +547,14 @@ enum Command {
         )]
         debug: bool,
 
+        /// Use the full-screen terminal UI
+        #[arg(
+            long,
+            help = "Run the session in a full-screen terminal UI",
+            long_help = "Run the session in a full-screen terminal UI with a scrollable transcript, collapsible tool calls, an input editor and a side panel showing context usage, session cost and active extensions."
+        )]
+        tui: bool,
+
         /// Maximum number of consecutive identical tool calls allowed
         #[arg(
             long = "max-tool-repetitions",
@@ -430,6 +684,45 @@ enum Command {
         )]
         max_tool_repetitions: Option<u32>,
 
+        /// JSON schema for a structured result
+        #[arg(
+            long = "output-schema",
+            value_name = "FILE",
+            help = "Path to a JSON schema the final result must match",
+            long_help = "Path to a JSON schema file. The run ends with a JSON result that is validated against the schema and printed to stdout, instead of the conversation. Overrides the recipe's output_schema."
+        )]
+        output_schema: Option<PathBuf>,
+
+        /// How to report the run
+        #[arg(
+            long = "output-format",
+            value_name = "FORMAT",
+            value_enum,
+            default_value_t = OutputFormat::Text,
+            conflicts_with = "interactive",
+            help = "Output format: text, json or stream-json",
+            long_help = "How to report the run. 'text' is formatted for people. 'json' prints a single JSON object when the run ends, and 'stream-json' prints one JSON event per line as the run goes. The exit code tells how the run ended: 0 success, 1 error, 3 budget exceeded, 4 tool denied, 5 provider error."
+        )]
+        output_format: OutputFormat,
+
+        /// Maximum number of model responses
+        #[arg(
+            long = "max-turns",
+            value_name = "NUMBER",
+            help = "Stop the run after this many model responses",
+            long_help = "Stop the run when the model has responded this many times and still wants to call tools. The run then exits with code 3 (budget exceeded)."
+        )]
+        max_turns: Option<usize>,
+
+        /// Rules answering tool confirmations
+        #[arg(
+            long = "approval-policy",
+            value_name = "FILE",
+            help = "Path to an approval policy answering tool confirmations",
+            long_help = "Path to a YAML approval policy. In approve and smart_approve modes, tool calls that would ask for confirmation run when an allow rule matches them, and are denied when a deny rule does, with the reason given to the model. In a headless run, calls no rule matches are denied too; with --interactive they ask for confirmation as usual. Calls no rule matches can also be relayed to a webhook or socket for a person to answer. Overrides the recipe's approval_policy, whose allow rules and relay only apply once you approve them."
+        )]
+        approval_policy: Option<PathBuf>,
+
         /// Identifier for this run session
         #[command(flatten)]
         identifier: Option<Identifier>,
@@ -497,6 +790,13 @@ enum Command {
         command: SchedulerCommand,
     },
 
+    /// Query the tool call audit log
+    #[command(about = "Query and export the tool call audit log")]
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommand,
+    },
+
     /// Update the Goose CLI version
     #[command(about = "Update the goose CLI version")]
     Update {
@@ -556,6 +856,62 @@ struct InputConfig {
     contents: Option<String>,
     extensions_override: Option<Vec<ExtensionConfig>>,
     additional_system_prompt: Option<String>,
+    truncation_strategy: Option<TruncationStrategyKind>,
+    output_schema: Option<Value>,
+    reasoning: Option<ReasoningConfig>,
+    approval_policy: Option<ApprovalPolicy>,
+}
+
+fn load_output_schema(path: &Path) -> Result<Value> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+/// The part of a recipe's approval policy to apply. Allow rules and relays let calls run
+/// without the user, so they only apply when the user approves them here; deny rules always do,
+/// and the calls they don't refuse are asked about as usual.
+fn recipe_approval_policy(policy: ApprovalPolicy) -> Result<ApprovalPolicy> {
+    policy.validate()?;
+    if !policy.grants() {
+        return Ok(policy.deny_only());
+    }
+
+    let mut grants = Vec::new();
+    for rule in &policy.allow {
+        let arguments: Vec<String> = rule
+            .arguments
+            .iter()
+            .map(|(name, pattern)| format!("{}: {}", name, pattern))
+            .collect();
+        if arguments.is_empty() {
+            grants.push(format!("  allow {}", rule.tool));
+        } else {
+            grants.push(format!("  allow {} ({})", rule.tool, arguments.join(", ")));
+        }
+    }
+    match policy.relay.as_ref().map(|relay| &relay.target) {
+        Some(RelayTarget::Webhook(url)) => grants.push(format!("  ask the webhook {}", url)),
+        Some(RelayTarget::Socket(path)) => {
+            grants.push(format!("  ask the socket {}", path.display()))
+        }
+        None => {}
+    }
+
+    let approved = std::io::stdin().is_terminal()
+        && cliclack::confirm(format!(
+            "This recipe's approval policy runs tool calls without asking:\n{}\nApply it?",
+            grants.join("\n")
+        ))
+        .initial_value(false)
+        .interact()?;
+    if approved {
+        return Ok(policy);
+    }
+    eprintln!(
+        "{}: only the deny rules of the recipe's approval policy apply; other calls ask for confirmation as usual. Pass --approval-policy to allow calls without asking.",
+        console::style("Warning").yellow().bold()
+    );
+    Ok(policy.deny_only())
 }
 
 pub async fn cli() -> Result<()> {
@@ -566,6 +922,22 @@ pub async fn cli() -> Result<()> {
         eprintln!("Warning: Failed to update project tracker: {}", e);
     }
 
+    // Apply the retention policy when a new interactive session starts. Other commands leave
+    // sessions alone, and a resumed session may be the oldest.
+    let starts_session = matches!(
+        cli.command,
+        None | Some(Command::Session {
+            command: None,
+            resume: false,
+            ..
+        })
+    );
+    if starts_session {
+        if let Err(e) = cleanup_on_startup() {
+            eprintln!("Warning: Failed to clean up old sessions: {}", e);
+        }
+    }
+
     match cli.command {
         Some(Command::Configure {}) => {
             let _ = handle_configure().await;
@@ -584,6 +956,7 @@ pub async fn cli() -> Result<()> {
             resume,
             history,
             debug,
+            tui,
             max_tool_repetitions,
             extensions,
             remote_extensions,
@@ -594,15 +967,65 @@ pub async fn cli() -> Result<()> {
                     verbose,
                     format,
                     ascending,
+                    since,
+                    until,
+                    working_dir,
+                    schedule_id,
+                    description,
+                    limit,
+                    offset,
+                    tree,
                 }) => {
-                    handle_session_list(verbose, format, ascending)?;
+                    let query = SessionQuery {
+                        since,
+                        until,
+                        working_dir,
+                        schedule_id,
+                        description,
+                        order: if ascending {
+                            SortOrder::Ascending
+                        } else {
+                            SortOrder::Descending
+                        },
+                        limit,
+                        offset,
+                    };
+                    handle_session_list(verbose, format, query, tree)?;
+                    Ok(())
+                }
+                Some(SessionCommand::Fork { id, at }) => {
+                    handle_session_fork(id, at)?;
+                    Ok(())
+                }
+                Some(SessionCommand::Search {
+                    query,
+                    working_dir,
+                    limit,
+                    semantic,
+                    format,
+                }) => {
+                    let query = SearchQuery {
+                        text: query.join(" "),
+                        working_dir,
+                        limit,
+                    };
+                    handle_session_search(query, semantic, format).await?;
                     Ok(())
                 }
                 Some(SessionCommand::Remove { id, regex }) => {
                     handle_session_remove(id, regex)?;
                     return Ok(());
                 }
-                Some(SessionCommand::Export { identifier, output }) => {
+                Some(SessionCommand::Cleanup { dry_run, format }) => {
+                    handle_session_cleanup(dry_run, format)?;
+                    Ok(())
+                }
+                Some(SessionCommand::Export {
+                    identifier,
+                    output,
+                    format,
+                    recipe,
+                }) => {
                     let session_identifier = if let Some(id) = identifier {
                         extract_identifier(id)
                     } else {
@@ -616,7 +1039,20 @@ pub async fn cli() -> Result<()> {
                         }
                     };
 
-                    crate::commands::session::handle_session_export(session_identifier, output)?;
+                    crate::commands::session::handle_session_export(
+                        session_identifier,
+                        output,
+                        &format,
+                        recipe,
+                    )?;
+                    Ok(())
+                }
+                Some(SessionCommand::Import {
+                    file,
+                    working_dir,
+                    with_extensions,
+                }) => {
+                    handle_session_import(&file, working_dir, with_extensions)?;
                     Ok(())
                 }
                 None => {
@@ -632,6 +1068,12 @@ pub async fn cli() -> Result<()> {
                         additional_system_prompt: None,
                         debug,
                         max_tool_repetitions,
+                        truncation_strategy: None,
+                        output_schema: None,
+                        reasoning: None,
+                        approval_policy: None,
+                        max_turns: None,
+                        output_format: OutputFormat::Text,
                         interactive: true, // Session command is always interactive
                     })
                     .await;
@@ -640,6 +1082,14 @@ pub async fn cli() -> Result<()> {
                         None,
                     )?;
 
+                    if tui {
+                        // The full-screen UI always shows the whole conversation
+                        if let Err(e) = session.tui().await {
+                            eprintln!("Error: {}", e);
+                        }
+                        return Ok(());
+                    }
+
                     // Render previous messages if resuming a session and history flag is set
                     if resume && history {
                         session.render_message_history();
@@ -670,6 +1120,10 @@ pub async fn cli() -> Result<()> {
             no_session,
             debug,
             max_tool_repetitions,
+            output_schema,
+            output_format,
+            max_turns,
+            approval_policy,
             extensions,
             remote_extensions,
             builtins,
@@ -687,6 +1141,10 @@ pub async fn cli() -> Result<()> {
                         contents: Some(input),
                         extensions_override: None,
                         additional_system_prompt: None,
+                        truncation_strategy: None,
+                        output_schema: None,
+                        reasoning: None,
+                        approval_policy: None,
                     }
                 }
                 (Some(file), _, _, _) => {
@@ -701,12 +1159,20 @@ pub async fn cli() -> Result<()> {
                         contents: Some(contents),
                         extensions_override: None,
                         additional_system_prompt: None,
+                        truncation_strategy: None,
+                        output_schema: None,
+                        reasoning: None,
+                        approval_policy: None,
                     }
                 }
                 (_, Some(text), _, _) => InputConfig {
                     contents: Some(text),
                     extensions_override: None,
                     additional_system_prompt: None,
+                    truncation_strategy: None,
+                    output_schema: None,
+                    reasoning: None,
+                    approval_policy: None,
                 },
                 (_, _, Some(recipe_name), explain) => {
                     if explain {
@@ -722,6 +1188,10 @@ pub async fn cli() -> Result<()> {
                         contents: recipe.prompt,
                         extensions_override: recipe.extensions,
                         additional_system_prompt: recipe.instructions,
+                        truncation_strategy: recipe.truncation_strategy,
+                        output_schema: recipe.output_schema,
+                        reasoning: recipe.reasoning,
+                        approval_policy: recipe.approval_policy,
                     }
                 }
                 (None, None, None, _) => {
@@ -730,6 +1200,46 @@ pub async fn cli() -> Result<()> {
                 }
             };
 
+            let output_schema = match output_schema {
+                Some(path) => Some(load_output_schema(&path).unwrap_or_else(|err| {
+                    eprintln!(
+                        "{}: failed to read output schema {}: {}",
+                        console::style("Error").red().bold(),
+                        path.display(),
+                        err
+                    );
+                    std::process::exit(1);
+                })),
+                None => input_config.output_schema,
+            };
+            if let Some(Err(err)) = output_schema.as_ref().map(check_schema) {
+                eprintln!("{}: {}", console::style("Error").red().bold(), err);
+                std::process::exit(1);
+            }
+            let approval_policy = match approval_policy {
+                Some(path) => ApprovalPolicy::load(&path).map(Some),
+                None => input_config
+                    .approval_policy
+                    .map(recipe_approval_policy)
+                    .transpose(),
+            }
+            .unwrap_or_else(|err| {
+                eprintln!("{}: {:#}", console::style("Error").red().bold(), err);
+                std::process::exit(1);
+            });
+            // Only a headless run denies the calls the policy doesn't decide; otherwise they are asked
+            let approval_policy = approval_policy.map(|policy| {
+                if interactive {
+                    policy.ask_unmatched()
+                } else {
+                    policy
+                }
+            });
+            // Keep stdout for the JSON result when running headless with a schema
+            let structured_output = output_schema.is_some() && !interactive;
+            let json_output = output_format != OutputFormat::Text;
+            session::set_quiet(structured_output || json_output);
+
             let mut session = build_session(SessionBuilderConfig {
                 identifier: identifier.map(extract_identifier),
                 resume,
@@ -741,6 +1251,12 @@ pub async fn cli() -> Result<()> {
                 additional_system_prompt: input_config.additional_system_prompt,
                 debug,
                 max_tool_repetitions,
+                truncation_strategy: input_config.truncation_strategy,
+                output_schema,
+                reasoning: input_config.reasoning,
+                approval_policy,
+                max_turns,
+                output_format,
                 interactive, // Use the interactive flag from the Run command
             })
             .await;
@@ -753,7 +1269,36 @@ pub async fn cli() -> Result<()> {
             if interactive {
                 let _ = session.interactive(input_config.contents).await;
             } else if let Some(contents) = input_config.contents {
-                let _ = session.headless(contents).await;
+                if let Err(e) = session.start_run().await {
+                    eprintln!("{}: {}", console::style("Error").red().bold(), e);
+                    std::process::exit(RunStatus::Error.exit_code());
+                }
+                let outcome = session.headless(contents).await;
+                if let Err(e) = &outcome {
+                    eprintln!("{}: {}", console::style("Error").red().bold(), e);
+                }
+                if structured_output && !json_output {
+                    match session.final_output().await {
+                        Some(output) => println!("{}", serde_json::to_string_pretty(&output)?),
+                        None => {
+                            let reason = session
+                                .message_history()
+                                .last()
+                                .map(|message| message.as_concat_text())
+                                .unwrap_or_default();
+                            eprintln!(
+                                "{}: the run did not produce output matching the schema\n{}",
+                                console::style("Error").red().bold(),
+                                reason
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                let status = session.finish_run(outcome, structured_output).await;
+                if status != RunStatus::Success {
+                    std::process::exit(status.exit_code());
+                }
             } else {
                 eprintln!("Error: no text provided for prompt in headless mode");
                 std::process::exit(1);
@@ -796,6 +1341,22 @@ pub async fn cli() -> Result<()> {
             }
             return Ok(());
         }
+        Some(Command::Audit { command }) => {
+            match command {
+                AuditCommand::List {
+                    filter,
+                    limit,
+                    format,
+                } => handle_audit_list(filter.into(), limit, format)?,
+                AuditCommand::Verify {} => handle_audit_verify()?,
+                AuditCommand::Export {
+                    filter,
+                    format,
+                    output,
+                } => handle_audit_export(filter.into(), format, output)?,
+            }
+            return Ok(());
+        }
         Some(Command::Update {
             canary,
             reconfigure,
@@ -856,6 +1417,12 @@ pub async fn cli() -> Result<()> {
                     additional_system_prompt: None,
                     debug: false,
                     max_tool_repetitions: None,
+                    truncation_strategy: None,
+                    output_schema: None,
+                    reasoning: None,
+                    approval_policy: None,
+                    max_turns: None,
+                    output_format: OutputFormat::Text,
                     interactive: true, // Default case is always interactive
                 })
                 .await;
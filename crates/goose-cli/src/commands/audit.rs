@@ -0,0 +1,219 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use goose::audit::{AuditEntry, AuditFilter, AuditLog, Decision, Outcome, Verification};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Parse `--since`, accepting either an RFC 3339 timestamp or a plain date
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|_| format!("invalid date '{}', expected YYYY-MM-DD or RFC 3339", value))
+}
+
+pub fn parse_decision(value: &str) -> Result<Decision, String> {
+    match value {
+        "approved" => Ok(Decision::Approved),
+        "denied" => Ok(Decision::Denied),
+        "skipped" => Ok(Decision::Skipped),
+        _ => Err(format!(
+            "invalid decision '{}', expected approved, denied or skipped",
+            value
+        )),
+    }
+}
+
+fn open_log() -> AuditLog {
+    AuditLog::global()
+        .cloned()
+        .unwrap_or_else(|| AuditLog::new(AuditLog::configured_path()))
+}
+
+fn filtered_entries(filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+    let log = open_log();
+    Ok(log
+        .read()?
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        .collect())
+}
+
+pub fn handle_audit_list(filter: AuditFilter, limit: usize, format: String) -> Result<()> {
+    let entries = filtered_entries(&filter)?;
+    let skip = entries.len().saturating_sub(limit);
+    let entries = &entries[skip..];
+
+    if format == "json" {
+        println!("{}", serde_json::to_string(entries)?);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("No audit entries found");
+        return Ok(());
+    }
+
+    for entry in entries {
+        let record = &entry.record;
+        let outcome = match record.outcome {
+            Outcome::Success => console::style("ok".to_string()).green(),
+            Outcome::Error => console::style(format!(
+                "error: {}",
+                record.error.as_deref().unwrap_or_default()
+            ))
+            .red(),
+            Outcome::NotRun => console::style("not run".to_string()).dim(),
+        };
+        println!(
+            "#{} {} {}@{} {}__{} {:?} by {:?} ({}ms) {}",
+            record.seq,
+            record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            record.user,
+            record.host,
+            record.extension,
+            record.tool,
+            record.decision,
+            record.approver,
+            record.duration_ms,
+            outcome,
+        );
+        println!(
+            "    session: {}  args: {}",
+            record.session_id.as_deref().unwrap_or("-"),
+            record.arguments
+        );
+    }
+    Ok(())
+}
+
+pub fn handle_audit_verify() -> Result<()> {
+    let log = open_log();
+    match log.verify()? {
+        Verification::Valid { entries } => {
+            println!(
+                "{} {} entries verified in {}",
+                console::style("✓").green(),
+                entries,
+                log.path().display()
+            );
+            Ok(())
+        }
+        Verification::Recovered { entries, skipped } => {
+            println!(
+                "{} {} entries verified in {}",
+                console::style("✓").green(),
+                entries,
+                log.path().display()
+            );
+            println!(
+                "{} skipped unreadable line(s) {}, left by writes that were cut short",
+                console::style("!").yellow(),
+                skipped
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            Ok(())
+        }
+        Verification::Broken { line, reason } => Err(anyhow!(
+            "Audit log {} failed verification at line {}: {}",
+            log.path().display(),
+            line,
+            reason
+        )),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(entries: &[AuditEntry]) -> Result<String> {
+    let mut out = String::from(
+        "seq,timestamp,host,user,session_id,working_dir,mode,request_id,extension,tool,arguments,decision,approver,duration_ms,outcome,error,prev_hash,hash\n",
+    );
+    for entry in entries {
+        let record = &entry.record;
+        let enum_name = |value: serde_json::Value| value.as_str().unwrap_or_default().to_string();
+        let fields = [
+            record.seq.to_string(),
+            record.timestamp.to_rfc3339(),
+            record.host.clone(),
+            record.user.clone(),
+            record.session_id.clone().unwrap_or_default(),
+            record
+                .working_dir
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+            record.mode.clone(),
+            record.request_id.clone(),
+            record.extension.clone(),
+            record.tool.clone(),
+            record.arguments.to_string(),
+            enum_name(serde_json::to_value(record.decision)?),
+            enum_name(serde_json::to_value(record.approver)?),
+            record.duration_ms.to_string(),
+            enum_name(serde_json::to_value(record.outcome)?),
+            record.error.clone().unwrap_or_default(),
+            record.prev_hash.clone(),
+            entry.hash.clone(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+pub fn handle_audit_export(
+    filter: AuditFilter,
+    format: String,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let entries = filtered_entries(&filter)?;
+
+    let content = match format.as_str() {
+        "json" => serde_json::to_string_pretty(&entries)?,
+        "csv" => to_csv(&entries)?,
+        "jsonl" => {
+            let mut out = String::new();
+            for entry in &entries {
+                out.push_str(&serde_json::to_string(entry)?);
+                out.push('\n');
+            }
+            out
+        }
+        other => {
+            return Err(anyhow!(
+                "Unsupported export format '{}', expected jsonl, json or csv",
+                other
+            ))
+        }
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(&path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!(
+                "Exported {} audit entries to {}",
+                entries.len(),
+                path.display()
+            );
+        }
+        None => {
+            std::io::stdout().write_all(content.as_bytes())?;
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use crate::session::build_session;
-use crate::session::SessionBuilderConfig;
+use crate::session::{OutputFormat, SessionBuilderConfig};
 use crate::{logging, session, Session};
 use async_trait::async_trait;
 use goose::message::Message;
@@ -42,6 +42,12 @@ pub async fn agent_generator(
         additional_system_prompt: None,
         debug: false,
         max_tool_repetitions: None,
+        truncation_strategy: None,
+        output_schema: None,
+        reasoning: None,
+        approval_policy: None,
+        max_turns: None,
+        output_format: OutputFormat::Text,
         interactive: false, // Benchmarking is non-interactive
     })
     .await;
@@ -14,6 +14,9 @@ use goose::config::{
     PermissionManager,
 };
 use goose::message::Message;
+use goose::model::{
+    ReasoningEffort, MIN_THINKING_BUDGET, REASONING_EFFORT_CONFIG_KEY, THINKING_BUDGET_CONFIG_KEY,
+};
 use goose::providers::{create, providers};
 use mcp_core::tool::ToolAnnotations;
 use mcp_core::Tool;
@@ -35,6 +38,7 @@ fn get_display_name(extension_id: &str) -> String {
         "memory" => "Memory".to_string(),
         "tutorial" => "Tutorial".to_string(),
         "jetbrains" => "JetBrains".to_string(),
+        "lsp" => "Language Servers".to_string(),
         // Add other extensions as needed
         _ => {
             extension_id
@@ -529,6 +533,11 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                     "Access interactive tutorials and guides",
                 )
                 .item("jetbrains", "JetBrains", "Connect to jetbrains IDEs")
+                .item(
+                    "lsp",
+                    "Language Servers",
+                    "Diagnostics, navigation and refactors from local language servers",
+                )
                 .interact()?
                 .to_string();
 
@@ -839,6 +848,11 @@ pub async fn configure_settings_dialog() -> Result<(), Box<dyn Error>> {
             "Tool Output",
             "Show more or less tool output",
         )
+        .item(
+            "reasoning",
+            "Reasoning",
+            "Set how much the model thinks before it answers",
+        )
         .item(
             "experiment",
             "Toggle Experiment",
@@ -864,6 +878,9 @@ pub async fn configure_settings_dialog() -> Result<(), Box<dyn Error>> {
         "tool_output" => {
             configure_tool_output_dialog()?;
         }
+        "reasoning" => {
+            configure_reasoning_dialog()?;
+        }
         "experiment" => {
             toggle_experiments_dialog()?;
         }
@@ -1003,6 +1020,69 @@ pub fn configure_tool_output_dialog() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+pub fn configure_reasoning_dialog() -> Result<(), Box<dyn Error>> {
+    let config = Config::global();
+    if std::env::var(REASONING_EFFORT_CONFIG_KEY).is_ok()
+        || std::env::var(THINKING_BUDGET_CONFIG_KEY).is_ok()
+    {
+        let _ = cliclack::log::info(format!(
+            "Notice: {} or {} is set as an environment variable and will override the configuration here.",
+            REASONING_EFFORT_CONFIG_KEY, THINKING_BUDGET_CONFIG_KEY
+        ));
+    }
+
+    let effort = cliclack::select("How much should the model reason before it answers?")
+        .item(
+            "default",
+            "Provider default",
+            "Only reasoning models think, as much as they decide to",
+        )
+        .item("low", "Low", "Faster and cheaper answers")
+        .item("medium", "Medium", "A balance of speed and depth")
+        .item("high", "High", "Slower, more thorough answers")
+        .interact()?;
+
+    if effort == "default" {
+        config.delete(REASONING_EFFORT_CONFIG_KEY)?;
+        config.delete(THINKING_BUDGET_CONFIG_KEY)?;
+        cliclack::outro("Using the provider's default reasoning")?;
+        return Ok(());
+    }
+    config.set_param(
+        REASONING_EFFORT_CONFIG_KEY,
+        Value::String(effort.to_string()),
+    )?;
+
+    // Providers that take a token budget (Anthropic, Gemini, Bedrock) derive one from the
+    // effort unless it's set explicitly
+    let default_budget = effort
+        .parse::<ReasoningEffort>()
+        .map(|effort| effort.budget_tokens())
+        .unwrap_or_default();
+    let budget: String = cliclack::input("Thinking budget in tokens, for providers that use one:")
+        .default_input(&default_budget.to_string())
+        .validate(|input: &String| match input.parse::<u32>() {
+            Ok(budget) if budget >= MIN_THINKING_BUDGET => Ok(()),
+            _ => Err(format!(
+                "Please enter a number of at least {}",
+                MIN_THINKING_BUDGET
+            )),
+        })
+        .interact()?;
+    let budget: u32 = budget.parse()?;
+    if budget == default_budget {
+        config.delete(THINKING_BUDGET_CONFIG_KEY)?;
+    } else {
+        config.set_param(THINKING_BUDGET_CONFIG_KEY, Value::from(budget))?;
+    }
+
+    cliclack::outro(format!(
+        "Reasoning effort set to {} with a thinking budget of {} tokens",
+        effort, budget
+    ))?;
+    Ok(())
+}
+
 /// Configure experiment features that can be used with goose
 /// Dialog for toggling which experiments are enabled/disabled
 pub fn toggle_experiments_dialog() -> Result<(), Box<dyn Error>> {
@@ -15,16 +15,27 @@ pub fn handle_info(verbose: bool) -> Result<()> {
         .unwrap_or_else(|| data_dir.in_data_dir("logs"));
     let sessions_dir = data_dir.in_data_dir("sessions");
 
-    // Get paths using a stored reference to the global config
-    let config = Config::global();
+    // Get paths from the global config, with the project configuration of the working directory
+    let config = match std::env::current_dir() {
+        Ok(dir) => Config::global().for_dir(&dir),
+        Err(_) => Config::global().for_project(None),
+    };
     let config_file = config.path();
 
     // Define the labels and their corresponding path values once.
-    let paths = [
-        ("Config file:", config_file.to_string()),
-        ("Sessions dir:", sessions_dir.display().to_string()),
-        ("Logs dir:", logs_dir.display().to_string()),
-    ];
+    let mut paths = vec![("Config file:", config_file.to_string())];
+    if let Some(project_file) = config.project_path() {
+        let trust = match config.is_project_trusted() {
+            true => "trusted",
+            false => "not trusted",
+        };
+        paths.push((
+            "Project config:",
+            format!("{} ({})", project_file.display(), trust),
+        ));
+    }
+    paths.push(("Sessions dir:", sessions_dir.display().to_string()));
+    paths.push(("Logs dir:", logs_dir.display().to_string()));
 
     // Calculate padding: use the max length of the label plus extra space.
     let basic_padding = paths.iter().map(|(l, _)| l.len()).max().unwrap_or(0) + 4;
@@ -43,7 +54,8 @@ pub fn handle_info(verbose: bool) -> Result<()> {
     // Print verbose info if requested
     if verbose {
         println!("\n{}", style("Goose Configuration:").cyan().bold());
-        match config.load_values() {
+        // Show what is in effect, with the project configuration laid over the user's
+        match config.load_effective_values() {
             Ok(values) => {
                 if values.is_empty() {
                     println!("  No configuration values set");
@@ -59,6 +71,29 @@ pub fn handle_info(verbose: bool) -> Result<()> {
             }
             Err(e) => println!("  Error loading configuration: {}", e),
         }
+
+        match config.load_project_values() {
+            Ok(values) if !values.is_empty() => {
+                let mut keys: Vec<_> = values.keys().cloned().collect();
+                keys.sort();
+                println!(
+                    "\n  {} {}",
+                    style("Set by the project:").dim(),
+                    keys.join(", ")
+                );
+            }
+            Ok(_) => {}
+            Err(e) => println!("  Error loading project configuration: {}", e),
+        }
+        if let Ok(keys) = config.untrusted_project_keys() {
+            if !keys.is_empty() {
+                println!(
+                    "  {} {}",
+                    style("Ignored until the project is trusted:").dim(),
+                    keys.join(", ")
+                );
+            }
+        }
     }
 
     Ok(())
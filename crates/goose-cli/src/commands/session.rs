@@ -1,10 +1,21 @@
-use crate::session::message_to_markdown;
+use crate::recipes::recipe::load_recipe;
+use crate::session::{message_to_markdown, session_to_html};
 use anyhow::{Context, Result};
 use cliclack::{confirm, multiselect, select};
+use console::style;
+use goose::session::bundle::enabled_extensions;
 use goose::session::info::{get_session_info, SessionInfo, SortOrder};
-use goose::session::{self, Identifier};
+use goose::session::retention::{self, RemovalReason, RETENTION_CONFIG_KEY};
+use goose::session::search::{embedding_provider, HIGHLIGHT};
+use goose::session::{
+    self, cleanup_sessions, query_sessions, search_sessions, semantic_search_sessions,
+    CleanupReport, Identifier, RetentionConfig, SearchHit, SearchQuery, SessionBundle,
+    SessionIndex, SessionPage, SessionQuery,
+};
 use regex::Regex;
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 const TRUNCATED_DESC_LENGTH: usize = 60;
@@ -21,7 +32,7 @@ pub fn remove_sessions(sessions: Vec<SessionInfo>) -> Result<()> {
 
     if should_delete {
         for session in sessions {
-            fs::remove_file(session.path.clone())
+            session::delete_session(Path::new(&session.path))
                 .with_context(|| format!("Failed to remove session file '{}'", session.path))?;
             println!("Session `{}` removed.", session.id);
         }
@@ -32,6 +43,116 @@ pub fn remove_sessions(sessions: Vec<SessionInfo>) -> Result<()> {
     Ok(())
 }
 
+/// Apply the retention policy and show what was removed and compacted
+pub fn handle_session_cleanup(dry_run: bool, format: String) -> Result<()> {
+    let config = RetentionConfig::from_config();
+    let report = cleanup_sessions(&config, dry_run)?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+
+    if config == RetentionConfig::default() {
+        println!(
+            "{}",
+            style(format!(
+                "No retention limits are configured. Set {} in your config to remove sessions by age, count or size.",
+                RETENTION_CONFIG_KEY
+            ))
+            .dim()
+        );
+    }
+    print_cleanup_report(&report);
+    Ok(())
+}
+
+/// Apply the retention policy if it is due
+///
+/// The summary goes to stderr, so it doesn't get mixed into output that is piped elsewhere.
+pub fn cleanup_on_startup() -> Result<()> {
+    if let Some(report) = retention::cleanup_on_startup()? {
+        if !report.is_empty() {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "Cleaned up old sessions: removed {}, compacted {}, freed {}",
+                    report.removed.len(),
+                    report.compacted.len(),
+                    format_bytes(report.bytes_freed())
+                ))
+                .dim()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_cleanup_report(report: &CleanupReport) {
+    if report.is_empty() {
+        println!("Nothing to clean up");
+        return;
+    }
+
+    let (remove, compact, free) = if report.dry_run {
+        ("Would remove", "Would compact", "Would free")
+    } else {
+        ("Removed", "Compacted", "Freed")
+    };
+    for session in &report.removed {
+        let reason = match session.reason {
+            RemovalReason::Age => "older than the age limit",
+            RemovalReason::Count => "over the session count limit",
+            RemovalReason::Size => "over the size limit",
+        };
+        println!(
+            "{} {}{} ({}, {})",
+            remove,
+            style(&session.id).bold(),
+            if session.scheduled {
+                " [scheduled]"
+            } else {
+                ""
+            },
+            reason,
+            format_bytes(session.bytes)
+        );
+    }
+    for session in &report.compacted {
+        println!(
+            "{} {} ({} tool outputs shortened, {})",
+            compact,
+            style(&session.id).bold(),
+            session.outputs,
+            format_bytes(session.bytes_saved)
+        );
+    }
+    if report.stale_responses > 0 {
+        println!(
+            "{} {} leftover large tool responses ({})",
+            remove,
+            report.stale_responses,
+            format_bytes(report.stale_response_bytes)
+        );
+    }
+    println!("{} {} in total", free, format_bytes(report.bytes_freed()));
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
 fn prompt_interactive_session_removal(sessions: &[SessionInfo]) -> Result<Vec<SessionInfo>> {
     if sessions.is_empty() {
         println!("No sessions to delete.");
@@ -118,15 +239,14 @@ pub fn handle_session_remove(id: Option<String>, regex_string: Option<String>) -
     remove_sessions(matched_sessions)
 }
 
-pub fn handle_session_list(verbose: bool, format: String, ascending: bool) -> Result<()> {
-    let sort_order = if ascending {
-        SortOrder::Ascending
-    } else {
-        SortOrder::Descending
-    };
-
-    let sessions = match get_session_info(sort_order) {
-        Ok(sessions) => sessions,
+pub fn handle_session_list(
+    verbose: bool,
+    format: String,
+    query: SessionQuery,
+    tree: bool,
+) -> Result<()> {
+    let SessionPage { sessions, total } = match query_sessions(&query) {
+        Ok(page) => page,
         Err(e) => {
             tracing::error!("Failed to list sessions: {:?}", e);
             return Err(anyhow::anyhow!("Failed to list sessions"));
@@ -142,25 +262,46 @@ pub fn handle_session_list(verbose: bool, format: String, ascending: bool) -> Re
                 println!("No sessions found");
                 return Ok(());
             } else {
-                println!("Available sessions:");
-                for SessionInfo {
-                    id,
-                    path,
-                    metadata,
-                    modified,
-                } in sessions
-                {
+                if sessions.len() < total {
+                    println!(
+                        "Available sessions ({}-{} of {}):",
+                        query.offset + 1,
+                        query.offset + sessions.len(),
+                        total
+                    );
+                } else {
+                    println!("Available sessions:");
+                }
+                let rows = if tree {
+                    tree_order(&sessions)
+                } else {
+                    sessions.iter().map(|session| (0, session)).collect()
+                };
+                for (depth, session) in rows {
+                    let SessionInfo {
+                        id,
+                        path,
+                        metadata,
+                        modified,
+                    } = session;
                     let description = if metadata.description.is_empty() {
                         "(none)"
                     } else {
                         &metadata.description
                     };
-                    let output = format!("{} - {} - {}", id, description, modified);
+                    let mut output = format!("{} - {} - {}", id, description, modified);
+                    let mut indent = String::new();
+                    if depth > 0 {
+                        indent = format!("{}└─ ", "   ".repeat(depth - 1));
+                        if let Some(branch_point) = metadata.branch_point {
+                            output.push_str(&format!(" (forked at message {})", branch_point));
+                        }
+                    }
                     if verbose {
-                        println!("  {}", output);
-                        println!("    Path: {}", path);
+                        println!("  {}{}", indent, output);
+                        println!("  {}    Path: {}", " ".repeat(indent.chars().count()), path);
                     } else {
-                        println!("{}", output);
+                        println!("{}{}", indent, output);
                     }
                 }
             }
@@ -169,11 +310,140 @@ pub fn handle_session_list(verbose: bool, format: String, ascending: bool) -> Re
     Ok(())
 }
 
-/// Export a session to Markdown without creating a full Session object
+/// Order sessions so forks follow the session they were forked from, with their depth in the
+/// tree. Sessions whose parent isn't listed are shown at the top level.
+fn tree_order(sessions: &[SessionInfo]) -> Vec<(usize, &SessionInfo)> {
+    let listed: HashSet<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+    let mut children: HashMap<&str, Vec<&SessionInfo>> = HashMap::new();
+    let mut roots = Vec::new();
+    for session in sessions {
+        match session.metadata.parent_session_id.as_deref() {
+            Some(parent) if listed.contains(parent) && parent != session.id => {
+                children.entry(parent).or_default().push(session)
+            }
+            _ => roots.push(session),
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(sessions.len());
+    let mut visited = HashSet::new();
+    // Going through every session after the roots also shows sessions caught in a cycle
+    for start in roots.into_iter().chain(sessions) {
+        let mut stack = vec![(0, start)];
+        while let Some((depth, session)) = stack.pop() {
+            if !visited.insert(session.id.as_str()) {
+                continue;
+            }
+            ordered.push((depth, session));
+            if let Some(forks) = children.get(session.id.as_str()) {
+                stack.extend(forks.iter().rev().map(|fork| (depth + 1, *fork)));
+            }
+        }
+    }
+    ordered
+}
+
+pub fn handle_session_fork(id: String, at: usize) -> Result<()> {
+    let session_file = session::get_path(Identifier::Name(id.clone()));
+    let (fork_id, _) = session::fork_session(&session_file, at)
+        .with_context(|| format!("Failed to fork session '{}'", id))?;
+
+    println!(
+        "Forked session `{}` from the first {} messages of `{}`.",
+        fork_id, at, id
+    );
+    println!("Resume it with: goose session --resume --name {}", fork_id);
+    Ok(())
+}
+
+pub async fn handle_session_search(
+    query: SearchQuery,
+    semantic: bool,
+    format: String,
+) -> Result<()> {
+    let hits = if semantic {
+        let provider = embedding_provider()?;
+        semantic_search_sessions(&query, &provider).await?
+    } else {
+        search_sessions(&query)?
+    };
+
+    if format == "json" {
+        println!("{}", serde_json::to_string(&hits)?);
+        return Ok(());
+    }
+
+    if hits.is_empty() {
+        println!("No matching messages found");
+        return Ok(());
+    }
+
+    // Show the hits grouped by session, keeping the sessions in order of their best hit
+    let mut sessions: Vec<(&str, Vec<&SearchHit>)> = Vec::new();
+    for hit in &hits {
+        match sessions.iter_mut().find(|(id, _)| *id == hit.session_id) {
+            Some((_, session_hits)) => session_hits.push(hit),
+            None => sessions.push((&hit.session_id, vec![hit])),
+        }
+    }
+
+    for (id, session_hits) in sessions {
+        let first = session_hits[0];
+        let description = if first.description.is_empty() {
+            "(none)"
+        } else {
+            &first.description
+        };
+        println!(
+            "{} - {} - {}",
+            style(id).bold(),
+            description,
+            style(&first.modified).dim()
+        );
+        println!("  {}", style(&first.working_dir).dim());
+        for hit in session_hits {
+            println!(
+                "  #{} {}: {}",
+                hit.message_index,
+                hit.role,
+                highlight_snippet(&hit.snippet)
+            );
+        }
+        println!(
+            "  {}",
+            style(format!("goose session --resume --name {}", id)).dim()
+        );
+        println!();
+    }
+    Ok(())
+}
+
+/// Put a snippet on one line and show the matched words in bold
+fn highlight_snippet(snippet: &str) -> String {
+    let snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    snippet
+        .split(HIGHLIGHT)
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 1 {
+                style(part).bold().yellow().to_string()
+            } else {
+                part.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Export a session without creating a full Session object
 ///
-/// This function directly reads messages from the session file and converts them to Markdown
-/// without creating an Agent or prompting about working directories.
-pub fn handle_session_export(identifier: Identifier, output_path: Option<PathBuf>) -> Result<()> {
+/// This function directly reads messages from the session file and converts them to the
+/// requested format without creating an Agent or prompting about working directories.
+pub fn handle_session_export(
+    identifier: Identifier,
+    output_path: Option<PathBuf>,
+    format: &str,
+    recipe: Option<String>,
+) -> Result<()> {
     // Get the session file path
     let session_file_path = goose::session::get_path(identifier.clone());
 
@@ -184,6 +454,27 @@ pub fn handle_session_export(identifier: Identifier, output_path: Option<PathBuf
         ));
     }
 
+    if recipe.is_some() && format != "bundle" {
+        return Err(anyhow::anyhow!(
+            "--recipe can only be used with --format bundle"
+        ));
+    }
+
+    match format {
+        "markdown" => {}
+        "jsonl" => return export_session_to_jsonl(identifier, &session_file_path, output_path),
+        "html" => return export_session_to_html(&session_file_path, output_path),
+        "json" | "bundle" => {
+            return export_session_to_json(&session_file_path, output_path, format, recipe)
+        }
+        other => {
+            return Err(anyhow::anyhow!(
+                "Unsupported export format '{}', expected markdown, html, json, jsonl or bundle",
+                other
+            ))
+        }
+    }
+
     // Read messages directly without using Session
     let messages = match goose::session::read_messages(&session_file_path) {
         Ok(msgs) => msgs,
@@ -207,6 +498,126 @@ pub fn handle_session_export(identifier: Identifier, output_path: Option<PathBuf
     Ok(())
 }
 
+/// Export a session in the session file format, reading named sessions from the index
+fn export_session_to_jsonl(
+    identifier: Identifier,
+    session_file: &Path,
+    output_path: Option<PathBuf>,
+) -> Result<()> {
+    let mut jsonl = Vec::new();
+    match identifier {
+        Identifier::Name(name) => {
+            let mut index = SessionIndex::open()?;
+            index.sync()?;
+            index.export_jsonl(&name, &mut jsonl)?;
+        }
+        Identifier::Path(_) => {
+            // Sessions outside the session directory aren't indexed
+            jsonl = fs::read(session_file)?;
+        }
+    }
+
+    write_export(output_path, &jsonl)
+}
+
+/// Export a session as a standalone HTML page
+fn export_session_to_html(session_file: &Path, output_path: Option<PathBuf>) -> Result<()> {
+    let bundle = SessionBundle::from_session(session_file)?;
+    let html = session_to_html(&bundle.session_id, &bundle.metadata, &bundle.messages);
+    write_export(output_path, html.as_bytes())
+}
+
+/// Export a session as JSON with every message in full, adding the enabled extensions and
+/// the recipe for a bundle
+fn export_session_to_json(
+    session_file: &Path,
+    output_path: Option<PathBuf>,
+    format: &str,
+    recipe: Option<String>,
+) -> Result<()> {
+    let mut bundle = SessionBundle::from_session(session_file)?;
+    if format == "bundle" {
+        bundle = bundle.with_extensions(&enabled_extensions()?);
+        if let Some(recipe) = recipe {
+            bundle = bundle.with_recipe(load_recipe(&recipe)?);
+        }
+    }
+    let json = serde_json::to_vec_pretty(&bundle)?;
+    write_export(output_path, &json)
+}
+
+fn write_export(output_path: Option<PathBuf>, contents: &[u8]) -> Result<()> {
+    if let Some(output) = output_path {
+        fs::write(&output, contents)
+            .with_context(|| format!("Failed to write to output file: {}", output.display()))?;
+        println!("Session exported to {}", output.display());
+    } else {
+        std::io::stdout().write_all(contents)?;
+    }
+    Ok(())
+}
+
+/// Restore a session exported on another machine, along with its extensions and recipe
+pub fn handle_session_import(
+    file: &Path,
+    working_dir: Option<PathBuf>,
+    with_extensions: bool,
+) -> Result<()> {
+    let bundle = SessionBundle::load(file)?;
+    let working_dir = match working_dir {
+        Some(dir) => dir,
+        None => std::env::current_dir()?,
+    };
+    let session_dir = session::ensure_session_dir()?;
+    let (id, _) = bundle.import_into(&session_dir, working_dir)?;
+    println!(
+        "Imported session `{}` with {} messages.",
+        id,
+        bundle.messages.len()
+    );
+
+    // Bundled extensions run commands from the bundle, so they are only added when asked for,
+    // and then disabled until the user turns them on
+    let new_extensions = bundle.new_extensions()?;
+    if !new_extensions.is_empty() {
+        for extension in &new_extensions {
+            println!("  {}", style(extension).dim());
+        }
+        if with_extensions {
+            let added = bundle.restore_extensions()?;
+            println!(
+                "Added extensions, disabled: {}. Check their commands above and enable them with `goose configure`.",
+                added.join(", ")
+            );
+        } else {
+            println!(
+                "The bundle has these extensions you don't have. Import again with --with-extensions to add them, disabled."
+            );
+        }
+    }
+    let missing = bundle.missing_secrets();
+    if !missing.is_empty() {
+        println!(
+            "{}",
+            style(format!(
+                "Some extensions need secrets that aren't set here: {}. Add them with `goose configure` before resuming.",
+                missing.join(", ")
+            ))
+            .yellow()
+        );
+    }
+
+    if let Some(recipe) = &bundle.recipe {
+        let recipe_path = session_dir.join(format!("{}.recipe.yaml", id));
+        fs::write(&recipe_path, serde_yaml::to_string(recipe)?)
+            .with_context(|| format!("Failed to write {}", recipe_path.display()))?;
+        println!("Saved the session's recipe to {}", recipe_path.display());
+    }
+
+    println!("Resume it with: goose session --resume --name {}", id);
+    Ok(())
+}
+
 /// Convert a list of messages to markdown format for session export
 ///
 /// This function handles the formatting of a complete session including headers,
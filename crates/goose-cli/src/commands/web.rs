@@ -99,7 +99,7 @@ pub async fn handle_web(port: u16, host: String, open: bool) -> Result<()> {
         }
     };
 
-    let model_config = goose::model::ModelConfig::new(model.clone());
+    let model_config = goose::model::ModelConfig::from_config(model.clone());
 
     // Create the agent
     let agent = Agent::new();
@@ -211,18 +211,17 @@ async fn health_check() -> Json<serde_json::Value> {
 }
 
 async fn list_sessions() -> Json<serde_json::Value> {
-    match session::list_sessions() {
+    match session::get_session_info(session::SortOrder::Descending) {
         Ok(sessions) => {
             let session_info: Vec<serde_json::Value> = sessions
                 .into_iter()
-                .map(|(name, path)| {
-                    let metadata = session::read_metadata(&path).unwrap_or_default();
+                .map(|info| {
                     serde_json::json!({
-                        "name": name,
-                        "path": path,
-                        "description": metadata.description,
-                        "message_count": metadata.message_count,
-                        "working_dir": metadata.working_dir
+                        "name": info.id,
+                        "path": info.path,
+                        "description": info.metadata.description,
+                        "message_count": info.metadata.message_count,
+                        "working_dir": info.metadata.working_dir
                     })
                 })
                 .collect();
@@ -414,6 +413,7 @@ async fn process_message_streaming(
 ) -> Result<()> {
     use futures::StreamExt;
     use goose::agents::SessionConfig;
+    use goose::config::project_config_for;
     use goose::message::MessageContent;
     use goose::session;
 
@@ -450,9 +450,11 @@ async fn process_message_streaming(
     session::persist_messages(&session_file, &messages, Some(provider.clone())).await?;
 
     // Create a session config
+    let working_dir = std::env::current_dir()?;
     let session_config = SessionConfig {
         id: session::Identifier::Path(session_file.clone()),
-        working_dir: std::env::current_dir()?,
+        project_config: project_config_for(&working_dir),
+        working_dir,
         schedule_id: None,
     };
 
@@ -589,6 +591,20 @@ async fn process_message_streaming(
                         // For now, we'll just log them
                         tracing::info!("Received MCP notification in web interface");
                     }
+                    Ok(AgentEvent::ContextCompacted(compaction)) => {
+                        let current_messages = {
+                            let mut session_msgs = session_messages.lock().await;
+                            *session_msgs = compaction.messages;
+                            session_msgs.clone()
+                        };
+                        session::persist_messages(&session_file, &current_messages, None).await?;
+                    }
+                    Ok(AgentEvent::ProviderError(_)) => {
+                        // The previous message describes the error
+                    }
+                    Ok(AgentEvent::ToolDenied(_)) => {
+                        // The tool response that follows says so
+                    }
                     Err(e) => {
                         error!("Error in message stream: {}", e);
                         let mut sender = sender.lock().await;
@@ -2,13 +2,19 @@ use console::style;
 use goose::agents::extension::ExtensionError;
 use goose::agents::Agent;
 use goose::config::{Config, ExtensionConfig, ExtensionConfigManager};
+use goose::context_mgmt::truncate::TruncationStrategyKind;
+use goose::model::ReasoningConfig;
+use goose::permission::ApprovalPolicy;
 use goose::providers::create;
 use goose::session;
 use goose::session::Identifier;
 use mcp_client::transport::Error as McpClientError;
+use serde_json::Value;
+use std::io::IsTerminal;
 use std::process;
 use std::sync::Arc;
 
+use super::events::{OutputFormat, RunReport};
 use super::output;
 use super::Session;
 
@@ -38,10 +44,65 @@ pub struct SessionBuilderConfig {
     pub debug: bool,
     /// Maximum number of consecutive identical tool calls allowed
     pub max_tool_repetitions: Option<u32>,
+    /// Truncation strategy to use instead of the configured one
+    pub truncation_strategy: Option<TruncationStrategyKind>,
+    /// JSON schema the final result of each reply must match
+    pub output_schema: Option<Value>,
+    /// Reasoning settings to use instead of the configured ones
+    pub reasoning: Option<ReasoningConfig>,
+    /// Rules answering tool confirmations instead of the user
+    pub approval_policy: Option<ApprovalPolicy>,
+    /// Maximum number of model responses before a run is stopped
+    pub max_turns: Option<usize>,
+    /// How the session reports what happens
+    pub output_format: OutputFormat,
     /// Whether this session will be used interactively (affects debugging prompts)
     pub interactive: bool,
 }
 
+/// Asks whether to trust a project configuration that sets more than tightening permissions
+/// and mode. Until it is trusted, the rest of it is ignored.
+fn confirm_project_trust(config: &Config) {
+    let Some(project_file) = config.project_path() else {
+        return;
+    };
+    let keys = match config.untrusted_project_keys() {
+        Ok(keys) if !keys.is_empty() => keys,
+        _ => return,
+    };
+
+    if !std::io::stdin().is_terminal() {
+        eprintln!(
+            "{} ignoring {} in {} until the project configuration is trusted. Run goose session in the project to review it.",
+            style("Warning:").yellow().bold(),
+            keys.join(", "),
+            project_file.display()
+        );
+        return;
+    }
+
+    println!(
+        "{} {} sets {}, which can start extensions or change where requests go:",
+        style("Project configuration").yellow().bold(),
+        project_file.display(),
+        keys.join(", ")
+    );
+    if let Ok(contents) = std::fs::read_to_string(&project_file) {
+        for line in contents.lines() {
+            println!("  {}", style(line).dim());
+        }
+    }
+    let trusted = cliclack::confirm("Trust this project configuration?")
+        .initial_value(false)
+        .interact()
+        .unwrap_or(false);
+    if !trusted {
+        println!("Only its stricter mode and permissions apply.");
+    } else if let Err(e) = config.trust_project() {
+        output::render_error(&format!("Failed to trust the project configuration: {}", e));
+    }
+}
+
 /// Offers to help debug an extension failure by creating a minimal debugging session
 async fn offer_extension_debugging_help(
     extension_name: &str,
@@ -137,8 +198,12 @@ async fn offer_extension_debugging_help(
 }
 
 pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
-    // Load config and get provider/model
-    let config = Config::global();
+    // Load config and get provider/model, with the project configuration of the working directory
+    let mut config = match std::env::current_dir() {
+        Ok(dir) => Config::global().for_dir(&dir),
+        Err(_) => Config::global().for_project(None),
+    };
+    confirm_project_trust(&config);
 
     let provider_name: String = config
         .get_param("GOOSE_PROVIDER")
@@ -147,7 +212,8 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
     let model: String = config
         .get_param("GOOSE_MODEL")
         .expect("No model configured. Run 'goose configure' first");
-    let model_config = goose::model::ModelConfig::new(model.clone());
+    let model_config = goose::model::ModelConfig::from_config(model.clone())
+        .with_reasoning(session_config.reasoning);
 
     // Create the agent
     let agent: Agent = Agent::new();
@@ -177,10 +243,21 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
         });
 
     // Configure tool monitoring if max_tool_repetitions is set
-    if let Some(max_repetitions) = session_config.max_tool_repetitions {
+    let max_tool_repetitions = session_config
+        .max_tool_repetitions
+        .or_else(|| config.get_param("GOOSE_MAX_TOOL_REPETITIONS").ok());
+    if let Some(max_repetitions) = max_tool_repetitions {
         agent.configure_tool_monitor(Some(max_repetitions)).await;
     }
 
+    if let Some(strategy) = session_config.truncation_strategy {
+        agent.set_truncation_strategy(strategy).await;
+    }
+    agent.set_output_schema(session_config.output_schema).await;
+    agent
+        .set_approval_policy(session_config.approval_policy)
+        .await;
+
     // Handle session file resolution and resuming
     let session_file = if session_config.no_session {
         // Use a temporary path that won't be written to
@@ -251,6 +328,10 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
                         "Failed to switch to original working directory: {}",
                         e
                     ));
+                } else {
+                    // Use the project configuration of the session's directory
+                    config = Config::global().for_dir(&metadata.working_dir);
+                    confirm_project_trust(&config);
                 }
             }
         }
@@ -262,7 +343,7 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
     let extensions_to_run: Vec<_> = if let Some(extensions) = session_config.extensions_override {
         extensions.into_iter().collect()
     } else {
-        ExtensionConfigManager::get_all()
+        ExtensionConfigManager::get_all_in(&config)
             .expect("should load extensions")
             .into_iter()
             .filter(|ext| ext.enabled)
@@ -310,6 +391,15 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
 
     // Create new session
     let mut session = Session::new(agent, session_file.clone(), session_config.debug);
+    // A configured turn budget only applies to runs nobody is watching
+    session.max_turns = session_config.max_turns.or_else(|| {
+        if session_config.interactive {
+            None
+        } else {
+            config.get_param("GOOSE_MAX_TURNS").ok()
+        }
+    });
+    session.report = RunReport::new(session_config.output_format);
 
     // Add extensions if provided
     for extension_str in session_config.extensions {
@@ -419,6 +509,11 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
         session.agent.extend_system_prompt(additional_prompt).await;
     }
 
+    // Instructions from the project configuration, for every session in the project
+    if let Ok(instructions) = config.get_project_param::<String>("instructions") {
+        session.agent.extend_system_prompt(instructions).await;
+    }
+
     // Only override system prompt if a system override exists
     let system_prompt_file: Option<String> = config.get_param("GOOSE_SYSTEM_PROMPT_FILE_PATH").ok();
     if let Some(ref path) = system_prompt_file {
@@ -454,6 +549,12 @@ mod tests {
             additional_system_prompt: Some("Test prompt".to_string()),
             debug: true,
             max_tool_repetitions: Some(5),
+            truncation_strategy: Some(TruncationStrategyKind::Pinned),
+            output_schema: None,
+            reasoning: None,
+            approval_policy: Some(ApprovalPolicy::default()),
+            max_turns: Some(20),
+            output_format: OutputFormat::StreamJson,
             interactive: true,
         };
 
@@ -462,6 +563,13 @@ mod tests {
         assert_eq!(config.builtins.len(), 1);
         assert!(config.debug);
         assert_eq!(config.max_tool_repetitions, Some(5));
+        assert_eq!(config.max_turns, Some(20));
+        assert!(config.approval_policy.is_some());
+        assert_eq!(config.output_format, OutputFormat::StreamJson);
+        assert_eq!(
+            config.truncation_strategy,
+            Some(TruncationStrategyKind::Pinned)
+        );
         assert!(config.interactive);
     }
 
@@ -479,6 +587,12 @@ mod tests {
         assert!(config.additional_system_prompt.is_none());
         assert!(!config.debug);
         assert!(config.max_tool_repetitions.is_none());
+        assert!(config.truncation_strategy.is_none());
+        assert!(config.output_schema.is_none());
+        assert!(config.reasoning.is_none());
+        assert!(config.approval_policy.is_none());
+        assert!(config.max_turns.is_none());
+        assert_eq!(config.output_format, OutputFormat::Text);
         assert!(!config.interactive);
     }
 
@@ -0,0 +1,81 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::process::Command;
+
+/// Read the image on the system clipboard, using the tools each platform ships with
+/// (`osascript` on macOS, `wl-paste` or `xclip` on Linux and PowerShell on Windows)
+pub fn read_image() -> Result<Vec<u8>> {
+    let bytes = read_platform_image()?;
+    if bytes.is_empty() {
+        bail!("There is no image on the clipboard");
+    }
+    Ok(bytes)
+}
+
+fn run(command: &mut Command) -> Result<Vec<u8>> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!("There is no image on the clipboard");
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(target_os = "macos")]
+fn read_platform_image() -> Result<Vec<u8>> {
+    // AppleScript prints the PNG data as «data PNGf89504E47...»
+    let output = run(Command::new("osascript").args(["-e", "the clipboard as «class PNGf»"]))?;
+    let output = String::from_utf8_lossy(&output);
+    let hex = output
+        .trim()
+        .strip_prefix("«data PNGf")
+        .and_then(|rest| rest.strip_suffix('»'))
+        .ok_or_else(|| anyhow!("There is no image on the clipboard"))?;
+    decode_hex(hex)
+}
+
+#[cfg(target_os = "linux")]
+fn read_platform_image() -> Result<Vec<u8>> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        run(Command::new("wl-paste").args(["--no-newline", "--type", "image/png"]))
+    } else {
+        run(Command::new("xclip").args(["-selection", "clipboard", "-target", "image/png", "-out"]))
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn read_platform_image() -> Result<Vec<u8>> {
+    use base64::Engine;
+
+    const SCRIPT: &str = "Add-Type -AssemblyName System.Windows.Forms; \
+        $image = [System.Windows.Forms.Clipboard]::GetImage(); \
+        if ($image) { \
+            $stream = New-Object System.IO.MemoryStream; \
+            $image.Save($stream, [System.Drawing.Imaging.ImageFormat]::Png); \
+            [Convert]::ToBase64String($stream.ToArray()) \
+        }";
+    let output = run(Command::new("powershell").args(["-NoProfile", "-STA", "-Command", SCRIPT]))?;
+    base64::engine::general_purpose::STANDARD
+        .decode(String::from_utf8_lossy(&output).trim())
+        .context("Failed to decode the clipboard image")
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn read_platform_image() -> Result<Vec<u8>> {
+    bail!("Pasting images is not supported on this platform")
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        bail!("Clipboard data is not valid hex");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| anyhow!("Clipboard data is not valid hex"))
+        })
+        .collect()
+}
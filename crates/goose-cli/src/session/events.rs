@@ -0,0 +1,499 @@
+//! Machine-readable output for `goose run --output-format json|stream-json`
+//!
+//! A run is described as a sequence of events. With `stream-json` each event is printed as a
+//! line of JSON as soon as it happens; with `json` a single `result` event is printed at the end,
+//! holding every other event under `events`. The event schema is documented in the CLI guide
+//! and versioned by [`EVENT_SCHEMA_VERSION`]; fields are only ever added within a version.
+
+use goose::agents::CompactionStrategy;
+use goose::message::{Message, MessageContent};
+use goose::session::SessionMetadata;
+use mcp_core::content::Content;
+use mcp_core::role::Role;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Version of the event schema, reported in the `start` and `result` events
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// How `goose run` reports what happens
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Formatted for people
+    #[default]
+    Text,
+    /// A single JSON object when the run ends
+    Json,
+    /// One JSON event per line as the run goes
+    StreamJson,
+}
+
+/// How a run ended, most significant first
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Success,
+    ProviderError,
+    BudgetExceeded,
+    ToolDenied,
+    Error,
+}
+
+impl RunStatus {
+    /// Exit code of the `goose run` process
+    pub fn exit_code(self) -> i32 {
+        match self {
+            RunStatus::Success => 0,
+            RunStatus::Error => 1,
+            RunStatus::BudgetExceeded => 3,
+            RunStatus::ToolDenied => 4,
+            RunStatus::ProviderError => 5,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolResultStatus {
+    Success,
+    Error,
+    Denied,
+}
+
+/// Tokens used by one model response, or by the whole run
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub total_tokens: Option<i64>,
+}
+
+impl Usage {
+    fn add(&mut self, other: &Usage) {
+        let sum = |a: Option<i64>, b: Option<i64>| match (a, b) {
+            (Some(a), Some(b)) => Some(a + b),
+            _ => a.or(b),
+        };
+        self.input_tokens = sum(self.input_tokens, other.input_tokens);
+        self.output_tokens = sum(self.output_tokens, other.output_tokens);
+        self.total_tokens = sum(self.total_tokens, other.total_tokens);
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunEvent {
+    Start {
+        version: u32,
+        session_id: Option<String>,
+        provider: String,
+        model: String,
+    },
+    /// Text written by the model
+    Text { text: String },
+    ToolRequest {
+        id: String,
+        name: String,
+        arguments: Value,
+    },
+    ToolResult {
+        id: String,
+        status: ToolResultStatus,
+        output: String,
+    },
+    /// A log message from an extension
+    Notification { message: String },
+    /// Progress reported by an extension
+    Progress {
+        progress: f64,
+        total: Option<f64>,
+        message: Option<String>,
+    },
+    /// The conversation was shortened to fit the model's context
+    ContextCompacted {
+        strategy: CompactionStrategy,
+        tokens_before: usize,
+        tokens_after: usize,
+    },
+    /// Tokens used by the model response just before this event
+    Usage {
+        #[serde(flatten)]
+        usage: Usage,
+    },
+    Result {
+        version: u32,
+        status: RunStatus,
+        exit_code: i32,
+        /// Number of model responses in the run
+        turns: usize,
+        /// Text of the last model response
+        result: Option<String>,
+        /// The result matching the output schema, when one was given
+        #[serde(skip_serializing_if = "Option::is_none")]
+        structured_output: Option<Value>,
+        error: Option<String>,
+        usage: Usage,
+        /// Every other event, with `--output-format json`
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        events: Vec<RunEvent>,
+    },
+}
+
+/// What happened during a run, and where to report it
+#[derive(Debug, Default)]
+pub struct RunReport {
+    format: OutputFormat,
+    events: Vec<RunEvent>,
+    turns: usize,
+    /// Tool calls refused by the user, a permission setting or an approval policy
+    denied_tools: HashSet<String>,
+    /// Whether the last tool results of the run include a denial
+    ended_on_denial: bool,
+    budget_exceeded: bool,
+    provider_error: Option<String>,
+    error: Option<String>,
+    usage: Usage,
+    /// The session's accumulated token counts when usage was last recorded
+    usage_seen: Option<[Option<i32>; 3]>,
+}
+
+impl RunReport {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            ..Default::default()
+        }
+    }
+
+    /// Whether events are written as JSON instead of rendered for people
+    pub fn is_json(&self) -> bool {
+        self.format != OutputFormat::Text
+    }
+
+    /// Number of model responses so far
+    pub fn turns(&self) -> usize {
+        self.turns
+    }
+
+    pub fn emit(&mut self, event: RunEvent) {
+        match self.format {
+            OutputFormat::Text => {}
+            OutputFormat::Json => self.events.push(event),
+            OutputFormat::StreamJson => print_event(&event),
+        }
+    }
+
+    /// Record a message added to the conversation
+    pub fn record_message(&mut self, message: &Message) {
+        if message.role == Role::Assistant {
+            self.turns += 1;
+        }
+
+        let mut has_tool_results = false;
+        let mut denied = false;
+        for content in &message.content {
+            match content {
+                MessageContent::Text(text) if message.role == Role::Assistant => {
+                    self.emit(RunEvent::Text {
+                        text: text.text.clone(),
+                    });
+                }
+                MessageContent::ToolRequest(request) => {
+                    if let Ok(call) = &request.tool_call {
+                        self.emit(RunEvent::ToolRequest {
+                            id: request.id.clone(),
+                            name: call.name.clone(),
+                            arguments: call.arguments.clone(),
+                        });
+                    }
+                }
+                MessageContent::ToolResponse(response) => {
+                    has_tool_results = true;
+                    let output = match &response.tool_result {
+                        Ok(contents) => contents_to_text(contents),
+                        Err(e) => e.to_string(),
+                    };
+                    let status = if self.denied_tools.contains(&response.id) {
+                        denied = true;
+                        ToolResultStatus::Denied
+                    } else if response.tool_result.is_ok() {
+                        ToolResultStatus::Success
+                    } else {
+                        ToolResultStatus::Error
+                    };
+                    self.emit(RunEvent::ToolResult {
+                        id: response.id.clone(),
+                        status,
+                        output,
+                    });
+                }
+                _ => {}
+            }
+        }
+        if has_tool_results {
+            self.ended_on_denial = denied;
+        }
+    }
+
+    /// Record that a tool call was refused, before its tool response is recorded
+    pub fn record_denial(&mut self, request_id: String) {
+        self.denied_tools.insert(request_id);
+    }
+
+    /// Remember the usage already saved in the session, so only later responses are counted
+    pub fn set_usage_baseline(&mut self, metadata: &SessionMetadata) {
+        self.usage_seen = Some(accumulated_usage(metadata));
+    }
+
+    /// Record the usage of the last model response, as saved in the session. Nothing is
+    /// recorded when the session's totals haven't moved, such as after a provider error.
+    pub fn record_usage(&mut self, metadata: &SessionMetadata) {
+        let seen = accumulated_usage(metadata);
+        if self.usage_seen == Some(seen) {
+            return;
+        }
+        self.usage_seen = Some(seen);
+
+        let usage = Usage {
+            input_tokens: metadata.input_tokens.map(i64::from),
+            output_tokens: metadata.output_tokens.map(i64::from),
+            total_tokens: metadata.total_tokens.map(i64::from),
+        };
+        self.usage.add(&usage);
+        self.emit(RunEvent::Usage { usage });
+    }
+
+    /// Record that the run was stopped for going over its budget
+    pub fn record_budget_exceeded(&mut self) {
+        self.budget_exceeded = true;
+    }
+
+    /// Record that the provider failed, which ended the reply
+    pub fn record_provider_error(&mut self, error: String) {
+        self.provider_error = Some(error);
+    }
+
+    /// Record an error that ended the reply
+    pub fn record_error(&mut self, error: String) {
+        self.error.get_or_insert(error);
+    }
+
+    /// How the run ended, given any error that stopped it
+    pub fn status(&self, error: Option<&str>) -> RunStatus {
+        if self.provider_error.is_some() {
+            RunStatus::ProviderError
+        } else if self.budget_exceeded {
+            RunStatus::BudgetExceeded
+        } else if self.ended_on_denial {
+            RunStatus::ToolDenied
+        } else if error.is_some() || self.error.is_some() {
+            RunStatus::Error
+        } else {
+            RunStatus::Success
+        }
+    }
+
+    /// Report the end of the run and return how it ended
+    pub fn finish(
+        &mut self,
+        result: Option<String>,
+        structured_output: Option<Value>,
+        error: Option<String>,
+    ) -> RunStatus {
+        let status = self.status(error.as_deref());
+        let error = self
+            .provider_error
+            .clone()
+            .or_else(|| self.error.clone())
+            .or(error);
+        let event = RunEvent::Result {
+            version: EVENT_SCHEMA_VERSION,
+            status,
+            exit_code: status.exit_code(),
+            turns: self.turns,
+            result,
+            structured_output,
+            error,
+            usage: self.usage,
+            events: std::mem::take(&mut self.events),
+        };
+        if self.is_json() {
+            print_event(&event);
+        }
+        status
+    }
+}
+
+fn accumulated_usage(metadata: &SessionMetadata) -> [Option<i32>; 3] {
+    [
+        metadata.accumulated_input_tokens,
+        metadata.accumulated_output_tokens,
+        metadata.accumulated_total_tokens,
+    ]
+}
+
+fn print_event(event: &RunEvent) {
+    match serde_json::to_string(event) {
+        Ok(line) => println!("{}", line),
+        Err(e) => tracing::error!("Failed to serialize run event: {}", e),
+    }
+}
+
+/// The parts of a tool result meant for the model, as text
+fn contents_to_text(contents: &[Content]) -> String {
+    contents
+        .iter()
+        .filter(|content| {
+            content
+                .audience()
+                .is_none_or(|audience| audience.contains(&Role::Assistant))
+        })
+        .map(|content| match content {
+            Content::Text(text) => text.text.clone(),
+            Content::Image(image) => format!("[image: {}]", image.mime_type),
+            Content::Resource(_) => "[resource]".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::handler::ToolError;
+    use mcp_core::tool::ToolCall;
+
+    fn tool_call(id: &str) -> Message {
+        Message::assistant().with_tool_request(
+            id,
+            Ok(ToolCall::new(
+                "developer__shell",
+                serde_json::json!({"command": "ls"}),
+            )),
+        )
+    }
+
+    #[test]
+    fn test_event_schema() {
+        let mut report = RunReport::new(OutputFormat::Json);
+        report.record_message(&tool_call("1"));
+        report.record_message(
+            &Message::user().with_tool_response("1", Ok(vec![Content::text("Cargo.toml")])),
+        );
+
+        let events: Vec<Value> = report
+            .events
+            .iter()
+            .map(|event| serde_json::to_value(event).unwrap())
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                serde_json::json!({
+                    "type": "tool_request",
+                    "id": "1",
+                    "name": "developer__shell",
+                    "arguments": {"command": "ls"}
+                }),
+                serde_json::json!({
+                    "type": "tool_result",
+                    "id": "1",
+                    "status": "success",
+                    "output": "Cargo.toml"
+                }),
+            ]
+        );
+
+        let usage = serde_json::to_value(RunEvent::Usage {
+            usage: Usage {
+                input_tokens: Some(10),
+                output_tokens: Some(5),
+                total_tokens: Some(15),
+            },
+        })
+        .unwrap();
+        assert_eq!(
+            usage,
+            serde_json::json!({"type": "usage", "input_tokens": 10, "output_tokens": 5, "total_tokens": 15})
+        );
+    }
+
+    #[test]
+    fn test_run_status() {
+        let mut report = RunReport::new(OutputFormat::Text);
+        report.record_message(&Message::assistant().with_text("Done"));
+        assert_eq!(report.status(None), RunStatus::Success);
+        assert_eq!(report.status(Some("no output")), RunStatus::Error);
+
+        report.record_message(&tool_call("1"));
+        report.record_denial("1".to_string());
+        report.record_message(
+            &Message::user()
+                .with_tool_response("1", Ok(vec![Content::text("The user has declined")]))
+                .with_tool_response("2", Err(ToolError::ExecutionError("failed".to_string()))),
+        );
+        assert_eq!(report.status(None), RunStatus::ToolDenied);
+        assert_eq!(report.status(Some("stopped")), RunStatus::ToolDenied);
+
+        // A denial the run moves on from does not decide how it ended, and a result that only
+        // reads like a denial is not one
+        report.record_message(&tool_call("3"));
+        report.record_message(
+            &Message::user()
+                .with_tool_response("3", Ok(vec![Content::text("The user has declined")])),
+        );
+        report.record_message(&Message::assistant().with_text("Done another way"));
+        assert_eq!(report.status(None), RunStatus::Success);
+        assert_eq!(report.denied_tools.len(), 1);
+
+        report.record_budget_exceeded();
+        assert_eq!(report.status(None), RunStatus::BudgetExceeded);
+
+        // A reply that only mentions the error text is not a provider error
+        report.record_message(
+            &Message::assistant().with_text("Ran into this error: it was a typo, now fixed."),
+        );
+        assert_eq!(report.status(None), RunStatus::BudgetExceeded);
+
+        report.record_provider_error("Server error: 503".to_string());
+        assert_eq!(report.status(None), RunStatus::ProviderError);
+        assert_eq!(report.provider_error.as_deref(), Some("Server error: 503"));
+        assert_eq!(report.turns(), 5);
+        assert_eq!(RunStatus::ProviderError.exit_code(), 5);
+    }
+
+    #[test]
+    fn test_usage_is_recorded_once_per_response() {
+        let mut metadata = SessionMetadata {
+            input_tokens: Some(100),
+            output_tokens: Some(20),
+            total_tokens: Some(120),
+            accumulated_input_tokens: Some(300),
+            accumulated_output_tokens: Some(50),
+            accumulated_total_tokens: Some(350),
+            ..Default::default()
+        };
+
+        // Usage from before the run started is not counted
+        let mut report = RunReport::new(OutputFormat::Text);
+        report.set_usage_baseline(&metadata);
+        report.record_usage(&metadata);
+        assert_eq!(report.usage, Usage::default());
+
+        metadata.accumulated_input_tokens = Some(400);
+        metadata.accumulated_output_tokens = Some(70);
+        metadata.accumulated_total_tokens = Some(470);
+        report.record_usage(&metadata);
+        // The message after a provider error leaves the session's usage as it was
+        report.record_usage(&metadata);
+        assert_eq!(
+            report.usage,
+            Usage {
+                input_tokens: Some(100),
+                output_tokens: Some(20),
+                total_tokens: Some(120),
+            }
+        );
+    }
+}
@@ -1,7 +1,10 @@
 use goose::message::{Message, MessageContent, ToolRequest, ToolResponse};
+use goose::session::SessionMetadata;
 use mcp_core::content::Content as McpContent;
 use mcp_core::resource::ResourceContents;
 use mcp_core::role::Role;
+use mcp_core::tool::ToolCall;
+use mcp_core::ToolResult;
 use serde_json::Value;
 
 const MAX_STRING_LENGTH_MD_EXPORT: usize = 4096; // Generous limit for export
@@ -335,6 +338,13 @@ pub fn message_to_markdown(message: &Message, export_all_content: bool) -> Strin
                     image.data.chars().take(30).collect::<String>()
                 ));
             }
+            MessageContent::Document(document) => {
+                md.push_str(&format!(
+                    "**Document:** `{}` (type: {})\n\n",
+                    document.name.as_deref().unwrap_or("attachment"),
+                    document.mime_type
+                ));
+            }
             MessageContent::Thinking(thinking) => {
                 md.push_str("**Thinking:**\n");
                 md.push_str("> ");
@@ -355,6 +365,219 @@ pub fn message_to_markdown(message: &Message, export_all_content: bool) -> Strin
     md.trim_end_matches("\n").to_string()
 }
 
+const HTML_STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; max-width: 56rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328; line-height: 1.5; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 1.5rem; }
+.meta { color: #59636e; }
+.message { border: 1px solid #d0d7de; border-radius: 6px; padding: 0.75rem 1rem; margin-bottom: 1rem; }
+.message.user { background: #f6f8fa; }
+.role { font-weight: 600; margin-bottom: 0.5rem; }
+.text { white-space: pre-wrap; overflow-wrap: anywhere; }
+details { border: 1px solid #d0d7de; border-radius: 6px; padding: 0.25rem 0.75rem; margin: 0.5rem 0; background: #ffffff; }
+summary { cursor: pointer; color: #59636e; }
+pre { background: #f6f8fa; padding: 0.5rem; overflow-x: auto; white-space: pre-wrap; overflow-wrap: anywhere; }
+.error { color: #d1242f; }
+.note { color: #59636e; font-style: italic; }
+img { max-width: 100%; }
+"#;
+
+/// Escape text for use in HTML element content or attribute values
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn image_to_html(data: &str, mime_type: &str) -> String {
+    format!(
+        "<img src=\"data:{};base64,{}\" alt=\"Image\">\n",
+        escape_html(mime_type),
+        escape_html(data)
+    )
+}
+
+fn tool_call_to_html(tool_call: &ToolResult<ToolCall>) -> String {
+    match tool_call {
+        Ok(call) => format!(
+            "<details class=\"tool-call\"><summary>Tool call: <code>{}</code></summary>\n<pre>{}</pre>\n</details>\n",
+            escape_html(&call.name),
+            escape_html(
+                &serde_json::to_string_pretty(&call.arguments).unwrap_or_default()
+            )
+        ),
+        Err(e) => format!(
+            "<details class=\"tool-call\" open><summary class=\"error\">Invalid tool call</summary>\n<pre>{}</pre>\n</details>\n",
+            escape_html(&e.to_string())
+        ),
+    }
+}
+
+fn tool_response_to_html(resp: &ToolResponse) -> String {
+    let contents = match &resp.tool_result {
+        Ok(contents) => contents,
+        Err(e) => {
+            return format!(
+                "<details class=\"tool-result\"><summary class=\"error\">Tool error</summary>\n<pre>{}</pre>\n</details>\n",
+                escape_html(&e.to_string())
+            );
+        }
+    };
+
+    let mut html = String::from("<details class=\"tool-result\"><summary>Tool result</summary>\n");
+    if contents.is_empty() {
+        html.push_str("<p class=\"note\">No output</p>\n");
+    }
+    for content in contents {
+        match content {
+            McpContent::Text(text) => {
+                html.push_str(&format!("<pre>{}</pre>\n", escape_html(&text.text)));
+            }
+            McpContent::Image(image) => {
+                html.push_str(&image_to_html(&image.data, &image.mime_type));
+            }
+            McpContent::Resource(resource) => match &resource.resource {
+                ResourceContents::TextResourceContents { uri, text, .. } => {
+                    html.push_str(&format!(
+                        "<p><code>{}</code></p>\n<pre>{}</pre>\n",
+                        escape_html(uri),
+                        escape_html(text)
+                    ));
+                }
+                ResourceContents::BlobResourceContents {
+                    uri,
+                    mime_type,
+                    blob,
+                } => {
+                    html.push_str(&format!(
+                        "<p class=\"note\">Binary file <code>{}</code> ({}, {} bytes)</p>\n",
+                        escape_html(uri),
+                        escape_html(mime_type.as_deref().unwrap_or("unknown")),
+                        blob.len()
+                    ));
+                }
+            },
+        }
+    }
+    html.push_str("</details>\n");
+    html
+}
+
+/// Render the contents of a message as HTML, with tool calls and thinking collapsed
+pub fn message_to_html(message: &Message) -> String {
+    let mut html = String::new();
+    for content in &message.content {
+        match content {
+            MessageContent::Text(text) => {
+                html.push_str(&format!(
+                    "<div class=\"text\">{}</div>\n",
+                    escape_html(&text.text)
+                ));
+            }
+            MessageContent::Image(image) => {
+                html.push_str(&image_to_html(&image.data, &image.mime_type));
+            }
+            MessageContent::Document(document) => {
+                let name = document.name.as_deref().unwrap_or("attachment");
+                match &document.text {
+                    Some(text) => html.push_str(&format!(
+                        "<details class=\"document\"><summary>Document: <code>{}</code> ({})</summary>\n<pre>{}</pre>\n</details>\n",
+                        escape_html(name),
+                        escape_html(&document.mime_type),
+                        escape_html(text)
+                    )),
+                    None => html.push_str(&format!(
+                        "<p class=\"note\">Document: <code>{}</code> ({})</p>\n",
+                        escape_html(name),
+                        escape_html(&document.mime_type)
+                    )),
+                }
+            }
+            MessageContent::ToolRequest(req) => html.push_str(&tool_call_to_html(&req.tool_call)),
+            MessageContent::FrontendToolRequest(req) => {
+                html.push_str(&tool_call_to_html(&req.tool_call))
+            }
+            MessageContent::ToolResponse(resp) => html.push_str(&tool_response_to_html(resp)),
+            MessageContent::Thinking(thinking) => {
+                html.push_str(&format!(
+                    "<details class=\"thinking\"><summary>Thinking</summary>\n<div class=\"text\">{}</div>\n</details>\n",
+                    escape_html(&thinking.thinking)
+                ));
+            }
+            MessageContent::RedactedThinking(_) => {
+                html.push_str("<p class=\"note\">Thinking was redacted</p>\n");
+            }
+            MessageContent::ContextLengthExceeded(notice) => {
+                html.push_str(&format!(
+                    "<p class=\"note\">{}</p>\n",
+                    escape_html(&notice.msg)
+                ));
+            }
+            MessageContent::SummarizationRequested(notice) => {
+                html.push_str(&format!(
+                    "<p class=\"note\">{}</p>\n",
+                    escape_html(&notice.msg)
+                ));
+            }
+            MessageContent::ToolConfirmationRequest(_) => {}
+        }
+    }
+    html
+}
+
+/// Render a whole session as a standalone HTML page
+///
+/// Styles and images are inlined, so the page can be shared as a single file.
+pub fn session_to_html(
+    session_name: &str,
+    metadata: &SessionMetadata,
+    messages: &[Message],
+) -> String {
+    let title = escape_html(session_name);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<header>\n<h1>{}</h1>\n",
+        title, HTML_STYLE, title
+    );
+    if !metadata.description.is_empty() {
+        html.push_str(&format!("<p>{}</p>\n", escape_html(&metadata.description)));
+    }
+    html.push_str(&format!(
+        "<p class=\"meta\"><code>{}</code> &middot; {} messages</p>\n</header>\n<main>\n",
+        escape_html(&metadata.working_dir.display().to_string()),
+        messages.len()
+    ));
+
+    for message in messages {
+        // Tool results come back as user messages, but belong with the call before them
+        let is_only_tool_response = message
+            .content
+            .iter()
+            .all(|content| matches!(content, MessageContent::ToolResponse(_)));
+        let (class, role) = match message.role {
+            Role::User if is_only_tool_response => ("message tool", None),
+            Role::User => ("message user", Some("User")),
+            Role::Assistant => ("message assistant", Some("Assistant")),
+        };
+        html.push_str(&format!("<section class=\"{}\">\n", class));
+        if let Some(role) = role {
+            html.push_str(&format!("<div class=\"role\">{}</div>\n", role));
+        }
+        html.push_str(&message_to_html(message));
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</main>\n</body>\n</html>\n");
+    html
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1092,4 +1315,60 @@ found 0 vulnerabilities"#;
         assert!(response_result.contains("added 57 packages"));
         assert!(response_result.contains("found 0 vulnerabilities"));
     }
+
+    #[test]
+    fn test_message_to_html_escapes_text() {
+        let message = Message::user().with_text("<script>alert('hi')</script> & more");
+        let html = message_to_html(&message);
+        assert_eq!(
+            html,
+            "<div class=\"text\">&lt;script&gt;alert(&#39;hi&#39;)&lt;/script&gt; &amp; more</div>\n"
+        );
+    }
+
+    #[test]
+    fn test_message_to_html_collapses_tool_calls() {
+        let call = ToolCall::new("developer__shell", json!({"command": "ls <dir>"}));
+        let request = Message::assistant().with_tool_request("1", Ok(call));
+        let html = message_to_html(&request);
+        assert!(html.starts_with("<details class=\"tool-call\">"));
+        assert!(html.contains("<code>developer__shell</code>"));
+        assert!(html.contains("ls &lt;dir&gt;"));
+
+        let response = Message::user().with_tool_response(
+            "1",
+            Ok(vec![McpContent::Text(TextContent {
+                text: "a.txt\nb.txt".to_string(),
+                annotations: None,
+            })]),
+        );
+        let html = message_to_html(&response);
+        assert!(html.starts_with("<details class=\"tool-result\">"));
+        assert!(html.contains("<pre>a.txt\nb.txt</pre>"));
+    }
+
+    #[test]
+    fn test_session_to_html() {
+        let metadata = SessionMetadata {
+            description: "Tidy <the> repo".to_string(),
+            ..SessionMetadata::default()
+        };
+        let messages = vec![
+            Message::user().with_text("List the files"),
+            Message::assistant()
+                .with_thinking("The shell can do this", "sig")
+                .with_tool_request("1", Ok(ToolCall::new("developer__shell", json!({})))),
+            Message::user().with_tool_response("1", Ok(vec![])),
+            Message::assistant().with_text("There are no files"),
+        ];
+
+        let html = session_to_html("tidy", &metadata, &messages);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>tidy</title>"));
+        assert!(html.contains("<p>Tidy &lt;the&gt; repo</p>"));
+        assert!(html.contains("<details class=\"thinking\"><summary>Thinking</summary>"));
+        assert_eq!(html.matches("<div class=\"role\">User</div>").count(), 1);
+        assert_eq!(html.matches("<section class=\"message tool\">").count(), 1);
+        assert!(html.trim_end().ends_with("</html>"));
+    }
 }
@@ -3,6 +3,7 @@ use anyhow::Result;
 use rustyline::Editor;
 use shlex;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 #[derive(Debug)]
 pub enum InputResult {
@@ -19,6 +20,10 @@ pub enum InputResult {
     EndPlan,
     Recipe(Option<String>),
     Summarize,
+    Rewind(usize),
+    Edit(EditCommandOptions),
+    Regenerate(ModelOverride),
+    Paste,
 }
 
 #[derive(Debug)]
@@ -33,6 +38,26 @@ pub struct PlanCommandOptions {
     pub message_text: String,
 }
 
+/// A different provider or model to use for a rewritten turn
+#[derive(Debug, Default, PartialEq)]
+pub struct ModelOverride {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+}
+
+impl ModelOverride {
+    pub fn is_empty(&self) -> bool {
+        self.provider.is_none() && self.model.is_none()
+    }
+}
+
+#[derive(Debug)]
+pub struct EditCommandOptions {
+    /// Which prompt to edit, counting back from the latest (1)
+    pub turns: usize,
+    pub model: ModelOverride,
+}
+
 pub fn get_input(
     editor: &mut Editor<GooseCompleter, rustyline::history::DefaultHistory>,
 ) -> Result<InputResult> {
@@ -79,6 +104,19 @@ pub fn get_input(
     }
 }
 
+/// Let the user edit `initial` in place. Returns `None` if they cleared it or pressed Ctrl+C.
+pub fn get_edited_text(
+    editor: &mut Editor<GooseCompleter, rustyline::history::DefaultHistory>,
+    initial: &str,
+) -> Result<Option<String>> {
+    let prompt = format!("{} ", console::style("(edit)>").cyan().bold());
+    match editor.readline_with_initial(&prompt, (initial, "")) {
+        Ok(text) if !text.trim().is_empty() => Ok(Some(text.trim().to_string())),
+        Ok(_) | Err(rustyline::error::ReadlineError::Interrupted) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
 fn handle_slash_command(input: &str) -> Option<InputResult> {
     let input = input.trim();
 
@@ -93,6 +131,10 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
     const CMD_ENDPLAN: &str = "/endplan";
     const CMD_RECIPE: &str = "/recipe";
     const CMD_SUMMARIZE: &str = "/summarize";
+    const CMD_REWIND: &str = "/rewind";
+    const CMD_EDIT: &str = "/edit";
+    const CMD_RETRY: &str = "/retry";
+    const CMD_PASTE: &str = "/paste";
 
     match input {
         "/exit" | "/quit" => Some(InputResult::Exit),
@@ -136,10 +178,53 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
         s if s == CMD_ENDPLAN => Some(InputResult::EndPlan),
         s if s.starts_with(CMD_RECIPE) => parse_recipe_command(s),
         s if s == CMD_SUMMARIZE => Some(InputResult::Summarize),
+        s if s == CMD_REWIND || s.starts_with("/rewind ") => {
+            parse_rewind_command(s[CMD_REWIND.len()..].trim())
+        }
+        s if s == CMD_EDIT || s.starts_with("/edit ") => {
+            parse_edit_command(s[CMD_EDIT.len()..].trim())
+        }
+        s if s == CMD_RETRY || s.starts_with("/retry ") => {
+            parse_retry_command(s[CMD_RETRY.len()..].trim())
+        }
+        s if s == CMD_PASTE => Some(InputResult::Paste),
         _ => None,
     }
 }
 
+/// Find the files referenced as `@path` in a message. Words that start with `@` but don't
+/// name an existing file (such as email addresses or `@mentions`) are left alone.
+pub fn attachment_paths(text: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for word in text.split_whitespace() {
+        let Some(raw) = word.strip_prefix('@') else {
+            continue;
+        };
+        if raw.is_empty() || raw.starts_with('@') {
+            continue;
+        }
+
+        // Allow trailing punctuation, as in "compare @a.png and @b.png."
+        let candidates = [
+            raw,
+            raw.trim_end_matches([',', '.', ';', ':', '!', '?', ')']),
+        ];
+        let found = candidates.iter().find_map(|candidate| {
+            let path = match candidate.strip_prefix("~/") {
+                Some(rest) => etcetera::home_dir().ok()?.join(rest),
+                None => PathBuf::from(candidate),
+            };
+            path.is_file().then_some(path)
+        });
+        if let Some(path) = found {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths
+}
+
 fn parse_recipe_command(s: &str) -> Option<InputResult> {
     const CMD_RECIPE: &str = "/recipe";
 
@@ -165,6 +250,76 @@ fn parse_recipe_command(s: &str) -> Option<InputResult> {
     Some(InputResult::Recipe(Some(filepath.to_string())))
 }
 
+fn parse_rewind_command(args: &str) -> Option<InputResult> {
+    if args.is_empty() {
+        return Some(InputResult::Rewind(1));
+    }
+
+    match args.parse::<usize>() {
+        Ok(turns) if turns > 0 => Some(InputResult::Rewind(turns)),
+        _ => {
+            println!(
+                "{}",
+                console::style("Usage: /rewind [N] where N is a positive number of turns").red()
+            );
+            Some(InputResult::Retry)
+        }
+    }
+}
+
+/// Split off `--provider` and `--model` flags, returning the remaining arguments
+fn parse_model_override(args: &str) -> Option<(ModelOverride, Vec<String>)> {
+    let mut parts = shlex::split(args)?.into_iter();
+    let mut model = ModelOverride::default();
+    let mut rest = Vec::new();
+    while let Some(part) = parts.next() {
+        match part.as_str() {
+            "--provider" => model.provider = Some(parts.next()?),
+            "--model" => model.model = Some(parts.next()?),
+            _ => rest.push(part),
+        }
+    }
+    Some((model, rest))
+}
+
+fn parse_edit_command(args: &str) -> Option<InputResult> {
+    let parsed = parse_model_override(args).and_then(|(model, rest)| match rest.as_slice() {
+        [] => Some((1, model)),
+        [turns] => match turns.parse::<usize>() {
+            Ok(turns) if turns > 0 => Some((turns, model)),
+            _ => None,
+        },
+        _ => None,
+    });
+
+    match parsed {
+        Some((turns, model)) => Some(InputResult::Edit(EditCommandOptions { turns, model })),
+        None => {
+            println!(
+                "{}",
+                console::style(
+                    "Usage: /edit [N] [--provider <name>] [--model <name>] where N counts prompts back from the latest"
+                )
+                .red()
+            );
+            Some(InputResult::Retry)
+        }
+    }
+}
+
+fn parse_retry_command(args: &str) -> Option<InputResult> {
+    match parse_model_override(args) {
+        Some((model, rest)) if rest.is_empty() => Some(InputResult::Regenerate(model)),
+        _ => {
+            println!(
+                "{}",
+                console::style("Usage: /retry [--provider <name>] [--model <name>]").red()
+            );
+            Some(InputResult::Retry)
+        }
+    }
+}
+
 fn parse_prompts_command(args: &str) -> Option<InputResult> {
     let parts: Vec<String> = shlex::split(args).unwrap_or_default();
 
@@ -245,8 +400,17 @@ fn print_help() {
 /recipe [filepath] - Generate a recipe from the current conversation and save it to the specified filepath (must end with .yaml).
                        If no filepath is provided, it will be saved to ./recipe.yaml.
 /summarize - Summarize the current conversation to reduce context length while preserving key information.
+/rewind [N] - Undo the last N turns (default 1), restoring files in the working directory and trimming the conversation.
+/edit [N] [--provider <name>] [--model <name>] - Edit your Nth most recent message (default 1) and continue the conversation from there.
+/retry [--provider <name>] [--model <name>] - Regenerate the reply to your last message, optionally with a different model.
+                       /edit and /retry keep the replaced conversation as a branch of the session (see `goose session list --tree`).
+                       They don't touch files in the working directory; use /rewind for that.
+/paste - Attach the image on the clipboard to your next message
 /? or /help - Display this help message
 
+Attachments:
+@<path> - Attach an image, PDF or text file to your message, e.g. \"what is wrong in @screenshot.png?\"
+
 Navigation:
 Ctrl+C - Interrupt goose (resets the interaction to before the interrupted user request)
 Ctrl+J - Add a newline
@@ -489,4 +653,104 @@ mod tests {
         let result = handle_slash_command("  /summarize  ");
         assert!(matches!(result, Some(InputResult::Summarize)));
     }
+
+    #[test]
+    fn test_rewind_command() {
+        assert!(matches!(
+            handle_slash_command("/rewind"),
+            Some(InputResult::Rewind(1))
+        ));
+        assert!(matches!(
+            handle_slash_command("  /rewind 3  "),
+            Some(InputResult::Rewind(3))
+        ));
+        assert!(matches!(
+            handle_slash_command("/rewind 0"),
+            Some(InputResult::Retry)
+        ));
+        assert!(matches!(
+            handle_slash_command("/rewind many"),
+            Some(InputResult::Retry)
+        ));
+        assert!(handle_slash_command("/rewinding").is_none());
+    }
+
+    #[test]
+    fn test_edit_command() {
+        match handle_slash_command("/edit") {
+            Some(InputResult::Edit(options)) => {
+                assert_eq!(options.turns, 1);
+                assert!(options.model.is_empty());
+            }
+            other => panic!("Expected an edit, got {:?}", other),
+        }
+        match handle_slash_command("/edit 2 --model gpt-4o --provider openai") {
+            Some(InputResult::Edit(options)) => {
+                assert_eq!(options.turns, 2);
+                assert_eq!(options.model.provider.as_deref(), Some("openai"));
+                assert_eq!(options.model.model.as_deref(), Some("gpt-4o"));
+            }
+            other => panic!("Expected an edit, got {:?}", other),
+        }
+        assert!(matches!(
+            handle_slash_command("/edit 0"),
+            Some(InputResult::Retry)
+        ));
+        assert!(matches!(
+            handle_slash_command("/edit --model"),
+            Some(InputResult::Retry)
+        ));
+        assert!(handle_slash_command("/editor").is_none());
+    }
+
+    #[test]
+    fn test_retry_command() {
+        match handle_slash_command("/retry") {
+            Some(InputResult::Regenerate(model)) => assert!(model.is_empty()),
+            other => panic!("Expected a retry, got {:?}", other),
+        }
+        match handle_slash_command("/retry --model claude-3-7-sonnet-latest") {
+            Some(InputResult::Regenerate(model)) => {
+                assert_eq!(model.model.as_deref(), Some("claude-3-7-sonnet-latest"));
+                assert_eq!(model.provider, None);
+            }
+            other => panic!("Expected a retry, got {:?}", other),
+        }
+        assert!(matches!(
+            handle_slash_command("/retry 2"),
+            Some(InputResult::Retry)
+        ));
+    }
+
+    #[test]
+    fn test_paste_command() {
+        assert!(matches!(
+            handle_slash_command("/paste"),
+            Some(InputResult::Paste)
+        ));
+        assert!(handle_slash_command("/pastebin").is_none());
+    }
+
+    #[test]
+    fn test_attachment_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("shot.png");
+        let report = dir.path().join("report.pdf");
+        std::fs::write(&image, b"png").unwrap();
+        std::fs::write(&report, b"pdf").unwrap();
+
+        let text = format!(
+            "compare @{} with @{}, then email @someone and @@{}",
+            image.display(),
+            report.display(),
+            image.display()
+        );
+        assert_eq!(attachment_paths(&text), vec![image.clone(), report]);
+
+        // Repeated references attach the file once
+        let text = format!("@{} and again @{}.", image.display(), image.display());
+        assert_eq!(attachment_paths(&text), vec![image]);
+
+        assert!(attachment_paths("no attachments here").is_empty());
+    }
 }
@@ -1,27 +1,33 @@
 mod builder;
+mod clipboard;
 mod completion;
+mod events;
 mod export;
 mod input;
 mod output;
 mod prompt;
 mod thinking;
+mod tui;
 
-pub use self::export::message_to_markdown;
+pub use self::export::{message_to_markdown, session_to_html};
 pub use builder::{build_session, SessionBuilderConfig};
 use console::Color;
+pub use events::{OutputFormat, RunStatus};
 use goose::agents::AgentEvent;
 use goose::permission::permission_confirmation::PrincipalType;
 use goose::permission::Permission;
 use goose::permission::PermissionConfirmation;
 use goose::providers::base::Provider;
 pub use goose::session::Identifier;
+pub use output::set_quiet;
 
 use anyhow::{Context, Result};
 use completion::GooseCompleter;
 use etcetera::{choose_app_strategy, AppStrategy};
+use events::RunEvent;
 use goose::agents::extension::{Envs, ExtensionConfig};
 use goose::agents::{Agent, SessionConfig};
-use goose::config::Config;
+use goose::config::{project_config_for, Config};
 use goose::message::{Message, MessageContent};
 use goose::session;
 use input::InputResult;
@@ -51,6 +57,14 @@ pub struct Session {
     completion_cache: Arc<std::sync::RwLock<CompletionCache>>,
     debug: bool, // New field for debug mode
     run_mode: RunMode,
+    // Attachments, such as pasted images, waiting to be sent with the next message
+    pending_attachments: Vec<MessageContent>,
+    // Maximum number of model responses before a run is stopped
+    max_turns: Option<usize>,
+    // What happened during a run, for its exit code and JSON output
+    report: events::RunReport,
+    // The full-screen UI, while the session runs in it
+    ui: Option<tui::UiHandle>,
 }
 
 // Cache structure for completion data
@@ -106,6 +120,19 @@ pub async fn classify_planner_response(
     }
 }
 
+/// Resolves when the user interrupts a reply, with Ctrl+C or from the full-screen UI
+async fn interrupted(ui: Option<&tui::UiHandle>) {
+    match ui {
+        Some(ui) => tokio::select! {
+            _ = ui.cancelled() => {}
+            _ = tokio::signal::ctrl_c() => {}
+        },
+        None => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
 impl Session {
     pub fn new(agent: Agent, session_file: PathBuf, debug: bool) -> Self {
         let messages = match session::read_messages(&session_file) {
@@ -123,9 +150,125 @@ impl Session {
             completion_cache: Arc::new(std::sync::RwLock::new(CompletionCache::new())),
             debug,
             run_mode: RunMode::Normal,
+            pending_attachments: Vec::new(),
+            max_turns: None,
+            report: events::RunReport::default(),
+            ui: None,
+        }
+    }
+
+    /// Build a user message from the text, attaching any `@path` files it references and
+    /// anything pasted since the last message
+    fn user_message(&mut self, text: &str) -> Message {
+        let mut message = Message::user().with_text(text);
+        for path in input::attachment_paths(text) {
+            match goose::attachments::load_attachment(&path) {
+                Ok(content) => message = message.with_content(content),
+                Err(e) => eprintln!(
+                    "{}",
+                    console::style(format!(
+                        "Warning: Failed to attach {}: {}",
+                        path.display(),
+                        e
+                    ))
+                    .yellow()
+                ),
+            }
+        }
+        for content in self.pending_attachments.drain(..) {
+            message = message.with_content(content);
+        }
+        message
+    }
+
+    /// Use a different provider or model from now on
+    ///
+    /// A model without a provider stays with the current provider; a provider without a model
+    /// uses that provider's default model. Reasoning settings carry over.
+    async fn switch_model(&mut self, model: &input::ModelOverride) -> Result<()> {
+        if model.is_empty() {
+            return Ok(());
+        }
+        let config = Config::global();
+        let provider_name = match &model.provider {
+            Some(provider) => provider.clone(),
+            None => config.get_param("GOOSE_PROVIDER")?,
+        };
+        let model_name = match &model.model {
+            Some(model) => model.clone(),
+            None => goose::providers::providers()
+                .into_iter()
+                .find(|metadata| metadata.name == provider_name)
+                .map(|metadata| metadata.default_model)
+                .ok_or_else(|| anyhow::anyhow!("Unknown provider {}", provider_name))?,
+        };
+
+        let current = self.agent.provider().await?.get_model_config();
+        let model_config = goose::model::ModelConfig::new(model_name.clone())
+            .with_thinking_budget(current.thinking_budget)
+            .with_reasoning_effort(current.reasoning_effort);
+        let provider = goose::providers::create(&provider_name, model_config)?;
+        self.agent.update_provider(provider).await?;
+
+        self.render_notice(&format!(
+            "Switched to {} with {} for the rest of this session.",
+            model_name, provider_name
+        ));
+        Ok(())
+    }
+
+    /// Show a note about the session, in the full-screen UI when it is open
+    fn render_notice(&self, text: &str) {
+        match &self.ui {
+            Some(ui) => ui.send(tui::UiEvent::Notice(text.to_string())),
+            None => println!("{}", console::style(text).dim()),
         }
     }
 
+    /// Show an error, in the full-screen UI when it is open
+    fn render_failure(&self, text: &str) {
+        match &self.ui {
+            Some(ui) => ui.send(tui::UiEvent::Error(text.to_string())),
+            None => output::render_error(text),
+        }
+    }
+
+    /// Cut the conversation back to its first `keep` messages, add `prompt` if given and run
+    /// the agent from there. The replaced messages are kept as a branch of the session.
+    async fn rewrite_and_continue(&mut self, keep: usize, prompt: Option<Message>) -> Result<()> {
+        if self.session_file.is_file() {
+            match session::rewrite_session(&self.session_file, keep) {
+                Ok(rewrite) => {
+                    self.messages = rewrite.messages;
+                    if let Some(branch_id) = rewrite.branch_id {
+                        self.render_notice(&format!(
+                            "The replaced conversation was saved as session {}.",
+                            branch_id
+                        ));
+                    }
+                }
+                Err(e) => {
+                    self.render_failure(&format!("Failed to rewrite the conversation: {}", e));
+                    return Ok(());
+                }
+            }
+        } else {
+            // Sessions that aren't saved have no history to keep
+            self.messages.truncate(keep);
+        }
+
+        if let Some(prompt) = prompt {
+            self.messages.push(prompt);
+        }
+        let provider = self.agent.provider().await?;
+        session::persist_messages(&self.session_file, &self.messages, Some(provider)).await?;
+
+        output::show_thinking();
+        self.process_agent_response(true).await?;
+        output::hide_thinking();
+        Ok(())
+    }
+
     /// Helper function to summarize context messages
     async fn summarize_context_messages(
         messages: &mut Vec<Message>,
@@ -302,7 +445,8 @@ impl Session {
 
     /// Process a single message and get the response
     async fn process_message(&mut self, message: String) -> Result<()> {
-        self.messages.push(Message::user().with_text(&message));
+        let user_message = self.user_message(&message);
+        self.messages.push(user_message);
         // Get the provider from the agent for description generation
         let provider = self.agent.provider().await?;
 
@@ -393,7 +537,8 @@ impl Session {
                         RunMode::Normal => {
                             save_history(&mut editor);
 
-                            self.messages.push(Message::user().with_text(&content));
+                            let user_message = self.user_message(&content);
+                            self.messages.push(user_message);
 
                             // Track the current directory and last instruction in projects.json
                             let session_id = self
@@ -426,7 +571,7 @@ impl Session {
                         }
                         RunMode::Plan => {
                             let mut plan_messages = self.messages.clone();
-                            plan_messages.push(Message::user().with_text(&content));
+                            plan_messages.push(self.user_message(&content));
                             let reasoner = get_reasoner()?;
                             self.plan_with_reasoner_model(plan_messages, reasoner)
                                 .await?;
@@ -612,6 +757,109 @@ impl Session {
                         println!("{}", console::style("Summarization cancelled.").yellow());
                     }
 
+                    continue;
+                }
+                InputResult::Rewind(turns) => {
+                    save_history(&mut editor);
+
+                    match session::rewind_session(&self.session_file, turns).await {
+                        Ok(messages) => {
+                            self.messages = messages;
+                            println!(
+                                "{}",
+                                console::style(format!(
+                                    "Rewound {} turn(s). Files restored and conversation trimmed to {} message(s).",
+                                    turns,
+                                    self.messages.len()
+                                ))
+                                .green()
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!(
+                                "{}",
+                                console::style(format!("Failed to rewind: {}", e)).red()
+                            );
+                        }
+                    }
+
+                    continue;
+                }
+                InputResult::Edit(options) => {
+                    save_history(&mut editor);
+
+                    let Some(index) = session::rewrite::prompt_index(&self.messages, options.turns)
+                    else {
+                        eprintln!(
+                            "{}",
+                            console::style(format!(
+                                "There is no message {} back to edit.",
+                                options.turns
+                            ))
+                            .red()
+                        );
+                        continue;
+                    };
+                    let original = self.messages[index].clone();
+                    let original_text = original.as_concat_text();
+                    let text = match input::get_edited_text(&mut editor, &original_text)? {
+                        Some(text) if text != original_text || !options.model.is_empty() => text,
+                        _ => {
+                            println!("{}", console::style("Edit cancelled.").yellow());
+                            continue;
+                        }
+                    };
+
+                    if let Err(e) = self.switch_model(&options.model).await {
+                        output::render_error(&format!("Failed to switch model: {}", e));
+                        continue;
+                    }
+                    let edited = session::rewrite::edit_prompt(&original, &text);
+                    self.rewrite_and_continue(index, Some(edited)).await?;
+                }
+                InputResult::Regenerate(model) => {
+                    save_history(&mut editor);
+
+                    let Some(index) = session::rewrite::prompt_index(&self.messages, 1) else {
+                        eprintln!("{}", console::style("There is no reply to retry.").red());
+                        continue;
+                    };
+                    if let Err(e) = self.switch_model(&model).await {
+                        output::render_error(&format!("Failed to switch model: {}", e));
+                        continue;
+                    }
+                    self.rewrite_and_continue(index + 1, None).await?;
+                }
+                InputResult::Paste => {
+                    save_history(&mut editor);
+
+                    let attachment = clipboard::read_image().and_then(|bytes| {
+                        goose::attachments::attachment_from_bytes(
+                            &bytes,
+                            Some("clipboard.png"),
+                            None,
+                        )
+                    });
+                    match attachment {
+                        Ok(content) => {
+                            self.pending_attachments.push(content);
+                            println!(
+                                "{}",
+                                console::style(format!(
+                                    "Attached the clipboard image to your next message ({} pending).",
+                                    self.pending_attachments.len()
+                                ))
+                                .green()
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!(
+                                "{}",
+                                console::style(format!("Failed to paste: {}", e)).red()
+                            );
+                        }
+                    }
+
                     continue;
                 }
             }
@@ -706,22 +954,75 @@ impl Session {
         self.process_message(message).await
     }
 
+    /// Report the start of a headless run
+    pub async fn start_run(&mut self) -> Result<()> {
+        let provider = self.agent.provider().await?;
+        let provider_name: String = Config::global()
+            .get_param("GOOSE_PROVIDER")
+            .unwrap_or_default();
+        let no_session = matches!(self.session_file.to_str(), Some("/dev/null") | Some("NUL"));
+        let session_id = self
+            .session_file
+            .file_stem()
+            .filter(|_| !no_session)
+            .map(|stem| stem.to_string_lossy().to_string());
+
+        self.report.emit(RunEvent::Start {
+            version: events::EVENT_SCHEMA_VERSION,
+            session_id,
+            provider: provider_name,
+            model: provider.get_model_config().model_name,
+        });
+        if self.session_file.is_file() {
+            if let Ok(metadata) = session::read_metadata(&self.session_file) {
+                self.report.set_usage_baseline(&metadata);
+            }
+        }
+        Ok(())
+    }
+
+    /// Report the end of a headless run, given what [`Session::headless`] returned, and
+    /// return how it ended. With an output schema, a run without a result is an error.
+    pub async fn finish_run(&mut self, outcome: Result<()>, output_schema: bool) -> RunStatus {
+        let mut error = outcome.err().map(|e| e.to_string());
+        let structured_output = if output_schema {
+            self.final_output().await
+        } else {
+            None
+        };
+        if output_schema && structured_output.is_none() && error.is_none() {
+            error = Some("The run did not produce output matching the schema".to_string());
+        }
+        let result = self
+            .messages
+            .iter()
+            .rev()
+            .find(|message| message.role == mcp_core::role::Role::Assistant)
+            .map(|message| message.as_concat_text())
+            .filter(|text| !text.is_empty());
+
+        self.report.finish(result, structured_output, error)
+    }
+
     async fn process_agent_response(&mut self, interactive: bool) -> Result<()> {
         let session_id = session::Identifier::Path(self.session_file.clone());
+        let working_dir =
+            std::env::current_dir().expect("failed to get current session working directory");
+        let session_config = SessionConfig {
+            id: session_id.clone(),
+            project_config: project_config_for(&working_dir),
+            working_dir,
+            schedule_id: None,
+        };
         let mut stream = self
             .agent
-            .reply(
-                &self.messages,
-                Some(SessionConfig {
-                    id: session_id.clone(),
-                    working_dir: std::env::current_dir()
-                        .expect("failed to get current session working directory"),
-                    schedule_id: None,
-                }),
-            )
+            .reply(&self.messages, Some(session_config.clone()))
             .await?;
 
         let mut progress_bars = output::McpSpinners::new();
+        // The full-screen UI shows the conversation and asks for confirmations itself
+        let ui = self.ui.clone();
+        let interactive = interactive && ui.is_none();
 
         use futures::StreamExt;
         loop {
@@ -736,26 +1037,37 @@ impl Session {
                                 // Format the confirmation prompt
                                 let prompt = "Goose would like to call the above tool, do you allow?".to_string();
 
-                                // Get confirmation from user
-                                let permission_result = cliclack::select(prompt)
-                                    .item(Permission::AllowOnce, "Allow", "Allow the tool call once")
-                                    .item(Permission::AlwaysAllow, "Always Allow", "Always allow the tool call")
-                                    .item(Permission::DenyOnce, "Deny", "Deny the tool call")
-                                    .item(Permission::Cancel, "Cancel", "Cancel the AI response and tool call")
-                                    .interact();
-
-                                let permission = match permission_result {
-                                    Ok(p) => p, // If Ok, use the selected permission
-                                    Err(e) => {
-                                        // Check if the error is an interruption (Ctrl+C/Cmd+C, Escape)
-                                        if e.kind() == std::io::ErrorKind::Interrupted {
-                                            Permission::Cancel // If interrupted, set permission to Cancel
-                                        } else {
-                                            return Err(e.into()); // Otherwise, convert and propagate the original error
+                                let permission = if let Some(ui) = &ui {
+                                    ui.confirm(confirmation.clone()).await
+                                } else if self.report.is_json() {
+                                    // Nobody is there to answer when a program reads the output
+                                    Permission::DenyOnce
+                                } else {
+                                    // Get confirmation from user
+                                    let permission_result = cliclack::select(prompt)
+                                        .item(Permission::AllowOnce, "Allow", "Allow the tool call once")
+                                        .item(Permission::AlwaysAllow, "Always Allow", "Always allow the tool call")
+                                        .item(Permission::DenyOnce, "Deny", "Deny the tool call")
+                                        .item(Permission::Cancel, "Cancel", "Cancel the AI response and tool call")
+                                        .interact();
+
+                                    match permission_result {
+                                        Ok(p) => p, // If Ok, use the selected permission
+                                        Err(e) => {
+                                            // Check if the error is an interruption (Ctrl+C/Cmd+C, Escape)
+                                            if e.kind() == std::io::ErrorKind::Interrupted {
+                                                Permission::Cancel // If interrupted, set permission to Cancel
+                                            } else {
+                                                return Err(e.into()); // Otherwise, convert and propagate the original error
+                                            }
                                         }
                                     }
                                 };
 
+                                if !matches!(permission, Permission::AllowOnce | Permission::AlwaysAllow) {
+                                    self.report.record_denial(confirmation.id.clone());
+                                }
+
                                 if permission == Permission::Cancel {
                                     output::render_text("Tool call cancelled. Returning to chat...", Some(Color::Yellow), true);
 
@@ -764,6 +1076,7 @@ impl Session {
                                         confirmation.id.clone(),
                                         Err(ToolError::ExecutionError("Tool call cancelled by user".to_string()))
                                     ));
+                                    self.report.record_message(&response_message);
                                     self.messages.push(response_message);
                                     session::persist_messages(&self.session_file, &self.messages, None).await?;
 
@@ -845,15 +1158,7 @@ impl Session {
                                 // Restart the stream after handling ContextLengthExceeded
                                 stream = self
                                     .agent
-                                    .reply(
-                                        &self.messages,
-                                        Some(SessionConfig {
-                                            id: session_id.clone(),
-                                            working_dir: std::env::current_dir()
-                                                .expect("failed to get current session working directory"),
-                                            schedule_id: None,
-                                        }),
-                                    )
+                                    .reply(&self.messages, Some(session_config.clone()))
                                     .await?;
                             }
                             // otherwise we have a model/tool to render
@@ -867,6 +1172,22 @@ impl Session {
                                 let _ = progress_bars.hide();
                                 output::render_message(&message, self.debug);
                                 if interactive {output::show_thinking()};
+                                if let Some(ui) = &ui {
+                                    ui.send(tui::UiEvent::Message(message.clone()));
+                                }
+
+                                self.report.record_message(&message);
+                                if message.role == mcp_core::role::Role::Assistant && self.session_file.is_file() {
+                                    if let Ok(metadata) = session::read_metadata(&self.session_file) {
+                                        self.report.record_usage(&metadata);
+                                    }
+                                    self.send_ui_status().await;
+                                }
+                                if message.is_tool_call() && self.max_turns.is_some_and(|max| self.report.turns() >= max) {
+                                    drop(stream);
+                                    self.stop_for_budget().await?;
+                                    break;
+                                }
                             }
                         }
                         Some(Ok(AgentEvent::McpNotification((_id, message)))) => {
@@ -891,7 +1212,11 @@ impl Session {
                                                     v.to_string()
                                             },
                                         };
-                                        if interactive {
+                                        if let Some(ui) = &ui {
+                                            ui.send(tui::UiEvent::Activity(message));
+                                        } else if self.report.is_json() {
+                                            self.report.emit(RunEvent::Notification { message });
+                                        } else if interactive {
                                             output::set_thinking_message(&message);
                                         } else {
                                             progress_bars.log(&message);
@@ -904,7 +1229,21 @@ impl Session {
                                         let total = o
                                             .get("total")
                                             .and_then(|v| v.as_f64());
-                                        if let (Some(progress), Some(token)) = (progress, token) {
+                                        if let (Some(ui), Some(progress)) = (&ui, progress) {
+                                            let activity = match total {
+                                                Some(total) => format!("{} ({:.0}/{:.0})", message.unwrap_or("Working"), progress, total),
+                                                None => format!("{} ({:.0})", message.unwrap_or("Working"), progress),
+                                            };
+                                            ui.send(tui::UiEvent::Activity(activity));
+                                        } else if self.report.is_json() {
+                                            if let Some(progress) = progress {
+                                                self.report.emit(RunEvent::Progress {
+                                                    progress,
+                                                    total,
+                                                    message: message.map(str::to_string),
+                                                });
+                                            }
+                                        } else if let (Some(progress), Some(token)) = (progress, token) {
                                             progress_bars.update(
                                                 token.as_str(),
                                                 progress,
@@ -917,27 +1256,62 @@ impl Session {
                                 }
                             }
                         }
+                        Some(Ok(AgentEvent::ContextCompacted(compaction))) => {
+                            if interactive {output::hide_thinking()};
+                            let _ = progress_bars.hide();
+                            output::render_context_compacted(&compaction);
+                            if interactive {output::show_thinking()};
+                            self.report.emit(RunEvent::ContextCompacted {
+                                strategy: compaction.strategy,
+                                tokens_before: compaction.tokens_before,
+                                tokens_after: compaction.tokens_after,
+                            });
+
+                            self.messages = compaction.messages;
+                            session::persist_messages(&self.session_file, &self.messages, None).await?;
+                        }
+                        Some(Ok(AgentEvent::ProviderError(error))) => {
+                            // The previous message describes the error for the user
+                            self.report.record_provider_error(error);
+                        }
+                        Some(Ok(AgentEvent::ToolDenied(id))) => {
+                            self.report.record_denial(id);
+                        }
                         Some(Err(e)) => {
-                            eprintln!("Error: {}", e);
+                            self.report.record_error(e.to_string());
                             drop(stream);
+                            if let Some(ui) = &ui {
+                                ui.send(tui::UiEvent::Error(format!("Error: {}", e)));
+                                if let Err(e) = self.handle_interrupted_messages(false).await {
+                                    ui.send(tui::UiEvent::Error(format!("Error handling interruption: {}", e)));
+                                }
+                                ui.send(tui::UiEvent::Notice(
+                                    "The conversation was cut back to the most recent user message. \
+                                    Depending on the error you may be able to continue.".to_string(),
+                                ));
+                                break;
+                            }
+                            eprintln!("Error: {}", e);
                             if let Err(e) = self.handle_interrupted_messages(false).await {
                                 eprintln!("Error handling interruption: {}", e);
                             }
-                            output::render_error(
-                                "The error above was an exception we were not able to handle.\n\
-                                These errors are often related to connection or authentication\n\
-                                We've removed the conversation up to the most recent user message\n\
-                                - depending on the error you may be able to continue",
-                            );
+                            if !self.report.is_json() {
+                                output::render_error(
+                                    "The error above was an exception we were not able to handle.\n\
+                                    These errors are often related to connection or authentication\n\
+                                    We've removed the conversation up to the most recent user message\n\
+                                    - depending on the error you may be able to continue",
+                                );
+                            }
                             break;
                         }
                         None => break,
                     }
                 }
-                _ = tokio::signal::ctrl_c() => {
+                _ = interrupted(ui.as_ref()) => {
                     drop(stream);
                     if let Err(e) = self.handle_interrupted_messages(true).await {
-                        eprintln!("Error handling interruption: {}", e);
+                        self.render_failure(&format!("Error handling interruption: {}", e));
                     }
                     break;
                 }
@@ -947,6 +1321,43 @@ impl Session {
         Ok(())
     }
 
+    /// Stop a run that used up its turns, answering the tool calls it won't make
+    async fn stop_for_budget(&mut self) -> Result<()> {
+        let reason = format!(
+            "Not run: the run reached its limit of {} turns",
+            self.max_turns.unwrap_or_default()
+        );
+        let tool_ids: Vec<String> = self
+            .messages
+            .last()
+            .filter(|message| message.role == mcp_core::role::Role::Assistant)
+            .map_or(Vec::new(), |message| {
+                message
+                    .content
+                    .iter()
+                    .filter_map(|content| match content {
+                        MessageContent::ToolRequest(request) => Some(request.id.clone()),
+                        _ => None,
+                    })
+                    .collect()
+            });
+
+        if !tool_ids.is_empty() {
+            let mut response = Message::user();
+            for id in tool_ids {
+                response =
+                    response.with_tool_response(id, Err(ToolError::ExecutionError(reason.clone())));
+            }
+            self.report.record_message(&response);
+            self.messages.push(response);
+            session::persist_messages(&self.session_file, &self.messages, None).await?;
+        }
+
+        self.report.record_budget_exceeded();
+        output::render_text(&reason, Some(Color::Yellow), true);
+        Ok(())
+    }
+
     async fn handle_interrupted_messages(&mut self, interrupt: bool) -> Result<()> {
         // First, get any tool requests from the last message if it exists
         let tool_requests = self
@@ -1082,6 +1493,11 @@ impl Session {
         cache.last_updated = Instant::now();
     }
 
+    /// The validated result of the last reply, when the session has an output schema
+    pub async fn final_output(&self) -> Option<Value> {
+        self.agent.final_output().await
+    }
+
     pub fn message_history(&self) -> Vec<Message> {
         self.messages.clone()
     }
@@ -1277,7 +1693,7 @@ fn get_reasoner() -> Result<Arc<dyn Provider>, anyhow::Error> {
             .expect("No model configured. Run 'goose configure' first")
     };
 
-    let model_config = ModelConfig::new(model);
+    let model_config = ModelConfig::from_config(model);
     let reasoner = create(&provider, model_config)?;
 
     Ok(reasoner)
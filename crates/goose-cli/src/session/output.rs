@@ -1,5 +1,6 @@
 use bat::WrappingMode;
 use console::{style, Color};
+use goose::agents::{CompactionStrategy, ContextCompaction};
 use goose::config::Config;
 use goose::message::{Message, MessageContent, ToolRequest, ToolResponse};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
@@ -10,6 +11,7 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::Error;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -110,6 +112,9 @@ thread_local! {
 }
 
 pub fn show_thinking() {
+    if is_quiet() {
+        return;
+    }
     THINKING.with(|t| t.borrow_mut().show());
 }
 
@@ -125,7 +130,21 @@ pub fn set_thinking_message(s: &String) {
     });
 }
 
+// When set, the conversation is not printed so that stdout only holds the final result
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
 pub fn render_message(message: &Message, debug: bool) {
+    if is_quiet() {
+        return;
+    }
     let theme = get_theme();
 
     for content in &message.content {
@@ -136,6 +155,13 @@ pub fn render_message(message: &Message, debug: bool) {
             MessageContent::Image(image) => {
                 println!("Image: [data: {}, type: {}]", image.data, image.mime_type);
             }
+            MessageContent::Document(document) => {
+                println!(
+                    "Document: [name: {}, type: {}]",
+                    document.name.as_deref().unwrap_or("attachment"),
+                    document.mime_type
+                );
+            }
             MessageContent::Thinking(thinking) => {
                 if std::env::var("GOOSE_CLI_SHOW_THINKING").is_ok() {
                     println!("\n{}", style("Thinking:").dim().italic());
@@ -160,6 +186,9 @@ pub fn render_text(text: &str, color: Option<Color>, dim: bool) {
 }
 
 pub fn render_text_no_newlines(text: &str, color: Option<Color>, dim: bool) {
+    if is_quiet() {
+        return;
+    }
     let mut styled_text = style(text);
     if dim {
         styled_text = styled_text.dim();
@@ -195,6 +224,25 @@ pub fn render_exit_plan_mode() {
     println!("\n{}\n", style("Exiting plan mode.").green().bold());
 }
 
+pub fn render_context_compacted(compaction: &ContextCompaction) {
+    if is_quiet() {
+        return;
+    }
+    let how = match compaction.strategy {
+        CompactionStrategy::Summarize => "summarized",
+        CompactionStrategy::Truncate => "truncated",
+    };
+    println!(
+        "\n{}\n",
+        style(format!(
+            "Context {} to stay within the model's limit ({} → {} tokens)",
+            how, compaction.tokens_before, compaction.tokens_after
+        ))
+        .yellow()
+        .dim()
+    );
+}
+
 pub fn goose_mode_message(text: &str) {
     println!("\n{}", style(text).yellow(),);
 }
@@ -552,6 +600,9 @@ pub fn display_session_info(
     session_file: &Path,
     provider_instance: Option<&Arc<dyn goose::providers::base::Provider>>,
 ) {
+    if is_quiet() {
+        return;
+    }
     let start_session_msg = if resume {
         "resuming session |"
     } else if session_file.to_str() == Some("/dev/null") || session_file.to_str() == Some("NUL") {
@@ -0,0 +1,516 @@
+//! State of the full-screen UI, and what the keys do to it
+
+use goose::message::{Message, MessageContent, ToolConfirmationRequest};
+use goose::permission::Permission;
+use mcp_core::content::Content;
+use mcp_core::role::Role;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+use super::{Status, UiCommand, UiEvent};
+
+const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Answers offered by the approval dialog, in the order they are shown
+pub const CONFIRM_OPTIONS: &[(Permission, &str)] = &[
+    (Permission::AllowOnce, "Allow"),
+    (Permission::AlwaysAllow, "Always allow"),
+    (Permission::DenyOnce, "Deny"),
+    (Permission::Cancel, "Cancel"),
+];
+
+pub const HELP: &str =
+    "Enter send · Alt+Enter new line · Esc/Ctrl+C cancel reply · Ctrl+R retry · \
+    Ctrl+B rewind · Tab select tool call · Ctrl+O expand it · Ctrl+T expand all · \
+    PgUp/PgDn scroll · F2 side panel · Ctrl+D quit";
+
+/// One block of the transcript
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entry {
+    User(String),
+    Assistant(String),
+    Tool(ToolEntry),
+    Notice(String),
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolEntry {
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
+    /// Output of the call once it finished, and whether it succeeded
+    pub result: Option<(bool, String)>,
+    pub expanded: bool,
+}
+
+/// A confirmation waiting for an answer
+pub struct PendingConfirm {
+    pub request: ToolConfirmationRequest,
+    pub selected: usize,
+    reply: oneshot::Sender<Permission>,
+}
+
+/// What a key asks of the rest of the UI
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    Command(UiCommand),
+    /// Stop the reply in progress
+    Cancel,
+}
+
+#[derive(Default)]
+pub struct App {
+    pub entries: Vec<Entry>,
+    pub input: String,
+    /// Cursor position in `input`, in characters
+    pub cursor: usize,
+    /// Lines scrolled up from the end of the transcript
+    pub scroll: usize,
+    /// Index in `entries` of the selected tool call
+    pub selected: Option<usize>,
+    pub busy: bool,
+    /// What the agent is doing, reported by extensions
+    pub activity: Option<String>,
+    pub status: Status,
+    pub confirm: Option<PendingConfirm>,
+    pub hide_side_panel: bool,
+    /// Height of the transcript when it was last drawn, for paging
+    pub page_height: usize,
+    ticks: usize,
+}
+
+impl App {
+    pub fn apply(&mut self, event: UiEvent) {
+        match event {
+            UiEvent::Transcript(messages) => {
+                let expanded: Vec<String> = self
+                    .tools()
+                    .filter(|tool| tool.expanded)
+                    .map(|tool| tool.id.clone())
+                    .collect();
+                self.entries.clear();
+                self.selected = None;
+                for message in &messages {
+                    self.add_message(message);
+                }
+                for entry in &mut self.entries {
+                    if let Entry::Tool(tool) = entry {
+                        tool.expanded = expanded.contains(&tool.id);
+                    }
+                }
+                self.scroll = 0;
+            }
+            UiEvent::Message(message) => self.add_message(&message),
+            UiEvent::Busy(busy) => {
+                self.busy = busy;
+                self.activity = None;
+            }
+            UiEvent::Activity(activity) => self.activity = Some(activity),
+            UiEvent::Notice(text) => self.entries.push(Entry::Notice(text)),
+            UiEvent::Error(text) => self.entries.push(Entry::Error(text)),
+            UiEvent::Status(status) => self.status = status,
+            UiEvent::Confirm { request, reply } => {
+                self.confirm = Some(PendingConfirm {
+                    request,
+                    selected: 0,
+                    reply,
+                });
+            }
+        }
+    }
+
+    /// Add the parts of a message worth showing to the transcript
+    fn add_message(&mut self, message: &Message) {
+        for content in &message.content {
+            match content {
+                MessageContent::Text(text) if text.text.trim().is_empty() => {}
+                MessageContent::Text(text) => match message.role {
+                    Role::User => self.entries.push(Entry::User(text.text.clone())),
+                    Role::Assistant => self.entries.push(Entry::Assistant(text.text.clone())),
+                },
+                MessageContent::Image(image) => {
+                    self.entries
+                        .push(Entry::Notice(format!("[image: {}]", image.mime_type)));
+                }
+                MessageContent::Document(document) => {
+                    self.entries.push(Entry::Notice(format!(
+                        "[document: {}]",
+                        document.name.as_deref().unwrap_or("attachment")
+                    )));
+                }
+                MessageContent::ToolRequest(request) => {
+                    let (name, arguments) = match &request.tool_call {
+                        Ok(call) => (call.name.clone(), call.arguments.clone()),
+                        Err(e) => (
+                            "invalid tool call".to_string(),
+                            Value::String(e.to_string()),
+                        ),
+                    };
+                    self.entries.push(Entry::Tool(ToolEntry {
+                        id: request.id.clone(),
+                        name,
+                        arguments,
+                        result: None,
+                        expanded: false,
+                    }));
+                }
+                MessageContent::ToolResponse(response) => {
+                    let result = match &response.tool_result {
+                        Ok(contents) => (true, contents_to_text(contents)),
+                        Err(e) => (false, e.to_string()),
+                    };
+                    let tool = self.entries.iter_mut().rev().find_map(|entry| match entry {
+                        Entry::Tool(tool) if tool.id == response.id => Some(tool),
+                        _ => None,
+                    });
+                    if let Some(tool) = tool {
+                        tool.result = Some(result);
+                    }
+                }
+                MessageContent::ContextLengthExceeded(_) => {
+                    self.entries
+                        .push(Entry::Notice("The context window is full".to_string()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn tools(&self) -> impl Iterator<Item = &ToolEntry> {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::Tool(tool) => Some(tool),
+            _ => None,
+        })
+    }
+
+    pub fn tick(&mut self) {
+        self.ticks = self.ticks.wrapping_add(1);
+    }
+
+    pub fn spinner(&self) -> char {
+        SPINNER[self.ticks % SPINNER.len()]
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if self.confirm.is_some() {
+            self.handle_confirm_key(key);
+            return None;
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Esc if self.busy => return Some(Action::Cancel),
+            KeyCode::Esc => self.selected = None,
+            KeyCode::Char('c') if ctrl => {
+                if self.busy {
+                    return Some(Action::Cancel);
+                }
+                if self.input.is_empty() {
+                    return Some(Action::Command(UiCommand::Quit));
+                }
+                self.input.clear();
+                self.cursor = 0;
+            }
+            KeyCode::Char('d') if ctrl && !self.busy && self.input.is_empty() => {
+                return Some(Action::Command(UiCommand::Quit));
+            }
+            KeyCode::Char('r') if ctrl && !self.busy => {
+                return Some(Action::Command(UiCommand::Retry));
+            }
+            KeyCode::Char('b') if ctrl && !self.busy => {
+                return Some(Action::Command(UiCommand::Rewind(1)));
+            }
+            KeyCode::Char('o') if ctrl => self.toggle_selected(),
+            KeyCode::Char('t') if ctrl => self.toggle_all(),
+            KeyCode::Char('j') if ctrl => self.insert('\n'),
+            KeyCode::Enter if alt => self.insert('\n'),
+            KeyCode::Enter => return self.submit(),
+            KeyCode::Tab => self.select_tool(true),
+            KeyCode::BackTab => self.select_tool(false),
+            KeyCode::F(2) => self.hide_side_panel = !self.hide_side_panel,
+            KeyCode::PageUp => self.scroll_by(self.page_height.max(1) as isize),
+            KeyCode::PageDown => self.scroll_by(-(self.page_height.max(1) as isize)),
+            KeyCode::Up if ctrl => self.scroll_by(1),
+            KeyCode::Down if ctrl => self.scroll_by(-1),
+            KeyCode::End if ctrl => self.scroll = 0,
+            KeyCode::Char(c) if !ctrl => self.insert(c),
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.input.remove(self.byte_index());
+            }
+            KeyCode::Delete if self.cursor < self.input.chars().count() => {
+                self.input.remove(self.byte_index());
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.input.chars().count()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.input.chars().count(),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_confirm_key(&mut self, key: KeyEvent) {
+        let Some(confirm) = self.confirm.as_mut() else {
+            return;
+        };
+        let answer = match key.code {
+            KeyCode::Char('y') => Some(Permission::AllowOnce),
+            KeyCode::Char('a') => Some(Permission::AlwaysAllow),
+            KeyCode::Char('n') => Some(Permission::DenyOnce),
+            KeyCode::Esc => Some(Permission::Cancel),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Permission::Cancel)
+            }
+            KeyCode::Enter => Some(CONFIRM_OPTIONS[confirm.selected].0.clone()),
+            KeyCode::Left | KeyCode::Up | KeyCode::BackTab => {
+                confirm.selected =
+                    (confirm.selected + CONFIRM_OPTIONS.len() - 1) % CONFIRM_OPTIONS.len();
+                None
+            }
+            KeyCode::Right | KeyCode::Down | KeyCode::Tab => {
+                confirm.selected = (confirm.selected + 1) % CONFIRM_OPTIONS.len();
+                None
+            }
+            _ => None,
+        };
+        if let Some(answer) = answer {
+            if let Some(confirm) = self.confirm.take() {
+                let _ = confirm.reply.send(answer);
+            }
+        }
+    }
+
+    /// Send the input, or run it when it is one of the few commands the UI knows
+    fn submit(&mut self) -> Option<Action> {
+        let text = self.input.trim().to_string();
+        if text.is_empty() || self.busy {
+            return None;
+        }
+        self.input.clear();
+        self.cursor = 0;
+        self.scroll = 0;
+
+        let mut words = text.split_whitespace();
+        let command = match words.next() {
+            Some("/exit") | Some("/quit") => UiCommand::Quit,
+            Some("/retry") => UiCommand::Retry,
+            Some("/rewind") => match words.next().map(str::parse::<usize>) {
+                None => UiCommand::Rewind(1),
+                Some(Ok(turns)) if turns > 0 => UiCommand::Rewind(turns),
+                Some(_) => {
+                    self.entries.push(Entry::Error(
+                        "/rewind takes a number of turns, e.g. /rewind 2".to_string(),
+                    ));
+                    return None;
+                }
+            },
+            Some("/help") | Some("/?") => {
+                self.entries.push(Entry::Notice(HELP.to_string()));
+                return None;
+            }
+            Some(command) if command.starts_with('/') => {
+                self.entries.push(Entry::Error(format!(
+                    "{} isn't available in the full-screen UI, run goose session without --tui to use it",
+                    command
+                )));
+                return None;
+            }
+            _ => {
+                self.entries.push(Entry::User(text.clone()));
+                UiCommand::Submit(text)
+            }
+        };
+        Some(Action::Command(command))
+    }
+
+    fn insert(&mut self, c: char) {
+        let index = self.byte_index();
+        self.input.insert(index, c);
+        self.cursor += 1;
+    }
+
+    fn byte_index(&self) -> usize {
+        self.input
+            .char_indices()
+            .nth(self.cursor)
+            .map_or(self.input.len(), |(index, _)| index)
+    }
+
+    fn scroll_by(&mut self, lines: isize) {
+        self.scroll = self.scroll.saturating_add_signed(lines);
+    }
+
+    /// Move the selection to the next or previous tool call
+    fn select_tool(&mut self, forward: bool) {
+        let tools: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| matches!(entry, Entry::Tool(_)))
+            .map(|(index, _)| index)
+            .collect();
+        if tools.is_empty() {
+            return;
+        }
+        let position = self
+            .selected
+            .and_then(|selected| tools.iter().position(|&index| index == selected));
+        let next = match (position, forward) {
+            (None, true) => 0,
+            (None, false) => tools.len() - 1,
+            (Some(position), true) => (position + 1) % tools.len(),
+            (Some(position), false) => (position + tools.len() - 1) % tools.len(),
+        };
+        self.selected = Some(tools[next]);
+    }
+
+    /// Expand or collapse the selected tool call, or the latest one when none is selected
+    fn toggle_selected(&mut self) {
+        let index = self.selected.or_else(|| {
+            self.entries
+                .iter()
+                .rposition(|entry| matches!(entry, Entry::Tool(_)))
+        });
+        if let Some(Entry::Tool(tool)) = index.and_then(|index| self.entries.get_mut(index)) {
+            tool.expanded = !tool.expanded;
+        }
+    }
+
+    fn toggle_all(&mut self) {
+        let expand = !self.tools().all(|tool| tool.expanded);
+        for entry in &mut self.entries {
+            if let Entry::Tool(tool) = entry {
+                tool.expanded = expand;
+            }
+        }
+    }
+}
+
+/// The parts of a tool result meant for people, as text
+fn contents_to_text(contents: &[Content]) -> String {
+    contents
+        .iter()
+        .filter(|content| {
+            content
+                .audience()
+                .is_none_or(|audience| audience.contains(&Role::User))
+        })
+        .map(|content| match content {
+            Content::Text(text) => text.text.clone(),
+            Content::Image(image) => format!("[image: {}]", image.mime_type),
+            Content::Resource(_) => "[resource]".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::tool::ToolCall;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            app.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn test_transcript_pairs_tool_results() {
+        let mut app = App::default();
+        app.apply(UiEvent::Transcript(vec![
+            Message::user().with_text("List the files"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    serde_json::json!({"command": "ls"}),
+                )),
+            ),
+            Message::user().with_tool_response("1", Ok(vec![Content::text("Cargo.toml")])),
+            Message::assistant().with_text("There is one file"),
+        ]));
+
+        assert_eq!(app.entries.len(), 3);
+        assert_eq!(app.entries[0], Entry::User("List the files".to_string()));
+        let Entry::Tool(tool) = &app.entries[1] else {
+            panic!("expected a tool call");
+        };
+        assert_eq!(tool.name, "developer__shell");
+        assert_eq!(tool.result, Some((true, "Cargo.toml".to_string())));
+        assert!(!tool.expanded);
+
+        app.handle_key(key(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(app.selected, Some(1));
+        app.handle_key(key(KeyCode::Char('o'), KeyModifiers::CONTROL));
+        assert!(matches!(&app.entries[1], Entry::Tool(tool) if tool.expanded));
+    }
+
+    #[test]
+    fn test_keys() {
+        let mut app = App::default();
+        type_text(&mut app, "héllo");
+        app.handle_key(key(KeyCode::Left, KeyModifiers::NONE));
+        app.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE));
+        assert_eq!(app.input, "hélo");
+
+        assert_eq!(
+            app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(Action::Command(UiCommand::Submit("hélo".to_string())))
+        );
+        assert!(app.input.is_empty());
+
+        type_text(&mut app, "/rewind 2");
+        assert_eq!(
+            app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(Action::Command(UiCommand::Rewind(2)))
+        );
+
+        app.busy = true;
+        assert_eq!(
+            app.handle_key(key(KeyCode::Char('r'), KeyModifiers::CONTROL)),
+            None
+        );
+        assert_eq!(
+            app.handle_key(key(KeyCode::Esc, KeyModifiers::NONE)),
+            Some(Action::Cancel)
+        );
+        app.busy = false;
+        assert_eq!(
+            app.handle_key(key(KeyCode::Char('d'), KeyModifiers::CONTROL)),
+            Some(Action::Command(UiCommand::Quit))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_confirm_dialog() {
+        let mut app = App::default();
+        let (reply, answer) = oneshot::channel();
+        app.apply(UiEvent::Confirm {
+            request: ToolConfirmationRequest {
+                id: "1".to_string(),
+                tool_name: "developer__shell".to_string(),
+                arguments: serde_json::json!({"command": "rm -rf target"}),
+                prompt: None,
+            },
+            reply,
+        });
+
+        // Keys go to the dialog while it is open
+        type_text(&mut app, "x");
+        assert!(app.input.is_empty());
+        app.handle_key(key(KeyCode::Right, KeyModifiers::NONE));
+        app.handle_key(key(KeyCode::Right, KeyModifiers::NONE));
+        app.handle_key(key(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(app.confirm.is_none());
+        assert_eq!(answer.await.unwrap(), Permission::DenyOnce);
+    }
+}
@@ -0,0 +1,335 @@
+//! Full-screen terminal UI for `goose session --tui`
+//!
+//! The UI runs on its own task and only draws: the [`Session`] still runs the conversation.
+//! The session sends [`UiEvent`]s as the conversation changes and reads the [`UiCommand`]s the
+//! user gives, so replies, confirmations, retries and rewinds go through the same code as the
+//! line-based prompt.
+
+mod app;
+mod view;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use goose::config::Config;
+use goose::message::{Message, ToolConfirmationRequest};
+use goose::permission::Permission;
+use goose::session;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::DefaultTerminal;
+use tokio::sync::{mpsc, oneshot, Notify};
+use tokio::task::JoinHandle;
+
+use super::{output, Session};
+use app::{Action, App};
+
+/// What the session tells the UI
+pub enum UiEvent {
+    /// The whole conversation, after it was loaded or rewritten
+    Transcript(Vec<Message>),
+    /// A message added to the conversation
+    Message(Message),
+    /// Whether the agent is working on a reply
+    Busy(bool),
+    /// What the agent is doing, as reported by extensions
+    Activity(String),
+    Notice(String),
+    Error(String),
+    Status(Status),
+    /// A tool call waiting for the user's approval
+    Confirm {
+        request: ToolConfirmationRequest,
+        reply: oneshot::Sender<Permission>,
+    },
+}
+
+/// What the user asks the session to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UiCommand {
+    Submit(String),
+    /// Regenerate the last reply
+    Retry,
+    /// Undo this many turns, restoring files
+    Rewind(usize),
+    Quit,
+}
+
+/// The model, extensions and usage shown around the conversation
+#[derive(Debug, Clone, Default)]
+pub struct Status {
+    pub provider: String,
+    pub model: String,
+    pub session: String,
+    pub context_limit: usize,
+    /// Tokens in the context after the last reply
+    pub context_tokens: Option<i32>,
+    /// Tokens used by the whole session
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
+    /// Cost of the session in USD, when token prices are configured
+    pub cost: Option<f64>,
+    /// Extensions and the names of their tools
+    pub extensions: Vec<(String, Vec<String>)>,
+}
+
+/// Lets the session talk to the UI
+#[derive(Clone)]
+pub struct UiHandle {
+    events: mpsc::UnboundedSender<UiEvent>,
+    cancel: Arc<Notify>,
+}
+
+impl UiHandle {
+    pub fn send(&self, event: UiEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Ask the user whether a tool call may run
+    pub async fn confirm(&self, request: ToolConfirmationRequest) -> Permission {
+        let (reply, answer) = oneshot::channel();
+        self.send(UiEvent::Confirm { request, reply });
+        answer.await.unwrap_or(Permission::Cancel)
+    }
+
+    /// Resolves when the user cancels the reply in progress
+    pub async fn cancelled(&self) {
+        self.cancel.notified().await
+    }
+}
+
+/// The running UI
+pub struct Tui {
+    handle: UiHandle,
+    commands: mpsc::UnboundedReceiver<UiCommand>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl Tui {
+    /// Take over the terminal and start drawing
+    pub fn start() -> Result<Self> {
+        let terminal = ratatui::try_init()?;
+        let (events, event_rx) = mpsc::unbounded_channel();
+        let (command_tx, commands) = mpsc::unbounded_channel();
+        let cancel = Arc::new(Notify::new());
+        let task = tokio::spawn(run(terminal, event_rx, command_tx, cancel.clone()));
+        Ok(Self {
+            handle: UiHandle { events, cancel },
+            commands,
+            task,
+        })
+    }
+
+    pub fn handle(&self) -> UiHandle {
+        self.handle.clone()
+    }
+
+    pub async fn next_command(&mut self) -> Option<UiCommand> {
+        self.commands.recv().await
+    }
+
+    /// Give the terminal back. Every [`UiHandle`] must be dropped first.
+    pub async fn close(self) -> Result<()> {
+        drop(self.handle);
+        self.task.await?
+    }
+}
+
+async fn run(
+    mut terminal: DefaultTerminal,
+    mut events: mpsc::UnboundedReceiver<UiEvent>,
+    commands: mpsc::UnboundedSender<UiCommand>,
+    cancel: Arc<Notify>,
+) -> Result<()> {
+    let mut app = App::default();
+    let mut terminal_events = read_terminal_events();
+    let mut reading = true;
+    let mut tick = tokio::time::interval(Duration::from_millis(100));
+
+    let result = loop {
+        if let Err(e) = terminal.draw(|frame| view::draw(frame, &mut app)) {
+            break Err(e.into());
+        }
+        tokio::select! {
+            event = events.recv() => match event {
+                Some(event) => app.apply(event),
+                // The session is done with the UI
+                None => break Ok(()),
+            },
+            event = terminal_events.recv(), if reading => match event {
+                Some(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    match app.handle_key(key) {
+                        Some(Action::Command(command)) => {
+                            // Don't take another command until the session is done with this one
+                            if command != UiCommand::Quit {
+                                app.apply(UiEvent::Busy(true));
+                            }
+                            let _ = commands.send(command);
+                        }
+                        Some(Action::Cancel) => cancel.notify_waiters(),
+                        None => {}
+                    }
+                }
+                Some(_) => {}
+                None => {
+                    // Nothing more can be typed, so end the session
+                    reading = false;
+                    let _ = commands.send(UiCommand::Quit);
+                }
+            },
+            _ = tick.tick() => app.tick(),
+        }
+    };
+
+    ratatui::restore();
+    result
+}
+
+/// Read keys and resizes on a thread of their own, since crossterm blocks
+fn read_terminal_events() -> mpsc::UnboundedReceiver<Event> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while !tx.is_closed() {
+            match event::poll(Duration::from_millis(100)) {
+                Ok(true) => match event::read() {
+                    Ok(event) => {
+                        if tx.send(event).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                },
+                Ok(false) => {}
+                Err(_) => break,
+            }
+        }
+    });
+    rx
+}
+
+/// Cost in USD of the given tokens, from `GOOSE_INPUT_TOKEN_COST` and `GOOSE_OUTPUT_TOKEN_COST`
+/// in USD per million tokens
+fn token_cost(input_tokens: Option<i32>, output_tokens: Option<i32>) -> Option<f64> {
+    let config = Config::global();
+    let input_price: f64 = config.get_param("GOOSE_INPUT_TOKEN_COST").ok()?;
+    let output_price: f64 = config.get_param("GOOSE_OUTPUT_TOKEN_COST").ok()?;
+    let tokens = |count: Option<i32>| count.unwrap_or(0).max(0) as f64 / 1_000_000.0;
+    Some(tokens(input_tokens) * input_price + tokens(output_tokens) * output_price)
+}
+
+impl Session {
+    /// Run the session in the full-screen UI until the user quits
+    pub async fn tui(&mut self) -> Result<()> {
+        output::set_quiet(true);
+        let mut tui = Tui::start()?;
+        let ui = tui.handle();
+        self.ui = Some(ui.clone());
+
+        ui.send(UiEvent::Transcript(self.messages.clone()));
+        self.send_ui_status().await;
+
+        while let Some(command) = tui.next_command().await {
+            if command == UiCommand::Quit {
+                break;
+            }
+            ui.send(UiEvent::Busy(true));
+            if let Err(e) = self.run_ui_command(command).await {
+                ui.send(UiEvent::Error(e.to_string()));
+            }
+            ui.send(UiEvent::Busy(false));
+            ui.send(UiEvent::Transcript(self.messages.clone()));
+            self.send_ui_status().await;
+        }
+
+        self.ui = None;
+        drop(ui);
+        let closed = tui.close().await;
+        output::set_quiet(false);
+        println!(
+            "Closing session. Recorded to {}",
+            self.session_file.display()
+        );
+        closed
+    }
+
+    async fn run_ui_command(&mut self, command: UiCommand) -> Result<()> {
+        match command {
+            UiCommand::Submit(text) => self.process_message(text).await,
+            UiCommand::Retry => match session::rewrite::prompt_index(&self.messages, 1) {
+                Some(index) => self.rewrite_and_continue(index + 1, None).await,
+                None => {
+                    self.render_failure("There is no reply to retry.");
+                    Ok(())
+                }
+            },
+            UiCommand::Rewind(turns) => {
+                match session::rewind_session(&self.session_file, turns).await {
+                    Ok(messages) => {
+                        self.messages = messages;
+                        self.render_notice(&format!(
+                            "Rewound {} turn(s). Files restored and conversation trimmed to {} message(s).",
+                            turns,
+                            self.messages.len()
+                        ));
+                    }
+                    Err(e) => self.render_failure(&format!("Failed to rewind: {}", e)),
+                }
+                Ok(())
+            }
+            UiCommand::Quit => Ok(()),
+        }
+    }
+
+    /// Send the model, extensions and usage to the UI, when it is open
+    pub(super) async fn send_ui_status(&self) {
+        let Some(ui) = &self.ui else {
+            return;
+        };
+        let mut status = Status {
+            provider: Config::global()
+                .get_param("GOOSE_PROVIDER")
+                .unwrap_or_default(),
+            session: self
+                .session_file
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_string(),
+            ..Default::default()
+        };
+        if let Ok(provider) = self.agent.provider().await {
+            let model_config = provider.get_model_config();
+            status.model = model_config.model_name.clone();
+            status.context_limit = model_config.context_limit();
+        }
+        if self.session_file.is_file() {
+            if let Ok(metadata) = self.get_metadata() {
+                status.context_tokens = metadata.total_tokens;
+                status.input_tokens = metadata.accumulated_input_tokens;
+                status.output_tokens = metadata.accumulated_output_tokens;
+                status.cost = token_cost(status.input_tokens, status.output_tokens);
+            }
+        }
+
+        for tool in self.agent.list_tools(None).await {
+            let (extension, name) = tool
+                .name
+                .split_once("__")
+                .map_or(("platform", tool.name.as_str()), |(extension, name)| {
+                    (extension, name)
+                });
+            match status
+                .extensions
+                .iter_mut()
+                .find(|(existing, _)| existing == extension)
+            {
+                Some((_, tools)) => tools.push(name.to_string()),
+                None => status
+                    .extensions
+                    .push((extension.to_string(), vec![name.to_string()])),
+            }
+        }
+        status.extensions.sort();
+        ui.send(UiEvent::Status(status));
+    }
+}
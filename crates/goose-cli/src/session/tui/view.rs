@@ -0,0 +1,418 @@
+//! Drawing the full-screen UI
+
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, LineGauge, Paragraph, Wrap};
+use ratatui::Frame;
+use serde_json::Value;
+
+use super::app::{App, Entry, ToolEntry, CONFIRM_OPTIONS};
+
+const SIDE_PANEL_WIDTH: u16 = 34;
+/// Lines of a tool result shown when the call is expanded
+const MAX_RESULT_LINES: usize = 40;
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let input_height = (app.input.lines().count().max(1) as u16 + 2).min(8);
+    let [header, body, input, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(3),
+        Constraint::Length(input_height),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    draw_header(frame, app, header);
+    if app.hide_side_panel || body.width < SIDE_PANEL_WIDTH * 2 {
+        draw_transcript(frame, app, body);
+    } else {
+        let [transcript, side] =
+            Layout::horizontal([Constraint::Min(20), Constraint::Length(SIDE_PANEL_WIDTH)])
+                .areas(body);
+        draw_transcript(frame, app, transcript);
+        draw_side_panel(frame, app, side);
+    }
+    draw_input(frame, app, input);
+    frame.render_widget(
+        Paragraph::new(super::app::HELP)
+            .dark_gray()
+            .wrap(Wrap { trim: true }),
+        footer,
+    );
+
+    if app.confirm.is_some() {
+        draw_confirm(frame, app);
+    }
+}
+
+fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
+    let status = &app.status;
+    let line = Line::from(vec![
+        Span::styled(" goose ", Style::new().bold().black().on_green()),
+        Span::raw(" "),
+        Span::styled(&status.provider, Style::new().cyan()),
+        Span::raw(" · "),
+        Span::styled(&status.model, Style::new().cyan()),
+        Span::raw(" · "),
+        Span::styled(&status.session, Style::new().dark_gray()),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+fn draw_transcript(frame: &mut Frame, app: &mut App, area: Rect) {
+    let block = Block::bordered().title(" Conversation ");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let width = inner.width.max(1) as usize;
+    let mut lines: Vec<Line<'static>> = Vec::new();
+    for (index, entry) in app.entries.iter().enumerate() {
+        let selected = app.selected == Some(index);
+        entry_lines(entry, selected, width, &mut lines);
+        lines.push(Line::default());
+    }
+    if app.busy {
+        let activity = app.activity.as_deref().unwrap_or("Thinking...");
+        lines.push(Line::from(Span::styled(
+            format!("{} {}", app.spinner(), activity),
+            Style::new().yellow(),
+        )));
+    }
+
+    let height = inner.height as usize;
+    app.page_height = height;
+    let max_scroll = lines.len().saturating_sub(height);
+    app.scroll = app.scroll.min(max_scroll);
+    let start = max_scroll - app.scroll;
+    let visible: Vec<Line> = lines.into_iter().skip(start).take(height).collect();
+    frame.render_widget(Paragraph::new(visible), inner);
+
+    if app.scroll > 0 {
+        let hint = format!(" ↓ {} more lines (Ctrl+End) ", app.scroll);
+        let hint_area = Rect {
+            x: area.x + 2,
+            y: area.bottom().saturating_sub(1),
+            width: (hint.chars().count() as u16).min(area.width.saturating_sub(4)),
+            height: 1,
+        };
+        frame.render_widget(Paragraph::new(hint).yellow(), hint_area);
+    }
+}
+
+fn entry_lines(entry: &Entry, selected: bool, width: usize, lines: &mut Vec<Line<'static>>) {
+    match entry {
+        Entry::User(text) => {
+            lines.push(Line::from(Span::styled("you", Style::new().cyan().bold())));
+            push_wrapped(text, Style::new(), width, lines);
+        }
+        Entry::Assistant(text) => {
+            lines.push(Line::from(Span::styled(
+                "goose",
+                Style::new().green().bold(),
+            )));
+            push_wrapped(text, Style::new(), width, lines);
+        }
+        Entry::Tool(tool) => tool_lines(tool, selected, width, lines),
+        Entry::Notice(text) => push_wrapped(text, Style::new().yellow().dim(), width, lines),
+        Entry::Error(text) => push_wrapped(text, Style::new().red(), width, lines),
+    }
+}
+
+fn tool_lines(tool: &ToolEntry, selected: bool, width: usize, lines: &mut Vec<Line<'static>>) {
+    let (marker, marker_style) = match &tool.result {
+        None => ("…", Style::new().yellow()),
+        Some((true, _)) => ("✓", Style::new().green()),
+        Some((false, _)) => ("✗", Style::new().red()),
+    };
+    let arrow = if tool.expanded { "▾" } else { "▸" };
+    let mut name_style = Style::new().magenta();
+    if selected {
+        name_style = name_style.add_modifier(Modifier::REVERSED);
+    }
+    let mut header = vec![
+        Span::raw(format!("{} ", arrow)),
+        Span::styled(tool.name.clone(), name_style),
+        Span::raw(" "),
+        Span::styled(marker, marker_style),
+    ];
+    if !tool.expanded {
+        let summary = summarize_arguments(&tool.arguments);
+        let room = width.saturating_sub(tool.name.chars().count() + 6);
+        header.push(Span::styled(
+            format!("  {}", truncate(&summary, room)),
+            Style::new().dark_gray(),
+        ));
+    }
+    lines.push(Line::from(header));
+    if !tool.expanded {
+        return;
+    }
+
+    let arguments = serde_json::to_string_pretty(&tool.arguments)
+        .unwrap_or_else(|_| tool.arguments.to_string());
+    push_wrapped(&arguments, Style::new().dark_gray(), width, lines);
+    if let Some((ok, output)) = &tool.result {
+        let style = if *ok {
+            Style::new()
+        } else {
+            Style::new().red()
+        };
+        let mut output_lines: Vec<Line<'static>> = Vec::new();
+        push_wrapped(output, style, width.saturating_sub(2), &mut output_lines);
+        let hidden = output_lines.len().saturating_sub(MAX_RESULT_LINES);
+        for line in output_lines.into_iter().take(MAX_RESULT_LINES) {
+            let mut spans = vec![Span::styled("│ ", Style::new().dark_gray())];
+            spans.extend(line.spans);
+            lines.push(Line::from(spans));
+        }
+        if hidden > 0 {
+            lines.push(Line::from(Span::styled(
+                format!("│ … {} more lines", hidden),
+                Style::new().dark_gray(),
+            )));
+        }
+    }
+}
+
+/// A one-line summary of tool arguments, e.g. `command: ls -la`
+fn summarize_arguments(arguments: &Value) -> String {
+    match arguments {
+        Value::Object(map) => map
+            .iter()
+            .map(|(name, value)| match value {
+                Value::String(text) => format!("{}: {}", name, text.replace('\n', " ")),
+                other => format!("{}: {}", name, other),
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Add `text` wrapped to `width` columns
+fn push_wrapped(text: &str, style: Style, width: usize, lines: &mut Vec<Line<'static>>) {
+    for line in wrap(text, width) {
+        lines.push(Line::from(Span::styled(line, style)));
+    }
+}
+
+/// Wrap text at word boundaries, breaking words longer than a line
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for word in paragraph.split(' ') {
+            let word_width = word.chars().count();
+            if line_width > 0 && line_width + 1 + word_width > width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+            for c in word.chars() {
+                if line_width == width {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                }
+                line.push(c);
+                line_width += 1;
+            }
+        }
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+fn draw_side_panel(frame: &mut Frame, app: &App, area: Rect) {
+    let status = &app.status;
+    let [usage_area, extensions_area] =
+        Layout::vertical([Constraint::Length(7), Constraint::Min(3)]).areas(area);
+
+    let usage = Block::bordered().title(" Usage ");
+    let inner = usage.inner(usage_area);
+    frame.render_widget(usage, usage_area);
+    let [gauge_area, tokens_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).areas(inner);
+
+    let context_tokens = status.context_tokens.unwrap_or(0).max(0) as f64;
+    let ratio = if status.context_limit > 0 {
+        (context_tokens / status.context_limit as f64).min(1.0)
+    } else {
+        0.0
+    };
+    let gauge_color = if ratio > 0.85 {
+        Color::Red
+    } else if ratio > 0.6 {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
+    frame.render_widget(
+        LineGauge::default()
+            .ratio(ratio)
+            .label(format!("context {:>3.0}%", ratio * 100.0))
+            .filled_style(Style::new().fg(gauge_color)),
+        gauge_area,
+    );
+
+    let count = |tokens: Option<i32>| tokens.map_or("-".to_string(), |tokens| tokens.to_string());
+    let mut usage_lines = vec![
+        Line::from(format!(
+            "context  {} / {}",
+            count(status.context_tokens),
+            status.context_limit
+        )),
+        Line::from(format!("input    {}", count(status.input_tokens))),
+        Line::from(format!("output   {}", count(status.output_tokens))),
+    ];
+    usage_lines.push(match status.cost {
+        Some(cost) => Line::from(format!("cost     ${:.4}", cost)),
+        None => Line::from(Span::styled(
+            "cost     not priced",
+            Style::new().dark_gray(),
+        )),
+    });
+    frame.render_widget(Paragraph::new(usage_lines), tokens_area);
+
+    let extensions = Block::bordered().title(format!(" Extensions ({}) ", status.extensions.len()));
+    let inner = extensions.inner(extensions_area);
+    frame.render_widget(extensions, extensions_area);
+    let width = inner.width as usize;
+    let mut lines = Vec::new();
+    for (extension, tools) in &status.extensions {
+        lines.push(Line::from(vec![
+            Span::styled(extension.clone(), Style::new().bold()),
+            Span::styled(format!(" {}", tools.len()), Style::new().dark_gray()),
+        ]));
+        for tool in tools {
+            lines.push(Line::from(Span::styled(
+                format!("  {}", truncate(tool, width.saturating_sub(2))),
+                Style::new().dark_gray(),
+            )));
+        }
+    }
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn draw_input(frame: &mut Frame, app: &App, area: Rect) {
+    let title = if app.busy {
+        " Working... Esc to cancel "
+    } else {
+        " Message "
+    };
+    let block = Block::bordered().title(title).border_style(if app.busy {
+        Style::new().dark_gray()
+    } else {
+        Style::new().cyan()
+    });
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Keep the cursor in view by showing the end of long input
+    let before_cursor: String = app.input.chars().take(app.cursor).collect();
+    let cursor_row = before_cursor.matches('\n').count();
+    let cursor_col = before_cursor
+        .rsplit('\n')
+        .next()
+        .map_or(0, |line| line.chars().count());
+    let skip_rows = (cursor_row + 1).saturating_sub(inner.height as usize);
+    let skip_cols = (cursor_col + 1).saturating_sub(inner.width as usize);
+    frame.render_widget(
+        Paragraph::new(app.input.as_str()).scroll((skip_rows as u16, skip_cols as u16)),
+        inner,
+    );
+    if app.confirm.is_none() {
+        frame.set_cursor_position((
+            inner.x + (cursor_col - skip_cols) as u16,
+            inner.y + (cursor_row - skip_rows) as u16,
+        ));
+    }
+}
+
+fn draw_confirm(frame: &mut Frame, app: &App) {
+    let Some(confirm) = &app.confirm else {
+        return;
+    };
+    let area = frame.area();
+    let width = area.width.saturating_sub(8).min(80);
+    let arguments = serde_json::to_string_pretty(&confirm.request.arguments)
+        .unwrap_or_else(|_| confirm.request.arguments.to_string());
+    let argument_lines = wrap(&arguments, width.saturating_sub(2) as usize);
+    let shown = argument_lines.len().min(12);
+    let height = (shown as u16 + 6).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw("goose would like to call "),
+            Span::styled(
+                confirm.request.tool_name.clone(),
+                Style::new().magenta().bold(),
+            ),
+        ]),
+        Line::default(),
+    ];
+    for line in argument_lines.into_iter().take(shown) {
+        lines.push(Line::from(Span::styled(line, Style::new().dark_gray())));
+    }
+    lines.push(Line::default());
+    let mut options = Vec::new();
+    for (index, (_, label)) in CONFIRM_OPTIONS.iter().enumerate() {
+        let style = if index == confirm.selected {
+            Style::new().black().on_cyan().bold()
+        } else {
+            Style::new()
+        };
+        options.push(Span::styled(format!(" {} ", label), style));
+        options.push(Span::raw("  "));
+    }
+    lines.push(Line::from(options));
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::bordered()
+                .title(" Allow this tool call? (y/a/n, Esc cancels) ")
+                .border_style(Style::new().yellow()),
+        ),
+        popup,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("the quick brown fox", 10),
+            vec!["the quick", "brown fox"]
+        );
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("one\n\ntwo", 10), vec!["one", "", "two"]);
+        assert_eq!(wrap("", 10), vec![""]);
+    }
+}
@@ -266,6 +266,15 @@ pub unsafe extern "C" fn goose_agent_send_message(
                 Ok(AgentEvent::McpNotification(_)) => {
                     // TODO: Handle MCP notifications.
                 }
+                Ok(AgentEvent::ContextCompacted(_)) => {
+                    // The caller owns the history, so there is nothing to replace here
+                }
+                Ok(AgentEvent::ProviderError(_)) => {
+                    // The previous message describes the error
+                }
+                Ok(AgentEvent::ToolDenied(_)) => {
+                    // The tool response that follows says so
+                }
                 Err(e) => {
                     full_response.push_str(&format!("\nError in message stream: {}", e));
                 }
@@ -11,6 +11,7 @@ pub mod computercontroller;
 mod developer;
 pub mod google_drive;
 mod jetbrains;
+mod lsp;
 mod memory;
 mod tutorial;
 
@@ -18,5 +19,6 @@ pub use computercontroller::ComputerControllerRouter;
 pub use developer::DeveloperRouter;
 pub use google_drive::GoogleDriveRouter;
 pub use jetbrains::JetBrainsRouter;
+pub use lsp::LspRouter;
 pub use memory::MemoryRouter;
 pub use tutorial::TutorialRouter;
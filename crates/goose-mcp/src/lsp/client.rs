@@ -0,0 +1,471 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{oneshot, Mutex, Notify};
+use tokio::time::{Duration, Instant};
+use tracing::{debug, warn};
+use url::Url;
+
+use super::text::apply_workspace_edit;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// Servers often publish diagnostics in several passes (e.g. rust-analyzer publishes native
+// diagnostics first and cargo check results later), so wait for them to go quiet.
+const DIAGNOSTICS_SETTLE: Duration = Duration::from_millis(1500);
+
+type PendingRequests = Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value>>>>>;
+type DiagnosticsStore = Arc<Mutex<HashMap<String, PublishedDiagnostics>>>;
+
+#[derive(Debug, Clone, Default)]
+pub struct PublishedDiagnostics {
+    /// Incremented every time the server publishes diagnostics for the document
+    pub generation: u64,
+    pub diagnostics: Vec<Value>,
+}
+
+/// A minimal language server client speaking JSON-RPC over the server's stdio
+pub struct LspClient {
+    stdin: Arc<Mutex<ChildStdin>>,
+    // Held so the server is killed when the client is dropped
+    _child: Mutex<Child>,
+    next_id: AtomicI64,
+    pending: PendingRequests,
+    diagnostics: DiagnosticsStore,
+    diagnostics_changed: Arc<Notify>,
+    documents: Mutex<HashMap<String, i32>>,
+}
+
+impl LspClient {
+    /// Spawn the language server and run the initialize handshake for `root`
+    pub async fn start(command: &str, args: &[String], root: &Path) -> Result<Self> {
+        let mut child = Command::new(command)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start language server '{}'", command))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Language server stdin is not available"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("Language server stdout is not available"))?;
+
+        let stdin = Arc::new(Mutex::new(stdin));
+        let pending: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+        let diagnostics: DiagnosticsStore = Arc::new(Mutex::new(HashMap::new()));
+        let diagnostics_changed = Arc::new(Notify::new());
+
+        let reader_stdin = Arc::clone(&stdin);
+        let reader_pending = Arc::clone(&pending);
+        let reader_diagnostics = Arc::clone(&diagnostics);
+        let reader_notify = Arc::clone(&diagnostics_changed);
+        let reader_root = root.to_path_buf();
+        let server_name = command.to_string();
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stdout);
+            loop {
+                match read_message(&mut reader).await {
+                    Ok(Some(message)) => {
+                        handle_message(
+                            message,
+                            &reader_stdin,
+                            &reader_pending,
+                            &reader_diagnostics,
+                            &reader_notify,
+                            &reader_root,
+                        )
+                        .await
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("Failed to read from language server {}: {}", server_name, e);
+                        break;
+                    }
+                }
+            }
+            debug!("Language server {} closed its output", server_name);
+            for (_, tx) in reader_pending.lock().await.drain() {
+                let _ = tx.send(Err(anyhow!("Language server exited")));
+            }
+        });
+
+        let client = Self {
+            stdin,
+            _child: Mutex::new(child),
+            next_id: AtomicI64::new(1),
+            pending,
+            diagnostics,
+            diagnostics_changed,
+            documents: Mutex::new(HashMap::new()),
+        };
+        client.initialize(root).await?;
+        Ok(client)
+    }
+
+    async fn initialize(&self, root: &Path) -> Result<()> {
+        let root_uri = Url::from_directory_path(root)
+            .map_err(|_| anyhow!("Invalid workspace root {}", root.display()))?
+            .to_string();
+        let root_name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "workspace".to_string());
+
+        let params = json!({
+            "processId": std::process::id(),
+            "rootUri": root_uri,
+            "workspaceFolders": [{"uri": root_uri, "name": root_name}],
+            "capabilities": {
+                "general": {"positionEncodings": ["utf-16"]},
+                "workspace": {
+                    "applyEdit": true,
+                    "configuration": true,
+                    "workspaceFolders": true,
+                    "workspaceEdit": {
+                        "documentChanges": true,
+                        "resourceOperations": ["create", "rename", "delete"]
+                    }
+                },
+                "textDocument": {
+                    "synchronization": {"didSave": true},
+                    "publishDiagnostics": {"relatedInformation": false},
+                    "hover": {"contentFormat": ["markdown", "plaintext"]},
+                    "definition": {"linkSupport": true},
+                    "rename": {"prepareSupport": false},
+                    "codeAction": {
+                        "codeActionLiteralSupport": {
+                            "codeActionKind": {
+                                "valueSet": [
+                                    "", "quickfix", "refactor", "refactor.extract",
+                                    "refactor.inline", "refactor.rewrite", "source",
+                                    "source.organizeImports"
+                                ]
+                            }
+                        },
+                        "resolveSupport": {"properties": ["edit"]}
+                    }
+                }
+            }
+        });
+
+        self.request("initialize", params).await?;
+        self.notify("initialized", json!({})).await
+    }
+
+    /// Send a request and wait for the matching response
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().await.insert(id, tx);
+
+        let message = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        if let Err(e) = write_message(&self.stdin, &message).await {
+            self.pending.lock().await.remove(&id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(anyhow!(
+                "Language server stopped before answering {}",
+                method
+            )),
+            Err(_) => {
+                self.pending.lock().await.remove(&id);
+                Err(anyhow!(
+                    "Timed out waiting for the language server to answer {}",
+                    method
+                ))
+            }
+        }
+    }
+
+    pub async fn notify(&self, method: &str, params: Value) -> Result<()> {
+        let message = json!({"jsonrpc": "2.0", "method": method, "params": params});
+        write_message(&self.stdin, &message).await
+    }
+
+    /// Push the current on-disk contents of `path` to the server, opening it if needed.
+    ///
+    /// Returns the document uri and the diagnostics generation seen before the sync, which can
+    /// be passed to `wait_for_diagnostics` to wait for results reflecting this content.
+    pub async fn sync_document(&self, path: &Path, language_id: &str) -> Result<(String, u64)> {
+        let text = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let uri = path_to_uri(path)?;
+        let generation = self
+            .diagnostics
+            .lock()
+            .await
+            .get(&uri)
+            .map(|d| d.generation)
+            .unwrap_or(0);
+
+        let mut documents = self.documents.lock().await;
+        match documents.get_mut(&uri) {
+            Some(version) => {
+                *version += 1;
+                let version = *version;
+                self.notify(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": {"uri": uri, "version": version},
+                        "contentChanges": [{"text": text}]
+                    }),
+                )
+                .await?;
+            }
+            None => {
+                documents.insert(uri.clone(), 1);
+                self.notify(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": language_id,
+                            "version": 1,
+                            "text": text
+                        }
+                    }),
+                )
+                .await?;
+            }
+        }
+
+        // Some servers (rust-analyzer, gopls) only run their full checks on save
+        self.notify(
+            "textDocument/didSave",
+            json!({"textDocument": {"uri": uri}, "text": text}),
+        )
+        .await?;
+
+        Ok((uri, generation))
+    }
+
+    /// Whether the document has been opened with the server
+    pub async fn is_open(&self, uri: &str) -> bool {
+        self.documents.lock().await.contains_key(uri)
+    }
+
+    /// Wait until the server publishes diagnostics newer than `after_generation` and then goes
+    /// quiet, or until `timeout` elapses. Returns the latest diagnostics either way.
+    pub async fn wait_for_diagnostics(
+        &self,
+        uri: &str,
+        after_generation: u64,
+        timeout: Duration,
+    ) -> Vec<Value> {
+        let deadline = Instant::now() + timeout;
+        let mut seen_generation = after_generation;
+        let mut settle_deadline: Option<Instant> = None;
+
+        loop {
+            let notified = self.diagnostics_changed.notified();
+            if let Some(current) = self.diagnostics.lock().await.get(uri) {
+                if current.generation > seen_generation {
+                    seen_generation = current.generation;
+                    settle_deadline = Some(Instant::now() + DIAGNOSTICS_SETTLE);
+                }
+            }
+
+            let wait_until = match settle_deadline {
+                Some(settle) => settle.min(deadline),
+                None => deadline,
+            };
+            if tokio::time::timeout_at(wait_until, notified).await.is_err() {
+                break;
+            }
+        }
+
+        self.cached_diagnostics(uri).await
+    }
+
+    pub async fn cached_diagnostics(&self, uri: &str) -> Vec<Value> {
+        self.diagnostics
+            .lock()
+            .await
+            .get(uri)
+            .map(|d| d.diagnostics.clone())
+            .unwrap_or_default()
+    }
+}
+
+pub fn path_to_uri(path: &Path) -> Result<String> {
+    Url::from_file_path(path)
+        .map(|u| u.to_string())
+        .map_err(|_| anyhow!("Invalid file path {}", path.display()))
+}
+
+pub fn uri_to_path(uri: &str) -> Result<PathBuf> {
+    Url::parse(uri)
+        .ok()
+        .and_then(|u| u.to_file_path().ok())
+        .ok_or_else(|| anyhow!("Unsupported document uri {}", uri))
+}
+
+pub fn encode_message(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body).into_bytes()
+}
+
+async fn write_message(stdin: &Mutex<ChildStdin>, message: &Value) -> Result<()> {
+    let mut stdin = stdin.lock().await;
+    stdin.write_all(&encode_message(message)).await?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+/// Read one `Content-Length` framed message, returning `None` at end of stream
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Value>> {
+    let mut content_length: Option<usize> = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(value.trim().parse()?);
+            }
+        }
+    }
+
+    let length = content_length.ok_or_else(|| anyhow!("Missing Content-Length header"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+async fn handle_message(
+    message: Value,
+    stdin: &Mutex<ChildStdin>,
+    pending: &PendingRequests,
+    diagnostics: &DiagnosticsStore,
+    diagnostics_changed: &Notify,
+    root: &Path,
+) {
+    let method = message.get("method").and_then(|m| m.as_str());
+    let id = message.get("id").cloned();
+
+    match (method, id) {
+        (Some("textDocument/publishDiagnostics"), None) => {
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+            if let Some(uri) = params.get("uri").and_then(|u| u.as_str()) {
+                let published = params
+                    .get("diagnostics")
+                    .and_then(|d| d.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                let mut store = diagnostics.lock().await;
+                let entry = store.entry(uri.to_string()).or_default();
+                entry.generation += 1;
+                entry.diagnostics = published;
+            }
+            diagnostics_changed.notify_waiters();
+        }
+        (Some(method), Some(id)) => {
+            // Requests from the server; answer the ones we understand and acknowledge the rest
+            let result = match method {
+                "workspace/configuration" => {
+                    let count = message["params"]["items"]
+                        .as_array()
+                        .map(|items| items.len())
+                        .unwrap_or(0);
+                    Value::Array(vec![Value::Null; count])
+                }
+                "workspace/applyEdit" => {
+                    match apply_workspace_edit(&message["params"]["edit"], root) {
+                        Ok(_) => json!({"applied": true}),
+                        Err(e) => json!({"applied": false, "failureReason": e.to_string()}),
+                    }
+                }
+                _ => Value::Null,
+            };
+            let response = json!({"jsonrpc": "2.0", "id": id, "result": result});
+            if let Err(e) = write_message(stdin, &response).await {
+                warn!("Failed to answer language server request {}: {}", method, e);
+            }
+        }
+        (Some(_), None) => {}
+        (None, Some(id)) => {
+            let Some(id) = id.as_i64() else {
+                return;
+            };
+            if let Some(tx) = pending.lock().await.remove(&id) {
+                let result = match message.get("error") {
+                    Some(error) => Err(anyhow!(
+                        "Language server error: {}",
+                        error
+                            .get("message")
+                            .and_then(|m| m.as_str())
+                            .unwrap_or("unknown error")
+                    )),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = tx.send(result);
+            }
+        }
+        (None, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_message_round_trip() {
+        let first = json!({"jsonrpc": "2.0", "id": 1, "result": {"ok": true}});
+        let second = json!({"jsonrpc": "2.0", "method": "window/logMessage", "params": {}});
+        let mut bytes = encode_message(&first);
+        bytes.extend(encode_message(&second));
+
+        let mut reader = bytes.as_slice();
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(first));
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(second));
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_message_ignores_extra_headers() {
+        let body = r#"{"jsonrpc":"2.0","id":7,"result":null}"#;
+        let raw = format!(
+            "Content-Type: application/vscode-jsonrpc; charset=utf-8\r\ncontent-length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let mut reader = raw.as_bytes();
+        let message = read_message(&mut reader).await.unwrap().unwrap();
+        assert_eq!(message["id"], 7);
+    }
+
+    #[test]
+    fn test_uri_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("main.rs");
+        let uri = path_to_uri(&path).unwrap();
+        assert!(uri.starts_with("file://"));
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+    }
+}
@@ -0,0 +1,895 @@
+mod client;
+mod text;
+
+use anyhow::Result;
+use indoc::{formatdoc, indoc};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+};
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Duration;
+
+use mcp_core::{
+    content::Content,
+    handler::{PromptError, ResourceError, ToolError},
+    prompt::Prompt,
+    protocol::{JsonRpcMessage, ServerCapabilities},
+    resource::Resource,
+    role::Role,
+    tool::{Tool, ToolAnnotations},
+};
+use mcp_server::router::CapabilitiesBuilder;
+use mcp_server::Router;
+
+use self::client::{path_to_uri, uri_to_path, LspClient};
+use self::text::{apply_workspace_edit, display_position, lsp_position};
+
+const DEFAULT_DIAGNOSTICS_TIMEOUT_SECS: u64 = 10;
+
+/// A language server goose knows how to launch, keyed by the file extensions it handles
+struct LanguageServerSpec {
+    language: &'static str,
+    command: &'static str,
+    args: &'static [&'static str],
+    extensions: &'static [&'static str],
+}
+
+const LANGUAGE_SERVERS: &[LanguageServerSpec] = &[
+    LanguageServerSpec {
+        language: "rust",
+        command: "rust-analyzer",
+        args: &[],
+        extensions: &["rs"],
+    },
+    LanguageServerSpec {
+        language: "python",
+        command: "pyright-langserver",
+        args: &["--stdio"],
+        extensions: &["py", "pyi"],
+    },
+    LanguageServerSpec {
+        language: "go",
+        command: "gopls",
+        args: &[],
+        extensions: &["go"],
+    },
+    LanguageServerSpec {
+        language: "typescript",
+        command: "typescript-language-server",
+        args: &["--stdio"],
+        extensions: &["ts", "tsx", "js", "jsx", "mjs", "cjs"],
+    },
+    LanguageServerSpec {
+        language: "c",
+        command: "clangd",
+        args: &[],
+        extensions: &["c", "h", "cc", "cpp", "cxx", "hpp"],
+    },
+];
+
+impl LanguageServerSpec {
+    fn for_path(path: &Path) -> Option<&'static LanguageServerSpec> {
+        let extension = path.extension()?.to_str()?;
+        LANGUAGE_SERVERS
+            .iter()
+            .find(|spec| spec.extensions.contains(&extension))
+    }
+
+    /// The command to launch, which can be overridden with e.g. `GOOSE_LSP_PYTHON="pylsp"`
+    fn command_line(&self) -> (String, Vec<String>) {
+        let env_key = format!("GOOSE_LSP_{}", self.language.to_uppercase());
+        if let Ok(custom) = std::env::var(&env_key) {
+            let mut parts = custom.split_whitespace().map(String::from);
+            if let Some(command) = parts.next() {
+                return (command, parts.collect());
+            }
+        }
+        (
+            self.command.to_string(),
+            self.args.iter().map(|a| a.to_string()).collect(),
+        )
+    }
+}
+
+/// The languageId the LSP spec expects for a document
+fn language_id(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("rs") => "rust",
+        Some("py") | Some("pyi") => "python",
+        Some("go") => "go",
+        Some("ts") => "typescript",
+        Some("tsx") => "typescriptreact",
+        Some("js") | Some("mjs") | Some("cjs") => "javascript",
+        Some("jsx") => "javascriptreact",
+        Some("c") | Some("h") => "c",
+        Some("cc") | Some("cpp") | Some("cxx") | Some("hpp") => "cpp",
+        _ => "plaintext",
+    }
+}
+
+fn severity_label(diagnostic: &Value) -> &'static str {
+    match diagnostic.get("severity").and_then(|s| s.as_u64()) {
+        Some(1) => "error",
+        Some(2) => "warning",
+        Some(3) => "info",
+        Some(4) => "hint",
+        _ => "diagnostic",
+    }
+}
+
+fn format_diagnostic(path: &Path, text: &str, diagnostic: &Value) -> String {
+    let (line, column) = display_position(text, &diagnostic["range"]["start"]);
+    let message = diagnostic
+        .get("message")
+        .and_then(|m| m.as_str())
+        .unwrap_or_default();
+    let source = match (
+        diagnostic.get("source").and_then(|s| s.as_str()),
+        diagnostic.get("code"),
+    ) {
+        (Some(source), Some(Value::String(code))) => format!(" [{}({})]", source, code),
+        (Some(source), Some(Value::Number(code))) => format!(" [{}({})]", source, code),
+        (Some(source), _) => format!(" [{}]", source),
+        _ => String::new(),
+    };
+    format!(
+        "{}:{}:{}: {}: {}{}",
+        path.display(),
+        line,
+        column,
+        severity_label(diagnostic),
+        message,
+        source
+    )
+}
+
+/// Normalize the many shapes of hover contents into plain text
+fn hover_text(contents: &Value) -> String {
+    match contents {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(hover_text)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Value::Object(obj) => match (obj.get("language"), obj.get("value")) {
+            (Some(language), Some(value)) => format!(
+                "```{}\n{}\n```",
+                language.as_str().unwrap_or_default(),
+                value.as_str().unwrap_or_default()
+            ),
+            (None, Some(value)) => value.as_str().unwrap_or_default().to_string(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+/// Normalize Location, Location[] and LocationLink[] into (uri, range) pairs
+fn locations(result: &Value) -> Vec<(String, Value)> {
+    let items = match result {
+        Value::Array(items) => items.clone(),
+        Value::Object(_) => vec![result.clone()],
+        _ => vec![],
+    };
+    items
+        .into_iter()
+        .filter_map(|item| {
+            if let Some(uri) = item.get("targetUri").and_then(|u| u.as_str()) {
+                let range = item
+                    .get("targetSelectionRange")
+                    .or_else(|| item.get("targetRange"))
+                    .cloned()?;
+                Some((uri.to_string(), range))
+            } else {
+                let uri = item.get("uri").and_then(|u| u.as_str())?;
+                Some((uri.to_string(), item.get("range").cloned()?))
+            }
+        })
+        .collect()
+}
+
+fn ranges_overlap(a: &Value, b: &Value) -> bool {
+    let key = |p: &Value| {
+        (
+            p.get("line").and_then(|l| l.as_u64()).unwrap_or(0),
+            p.get("character").and_then(|c| c.as_u64()).unwrap_or(0),
+        )
+    };
+    key(&a["start"]) <= key(&b["end"]) && key(&b["start"]) <= key(&a["end"])
+}
+
+pub struct LspRouter {
+    tools: Vec<Tool>,
+    instructions: String,
+    root: PathBuf,
+    clients: Arc<Mutex<HashMap<&'static str, Arc<LspClient>>>>,
+}
+
+impl Default for LspRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LspRouter {
+    pub fn new() -> Self {
+        let position_properties = json!({
+            "path": {
+                "type": "string",
+                "description": "Path to the source file, absolute or relative to the working directory"
+            },
+            "line": {
+                "type": "integer",
+                "description": "1-based line number"
+            },
+            "column": {
+                "type": "integer",
+                "description": "1-based column of the symbol on that line"
+            }
+        });
+
+        let diagnostics_tool = Tool::new(
+            "lsp_diagnostics",
+            indoc! {r#"
+                Get compiler and linter diagnostics for a file from its language server.
+
+                The file is re-read from disk and synced to the language server first, so call this
+                right after editing a file to check for errors without running a full build.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["path"],
+                "properties": {
+                    "path": position_properties["path"],
+                    "timeout_secs": {
+                        "type": "integer",
+                        "default": DEFAULT_DIAGNOSTICS_TIMEOUT_SECS,
+                        "description": "How long to wait for the language server to report diagnostics"
+                    }
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Get diagnostics".to_string()),
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let definition_tool = Tool::new(
+            "lsp_definition",
+            "Find where the symbol at a position is defined. Returns file locations with the source line.",
+            json!({
+                "type": "object",
+                "required": ["path", "line", "column"],
+                "properties": position_properties.clone()
+            }),
+            Some(ToolAnnotations {
+                title: Some("Go to definition".to_string()),
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let hover_tool = Tool::new(
+            "lsp_hover",
+            "Show type information and documentation for the symbol at a position.",
+            json!({
+                "type": "object",
+                "required": ["path", "line", "column"],
+                "properties": position_properties.clone()
+            }),
+            Some(ToolAnnotations {
+                title: Some("Hover type information".to_string()),
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let mut rename_properties = position_properties.clone();
+        rename_properties["new_name"] = json!({
+            "type": "string",
+            "description": "The new name for the symbol"
+        });
+        let rename_tool = Tool::new(
+            "lsp_rename",
+            indoc! {r#"
+                Rename the symbol at a position across the whole project using the language server.
+                Every reference is updated on disk and the list of changed files is returned.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["path", "line", "column", "new_name"],
+                "properties": rename_properties
+            }),
+            Some(ToolAnnotations {
+                title: Some("Rename symbol".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: false,
+                open_world_hint: false,
+            }),
+        );
+
+        let mut code_action_properties = position_properties;
+        code_action_properties["end_line"] = json!({
+            "type": "integer",
+            "description": "Optional 1-based end line of the range, defaults to `line`"
+        });
+        code_action_properties["end_column"] = json!({
+            "type": "integer",
+            "description": "Optional 1-based end column of the range, defaults to `column`"
+        });
+        code_action_properties["apply"] = json!({
+            "type": "integer",
+            "description": "Index of the code action to apply, as returned by a previous call without `apply`"
+        });
+        let code_actions_tool = Tool::new(
+            "lsp_code_actions",
+            indoc! {r#"
+                List the quick fixes and refactorings the language server offers for a position or range.
+
+                Call it without `apply` to list the available actions, then call it again with the index
+                of the action to apply. Applied edits are written to disk.
+            "#},
+            json!({
+                "type": "object",
+                "required": ["path", "line", "column"],
+                "properties": code_action_properties
+            }),
+            Some(ToolAnnotations {
+                title: Some("Code actions".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: false,
+                open_world_hint: false,
+            }),
+        );
+
+        let root = std::env::current_dir().expect("should have a current working dir");
+        let servers = LANGUAGE_SERVERS
+            .iter()
+            .map(|spec| {
+                format!(
+                    "- {} ({}): `{}`",
+                    spec.language,
+                    spec.extensions.join(", "),
+                    spec.command_line().0
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let instructions = formatdoc! {r#"
+            The lsp extension connects to language servers for the project in {root}.
+            Servers are started on first use for each language and must be installed locally:
+            {servers}
+
+            After editing source files, use lsp_diagnostics to check for compile errors instead of
+            running a full build. Use lsp_definition and lsp_hover to navigate unfamiliar code, and
+            prefer lsp_rename over search and replace when renaming symbols.
+
+            Lines and columns are 1-based, matching the positions reported by lsp_diagnostics.
+            "#,
+            root=root.display(),
+            servers=servers,
+        };
+
+        Self {
+            tools: vec![
+                diagnostics_tool,
+                definition_tool,
+                hover_tool,
+                rename_tool,
+                code_actions_tool,
+            ],
+            instructions,
+            root,
+            clients: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn resolve_path(&self, params: &Value) -> Result<PathBuf, ToolError> {
+        let path_str = params
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'path' parameter".into()))?;
+        let expanded = PathBuf::from(shellexpand::tilde(path_str).into_owned());
+        let path = if expanded.is_absolute() {
+            expanded
+        } else {
+            self.root.join(expanded)
+        };
+        if !path.is_file() {
+            return Err(ToolError::InvalidParameters(format!(
+                "The path '{}' does not exist or is not a file.",
+                path.display()
+            )));
+        }
+        Ok(path)
+    }
+
+    /// Get the running client for the file's language, starting the server if needed
+    async fn client_for(&self, path: &Path) -> Result<Arc<LspClient>, ToolError> {
+        let spec = LanguageServerSpec::for_path(path).ok_or_else(|| {
+            ToolError::InvalidParameters(format!(
+                "No language server is configured for '{}'",
+                path.display()
+            ))
+        })?;
+
+        let mut clients = self.clients.lock().await;
+        if let Some(client) = clients.get(spec.language) {
+            return Ok(Arc::clone(client));
+        }
+
+        let (command, args) = spec.command_line();
+        tracing::info!("Starting {} language server: {}", spec.language, command);
+        let client = LspClient::start(&command, &args, &self.root)
+            .await
+            .map_err(|e| {
+                ToolError::ExecutionError(format!(
+                    "{}. Make sure `{}` is installed and on your PATH, or set GOOSE_LSP_{} to the command to run.",
+                    e,
+                    command,
+                    spec.language.to_uppercase()
+                ))
+            })?;
+        let client = Arc::new(client);
+        clients.insert(spec.language, Arc::clone(&client));
+        Ok(client)
+    }
+
+    /// Sync the file and build the LSP position from the tool's 1-based line and column
+    async fn prepare_position(
+        &self,
+        params: &Value,
+    ) -> Result<(Arc<LspClient>, PathBuf, String, Value), ToolError> {
+        let path = self.resolve_path(params)?;
+        let line = params
+            .get("line")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'line' parameter".into()))?;
+        let column = params
+            .get("column")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'column' parameter".into()))?;
+
+        let client = self.client_for(&path).await?;
+        let (uri, _) = client
+            .sync_document(&path, language_id(&path))
+            .await
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to read file: {}", e)))?;
+        let position = lsp_position(&text, line, column);
+        let params = json!({"textDocument": {"uri": uri}, "position": position});
+        Ok((client, path, text, params))
+    }
+
+    /// Re-sync open documents after edits were written to disk so later queries see them
+    async fn refresh_changed(&self, client: &LspClient, changed: &[PathBuf]) {
+        for path in changed {
+            let Ok(uri) = path_to_uri(path) else {
+                continue;
+            };
+            if client.is_open(&uri).await {
+                if let Err(e) = client.sync_document(path, language_id(path)).await {
+                    tracing::warn!("Failed to sync {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    async fn diagnostics(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let path = self.resolve_path(&params)?;
+        let timeout = params
+            .get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_DIAGNOSTICS_TIMEOUT_SECS);
+
+        let client = self.client_for(&path).await?;
+        let (uri, generation) = client
+            .sync_document(&path, language_id(&path))
+            .await
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        let diagnostics = client
+            .wait_for_diagnostics(&uri, generation, Duration::from_secs(timeout))
+            .await;
+
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to read file: {}", e)))?;
+        let output = if diagnostics.is_empty() {
+            format!("No diagnostics reported for {}", path.display())
+        } else {
+            let errors = diagnostics
+                .iter()
+                .filter(|d| d.get("severity").and_then(|s| s.as_u64()) == Some(1))
+                .count();
+            let lines = diagnostics
+                .iter()
+                .map(|d| format_diagnostic(&path, &text, d))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!(
+                "{} diagnostics ({} errors) for {}:\n{}",
+                diagnostics.len(),
+                errors,
+                path.display(),
+                lines
+            )
+        };
+
+        Ok(vec![Content::text(output)])
+    }
+
+    async fn definition(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let (client, _, _, request) = self.prepare_position(&params).await?;
+        let result = client
+            .request("textDocument/definition", request)
+            .await
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+
+        let found = locations(&result);
+        if found.is_empty() {
+            return Ok(vec![Content::text("No definition found")]);
+        }
+
+        let mut output = Vec::new();
+        for (uri, range) in found {
+            let Ok(path) = uri_to_path(&uri) else {
+                output.push(uri);
+                continue;
+            };
+            let target_text = std::fs::read_to_string(&path).unwrap_or_default();
+            let (line, column) = display_position(&target_text, &range["start"]);
+            let source_line = target_text
+                .lines()
+                .nth(line - 1)
+                .map(|l| l.trim())
+                .unwrap_or_default();
+            output.push(format!(
+                "{}:{}:{}\n    {}",
+                path.display(),
+                line,
+                column,
+                source_line
+            ));
+        }
+
+        Ok(vec![Content::text(output.join("\n"))])
+    }
+
+    async fn hover(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let (client, _, _, request) = self.prepare_position(&params).await?;
+        let result = client
+            .request("textDocument/hover", request)
+            .await
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+
+        let text = hover_text(&result["contents"]);
+        if text.trim().is_empty() {
+            return Ok(vec![Content::text("No hover information available")]);
+        }
+        Ok(vec![Content::text(text)])
+    }
+
+    async fn rename(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let new_name = params
+            .get("new_name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'new_name' parameter".into()))?
+            .to_string();
+        let (client, _, _, mut request) = self.prepare_position(&params).await?;
+        request["newName"] = json!(new_name);
+
+        let edit = client
+            .request("textDocument/rename", request)
+            .await
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        if edit.is_null() {
+            return Err(ToolError::ExecutionError(
+                "The language server cannot rename the symbol at this position".into(),
+            ));
+        }
+
+        let changed = apply_workspace_edit(&edit, &self.root)
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to apply rename: {}", e)))?;
+        self.refresh_changed(&client, &changed).await;
+
+        let files = changed
+            .iter()
+            .map(|p| format!("- {}", p.display()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(vec![
+            Content::text(format!(
+                "Renamed to `{}` in {} files:\n{}",
+                new_name,
+                changed.len(),
+                files
+            ))
+            .with_audience(vec![Role::Assistant]),
+            Content::text(files)
+                .with_audience(vec![Role::User])
+                .with_priority(0.0),
+        ])
+    }
+
+    async fn code_actions(&self, params: Value) -> Result<Vec<Content>, ToolError> {
+        let (client, _, text, request) = self.prepare_position(&params).await?;
+        let start = request["position"].clone();
+        let end = match (
+            params.get("end_line").and_then(|v| v.as_u64()),
+            params.get("end_column").and_then(|v| v.as_u64()),
+        ) {
+            (None, None) => start.clone(),
+            (end_line, end_column) => lsp_position(
+                &text,
+                end_line.unwrap_or_else(|| params["line"].as_u64().unwrap_or(1)),
+                end_column.unwrap_or_else(|| params["column"].as_u64().unwrap_or(1)),
+            ),
+        };
+        let range = json!({"start": start, "end": end});
+        let uri = request["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let diagnostics = client
+            .cached_diagnostics(&uri)
+            .await
+            .into_iter()
+            .filter(|d| ranges_overlap(&d["range"], &range))
+            .collect::<Vec<_>>();
+
+        let result = client
+            .request(
+                "textDocument/codeAction",
+                json!({
+                    "textDocument": {"uri": uri},
+                    "range": range,
+                    "context": {"diagnostics": diagnostics}
+                }),
+            )
+            .await
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        let actions = result.as_array().cloned().unwrap_or_default();
+
+        let Some(index) = params.get("apply").and_then(|v| v.as_u64()) else {
+            if actions.is_empty() {
+                return Ok(vec![Content::text("No code actions available")]);
+            }
+            let listing = actions
+                .iter()
+                .enumerate()
+                .map(|(i, action)| {
+                    let title = action["title"].as_str().unwrap_or_default();
+                    match action.get("kind").and_then(|k| k.as_str()) {
+                        Some(kind) if !kind.is_empty() => format!("{}: {} ({})", i, title, kind),
+                        _ => format!("{}: {}", i, title),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            return Ok(vec![Content::text(format!(
+                "Available code actions (apply one by passing its index as `apply`):\n{}",
+                listing
+            ))]);
+        };
+
+        let mut action = actions.get(index as usize).cloned().ok_or_else(|| {
+            ToolError::InvalidParameters(format!(
+                "No code action with index {}, there are {} available",
+                index,
+                actions.len()
+            ))
+        })?;
+        let title = action["title"].as_str().unwrap_or_default().to_string();
+
+        // A bare Command rather than a CodeAction
+        if action.get("command").and_then(|c| c.as_str()).is_some() {
+            client
+                .request(
+                    "workspace/executeCommand",
+                    json!({"command": action["command"], "arguments": action["arguments"]}),
+                )
+                .await
+                .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+            return Ok(vec![Content::text(format!("Applied '{}'", title))]);
+        }
+
+        if action.get("edit").is_none() && action.get("data").is_some() {
+            action = client
+                .request("codeAction/resolve", action)
+                .await
+                .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        }
+
+        let mut changed = Vec::new();
+        if let Some(edit) = action.get("edit") {
+            changed = apply_workspace_edit(edit, &self.root).map_err(|e| {
+                ToolError::ExecutionError(format!("Failed to apply code action: {}", e))
+            })?;
+        }
+        if let Some(command) = action.get("command") {
+            client
+                .request(
+                    "workspace/executeCommand",
+                    json!({"command": command["command"], "arguments": command["arguments"]}),
+                )
+                .await
+                .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        }
+        self.refresh_changed(&client, &changed).await;
+
+        let files = changed
+            .iter()
+            .map(|p| format!("- {}", p.display()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(vec![Content::text(format!(
+            "Applied '{}'{}",
+            title,
+            if files.is_empty() {
+                String::new()
+            } else {
+                format!(", changed files:\n{}", files)
+            }
+        ))])
+    }
+}
+
+impl Router for LspRouter {
+    fn name(&self) -> String {
+        "lsp".to_string()
+    }
+
+    fn instructions(&self) -> String {
+        self.instructions.clone()
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        CapabilitiesBuilder::new().with_tools(false).build()
+    }
+
+    fn list_tools(&self) -> Vec<Tool> {
+        self.tools.clone()
+    }
+
+    fn call_tool(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        _notifier: mpsc::Sender<JsonRpcMessage>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>> {
+        let this = self.clone();
+        let tool_name = tool_name.to_string();
+        Box::pin(async move {
+            match tool_name.as_str() {
+                "lsp_diagnostics" => this.diagnostics(arguments).await,
+                "lsp_definition" => this.definition(arguments).await,
+                "lsp_hover" => this.hover(arguments).await,
+                "lsp_rename" => this.rename(arguments).await,
+                "lsp_code_actions" => this.code_actions(arguments).await,
+                _ => Err(ToolError::NotFound(format!("Tool {} not found", tool_name))),
+            }
+        })
+    }
+
+    fn list_resources(&self) -> Vec<Resource> {
+        Vec::new()
+    }
+
+    fn read_resource(
+        &self,
+        _uri: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
+        Box::pin(async move { Ok("".to_string()) })
+    }
+
+    fn list_prompts(&self) -> Vec<Prompt> {
+        vec![]
+    }
+
+    fn get_prompt(
+        &self,
+        prompt_name: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>> {
+        let prompt_name = prompt_name.to_string();
+        Box::pin(async move {
+            Err(PromptError::NotFound(format!(
+                "Prompt {} not found",
+                prompt_name
+            )))
+        })
+    }
+}
+
+impl Clone for LspRouter {
+    fn clone(&self) -> Self {
+        Self {
+            tools: self.tools.clone(),
+            instructions: self.instructions.clone(),
+            root: self.root.clone(),
+            clients: Arc::clone(&self.clients),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_detection() {
+        assert_eq!(
+            LanguageServerSpec::for_path(Path::new("src/main.rs"))
+                .unwrap()
+                .command,
+            "rust-analyzer"
+        );
+        assert_eq!(
+            LanguageServerSpec::for_path(Path::new("app.tsx"))
+                .unwrap()
+                .language,
+            "typescript"
+        );
+        assert!(LanguageServerSpec::for_path(Path::new("README.md")).is_none());
+        assert_eq!(language_id(Path::new("app.tsx")), "typescriptreact");
+        assert_eq!(language_id(Path::new("lib.hpp")), "cpp");
+    }
+
+    #[test]
+    fn test_hover_text_shapes() {
+        assert_eq!(
+            hover_text(&json!({"kind": "markdown", "value": "**fn** main()"})),
+            "**fn** main()"
+        );
+        assert_eq!(
+            hover_text(&json!([{"language": "rust", "value": "fn main()"}, "Entry point"])),
+            "```rust\nfn main()\n```\n\nEntry point"
+        );
+    }
+
+    #[test]
+    fn test_locations_shapes() {
+        let range =
+            json!({"start": {"line": 1, "character": 2}, "end": {"line": 1, "character": 5}});
+        let location = json!({"uri": "file:///a.rs", "range": range});
+        assert_eq!(locations(&location).len(), 1);
+        assert_eq!(locations(&json!([location.clone(), location])).len(), 2);
+
+        let link = json!([{"targetUri": "file:///b.rs", "targetRange": range, "targetSelectionRange": range}]);
+        assert_eq!(locations(&link)[0].0, "file:///b.rs");
+        assert!(locations(&Value::Null).is_empty());
+    }
+
+    #[test]
+    fn test_format_diagnostic() {
+        let diagnostic = json!({
+            "range": {"start": {"line": 1, "character": 4}, "end": {"line": 1, "character": 7}},
+            "severity": 1,
+            "source": "rustc",
+            "code": "E0425",
+            "message": "cannot find value `foo` in this scope"
+        });
+        let text = "fn main() {\n    foo;\n}\n";
+        assert_eq!(
+            format_diagnostic(Path::new("/src/main.rs"), text, &diagnostic),
+            "/src/main.rs:2:5: error: cannot find value `foo` in this scope [rustc(E0425)]"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use super::client::uri_to_path;
+
+/// Convert an LSP position (zero based line, UTF-16 character offset) into a byte offset.
+/// Positions past the end of a line or the document are clamped.
+pub fn offset_at(text: &str, position: &Value) -> usize {
+    let line = position.get("line").and_then(|l| l.as_u64()).unwrap_or(0) as usize;
+    let character = position
+        .get("character")
+        .and_then(|c| c.as_u64())
+        .unwrap_or(0) as usize;
+
+    let mut line_start = 0;
+    for _ in 0..line {
+        match text[line_start..].find('\n') {
+            Some(idx) => line_start += idx + 1,
+            None => return text.len(),
+        }
+    }
+
+    let rest = &text[line_start..];
+    let line_text = rest[..rest.find('\n').unwrap_or(rest.len())].trim_end_matches('\r');
+    let mut units = 0;
+    for (idx, ch) in line_text.char_indices() {
+        if units >= character {
+            return line_start + idx;
+        }
+        units += ch.len_utf16();
+    }
+    line_start + line_text.len()
+}
+
+/// Build an LSP position from a one based line and one based column counted in characters
+pub fn lsp_position(text: &str, line: u64, column: u64) -> Value {
+    let line = line.saturating_sub(1);
+    let column = column.saturating_sub(1) as usize;
+    let character: usize = text
+        .lines()
+        .nth(line as usize)
+        .map(|l| l.chars().take(column).map(char::len_utf16).sum())
+        .unwrap_or(0);
+    json!({"line": line, "character": character})
+}
+
+/// Convert an LSP position into a one based line and column counted in characters
+pub fn display_position(text: &str, position: &Value) -> (usize, usize) {
+    let line = position.get("line").and_then(|l| l.as_u64()).unwrap_or(0) as usize;
+    let offset = offset_at(text, position);
+    let line_start = text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line + 1, text[line_start..offset].chars().count() + 1)
+}
+
+/// Apply a list of LSP `TextEdit`s to `text`
+pub fn apply_text_edits(text: &str, edits: &[Value]) -> Result<String> {
+    let mut resolved = Vec::with_capacity(edits.len());
+    for (index, edit) in edits.iter().enumerate() {
+        let range = edit
+            .get("range")
+            .ok_or_else(|| anyhow!("Text edit is missing a range"))?;
+        let start = offset_at(text, &range["start"]);
+        let end = offset_at(text, &range["end"]).max(start);
+        let new_text = edit
+            .get("newText")
+            .and_then(|t| t.as_str())
+            .unwrap_or_default();
+        resolved.push((start, end, index, new_text));
+    }
+
+    // Apply back to front so earlier offsets stay valid; edits sharing a start position are
+    // applied in reverse so they end up in the order the server sent them
+    resolved.sort_by_key(|edit| std::cmp::Reverse((edit.0, edit.2)));
+
+    let mut result = text.to_string();
+    for (start, end, _, new_text) in resolved {
+        result.replace_range(start..end, new_text);
+    }
+    Ok(result)
+}
+
+/// Resolve a URI from the language server to a path, refusing anything outside the
+/// workspace `root` so a server can't touch files the session was not started in
+fn workspace_path(uri: &str, root: &Path) -> Result<PathBuf> {
+    let path = uri_to_path(uri)?;
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve workspace root {}", root.display()))?;
+
+    // Follow symlinks through the closest ancestor that exists; the rest may not exist yet
+    let mut existing = path.as_path();
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        let name = existing
+            .file_name()
+            .ok_or_else(|| anyhow!("Invalid path {}", path.display()))?;
+        missing.push(name);
+        existing = existing
+            .parent()
+            .ok_or_else(|| anyhow!("Invalid path {}", path.display()))?;
+    }
+    let mut resolved = existing.canonicalize()?;
+    resolved.extend(missing.iter().rev());
+
+    if resolved == root || !resolved.starts_with(&root) {
+        return Err(anyhow!(
+            "Refusing to change {}, it is outside the workspace {}",
+            path.display(),
+            root.display()
+        ));
+    }
+    Ok(path)
+}
+
+/// One change from a `WorkspaceEdit`, with its paths already checked
+enum FileChange {
+    Edit(PathBuf, Vec<Value>),
+    Create(PathBuf),
+    Rename(PathBuf, PathBuf),
+    Delete(PathBuf),
+}
+
+fn apply_file_edits(path: &Path, edits: &[Value]) -> Result<()> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let updated = apply_text_edits(&text, edits)?;
+    std::fs::write(path, updated).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Apply an LSP `WorkspaceEdit` to the files on disk, returning the paths that changed.
+/// Nothing is applied if any of the changes falls outside the workspace `root`.
+pub fn apply_workspace_edit(edit: &Value, root: &Path) -> Result<Vec<PathBuf>> {
+    let mut file_changes = Vec::new();
+
+    if let Some(changes) = edit.get("changes").and_then(|c| c.as_object()) {
+        for (uri, edits) in changes {
+            let edits = edits.as_array().cloned().unwrap_or_default();
+            file_changes.push(FileChange::Edit(workspace_path(uri, root)?, edits));
+        }
+    }
+
+    if let Some(document_changes) = edit.get("documentChanges").and_then(|c| c.as_array()) {
+        for change in document_changes {
+            let checked =
+                |key: &str| workspace_path(change[key].as_str().unwrap_or_default(), root);
+            let file_change = match change.get("kind").and_then(|k| k.as_str()) {
+                Some("create") => FileChange::Create(checked("uri")?),
+                Some("rename") => FileChange::Rename(checked("oldUri")?, checked("newUri")?),
+                Some("delete") => FileChange::Delete(checked("uri")?),
+                _ => {
+                    let uri = change["textDocument"]["uri"]
+                        .as_str()
+                        .ok_or_else(|| anyhow!("Document change is missing a uri"))?;
+                    let edits = change
+                        .get("edits")
+                        .and_then(|e| e.as_array())
+                        .cloned()
+                        .unwrap_or_default();
+                    FileChange::Edit(workspace_path(uri, root)?, edits)
+                }
+            };
+            file_changes.push(file_change);
+        }
+    }
+
+    let mut changed = BTreeSet::new();
+    for file_change in file_changes {
+        match file_change {
+            FileChange::Edit(path, edits) => {
+                apply_file_edits(&path, &edits)?;
+                changed.insert(path);
+            }
+            FileChange::Create(path) => {
+                if !path.exists() {
+                    std::fs::write(&path, "")?;
+                }
+                changed.insert(path);
+            }
+            FileChange::Rename(old_path, new_path) => {
+                std::fs::rename(&old_path, &new_path)?;
+                changed.remove(&old_path);
+                changed.insert(new_path);
+            }
+            FileChange::Delete(path) => {
+                if path.is_dir() {
+                    std::fs::remove_dir_all(&path)?;
+                } else if path.exists() {
+                    std::fs::remove_file(&path)?;
+                }
+                changed.remove(&path);
+            }
+        }
+    }
+
+    Ok(changed.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::client::path_to_uri;
+
+    fn edit(start: (u64, u64), end: (u64, u64), new_text: &str) -> Value {
+        json!({
+            "range": {
+                "start": {"line": start.0, "character": start.1},
+                "end": {"line": end.0, "character": end.1}
+            },
+            "newText": new_text
+        })
+    }
+
+    #[test]
+    fn test_offset_at_counts_utf16_units() {
+        let text = "let a = 1;\nlet é🦀 = 2;\n";
+        assert_eq!(offset_at(text, &json!({"line": 0, "character": 4})), 4);
+        // 'é' is one UTF-16 unit, the crab is two
+        let crab_end = offset_at(text, &json!({"line": 1, "character": 7}));
+        assert_eq!(&text[crab_end..crab_end + 2], " =");
+        // Clamped to the end of the line and the end of the document
+        assert_eq!(offset_at(text, &json!({"line": 0, "character": 99})), 10);
+        assert_eq!(
+            offset_at(text, &json!({"line": 9, "character": 0})),
+            text.len()
+        );
+    }
+
+    #[test]
+    fn test_lsp_position_round_trip() {
+        let text = "fn main() {\n    let crab🦀 = x;\n}\n";
+        let position = lsp_position(text, 2, 15);
+        assert_eq!(position, json!({"line": 1, "character": 15}));
+        assert_eq!(display_position(text, &position), (2, 15));
+    }
+
+    #[test]
+    fn test_apply_text_edits_in_any_order() {
+        let text = "let foo = 1;\nprintln!(\"{}\", foo);\n";
+        let edits = vec![edit((0, 4), (0, 7), "bar"), edit((1, 15), (1, 18), "bar")];
+        let expected = "let bar = 1;\nprintln!(\"{}\", bar);\n";
+        assert_eq!(apply_text_edits(text, &edits).unwrap(), expected);
+
+        let reversed: Vec<Value> = edits.into_iter().rev().collect();
+        assert_eq!(apply_text_edits(text, &reversed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_apply_text_edits_keeps_insert_order() {
+        let text = "x\n";
+        let edits = vec![edit((0, 0), (0, 0), "a"), edit((0, 0), (0, 0), "b")];
+        assert_eq!(apply_text_edits(text, &edits).unwrap(), "abx\n");
+    }
+
+    #[test]
+    fn test_apply_workspace_edit() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("a.py");
+        let second = dir.path().join("b.py");
+        std::fs::write(&first, "old = 1\n").unwrap();
+        std::fs::write(&second, "print(old)\n").unwrap();
+
+        let first_uri = path_to_uri(&first).unwrap();
+        let second_uri = path_to_uri(&second).unwrap();
+        let workspace_edit = json!({
+            "changes": {
+                first_uri: [edit((0, 0), (0, 3), "new")]
+            },
+            "documentChanges": [{
+                "textDocument": {"uri": second_uri, "version": 1},
+                "edits": [edit((0, 6), (0, 9), "new")]
+            }]
+        });
+
+        let changed = apply_workspace_edit(&workspace_edit, dir.path()).unwrap();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "new = 1\n");
+        assert_eq!(std::fs::read_to_string(&second).unwrap(), "print(new)\n");
+    }
+
+    #[test]
+    fn test_apply_workspace_edit_stays_in_workspace() {
+        let outside = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let inside = root.path().join("a.py");
+        let victim = outside.path().join("keep.py");
+        std::fs::write(&inside, "old = 1\n").unwrap();
+        std::fs::write(&victim, "keep = 1\n").unwrap();
+
+        let inside_uri = path_to_uri(&inside).unwrap();
+        let victim_uri = path_to_uri(&victim).unwrap();
+        let root_uri = path_to_uri(root.path()).unwrap();
+        let rejected = [
+            json!({"kind": "create", "uri": path_to_uri(&outside.path().join("new.py")).unwrap()}),
+            json!({"kind": "rename", "oldUri": victim_uri, "newUri": inside_uri}),
+            json!({"kind": "rename", "oldUri": inside_uri, "newUri": victim_uri}),
+            json!({"kind": "delete", "uri": victim_uri}),
+            json!({"kind": "delete", "uri": root_uri}),
+        ];
+        for change in rejected {
+            // The edit inside the workspace is not applied either
+            let workspace_edit = json!({
+                "changes": {inside_uri.clone(): [edit((0, 0), (0, 3), "new")]},
+                "documentChanges": [change]
+            });
+            assert!(apply_workspace_edit(&workspace_edit, root.path()).is_err());
+            assert_eq!(std::fs::read_to_string(&inside).unwrap(), "old = 1\n");
+        }
+        assert_eq!(std::fs::read_to_string(&victim).unwrap(), "keep = 1\n");
+        assert!(!outside.path().join("new.py").exists());
+
+        let created = root.path().join("b.py");
+        let workspace_edit = json!({
+            "documentChanges": [{"kind": "create", "uri": path_to_uri(&created).unwrap()}]
+        });
+        let changed = apply_workspace_edit(&workspace_edit, root.path()).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert!(created.exists());
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Result};
 use clap::Parser;
 use goose::agents::{Agent, SessionConfig};
-use goose::config::Config;
+use goose::config::{project_config_for, Config};
 use goose::message::Message;
 use goose::providers::create;
 use goose::recipe::Recipe;
@@ -86,7 +86,7 @@ async fn execute_recipe(job_id: &str, recipe_path: &str) -> Result<String> {
         anyhow!("GOOSE_MODEL not configured. Run 'goose configure' or set env var.")
     })?;
 
-    let model_config = goose::model::ModelConfig::new(model_name);
+    let model_config = goose::model::ModelConfig::from_config(model_name);
     let provider = create(&provider_name, model_config)
         .map_err(|e| anyhow!("Failed to create provider '{}': {}", provider_name, e))?;
 
@@ -137,6 +137,7 @@ async fn execute_recipe(job_id: &str, recipe_path: &str) -> Result<String> {
     let session_config = SessionConfig {
         id: goose::session::storage::Identifier::Name(session_id.clone()),
         working_dir: current_dir.clone(),
+        project_config: project_config_for(&current_dir),
         schedule_id: Some(job_id.to_string()),
     };
 
@@ -165,6 +166,19 @@ async fn execute_recipe(job_id: &str, recipe_path: &str) -> Result<String> {
             Ok(AgentEvent::McpNotification(_)) => {
                 // Handle notifications if needed
             }
+            Ok(AgentEvent::ContextCompacted(compaction)) => {
+                info!(
+                    "[Job {}] Context compacted from {} to {} tokens",
+                    job_id, compaction.tokens_before, compaction.tokens_after
+                );
+                messages = compaction.messages;
+            }
+            Ok(AgentEvent::ProviderError(_)) => {
+                // The previous message describes the error
+            }
+            Ok(AgentEvent::ToolDenied(_)) => {
+                // The tool response that follows says so
+            }
             Err(e) => {
                 return Err(anyhow!("Error receiving message from agent: {}", e));
             }
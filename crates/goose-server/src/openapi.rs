@@ -4,12 +4,14 @@ use goose::agents::ExtensionConfig;
 use goose::config::permission::PermissionLevel;
 use goose::config::ExtensionEntry;
 use goose::message::{
-    ContextLengthExceeded, FrontendToolRequest, Message, MessageContent, RedactedThinkingContent,
-    SummarizationRequested, ThinkingContent, ToolConfirmationRequest, ToolRequest, ToolResponse,
+    ContextLengthExceeded, DocumentContent, FrontendToolRequest, Message, MessageContent,
+    RedactedThinkingContent, SummarizationRequested, ThinkingContent, ToolConfirmationRequest,
+    ToolRequest, ToolResponse,
 };
 use goose::permission::permission_confirmation::PrincipalType;
 use goose::providers::base::{ConfigKey, ModelInfo, ProviderMetadata};
 use goose::session::info::SessionInfo;
+use goose::session::SearchHit;
 use goose::session::SessionMetadata;
 use mcp_core::content::{Annotations, Content, EmbeddedResource, ImageContent, TextContent};
 use mcp_core::handler::ToolResultSchema;
@@ -33,11 +35,19 @@ use utoipa::OpenApi;
         super::routes::config_management::read_all_config,
         super::routes::config_management::providers,
         super::routes::config_management::upsert_permissions,
+        super::routes::config_management::read_project_config,
+        super::routes::config_management::trust_project_config,
         super::routes::agent::get_tools,
         super::routes::reply::confirm_permission,
+        super::routes::reply::upload_attachments,
         super::routes::context::manage_context,
         super::routes::session::list_sessions,
+        super::routes::session::search_sessions,
         super::routes::session::get_session_history,
+        super::routes::session::rewind_session,
+        super::routes::session::fork_session,
+        super::routes::session::edit_session,
+        super::routes::session::retry_session,
         super::routes::schedule::create_schedule,
         super::routes::schedule::list_schedules,
         super::routes::schedule::delete_schedule,
@@ -59,16 +69,28 @@ use utoipa::OpenApi;
         super::routes::config_management::ExtensionQuery,
         super::routes::config_management::ToolPermission,
         super::routes::config_management::UpsertPermissionsQuery,
+        super::routes::config_management::ProjectConfigQuery,
+        super::routes::config_management::ProjectConfigResponse,
         super::routes::reply::PermissionConfirmationRequest,
+        super::routes::reply::AttachmentsUpload,
+        super::routes::reply::AttachmentsResponse,
         super::routes::context::ContextManageRequest,
         super::routes::context::ContextManageResponse,
         super::routes::session::SessionListResponse,
+        super::routes::session::SessionSearchResponse,
+        SearchHit,
         super::routes::session::SessionHistoryResponse,
+        super::routes::session::RewindRequest,
+        super::routes::session::ForkRequest,
+        super::routes::session::EditRequest,
+        super::routes::session::RetryRequest,
+        super::routes::session::SessionRewriteResponse,
         Message,
         MessageContent,
         Content,
         EmbeddedResource,
         ImageContent,
+        DocumentContent,
         Annotations,
         TextContent,
         ToolResponse,
@@ -207,7 +207,7 @@ async fn update_agent_provider(
             .get_param("GOOSE_MODEL")
             .expect("Did not find a model on payload or in env to update provider with")
     });
-    let model_config = ModelConfig::new(model);
+    let model_config = ModelConfig::from_config(model);
     let new_provider = create(&payload.provider, model_config).unwrap();
     agent
         .update_provider(new_provider)
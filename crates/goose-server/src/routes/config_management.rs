@@ -7,9 +7,9 @@ use axum::{
     Json, Router,
 };
 use etcetera::{choose_app_strategy, AppStrategy};
-use goose::config::Config;
 use goose::config::APP_STRATEGY;
 use goose::config::{extensions::name_to_key, PermissionManager};
+use goose::config::{Config, ConfigError};
 use goose::config::{ExtensionConfigManager, ExtensionEntry};
 use goose::model::ModelConfig;
 use goose::providers::base::ProviderMetadata;
@@ -19,6 +19,7 @@ use http::{HeaderMap, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_yaml;
+use std::path::Path;
 use std::{collections::HashMap, sync::Arc};
 use utoipa::ToSchema;
 
@@ -403,6 +404,80 @@ pub async fn upsert_permissions(
     Ok(Json("Permissions updated successfully".to_string()))
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct ProjectConfigQuery {
+    /// Working directory of the session, the project configuration is found in it or a parent
+    pub working_dir: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProjectConfigResponse {
+    /// Path of the project configuration file, if the working directory has one
+    pub path: Option<String>,
+    pub trusted: bool,
+    /// Settings that are ignored until the project configuration is trusted
+    pub untrusted_keys: Vec<String>,
+}
+
+fn project_config_response(config: &Config) -> Result<ProjectConfigResponse, StatusCode> {
+    Ok(ProjectConfigResponse {
+        path: config
+            .project_path()
+            .map(|path| path.to_string_lossy().to_string()),
+        trusted: config.is_project_trusted(),
+        untrusted_keys: config
+            .untrusted_project_keys()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/config/project",
+    request_body = ProjectConfigQuery,
+    responses(
+        (status = 200, description = "Project configuration of the working directory", body = ProjectConfigResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn read_project_config(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(query): Json<ProjectConfigQuery>,
+) -> Result<Json<ProjectConfigResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    // A copy for this working directory, leaving the global config and other sessions alone
+    let config = Config::global().for_dir(Path::new(&query.working_dir));
+    Ok(Json(project_config_response(&config)?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/config/project/trust",
+    request_body = ProjectConfigQuery,
+    responses(
+        (status = 200, description = "Project configuration trusted as it is now", body = ProjectConfigResponse),
+        (status = 404, description = "The working directory has no project configuration"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn trust_project_config(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(query): Json<ProjectConfigQuery>,
+) -> Result<Json<ProjectConfigResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    // A copy for this working directory, leaving the global config and other sessions alone
+    let config = Config::global().for_dir(Path::new(&query.working_dir));
+    match config.trust_project() {
+        Ok(()) => Ok(Json(project_config_response(&config)?)),
+        Err(ConfigError::NotFound(_)) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/config/backup",
@@ -454,6 +529,8 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/config/init", post(init_config))
         .route("/config/backup", post(backup_config))
         .route("/config/permissions", post(upsert_permissions))
+        .route("/config/project", post(read_project_config))
+        .route("/config/project/trust", post(trust_project_config))
         .with_state(state)
 }
 
@@ -1,7 +1,7 @@
 use super::utils::verify_secret_key;
 use crate::state::AppState;
 use axum::{
-    extract::State,
+    extract::{DefaultBodyLimit, Multipart, State},
     http::{self, HeaderMap, StatusCode},
     response::IntoResponse,
     routing::post,
@@ -10,7 +10,9 @@ use axum::{
 use bytes::Bytes;
 use futures::{stream::StreamExt, Stream};
 use goose::{
-    agents::{AgentEvent, SessionConfig},
+    agents::{AgentEvent, CompactionStrategy, SessionConfig},
+    attachments::{attachment_from_bytes, MAX_ATTACHMENT_BYTES},
+    config::project_config_for,
     message::{Message, MessageContent},
     permission::permission_confirmation::PrincipalType,
 };
@@ -24,7 +26,7 @@ use serde_json::json;
 use serde_json::Value;
 use std::{
     convert::Infallible,
-    path::PathBuf,
+    path::{Path, PathBuf},
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
@@ -92,6 +94,13 @@ enum MessageEvent {
         request_id: String,
         message: JsonRpcMessage,
     },
+    /// The agent compacted the conversation; `messages` replaces the client's history
+    ContextCompacted {
+        strategy: CompactionStrategy,
+        tokens_before: usize,
+        tokens_after: usize,
+        messages: Vec<Message>,
+    },
 }
 
 async fn stream_event(
@@ -176,7 +185,8 @@ async fn handler(
                 &messages,
                 Some(SessionConfig {
                     id: session::Identifier::Name(session_id.clone()),
-                    working_dir: PathBuf::from(session_working_dir),
+                    working_dir: PathBuf::from(&session_working_dir),
+                    project_config: project_config_for(Path::new(&session_working_dir)),
                     schedule_id: None,
                 }),
             )
@@ -247,6 +257,40 @@ async fn handler(
                                 ).await;
                             }
                         }
+                        Ok(Some(Ok(AgentEvent::ContextCompacted(compaction)))) => {
+                            all_messages = compaction.messages.clone();
+                            if let Err(e) = stream_event(MessageEvent::ContextCompacted {
+                                strategy: compaction.strategy,
+                                tokens_before: compaction.tokens_before,
+                                tokens_after: compaction.tokens_after,
+                                messages: compaction.messages,
+                            }, &tx).await {
+                                tracing::error!("Error sending message through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                                break;
+                            }
+
+                            let session_path = session_path.clone();
+                            let messages = all_messages.clone();
+                            let provider = Arc::clone(provider.as_ref().unwrap());
+                            tokio::spawn(async move {
+                                if let Err(e) = session::persist_messages(&session_path, &messages, Some(provider)).await {
+                                    tracing::error!("Failed to store session history: {:?}", e);
+                                }
+                            });
+                        }
+                        Ok(Some(Ok(AgentEvent::ProviderError(e)))) => {
+                            // The previous message tells the user about it
+                            tracing::error!("Provider error: {}", e);
+                        }
+                        Ok(Some(Ok(AgentEvent::ToolDenied(_)))) => {
+                            // The tool response that follows says so
+                        }
                         Ok(Some(Err(e))) => {
                             tracing::error!("Error processing message: {}", e);
                             let _ = stream_event(
@@ -323,7 +367,8 @@ async fn ask_handler(
             &messages,
             Some(SessionConfig {
                 id: session::Identifier::Name(session_id.clone()),
-                working_dir: PathBuf::from(session_working_dir),
+                working_dir: PathBuf::from(&session_working_dir),
+                project_config: project_config_for(Path::new(&session_working_dir)),
                 schedule_id: None,
             }),
         )
@@ -356,6 +401,15 @@ async fn ask_handler(
                 // Handle notifications if needed
                 tracing::info!("Received notification: {:?}", n);
             }
+            Ok(AgentEvent::ContextCompacted(compaction)) => {
+                // The compacted history already holds the responses so far
+                all_messages = compaction.messages;
+                response_message = Message::assistant();
+            }
+            Ok(AgentEvent::ProviderError(e)) => {
+                tracing::error!("Provider error: {}", e);
+            }
+            Ok(AgentEvent::ToolDenied(_)) => {}
             Err(e) => {
                 tracing::error!("Error processing as_ai message: {}", e);
                 return Err(StatusCode::INTERNAL_SERVER_ERROR);
@@ -474,9 +528,87 @@ async fn submit_tool_result(
     Ok(Json(json!({"status": "ok"})))
 }
 
+/// Files sent to `/reply/attachments` as `multipart/form-data`
+#[derive(ToSchema)]
+#[allow(dead_code)] // Only describes the form for OpenAPI generation
+pub struct AttachmentsUpload {
+    /// The files to attach; every part of the form is read as a file
+    #[schema(value_type = Vec<String>, format = Binary)]
+    pub files: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AttachmentsResponse {
+    /// Message content for each file that could be attached, to send with the next message
+    content: Vec<MessageContent>,
+    /// Why the other files could not be attached
+    errors: Vec<String>,
+}
+
+/// Turn uploaded files into message content for `/reply`
+///
+/// Images are downscaled and PDFs have their text extracted, so the client can add the result
+/// to a user message.
+#[utoipa::path(
+    post,
+    path = "/reply/attachments",
+    request_body(content = AttachmentsUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Files converted to message content", body = AttachmentsResponse),
+        (status = 400, description = "Malformed multipart body"),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 413, description = "Upload too large")
+    )
+)]
+pub async fn upload_attachments(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<Json<AttachmentsResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let mut response = AttachmentsResponse {
+        content: Vec::new(),
+        errors: Vec::new(),
+    };
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?
+    {
+        let name = field.file_name().map(str::to_string);
+        let mime_type = field.content_type().map(str::to_string);
+        let bytes = field.bytes().await.map_err(|_| StatusCode::BAD_REQUEST)?;
+
+        // Decoding images and extracting document text is CPU bound, keep it off the runtime
+        let field_name = name.clone();
+        let attachment = tokio::task::spawn_blocking(move || {
+            attachment_from_bytes(&bytes, field_name.as_deref(), mime_type.as_deref())
+        })
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        match attachment {
+            Ok(content) => response.content.push(content),
+            Err(e) => response.errors.push(format!(
+                "{}: {}",
+                name.as_deref().unwrap_or("attachment"),
+                e
+            )),
+        }
+    }
+
+    Ok(Json(response))
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/reply", post(handler))
+        .route(
+            "/reply/attachments",
+            post(upload_attachments)
+                .layer(DefaultBodyLimit::max(MAX_ATTACHMENT_BYTES + 1024 * 1024)),
+        )
         .route("/ask", post(ask_handler))
         .route("/confirm", post(confirm_permission))
         .route("/tool_result", post(submit_tool_result))
@@ -568,5 +700,50 @@ mod tests {
 
             assert_eq!(response.status(), StatusCode::OK);
         }
+
+        #[tokio::test]
+        async fn test_upload_attachments() {
+            let state = AppState::new(Arc::new(Agent::new()), "test-secret".to_string()).await;
+            let app = routes(state);
+
+            let boundary = "goose-boundary";
+            let body = format!(
+                "--{b}\r\n\
+                 Content-Disposition: form-data; name=\"file\"; filename=\"notes.txt\"\r\n\
+                 Content-Type: text/plain\r\n\r\n\
+                 remember the milk\r\n\
+                 --{b}\r\n\
+                 Content-Disposition: form-data; name=\"file\"; filename=\"blob.bin\"\r\n\
+                 Content-Type: application/octet-stream\r\n\r\n\
+                 \u{1}\u{2}\r\n\
+                 --{b}--\r\n",
+                b = boundary
+            );
+            let request = Request::builder()
+                .uri("/reply/attachments")
+                .method("POST")
+                .header(
+                    "content-type",
+                    format!("multipart/form-data; boundary={}", boundary),
+                )
+                .header("x-secret-key", "test-secret")
+                .body(Body::from(body))
+                .unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let json: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["content"][0]["type"], "text");
+            assert_eq!(
+                json["content"][0]["text"],
+                "<file name=\"notes.txt\">\nremember the milk\n</file>"
+            );
+            assert_eq!(json["errors"].as_array().unwrap().len(), 1);
+            assert!(json["errors"][0].as_str().unwrap().starts_with("blob.bin"));
+        }
     }
 }
@@ -3,23 +3,82 @@ use std::sync::Arc;
 
 use crate::state::AppState;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
+use chrono::{DateTime, Utc};
+use goose::config::Config;
 use goose::message::Message;
+use goose::model::ModelConfig;
+use goose::providers::base::Provider;
+use goose::providers::{create, providers};
 use goose::session;
-use goose::session::info::{get_session_info, SessionInfo, SortOrder};
-use goose::session::SessionMetadata;
-use serde::Serialize;
-use utoipa::ToSchema;
+use goose::session::info::{SessionInfo, SortOrder};
+use goose::session::rewrite::{edit_prompt, is_prompt, prompt_index};
+use goose::session::search::{
+    self, embedding_provider, semantic_search_sessions, SearchHit, SearchQuery,
+    DEFAULT_SEARCH_LIMIT,
+};
+use goose::session::{query_sessions, SessionMetadata, SessionPage, SessionQuery};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use utoipa::{IntoParams, ToSchema};
 
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionListResponse {
     /// List of available session information objects
     sessions: Vec<SessionInfo>,
+    /// Number of sessions matching the filters, ignoring limit and offset
+    total: usize,
+}
+
+// Query parameters for filtering and paging the session list
+#[derive(Deserialize, ToSchema, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionListQuery {
+    /// Only sessions active at or after this time
+    since: Option<DateTime<Utc>>,
+    /// Only sessions last active before this time
+    until: Option<DateTime<Utc>>,
+    /// Only sessions started in this working directory
+    working_dir: Option<String>,
+    /// Only sessions created by this schedule
+    schedule_id: Option<String>,
+    /// Only sessions whose description contains this text, ignoring case
+    description: Option<String>,
+    /// List the oldest sessions first
+    #[serde(default)]
+    ascending: bool,
+    /// Maximum number of sessions to return
+    limit: Option<usize>,
+    /// Number of matching sessions to skip
+    #[serde(default)]
+    offset: usize,
+}
+
+// Query parameters for searching past sessions
+#[derive(Deserialize, ToSchema, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchQuery {
+    /// Words to search for
+    query: String,
+    /// Only search sessions started in this working directory
+    working_dir: Option<String>,
+    /// Maximum number of results
+    limit: Option<usize>,
+    /// Search by meaning using the provider's embeddings instead of matching words
+    #[serde(default)]
+    semantic: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchResponse {
+    /// Matching messages, best matches first
+    results: Vec<SearchHit>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -33,9 +92,59 @@ pub struct SessionHistoryResponse {
     messages: Vec<Message>,
 }
 
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkRequest {
+    /// Number of messages to copy from the original session
+    at: usize,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RewindRequest {
+    /// Number of turns to undo, counting back from the most recent
+    turns: usize,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EditRequest {
+    /// Index of the user message to replace
+    message_index: usize,
+    /// New text for the message; attachments on the original message are kept
+    text: String,
+    /// Provider to use from now on, instead of the current one
+    provider: Option<String>,
+    /// Model to use from now on, instead of the current one
+    model: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryRequest {
+    /// Provider to use from now on, instead of the current one
+    provider: Option<String>,
+    /// Model to use from now on, instead of the current one
+    model: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRewriteResponse {
+    /// Unique identifier for the session
+    session_id: String,
+    /// Session metadata containing creation time and other details
+    metadata: SessionMetadata,
+    /// The conversation up to and including the prompt to reply to
+    messages: Vec<Message>,
+    /// Session the replaced messages were saved to, if any were replaced
+    branch_session_id: Option<String>,
+}
+
 #[utoipa::path(
     get,
     path = "/sessions",
+    params(SessionListQuery),
     responses(
         (status = 200, description = "List of available sessions retrieved successfully", body = SessionListResponse),
         (status = 401, description = "Unauthorized - Invalid or missing API key"),
@@ -46,17 +155,78 @@ pub struct SessionHistoryResponse {
     ),
     tag = "Session Management"
 )]
-// List all available sessions
+// List available sessions, newest first unless asked otherwise
 async fn list_sessions(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
+    Query(query): Query<SessionListQuery>,
 ) -> Result<Json<SessionListResponse>, StatusCode> {
     verify_secret_key(&headers, &state)?;
 
-    let sessions =
-        get_session_info(SortOrder::Descending).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let query = SessionQuery {
+        since: query.since,
+        until: query.until,
+        working_dir: query.working_dir.map(PathBuf::from),
+        schedule_id: query.schedule_id,
+        description: query.description,
+        order: if query.ascending {
+            SortOrder::Ascending
+        } else {
+            SortOrder::Descending
+        },
+        limit: query.limit,
+        offset: query.offset,
+    };
+    let SessionPage { sessions, total } = query_sessions(&query).map_err(|e| {
+        tracing::error!("Failed to list sessions: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(SessionListResponse { sessions, total }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/search",
+    params(SessionSearchQuery),
+    responses(
+        (status = 200, description = "Messages matching the search", body = SessionSearchResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Search the messages of past sessions
+async fn search_sessions(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<SessionSearchQuery>,
+) -> Result<Json<SessionSearchResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let search = SearchQuery {
+        text: query.query,
+        working_dir: query.working_dir.map(PathBuf::from),
+        limit: query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+    };
+    let results = if query.semantic {
+        let provider = embedding_provider().map_err(|e| {
+            tracing::error!("Failed to create embedding provider: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        semantic_search_sessions(&search, &provider).await
+    } else {
+        search::search_sessions(&search)
+    }
+    .map_err(|e| {
+        tracing::error!("Failed to search sessions: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    Ok(Json(SessionListResponse { sessions }))
+    Ok(Json(SessionSearchResponse { results }))
 }
 
 #[utoipa::path(
@@ -104,10 +274,307 @@ async fn get_session_history(
     }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/rewind",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    request_body = RewindRequest,
+    responses(
+        (status = 200, description = "Files restored and session trimmed to the checkpoint", body = SessionHistoryResponse),
+        (status = 400, description = "No checkpoint available that far back"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Undo the last turns of a session, restoring the working directory from its checkpoint
+async fn rewind_session(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+    Json(request): Json<RewindRequest>,
+) -> Result<Json<SessionHistoryResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let session_path = session::get_path(session::Identifier::Name(session_id.clone()));
+    if !session_path.exists() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let messages = match session::rewind_session(&session_path, request.turns).await {
+        Ok(messages) => messages,
+        Err(e) => {
+            tracing::error!("Failed to rewind session: {:?}", e);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+    let metadata = session::read_metadata(&session_path).map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Json(SessionHistoryResponse {
+        session_id,
+        metadata,
+        messages,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/fork",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session to fork")
+    ),
+    request_body = ForkRequest,
+    responses(
+        (status = 200, description = "New session created from the start of the original", body = SessionHistoryResponse),
+        (status = 400, description = "The session can't be forked at that message"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Start a new session from the first messages of an existing one, leaving the original as is
+async fn fork_session(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+    Json(request): Json<ForkRequest>,
+) -> Result<Json<SessionHistoryResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let session_path = session::get_path(session::Identifier::Name(session_id));
+    if !session_path.exists() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let (fork_id, fork_path) = session::fork_session(&session_path, request.at).map_err(|e| {
+        tracing::error!("Failed to fork session: {:?}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let metadata = session::read_metadata(&fork_path).map_err(|_| StatusCode::NOT_FOUND)?;
+    let messages = session::read_messages(&fork_path).map_err(|e| {
+        tracing::error!("Failed to read forked session: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(SessionHistoryResponse {
+        session_id: fork_id,
+        metadata,
+        messages,
+    }))
+}
+
+// Create the provider a rewritten session continues with, keeping the agent's reasoning
+// settings. A model without a provider stays with the configured provider; a provider without a
+// model uses that provider's default model. None keeps the agent's current provider.
+async fn requested_provider(
+    state: &AppState,
+    provider: Option<String>,
+    model: Option<String>,
+) -> Result<Option<Arc<dyn Provider>>, StatusCode> {
+    if provider.is_none() && model.is_none() {
+        return Ok(None);
+    }
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    let provider = match provider {
+        Some(provider) => provider,
+        None => Config::global()
+            .get_param("GOOSE_PROVIDER")
+            .map_err(|_| StatusCode::BAD_REQUEST)?,
+    };
+    let model = match model {
+        Some(model) => model,
+        None => providers()
+            .into_iter()
+            .find(|metadata| metadata.name == provider)
+            .map(|metadata| metadata.default_model)
+            .ok_or(StatusCode::BAD_REQUEST)?,
+    };
+
+    let mut model_config = ModelConfig::from_config(model);
+    if let Ok(current) = agent.provider().await {
+        let current = current.get_model_config();
+        model_config = model_config
+            .with_thinking_budget(current.thinking_budget)
+            .with_reasoning_effort(current.reasoning_effort);
+    }
+    create(&provider, model_config).map(Some).map_err(|e| {
+        tracing::error!("Failed to create provider {}: {:?}", provider, e);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+// Point the agent at the provider from `requested_provider`, once the rewrite has succeeded
+async fn switch_model(
+    state: &AppState,
+    provider: Option<Arc<dyn Provider>>,
+) -> Result<(), StatusCode> {
+    let Some(provider) = provider else {
+        return Ok(());
+    };
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+    agent
+        .update_provider(provider)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+// Cut a session back to its first `keep` messages, add `prompt` if given and describe the result
+async fn rewrite(
+    session_id: String,
+    session_path: &std::path::Path,
+    keep: usize,
+    prompt: Option<Message>,
+) -> Result<Json<SessionRewriteResponse>, StatusCode> {
+    let rewrite = session::rewrite_session(session_path, keep).map_err(|e| {
+        tracing::error!("Failed to rewrite session: {:?}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let mut messages = rewrite.messages;
+    if let Some(prompt) = prompt {
+        messages.push(prompt);
+        session::persist_messages(session_path, &messages, None)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to save session: {:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+    let metadata = session::read_metadata(session_path).map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Json(SessionRewriteResponse {
+        session_id,
+        metadata,
+        messages,
+        branch_session_id: rewrite.branch_id,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/edit",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    request_body = EditRequest,
+    responses(
+        (status = 200, description = "Session rewritten to end with the edited message; send the messages to /reply to continue", body = SessionRewriteResponse),
+        (status = 400, description = "The message can't be edited, or the provider can't be used"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Replace an earlier user message and drop everything after it, keeping the replaced
+// conversation as a branch of the session
+async fn edit_session(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+    Json(request): Json<EditRequest>,
+) -> Result<Json<SessionRewriteResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let session_path = session::get_path(session::Identifier::Name(session_id.clone()));
+    if !session_path.exists() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if request.text.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let messages = session::read_messages(&session_path).map_err(|e| {
+        tracing::error!("Failed to read session messages: {:?}", e);
+        StatusCode::NOT_FOUND
+    })?;
+    let original = messages
+        .get(request.message_index)
+        .filter(|message| is_prompt(message))
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let edited = edit_prompt(original, request.text.trim());
+
+    let provider = requested_provider(&state, request.provider, request.model).await?;
+    let response = rewrite(
+        session_id,
+        &session_path,
+        request.message_index,
+        Some(edited),
+    )
+    .await?;
+    switch_model(&state, provider).await?;
+    Ok(response)
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/retry",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    request_body = RetryRequest,
+    responses(
+        (status = 200, description = "Session rewritten to end with the last user message; send the messages to /reply to regenerate the reply", body = SessionRewriteResponse),
+        (status = 400, description = "There is no reply to retry, or the provider can't be used"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Drop the reply to the last user message, keeping it as a branch of the session
+async fn retry_session(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+    Json(request): Json<RetryRequest>,
+) -> Result<Json<SessionRewriteResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let session_path = session::get_path(session::Identifier::Name(session_id.clone()));
+    if !session_path.exists() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let messages = session::read_messages(&session_path).map_err(|e| {
+        tracing::error!("Failed to read session messages: {:?}", e);
+        StatusCode::NOT_FOUND
+    })?;
+    let index = prompt_index(&messages, 1).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let provider = requested_provider(&state, request.provider, request.model).await?;
+    let response = rewrite(session_id, &session_path, index + 1, None).await?;
+    switch_model(&state, provider).await?;
+    Ok(response)
+}
+
 // Configure routes for this module
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/sessions", get(list_sessions))
+        .route("/sessions/search", get(search_sessions))
         .route("/sessions/{session_id}", get(get_session_history))
+        .route("/sessions/{session_id}/rewind", post(rewind_session))
+        .route("/sessions/{session_id}/fork", post(fork_session))
+        .route("/sessions/{session_id}/edit", post(edit_session))
+        .route("/sessions/{session_id}/retry", post(retry_session))
         .with_state(state)
 }
@@ -10,30 +10,38 @@ use futures_util::stream;
 use futures_util::stream::StreamExt;
 use mcp_core::protocol::JsonRpcMessage;
 
+use crate::audit::{Approver, AuditScope, Decision};
+use crate::config::permission::PermissionConfig;
 use crate::config::{Config, ExtensionConfigManager, PermissionManager};
+use crate::context_mgmt::truncate::TruncationStrategyKind;
 use crate::message::Message;
 use crate::permission::permission_judge::check_tool_permissions;
-use crate::permission::PermissionConfirmation;
+use crate::permission::{ApprovalPolicy, PermissionConfirmation};
 use crate::providers::base::Provider;
 use crate::providers::errors::ProviderError;
 use crate::recipe::{Author, Recipe};
+use crate::redaction::{Redactor, SessionRedactor};
 use crate::tool_monitor::{ToolCall, ToolMonitor};
 use regex::Regex;
 use serde_json::Value;
 use tokio::sync::{mpsc, Mutex};
 use tracing::{debug, error, instrument};
 
+use crate::agents::context::{AutoCompactor, ContextCompaction};
 use crate::agents::extension::{ExtensionConfig, ExtensionError, ExtensionResult, ToolInfo};
 use crate::agents::extension_manager::{get_parameter_names, ExtensionManager};
+use crate::agents::large_response_handler::LargeResponseHandler;
 use crate::agents::platform_tools::{
     PLATFORM_LIST_RESOURCES_TOOL_NAME, PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME,
-    PLATFORM_READ_RESOURCE_TOOL_NAME, PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME,
+    PLATFORM_READ_RESOURCE_TOOL_NAME, PLATFORM_READ_RESPONSE_PAGE_TOOL_NAME,
+    PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME,
 };
 use crate::agents::prompt_manager::PromptManager;
 use crate::agents::router_tool_selector::{
     create_tool_selector, RouterToolSelectionStrategy, RouterToolSelector,
 };
 use crate::agents::router_tools::ROUTER_VECTOR_SEARCH_TOOL_NAME;
+use crate::agents::structured_output::{OutputStep, StructuredOutput};
 use crate::agents::tool_router_index_manager::ToolRouterIndexManager;
 use crate::agents::tool_vectordb::generate_table_id;
 use crate::agents::types::SessionConfig;
@@ -44,7 +52,9 @@ use mcp_core::{
 
 use super::platform_tools;
 use super::router_tools;
-use super::tool_execution::{ToolCallResult, CHAT_MODE_TOOL_SKIPPED_RESPONSE, DECLINED_RESPONSE};
+use super::tool_execution::{
+    audited_tool_stream, ToolCallResult, CHAT_MODE_TOOL_SKIPPED_RESPONSE, DECLINED_RESPONSE,
+};
 
 /// The main goose Agent
 pub struct Agent {
@@ -59,12 +69,23 @@ pub struct Agent {
     pub(super) tool_result_rx: ToolResultReceiver,
     pub(super) tool_monitor: Mutex<Option<ToolMonitor>>,
     pub(super) router_tool_selector: Mutex<Option<Arc<Box<dyn RouterToolSelector>>>>,
+    pub(super) large_responses: Arc<LargeResponseHandler>,
+    pub(super) truncation_strategy: Mutex<TruncationStrategyKind>,
+    pub(super) structured_output: Mutex<Option<StructuredOutput>>,
+    pub(super) approval_policy: Mutex<Option<ApprovalPolicy>>,
 }
 
 #[derive(Clone, Debug)]
 pub enum AgentEvent {
     Message(Message),
     McpNotification((String, JsonRpcMessage)),
+    ContextCompacted(ContextCompaction),
+    /// The provider failed and the reply ended. It comes right after the assistant message
+    /// that describes the error for the user.
+    ProviderError(String),
+    /// The agent refused a tool call without asking, by permission setting or approval
+    /// policy. Holds the request id; the tool response saying so follows.
+    ToolDenied(String),
 }
 
 impl Agent {
@@ -85,6 +106,10 @@ impl Agent {
             tool_result_rx: Arc::new(Mutex::new(tool_rx)),
             tool_monitor: Mutex::new(None),
             router_tool_selector: Mutex::new(None),
+            large_responses: Arc::new(LargeResponseHandler::from_config()),
+            truncation_strategy: Mutex::new(TruncationStrategyKind::from_config()),
+            structured_output: Mutex::new(None),
+            approval_policy: Mutex::new(None),
         }
     }
 
@@ -93,6 +118,16 @@ impl Agent {
         *tool_monitor = Some(ToolMonitor::new(max_repetitions));
     }
 
+    /// Choose how the conversation is truncated when it outgrows the context window
+    pub async fn set_truncation_strategy(&self, strategy: TruncationStrategyKind) {
+        *self.truncation_strategy.lock().await = strategy;
+    }
+
+    /// Answer tool confirmations from `policy` instead of asking. `None` goes back to asking.
+    pub async fn set_approval_policy(&self, policy: Option<ApprovalPolicy>) {
+        *self.approval_policy.lock().await = policy;
+    }
+
     pub async fn get_tool_stats(&self) -> Option<HashMap<String, u32>> {
         let tool_monitor = self.tool_monitor.lock().await;
         tool_monitor.as_ref().map(|monitor| monitor.get_stats())
@@ -183,12 +218,23 @@ impl Agent {
     }
 
     /// Dispatch a single tool call to the appropriate client
-    #[instrument(skip(self, tool_call, request_id), fields(input, output))]
+    #[instrument(skip(self, tool_call, request_id, redactor), fields(input, output))]
     pub(super) async fn dispatch_tool_call(
         &self,
         tool_call: mcp_core::tool::ToolCall,
         request_id: String,
+        redactor: Option<&SessionRedactor>,
     ) -> (String, Result<ToolCallResult, ToolError>) {
+        // Give the tools the user allows the real values behind any redaction placeholders the
+        // model passed along. Other tools keep the placeholders, so secrets can't leak through them.
+        let tool_call = match redactor {
+            Some(redactor) if redactor.restores_for(&tool_call.name) => mcp_core::tool::ToolCall {
+                arguments: redactor.restore_value(&tool_call.arguments),
+                ..tool_call
+            },
+            _ => tool_call,
+        };
+
         // Check if this tool call should be allowed based on repetition monitoring
         if let Some(monitor) = self.tool_monitor.lock().await.as_mut() {
             let tool_call_info = ToolCall::new(tool_call.name.clone(), tool_call.arguments.clone());
@@ -223,6 +269,11 @@ impl Agent {
             return (request_id, Ok(ToolCallResult::from(result)));
         }
 
+        if tool_call.name == PLATFORM_READ_RESPONSE_PAGE_TOOL_NAME {
+            let result = self.large_responses.read_page(tool_call.arguments.clone());
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
         let extension_manager = self.extension_manager.lock().await;
         let result: ToolCallResult = if tool_call.name == PLATFORM_READ_RESOURCE_TOOL_NAME {
             // Check if the tool is read_resource and handle it separately
@@ -264,6 +315,11 @@ impl Agent {
             }
         };
 
+        // Oversized output is handled after redaction so spilled files never hold raw secrets
+        let large_responses = Arc::clone(&self.large_responses);
+        let provider = self.provider().await.ok();
+        let tool_name = tool_call.name.clone();
+        let redactor = redactor.cloned();
         (
             request_id,
             Ok(ToolCallResult {
@@ -271,7 +327,16 @@ impl Agent {
                 result: Box::new(
                     result
                         .result
-                        .map(super::large_response_handler::process_tool_response),
+                        .map(move |response| match &redactor {
+                            Some(redactor) => redactor.redact_tool_result(response),
+                            None => response,
+                        })
+                        .then(move |response| async move {
+                            large_responses
+                                .process(&tool_name, response, provider)
+                                .await
+                        })
+                        .boxed(),
                 ),
             }),
         )
@@ -433,6 +498,10 @@ impl Agent {
             prefixed_tools.push(platform_tools::search_available_extensions_tool());
             prefixed_tools.push(platform_tools::manage_extensions_tool());
 
+            if self.large_responses.paging_enabled() {
+                prefixed_tools.push(platform_tools::read_response_page_tool());
+            }
+
             // Add resource tools if supported
             if extension_manager.supports_resources() {
                 prefixed_tools.push(platform_tools::read_resource_tool());
@@ -527,9 +596,18 @@ impl Agent {
     ) -> anyhow::Result<BoxStream<'_, anyhow::Result<AgentEvent>>> {
         let mut messages = messages.to_vec();
         let reply_span = tracing::Span::current();
+        self.reset_final_output().await;
 
-        // Load settings from config
-        let config = Config::global();
+        // Load settings from config, with the session's project configuration over the user's
+        let config = Config::global().for_project(
+            session
+                .as_ref()
+                .and_then(|session_config| session_config.project_config.as_deref()),
+        );
+        // Projects can set their own tool permissions under `permissions`
+        let project_permissions: PermissionConfig =
+            config.get_project_param("permissions").unwrap_or_default();
+        let project_trusted = config.is_project_trusted();
 
         // Setup tools and prompt
         let (mut tools, mut toolshim_tools, mut system_prompt) =
@@ -548,9 +626,40 @@ impl Agent {
             debug!("user_message" = &content);
         }
 
+        if let Some(session_config) = &session {
+            Self::record_turn_checkpoint(session_config, &messages).await;
+        }
+
+        let session_id = session
+            .as_ref()
+            .map(|s| crate::session::checkpoint::session_id(&s.id));
+        let session_file = session
+            .as_ref()
+            .map(|s| crate::session::storage::get_path(s.id.clone()));
+        self.large_responses.begin_session(session_id.as_deref());
+        let audit = AuditScope {
+            redactor: Redactor::for_session(session_file.as_deref()),
+            session_id,
+            working_dir: session.as_ref().map(|s| s.working_dir.clone()),
+            mode: goose_mode.clone(),
+        };
+
+        let compactor = AutoCompactor::from_config(
+            self.provider().await?,
+            *self.truncation_strategy.lock().await,
+        );
+
         Ok(Box::pin(async_stream::try_stream! {
             let _ = reply_span.enter();
+            let mut retried_after_overflow = false;
             loop {
+                if let Some(compactor) = &compactor {
+                    if let Some(compaction) = compactor.compact(&system_prompt, &messages, &tools, false).await {
+                        messages = compaction.messages.clone();
+                        yield AgentEvent::ContextCompacted(compaction);
+                    }
+                }
+
                 match Self::generate_response_from_provider(
                     self.provider().await?,
                     &system_prompt,
@@ -559,6 +668,8 @@ impl Agent {
                     &toolshim_tools,
                 ).await {
                     Ok((response, usage)) => {
+                        retried_after_overflow = false;
+
                         // record usage for the session in the session file
                         if let Some(session_config) = session.clone() {
                             Self::update_session_metrics(session_config, &usage, messages.len()).await?;
@@ -570,6 +681,11 @@ impl Agent {
                             filtered_response) =
                             self.categorize_tool_requests(&response).await;
 
+                        // The final output tool only records a result, so it skips permission checks
+                        let (output_requests, remaining_requests): (Vec<_>, Vec<_>) = remaining_requests
+                            .into_iter()
+                            .partition(|request| request.tool_call.as_ref().is_ok_and(|call| Self::is_final_output_tool(&call.name)));
+
                         // Record tool calls in the router selector
                         let selector = self.router_tool_selector.lock().await.clone();
                         if let Some(selector) = selector {
@@ -595,18 +711,39 @@ impl Agent {
 
                         tokio::task::yield_now().await;
 
-                        let num_tool_requests = frontend_requests.len() + remaining_requests.len();
+                        let num_tool_requests = frontend_requests.len() + remaining_requests.len() + output_requests.len();
                         if num_tool_requests == 0 {
+                            messages.push(response);
+                            match self.output_step_after_reply(self.provider().await?, &system_prompt, &messages).await {
+                                OutputStep::Continue(Some(reminder)) => {
+                                    yield AgentEvent::Message(reminder.clone());
+                                    messages.push(reminder);
+                                    continue;
+                                }
+                                OutputStep::Failed(message) => {
+                                    yield AgentEvent::Message(message);
+                                }
+                                OutputStep::Finished | OutputStep::Continue(None) => {}
+                            }
                             break;
                         }
 
                         // Process tool requests depending on frontend tools and then goose_mode
                         let message_tool_response = Arc::new(Mutex::new(Message::user()));
 
+                        for request in &output_requests {
+                            if let Ok(tool_call) = &request.tool_call {
+                                let result = self.record_final_output(&tool_call.arguments).await;
+                                let mut response = message_tool_response.lock().await;
+                                *response = response.clone().with_tool_response(request.id.clone(), result);
+                            }
+                        }
+
                         // First handle any frontend tool requests
                         let mut frontend_tool_stream = self.handle_frontend_tool_requests(
                             &frontend_requests,
-                            message_tool_response.clone()
+                            message_tool_response.clone(),
+                            &audit,
                         );
 
                         // we have a stream of frontend tools to handle, inside the stream
@@ -621,6 +758,9 @@ impl Agent {
                         if mode.as_str() == "chat" {
                             // Skip all tool calls in chat mode
                             for request in remaining_requests {
+                                if let Ok(tool_call) = &request.tool_call {
+                                    audit.record(&request.id, tool_call, Decision::Skipped, Approver::Mode, Default::default(), None);
+                                }
                                 let mut response = message_tool_response.lock().await;
                                 *response = response.clone().with_tool_response(
                                     request.id.clone(),
@@ -631,7 +771,8 @@ impl Agent {
                             // At this point, we have handled the frontend tool requests and know goose_mode != "chat"
                             // What remains is handling the remaining tool requests (enable extension,
                             // regular tool calls) in goose_mode == ["auto", "approve" or "smart_approve"]
-                            let mut permission_manager = PermissionManager::default();
+                            let mut permission_manager = PermissionManager::default()
+                                .with_project_permissions(project_permissions.clone(), project_trusted);
                             let (permission_check_result, enable_extension_request_ids) = check_tool_permissions(
                                 &remaining_requests,
                                 &mode,
@@ -646,38 +787,63 @@ impl Agent {
                             // Skip the confirmation for approved tools
                             for request in &permission_check_result.approved {
                                 if let Ok(tool_call) = request.tool_call.clone() {
-                                    let (req_id, tool_result) = self.dispatch_tool_call(tool_call, request.id.clone()).await;
-
-                                    tool_futures.push((req_id, match tool_result {
-                                        Ok(result) => tool_stream(
-                                            result.notification_stream.unwrap_or_else(|| Box::new(stream::empty())),
-                                            result.result,
-                                        ),
-                                        Err(e) => tool_stream(
-                                            Box::new(stream::empty()),
-                                            futures::future::ready(Err(e)),
-                                        ),
-                                    }));
+                                    let started = std::time::Instant::now();
+                                    let (req_id, tool_result) = self.dispatch_tool_call(tool_call.clone(), request.id.clone(), audit.redactor.as_ref()).await;
+
+                                    tool_futures.push((req_id.clone(), audited_tool_stream(
+                                        &audit,
+                                        &req_id,
+                                        tool_call,
+                                        permission_check_result.approver(&request.id),
+                                        started,
+                                        tool_result,
+                                    )));
                                 }
                             }
 
                             for request in &permission_check_result.denied {
-                                let mut response = message_tool_response.lock().await;
-                                *response = response.clone().with_tool_response(
-                                    request.id.clone(),
-                                    Ok(vec![Content::text(DECLINED_RESPONSE)]),
-                                );
+                                if let Ok(tool_call) = &request.tool_call {
+                                    audit.record(&request.id, tool_call, Decision::Denied, permission_check_result.approver(&request.id), Default::default(), None);
+                                }
+                                {
+                                    let mut response = message_tool_response.lock().await;
+                                    *response = response.clone().with_tool_response(
+                                        request.id.clone(),
+                                        Ok(vec![Content::text(DECLINED_RESPONSE)]),
+                                    );
+                                }
+                                yield AgentEvent::ToolDenied(request.id.clone());
                             }
 
+                            // An approval policy answers first; what it leaves undecided is asked as usual
+                            let approval_policy = self.approval_policy.lock().await.clone();
+                            let needs_approval = match &approval_policy {
+                                Some(policy) => {
+                                    let (undecided, denied) = self.handle_policy_tool_requests(
+                                        policy,
+                                        &permission_check_result.needs_approval,
+                                        &mut tool_futures,
+                                        message_tool_response.clone(),
+                                        &audit,
+                                    ).await;
+                                    for id in denied {
+                                        yield AgentEvent::ToolDenied(id);
+                                    }
+                                    undecided
+                                }
+                                None => permission_check_result.needs_approval,
+                            };
+
                             // We need interior mutability in handle_approval_tool_requests
                             let tool_futures_arc = Arc::new(Mutex::new(tool_futures));
 
                             // Process tools requiring approval (enable extension, regular tool calls)
                             let mut tool_approval_stream = self.handle_approval_tool_requests(
-                                &permission_check_result.needs_approval,
+                                &needs_approval,
                                 tool_futures_arc.clone(),
                                 &mut permission_manager,
-                                message_tool_response.clone()
+                                message_tool_response.clone(),
+                                &audit,
                             );
 
                             // We have a stream of tool_approval_requests to handle
@@ -732,6 +898,36 @@ impl Agent {
 
                         messages.push(response);
                         messages.push(final_message_tool_resp);
+
+                        match self.output_step_after_tools().await {
+                            OutputStep::Finished => break,
+                            OutputStep::Failed(message) => {
+                                yield AgentEvent::Message(message);
+                                break;
+                            }
+                            OutputStep::Continue(_) => {}
+                        }
+                    },
+                    Err(ProviderError::ContextLengthExceeded(_)) if !retried_after_overflow && compactor.is_some() => {
+                        // Our token estimate can be off from the provider's, so compact and retry once
+                        retried_after_overflow = true;
+                        let compacted = match &compactor {
+                            Some(compactor) => compactor.compact(&system_prompt, &messages, &tools, true).await,
+                            None => None,
+                        };
+                        match compacted {
+                            Some(compaction) => {
+                                messages = compaction.messages.clone();
+                                yield AgentEvent::ContextCompacted(compaction);
+                                continue;
+                            }
+                            None => {
+                                yield AgentEvent::Message(Message::assistant().with_context_length_exceeded(
+                                    "The context length of the model has been exceeded. Please start a new session and try again.",
+                                ));
+                                break;
+                            }
+                        }
                     },
                     Err(ProviderError::ContextLengthExceeded(_)) => {
                         // At this point, the last message should be a user message
@@ -746,6 +942,7 @@ impl Agent {
                         // Create an error message & terminate the stream
                         error!("Error: {}", e);
                         yield AgentEvent::Message(Message::assistant().with_text(format!("Ran into this error: {e}.\n\nPlease retry if you think this is a transient or recoverable error.")));
+                        yield AgentEvent::ProviderError(e.to_string());
                         break;
                     }
                 }
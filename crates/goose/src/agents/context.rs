@@ -1,31 +1,285 @@
-use anyhow::Ok;
+use std::collections::HashSet;
+use std::sync::Arc;
 
+use anyhow::{anyhow, Ok};
+use mcp_core::{Role, Tool};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::config::Config;
 use crate::message::Message;
+use crate::providers::base::Provider;
 use crate::token_counter::TokenCounter;
 
 use crate::context_mgmt::summarize::summarize_messages;
-use crate::context_mgmt::truncate::{truncate_messages, OldestFirstTruncation};
+use crate::context_mgmt::truncate::{
+    create_truncation_strategy, truncate_messages, TruncationStrategy, TruncationStrategyKind,
+};
 use crate::context_mgmt::{estimate_target_context_limit, get_messages_token_counts};
 
 use super::super::agents::Agent;
 
+/// Whether the agent compacts the conversation on its own before it overflows the context window
+pub const AUTO_COMPACT_KEY: &str = "GOOSE_AUTO_COMPACT";
+/// Share of the model's context limit at which automatic compaction kicks in
+pub const AUTO_COMPACT_THRESHOLD_KEY: &str = "GOOSE_AUTO_COMPACT_THRESHOLD";
+const DEFAULT_AUTO_COMPACT_THRESHOLD: f64 = 0.8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionStrategy {
+    Summarize,
+    Truncate,
+}
+
+/// Emitted when the agent compacted the conversation by itself
+///
+/// `messages` is the whole conversation as the agent continues with it, and replaces
+/// everything the caller has accumulated for this session so far.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContextCompaction {
+    pub strategy: CompactionStrategy,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+    pub messages: Vec<Message>,
+}
+
+/// Token accounting for automatic compaction during a single reply
+pub(super) struct AutoCompactor {
+    provider: Arc<dyn Provider>,
+    token_counter: TokenCounter,
+    threshold: usize,
+    target: usize,
+    truncation: TruncationStrategyKind,
+}
+
+impl AutoCompactor {
+    /// Returns None when automatic compaction is turned off
+    pub(super) fn from_config(
+        provider: Arc<dyn Provider>,
+        truncation: TruncationStrategyKind,
+    ) -> Option<Self> {
+        let config = Config::global();
+        if !config.get_param::<bool>(AUTO_COMPACT_KEY).unwrap_or(true) {
+            return None;
+        }
+        let ratio = config
+            .get_param::<f64>(AUTO_COMPACT_THRESHOLD_KEY)
+            .unwrap_or(DEFAULT_AUTO_COMPACT_THRESHOLD)
+            .clamp(0.1, 1.0);
+
+        let threshold = (provider.get_model_config().context_limit() as f64 * ratio) as usize;
+        // Aim well below the threshold so the next few turns don't compact again right away
+        let target = estimate_target_context_limit(provider.clone()).min(threshold * 3 / 4);
+        let token_counter = TokenCounter::new(provider.get_model_config().tokenizer_name());
+        Some(Self {
+            provider,
+            token_counter,
+            threshold,
+            target,
+            truncation,
+        })
+    }
+
+    /// Compact `messages` if they are above the threshold, or unconditionally when `force` is set
+    ///
+    /// Summarizes with the model first and falls back to the configured truncation strategy.
+    /// The most recent user message or tool request/response pair is always kept verbatim.
+    pub(super) async fn compact(
+        &self,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[Tool],
+        force: bool,
+    ) -> Option<ContextCompaction> {
+        let tokens_before = self
+            .token_counter
+            .count_chat_tokens(system_prompt, messages, tools);
+        if !force && tokens_before <= self.threshold {
+            return None;
+        }
+        info!(
+            "Compacting conversation at {} tokens (threshold {})",
+            tokens_before, self.threshold
+        );
+
+        let (compacted, strategy) = match self.summarize(messages).await {
+            std::result::Result::Ok(summarized) => (summarized, CompactionStrategy::Summarize),
+            Err(e) => {
+                warn!(
+                    "Summarizing the conversation failed, truncating instead: {}",
+                    e
+                );
+                let token_counts = get_messages_token_counts(&self.token_counter, messages);
+                let truncation =
+                    create_truncation_strategy(self.truncation, &self.provider, messages).await;
+                match truncate_keeping_ends(messages, &token_counts, self.target, &*truncation) {
+                    std::result::Result::Ok(truncated) => (truncated, CompactionStrategy::Truncate),
+                    Err(e) => {
+                        warn!("Unable to compact the conversation: {}", e);
+                        return None;
+                    }
+                }
+            }
+        };
+
+        let tokens_after = self
+            .token_counter
+            .count_chat_tokens(system_prompt, &compacted, tools);
+        Some(ContextCompaction {
+            strategy,
+            tokens_before,
+            tokens_after,
+            messages: compacted,
+        })
+    }
+
+    async fn summarize(&self, messages: &[Message]) -> anyhow::Result<Vec<Message>> {
+        let tail_len = active_tail_len(messages);
+        let (head, tail) = messages.split_at(messages.len() - tail_len);
+        if head.is_empty() {
+            return Err(anyhow!("nothing to summarize besides the latest exchange"));
+        }
+
+        let (summary, _) = summarize_messages(
+            self.provider.clone(),
+            head,
+            &self.token_counter,
+            self.target,
+        )
+        .await?;
+        if summary.is_empty() {
+            return Err(anyhow!("the summary came back empty"));
+        }
+
+        let compacted = join_summary(summary, tail);
+        let total: usize = get_messages_token_counts(&self.token_counter, &compacted)
+            .iter()
+            .sum();
+        if total > self.target {
+            return Err(anyhow!(
+                "the summarized conversation is still {} tokens, above the target of {}",
+                total,
+                self.target
+            ));
+        }
+        Ok(compacted)
+    }
+}
+
+/// Number of trailing messages that make up the exchange currently in flight: either the
+/// latest tool request with its responses, or the latest user message.
+fn active_tail_len(messages: &[Message]) -> usize {
+    match messages {
+        [.., request, response]
+            if request.role == Role::Assistant
+                && request.is_tool_call()
+                && response.role == Role::User
+                && response.is_tool_response() =>
+        {
+            2
+        }
+        [.., last] if last.role == Role::User => 1,
+        _ => 0,
+    }
+}
+
+/// Append the kept tail to a summary, keeping user and assistant turns alternating
+fn join_summary(mut summary: Vec<Message>, tail: &[Message]) -> Vec<Message> {
+    let ends_with_user = summary.last().is_some_and(|m| m.role == Role::User);
+    let tail_starts_with_user = tail.first().is_some_and(|m| m.role == Role::User);
+    if ends_with_user && tail_starts_with_user {
+        summary.push(
+            Message::assistant().with_text(
+                "I summarized our conversation so far to stay within the context limit.",
+            ),
+        );
+    }
+    summary.extend_from_slice(tail);
+    summary
+}
+
+/// Truncate with `strategy` while keeping the first message, usually the task, and the
+/// exchange in flight verbatim
+///
+/// Strategies keep tool requests and responses paired among the messages in between, and
+/// the kept ends are complete on their own.
+fn truncate_keeping_ends(
+    messages: &[Message],
+    token_counts: &[usize],
+    target: usize,
+    strategy: &dyn TruncationStrategy,
+) -> anyhow::Result<Vec<Message>> {
+    let tail_start = messages.len() - active_tail_len(messages);
+    let head_len = usize::from(
+        messages
+            .first()
+            .is_some_and(|m| m.role == Role::User && m.has_only_text_content()),
+    )
+    .min(tail_start);
+
+    let fixed: usize = token_counts[..head_len].iter().sum::<usize>()
+        + token_counts[tail_start..].iter().sum::<usize>();
+    let budget = target.checked_sub(fixed).ok_or_else(|| {
+        anyhow!(
+            "the first message and the latest exchange alone take {} tokens, above the target of {}",
+            fixed,
+            target
+        )
+    })?;
+
+    let mut middle = messages[head_len..tail_start].to_vec();
+    let mut middle_counts = token_counts[head_len..tail_start].to_vec();
+    strategy.elide_contents(&mut middle, &mut middle_counts, budget);
+    let remove = if middle_counts.iter().sum::<usize>() > budget {
+        strategy.determine_indices_to_remove(&middle, &middle_counts, budget)?
+    } else {
+        HashSet::new()
+    };
+
+    let kept_tokens: usize = middle_counts
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !remove.contains(i))
+        .map(|(_, tokens)| tokens)
+        .sum();
+    if kept_tokens > budget {
+        return Err(anyhow!(
+            "{} tokens remain after truncating, above the target of {}",
+            fixed + kept_tokens,
+            target
+        ));
+    }
+
+    Ok(messages[..head_len]
+        .iter()
+        .cloned()
+        .chain(
+            middle
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| !remove.contains(i))
+                .map(|(_, message)| message),
+        )
+        .chain(messages[tail_start..].iter().cloned())
+        .collect())
+}
+
 impl Agent {
-    /// Public API to truncate oldest messages so that the conversation's token count is within the allowed context limit.
+    /// Public API to truncate messages with the configured strategy so that the conversation's token count is within the allowed context limit.
     pub async fn truncate_context(
         &self,
         messages: &[Message], // last message is a user msg that led to assistant message with_context_length_exceeded
     ) -> Result<(Vec<Message>, Vec<usize>), anyhow::Error> {
         let provider = self.provider().await?;
         let token_counter = TokenCounter::new(provider.get_model_config().tokenizer_name());
-        let target_context_limit = estimate_target_context_limit(provider);
+        let target_context_limit = estimate_target_context_limit(provider.clone());
         let token_counts = get_messages_token_counts(&token_counter, messages);
+        let kind = *self.truncation_strategy.lock().await;
+        let strategy = create_truncation_strategy(kind, &provider, messages).await;
 
-        let (mut new_messages, mut new_token_counts) = truncate_messages(
-            messages,
-            &token_counts,
-            target_context_limit,
-            &OldestFirstTruncation,
-        )?;
+        let (mut new_messages, mut new_token_counts) =
+            truncate_messages(messages, &token_counts, target_context_limit, &*strategy)?;
 
         // Only add an assistant message if we have room for it and it won't cause another overflow
         let assistant_message = Message::assistant().with_text("I had run into a context length exceeded error so I truncated some of the oldest messages in our conversation.");
@@ -80,3 +334,82 @@ impl Agent {
         Ok((new_messages, new_token_counts))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context_mgmt::truncate::OldestFirstTruncation;
+    use mcp_core::tool::ToolCall;
+    use mcp_core::Content;
+    use serde_json::json;
+
+    fn tool_exchange(id: &str) -> [Message; 2] {
+        [
+            Message::assistant().with_tool_request(
+                id,
+                std::result::Result::Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "ls"}),
+                )),
+            ),
+            Message::user()
+                .with_tool_response(id, std::result::Result::Ok(vec![Content::text("a\nb")])),
+        ]
+    }
+
+    #[test]
+    fn test_active_tail_len() {
+        let mut messages = vec![Message::user().with_text("task")];
+        assert_eq!(active_tail_len(&messages), 1);
+
+        messages.extend(tool_exchange("1"));
+        assert_eq!(active_tail_len(&messages), 2);
+
+        messages.push(Message::assistant().with_text("done"));
+        assert_eq!(active_tail_len(&messages), 0);
+    }
+
+    #[test]
+    fn test_join_summary_keeps_turns_alternating() {
+        let summary = vec![Message::user().with_text("summary")];
+        let joined = join_summary(summary.clone(), &[Message::user().with_text("next")]);
+        let roles: Vec<_> = joined.iter().map(|m| m.role.clone()).collect();
+        assert_eq!(roles, vec![Role::User, Role::Assistant, Role::User]);
+
+        let joined = join_summary(summary, &tool_exchange("1"));
+        let roles: Vec<_> = joined.iter().map(|m| m.role.clone()).collect();
+        assert_eq!(roles, vec![Role::User, Role::Assistant, Role::User]);
+    }
+
+    #[test]
+    fn test_truncate_keeping_ends_keeps_task_and_pairs() {
+        let mut messages = vec![Message::user().with_text("task")];
+        for id in ["1", "2", "3", "4"] {
+            messages.extend(tool_exchange(id));
+        }
+        let token_counts = vec![10; messages.len()];
+
+        let compacted =
+            truncate_keeping_ends(&messages, &token_counts, 50, &OldestFirstTruncation).unwrap();
+
+        assert_eq!(compacted.len(), 5);
+        assert_eq!(compacted[0].as_concat_text(), "task");
+        for pair in compacted[1..].chunks(2) {
+            let requests = pair[0].get_tool_request_ids();
+            let responses = pair[1].get_tool_response_ids();
+            assert_eq!(requests, responses);
+        }
+        assert!(compacted[3].get_tool_request_ids().contains("4"));
+    }
+
+    #[test]
+    fn test_truncate_keeping_ends_fails_when_ends_are_too_big() {
+        let mut messages = vec![Message::user().with_text("task")];
+        messages.extend(tool_exchange("1"));
+        let token_counts = vec![10, 10, 100];
+
+        assert!(
+            truncate_keeping_ends(&messages, &token_counts, 50, &OldestFirstTruncation).is_err()
+        );
+    }
+}
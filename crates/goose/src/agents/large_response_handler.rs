@@ -1,76 +1,562 @@
-use chrono::Utc;
-use mcp_core::{Content, ToolError};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
-const LARGE_TEXT_THRESHOLD: usize = 200_000;
-
-/// Process tool response and handle large text content
-pub fn process_tool_response(
-    response: Result<Vec<Content>, ToolError>,
-) -> Result<Vec<Content>, ToolError> {
-    match response {
-        Ok(contents) => {
-            let mut processed_contents = Vec::new();
-
-            for content in contents {
-                match content {
-                    Content::Text(text_content) => {
-                        // Check if text exceeds threshold
-                        if text_content.text.len() > LARGE_TEXT_THRESHOLD {
-                            // Write to temp file
-                            match write_large_text_to_file(&text_content.text) {
-                                Ok(file_path) => {
-                                    // Create a new text content with reference to the file
-                                    let message = format!(
-                                        "The response returned from the tool call was larger ({} characters) and is stored in the file which you can use other tools to examine or search in: {}",
-                                        text_content.text.len(),
-                                        file_path
-                                    );
-                                    processed_contents.push(Content::text(message));
-                                }
-                                Err(e) => {
-                                    // If file writing fails, include original content with warning
-                                    let warning = format!(
-                                        "Warning: Failed to write large response to file: {}. Showing full content instead.\n\n{}",
-                                        e,
-                                        text_content.text
-                                    );
-                                    processed_contents.push(Content::text(warning));
-                                }
-                            }
-                        } else {
-                            // Keep original content for smaller texts
-                            processed_contents.push(Content::Text(text_content));
-                        }
+use mcp_core::{Content, ToolError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::config::Config;
+use crate::message::Message;
+use crate::model::GPT_4O_TOKENIZER;
+use crate::providers::base::Provider;
+use crate::token_counter::TokenCounter;
+
+/// Config key holding the large response settings, including per extension/tool overrides
+pub const LARGE_RESPONSE_CONFIG_KEY: &str = "GOOSE_LARGE_RESPONSE";
+/// Shorthand keys that override the top level threshold and strategy
+pub const LARGE_RESPONSE_THRESHOLD_KEY: &str = "GOOSE_LARGE_RESPONSE_THRESHOLD";
+pub const LARGE_RESPONSE_STRATEGY_KEY: &str = "GOOSE_LARGE_RESPONSE_STRATEGY";
+
+const DEFAULT_THRESHOLD_TOKENS: usize = 50_000;
+const RESPONSES_DIR: &str = "goose_mcp_responses";
+// Where responses go when the agent replies outside of a stored session
+const NO_SESSION_DIR: &str = "no_session";
+// Leftovers from sessions that crashed before cleaning up after themselves
+const STALE_RESPONSE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+const SUMMARY_PROMPT: &str = "You are good at summarizing the output of tools for another model. Keep every identifier, path, number and error message that could matter, and drop repetition.";
+// Share of the worker model's context window the summarization input may take
+const SUMMARY_INPUT_RATIO: f64 = 0.7;
+
+/// What to do with a text response that exceeds its token threshold
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LargeResponseStrategy {
+    /// Spill the text to a file and point the model at it
+    #[default]
+    File,
+    /// Keep the beginning and the end of the text
+    HeadTail,
+    /// Replace the text with a summary written by the worker model
+    Summarize,
+    /// Return the first page and let the model fetch the rest on demand
+    Page,
+}
+
+/// Threshold and strategy for a single extension or tool
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LargeResponseRule {
+    pub threshold: Option<usize>,
+    pub strategy: Option<LargeResponseStrategy>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LargeResponseConfig {
+    /// Maximum size of a text response in tokens before it gets handled
+    pub threshold: usize,
+    pub strategy: LargeResponseStrategy,
+    /// Tokens per page for the page strategy, defaults to the threshold
+    pub page_size: Option<usize>,
+    /// Overrides keyed by extension name (`developer`) or prefixed tool name (`developer__shell`)
+    pub overrides: HashMap<String, LargeResponseRule>,
+}
+
+impl Default for LargeResponseConfig {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD_TOKENS,
+            strategy: LargeResponseStrategy::default(),
+            page_size: None,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl LargeResponseConfig {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let mut settings: Self = config
+            .get_param(LARGE_RESPONSE_CONFIG_KEY)
+            .unwrap_or_default();
+        if let Ok(threshold) = config.get_param::<usize>(LARGE_RESPONSE_THRESHOLD_KEY) {
+            settings.threshold = threshold;
+        }
+        if let Ok(strategy) = config.get_param::<LargeResponseStrategy>(LARGE_RESPONSE_STRATEGY_KEY)
+        {
+            settings.strategy = strategy;
+        }
+        settings
+    }
+
+    /// Resolve the threshold and strategy for a tool, preferring the most specific override
+    pub fn rule_for(&self, tool_name: &str) -> (usize, LargeResponseStrategy) {
+        let extension = tool_name.split("__").next().unwrap_or(tool_name);
+        let tool_rule = self.overrides.get(tool_name);
+        let extension_rule = self.overrides.get(extension);
+
+        let threshold = tool_rule
+            .and_then(|r| r.threshold)
+            .or_else(|| extension_rule.and_then(|r| r.threshold))
+            .unwrap_or(self.threshold);
+        let strategy = tool_rule
+            .and_then(|r| r.strategy)
+            .or_else(|| extension_rule.and_then(|r| r.strategy))
+            .unwrap_or(self.strategy);
+        (threshold, strategy)
+    }
+
+    /// Whether any tool can end up paged, in which case the paging tool is offered
+    pub fn paging_enabled(&self) -> bool {
+        self.strategy == LargeResponseStrategy::Page
+            || self
+                .overrides
+                .values()
+                .any(|rule| rule.strategy == Some(LargeResponseStrategy::Page))
+    }
+}
+
+struct StoredResponse {
+    path: PathBuf,
+    page_chars: usize,
+}
+
+/// Large responses spilled to disk by an agent, kept for the session it is replying in
+///
+/// Each store owns its own directory, with a subdirectory per session. A session's responses
+/// are removed when the agent moves on to another session, when the session is deleted, and
+/// when the store is dropped.
+pub struct ResponseStore {
+    dir: PathBuf,
+    next_id: AtomicUsize,
+    session: Mutex<Option<String>>,
+    responses: Mutex<HashMap<String, StoredResponse>>,
+}
+
+impl ResponseStore {
+    pub fn new() -> Self {
+        let root = std::env::temp_dir().join(RESPONSES_DIR);
+        remove_stale_dirs(&root);
+        Self::with_dir(root.join(uuid::Uuid::new_v4().to_string()))
+    }
+
+    pub fn with_dir(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            next_id: AtomicUsize::new(1),
+            session: Mutex::new(None),
+            responses: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Store responses for `session_id` from now on. Responses stored for another session
+    /// are removed, as nothing in the new session can refer to them.
+    pub fn begin_session(&self, session_id: Option<&str>) {
+        let mut session = self.session.lock().unwrap();
+        if session.as_deref() == session_id {
+            return;
+        }
+        let previous = self.session_dir(session.as_deref());
+        *session = session_id.map(str::to_string);
+        self.responses.lock().unwrap().clear();
+        remove_dir(&previous);
+    }
+
+    fn session_dir(&self, session_id: Option<&str>) -> PathBuf {
+        match session_id {
+            Some(id) if is_plain_name(id) => self.dir.join(id),
+            _ => self.dir.join(NO_SESSION_DIR),
+        }
+    }
+
+    fn store(&self, text: &str, page_chars: usize) -> std::io::Result<(String, PathBuf)> {
+        let dir = self.session_dir(self.session.lock().unwrap().as_deref());
+        std::fs::create_dir_all(&dir)?;
+        let id = format!("resp_{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        let path = dir.join(format!("mcp_response_{}.txt", id));
+        let mut file = File::create(&path)?;
+        file.write_all(text.as_bytes())?;
+
+        self.responses.lock().unwrap().insert(
+            id.clone(),
+            StoredResponse {
+                path: path.clone(),
+                page_chars,
+            },
+        );
+        Ok((id, path))
+    }
+
+    /// Read a 1-based page of a stored response, returning the page and the page count
+    pub fn read_page(&self, id: &str, page: usize) -> Result<(String, usize), ToolError> {
+        let (path, page_chars) = {
+            let responses = self.responses.lock().unwrap();
+            let stored = responses.get(id).ok_or_else(|| {
+                ToolError::InvalidParameters(format!("No stored response with id '{}'", id))
+            })?;
+            (stored.path.clone(), stored.page_chars)
+        };
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            ToolError::ExecutionError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+
+        let pages = split_pages(&text, page_chars);
+        if page == 0 || page > pages.len() {
+            return Err(ToolError::InvalidParameters(format!(
+                "Page {} is out of range, response '{}' has {} pages",
+                page,
+                id,
+                pages.len()
+            )));
+        }
+        Ok((pages[page - 1].to_string(), pages.len()))
+    }
+}
+
+impl Default for ResponseStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ResponseStore {
+    fn drop(&mut self) {
+        remove_dir(&self.dir);
+    }
+}
+
+fn remove_dir(dir: &Path) {
+    if dir.exists() {
+        if let Err(e) = std::fs::remove_dir_all(dir) {
+            warn!("Failed to clean up {}: {}", dir.display(), e);
+        }
+    }
+}
+
+/// Remove the large responses of a deleted session, from the stores of every agent
+pub fn remove_session_responses(session_id: &str) {
+    if !is_plain_name(session_id) {
+        return;
+    }
+    let Ok(stores) = std::fs::read_dir(std::env::temp_dir().join(RESPONSES_DIR)) else {
+        return;
+    };
+    for store in stores.flatten() {
+        remove_dir(&store.path().join(session_id));
+    }
+}
+
+/// Whether `name` is a single path component that stays inside the directory it is joined to
+fn is_plain_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    )
+}
+
+/// Response stores left behind by agents that exited without cleaning up after themselves
+pub fn stale_response_stores() -> Vec<PathBuf> {
+    stale_entries(&std::env::temp_dir().join(RESPONSES_DIR))
+}
+
+fn stale_entries(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_some_and(|age| age > STALE_RESPONSE_AGE)
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+fn remove_stale_dirs(root: &Path) {
+    for path in stale_entries(root) {
+        let _ = if path.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        };
+    }
+}
+
+/// Split text into chunks of at most `page_chars` characters, preferring line boundaries
+fn split_pages(text: &str, page_chars: usize) -> Vec<&str> {
+    let page_chars = page_chars.max(1);
+    let mut pages = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = match rest.char_indices().nth(page_chars) {
+            None => rest.len(),
+            Some((limit, _)) => match rest[..limit].rfind('\n') {
+                Some(newline) if newline > 0 => newline + 1,
+                _ => limit,
+            },
+        };
+        pages.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    if pages.is_empty() {
+        pages.push("");
+    }
+    pages
+}
+
+/// Cut `text` to roughly `head_chars` from the start and `tail_chars` from the end
+fn head_tail(text: &str, head_chars: usize, tail_chars: usize, marker: &str) -> String {
+    let total = text.chars().count();
+    if head_chars + tail_chars >= total {
+        return text.to_string();
+    }
+    let head_end = text
+        .char_indices()
+        .nth(head_chars)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    let tail_start = text
+        .char_indices()
+        .nth(total - tail_chars)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    format!(
+        "{}\n\n{}\n\n{}",
+        &text[..head_end],
+        marker,
+        &text[tail_start..]
+    )
+}
+
+/// Applies the configured strategy to tool responses that are too large to hand to the model
+pub struct LargeResponseHandler {
+    config: LargeResponseConfig,
+    store: ResponseStore,
+    token_counters: Mutex<HashMap<String, Arc<TokenCounter>>>,
+}
+
+impl LargeResponseHandler {
+    pub fn new(config: LargeResponseConfig, store: ResponseStore) -> Self {
+        Self {
+            config,
+            store,
+            token_counters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(LargeResponseConfig::from_config(), ResponseStore::new())
+    }
+
+    pub fn paging_enabled(&self) -> bool {
+        self.config.paging_enabled()
+    }
+
+    /// Keep large responses for `session_id` from now on, see [`ResponseStore::begin_session`]
+    pub fn begin_session(&self, session_id: Option<&str>) {
+        self.store.begin_session(session_id);
+    }
+
+    fn token_counter(&self, tokenizer_name: &str) -> Arc<TokenCounter> {
+        let mut counters = self.token_counters.lock().unwrap();
+        counters
+            .entry(tokenizer_name.to_string())
+            .or_insert_with(|| Arc::new(TokenCounter::new(tokenizer_name)))
+            .clone()
+    }
+
+    /// Process a tool response, replacing text content above the tool's threshold
+    pub async fn process(
+        &self,
+        tool_name: &str,
+        response: Result<Vec<Content>, ToolError>,
+        provider: Option<Arc<dyn Provider>>,
+    ) -> Result<Vec<Content>, ToolError> {
+        let contents = response?;
+        let (threshold, strategy) = self.config.rule_for(tool_name);
+
+        let mut processed_contents = Vec::with_capacity(contents.len());
+        for content in contents {
+            match content {
+                // Every token is at least one byte, so shorter texts can't be over the threshold
+                Content::Text(text_content) if text_content.text.len() > threshold => {
+                    let tokenizer = provider
+                        .as_ref()
+                        .map(|p| p.get_model_config().tokenizer_name().to_string())
+                        .unwrap_or_else(|| GPT_4O_TOKENIZER.to_string());
+                    let tokens = self
+                        .token_counter(&tokenizer)
+                        .count_tokens(&text_content.text);
+                    if tokens <= threshold {
+                        processed_contents.push(Content::Text(text_content));
+                        continue;
                     }
-                    // Pass through other content types unchanged
-                    _ => processed_contents.push(content),
+                    let text = self
+                        .handle_large_text(
+                            tool_name,
+                            &text_content.text,
+                            tokens,
+                            threshold,
+                            strategy,
+                            provider.as_ref(),
+                        )
+                        .await;
+                    processed_contents.push(Content::text(text));
                 }
+                other => processed_contents.push(other),
             }
-
-            Ok(processed_contents)
         }
-        Err(e) => Err(e),
+        Ok(processed_contents)
     }
-}
 
-/// Write large text content to a temporary file
-fn write_large_text_to_file(content: &str) -> Result<String, std::io::Error> {
-    // Create temp directory if it doesn't exist
-    let temp_dir = std::env::temp_dir().join("goose_mcp_responses");
-    std::fs::create_dir_all(&temp_dir)?;
+    async fn handle_large_text(
+        &self,
+        tool_name: &str,
+        text: &str,
+        tokens: usize,
+        threshold: usize,
+        strategy: LargeResponseStrategy,
+        provider: Option<&Arc<dyn Provider>>,
+    ) -> String {
+        let chars = text.chars().count();
+        let chars_per_token = chars as f64 / tokens.max(1) as f64;
+        let to_chars = |tokens: usize| (tokens as f64 * chars_per_token) as usize;
+        let page_chars = to_chars(self.config.page_size.unwrap_or(threshold));
+
+        let (id, path) = match self.store.store(text, page_chars) {
+            Ok(stored) => stored,
+            Err(e) => {
+                // Without a copy on disk the only lossless option is to pass the text through
+                return format!(
+                    "Warning: Failed to write large response to file: {}. Showing full content instead.\n\n{}",
+                    e, text
+                );
+            }
+        };
+        let location = if self.paging_enabled() {
+            format!(
+                "{} (response id '{}', readable page by page with {})",
+                path.display(),
+                id,
+                super::platform_tools::PLATFORM_READ_RESPONSE_PAGE_TOOL_NAME
+            )
+        } else {
+            path.display().to_string()
+        };
+
+        match strategy {
+            LargeResponseStrategy::File => format!(
+                "The response returned from the tool call was larger ({} tokens) and is stored in the file which you can use other tools to examine or search in: {}",
+                tokens, location
+            ),
+            LargeResponseStrategy::Page => {
+                let pages = split_pages(text, page_chars);
+                format!(
+                    "The response returned from the tool call was larger ({} tokens) and is split into {} pages. Showing page 1; call {} with id '{}' and a page number to read the rest. It is also stored in: {}\n\n{}",
+                    tokens,
+                    pages.len(),
+                    super::platform_tools::PLATFORM_READ_RESPONSE_PAGE_TOOL_NAME,
+                    id,
+                    path.display(),
+                    pages[0]
+                )
+            }
+            LargeResponseStrategy::Summarize => {
+                match provider {
+                    Some(provider) => match summarize(provider, tool_name, text, to_chars).await {
+                        Ok(summary) => {
+                            return format!(
+                                "The response returned from the tool call was larger ({} tokens) and has been summarized. The full response is stored in: {}\n\nSummary:\n{}",
+                                tokens, location, summary
+                            )
+                        }
+                        Err(e) => warn!("Failed to summarize large response: {}", e),
+                    },
+                    None => warn!("No provider available to summarize large response"),
+                }
+                self.head_tail_message(text, tokens, threshold, &location, to_chars)
+            }
+            LargeResponseStrategy::HeadTail => {
+                self.head_tail_message(text, tokens, threshold, &location, to_chars)
+            }
+        }
+    }
 
-    // Generate a unique filename with timestamp
-    let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
-    let filename = format!("mcp_response_{}.txt", timestamp);
-    let file_path = temp_dir.join(&filename);
+    fn head_tail_message(
+        &self,
+        text: &str,
+        tokens: usize,
+        threshold: usize,
+        location: &str,
+        to_chars: impl Fn(usize) -> usize,
+    ) -> String {
+        // Leave a little room below the threshold for the marker and the note
+        let keep = to_chars(threshold * 2 / 5);
+        let marker = format!(
+            "[... middle of the response omitted, the full response ({} tokens) is stored in: {} ...]",
+            tokens, location
+        );
+        head_tail(text, keep, keep, &marker)
+    }
 
-    // Write content to file
-    let mut file = File::create(&file_path)?;
-    file.write_all(content.as_bytes())?;
+    /// Tool entry point for reading a page of a stored response
+    pub fn read_page(&self, arguments: Value) -> Result<Vec<Content>, ToolError> {
+        let id = arguments
+            .get("id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'id' parameter".to_string()))?;
+        let page = arguments.get("page").and_then(|v| v.as_u64()).unwrap_or(1) as usize;
+
+        let (text, pages) = self.store.read_page(id, page)?;
+        Ok(vec![Content::text(format!(
+            "Page {} of {} of response '{}':\n\n{}",
+            page, pages, id, text
+        ))])
+    }
+}
 
-    Ok(file_path.to_string_lossy().to_string())
+/// Summarize with the worker model when a lead/worker pair is configured, as this is cheap work
+async fn summarize(
+    provider: &Arc<dyn Provider>,
+    tool_name: &str,
+    text: &str,
+    to_chars: impl Fn(usize) -> usize,
+) -> anyhow::Result<String> {
+    let provider = provider
+        .as_lead_worker()
+        .map(|lead_worker| lead_worker.worker_provider())
+        .unwrap_or_else(|| Arc::clone(provider));
+
+    let budget =
+        (provider.get_model_config().context_limit() as f64 * SUMMARY_INPUT_RATIO) as usize;
+    let input = head_tail(
+        text,
+        to_chars(budget / 2),
+        to_chars(budget / 2),
+        "[... middle omitted ...]",
+    );
+    let request = Message::user().with_text(format!(
+        "Summarize the following output of the tool `{}` so that it can stand in for the full output:\n\n{}",
+        tool_name, input
+    ));
+
+    let (response, _) = provider.complete(SUMMARY_PROMPT, &[request], &[]).await?;
+    let summary = response.as_concat_text();
+    if summary.trim().is_empty() {
+        anyhow::bail!("the model returned an empty summary");
+    }
+    Ok(summary)
 }
 
 #[cfg(test)]
@@ -78,169 +564,341 @@ mod tests {
     use super::*;
     use mcp_core::{Content, ImageContent, TextContent, ToolError};
     use std::fs;
-    use std::path::Path;
 
-    #[test]
-    fn test_small_text_response_passes_through() {
-        // Create a small text response
-        let small_text = "This is a small text response";
-        let content = Content::Text(TextContent {
-            text: small_text.to_string(),
-            annotations: None,
-        });
+    fn handler(config: LargeResponseConfig) -> (LargeResponseHandler, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ResponseStore::with_dir(dir.path().join("responses"));
+        (LargeResponseHandler::new(config, store), dir)
+    }
 
-        let response = Ok(vec![content]);
+    fn config(threshold: usize, strategy: LargeResponseStrategy) -> LargeResponseConfig {
+        LargeResponseConfig {
+            threshold,
+            strategy,
+            ..Default::default()
+        }
+    }
 
-        // Process the response
-        let processed = process_tool_response(response).unwrap();
+    fn text_of(content: &Content) -> &str {
+        match content {
+            Content::Text(text_content) => &text_content.text,
+            _ => panic!("Expected text content"),
+        }
+    }
+
+    fn large_text(lines: usize) -> String {
+        (0..lines)
+            .map(|i| format!("line {} of the tool output", i))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[tokio::test]
+    async fn test_small_text_response_passes_through() {
+        let (handler, _dir) = handler(LargeResponseConfig::default());
+        let small_text = "This is a small text response";
+        let response = Ok(vec![Content::text(small_text)]);
+
+        let processed = handler
+            .process("developer__shell", response, None)
+            .await
+            .unwrap();
 
-        // Verify the response is unchanged
         assert_eq!(processed.len(), 1);
-        if let Content::Text(text_content) = &processed[0] {
-            assert_eq!(text_content.text, small_text);
-        } else {
-            panic!("Expected text content");
-        }
+        assert_eq!(text_of(&processed[0]), small_text);
     }
 
-    #[test]
-    fn test_large_text_response_redirected_to_file() {
-        // Create a text larger than the threshold
-        let large_text = "a".repeat(LARGE_TEXT_THRESHOLD + 1000);
+    #[tokio::test]
+    async fn test_threshold_counts_tokens_not_characters() {
+        // Far more characters than the threshold, but only a handful of tokens
+        let (handler, _dir) = handler(config(100, LargeResponseStrategy::File));
+        let text = " ".repeat(400);
+        let response = Ok(vec![Content::text(text.clone())]);
+
+        let processed = handler.process("developer__shell", response, None).await;
+
+        assert_eq!(text_of(&processed.unwrap()[0]), text);
+    }
+
+    #[tokio::test]
+    async fn test_large_text_response_redirected_to_file() {
+        let (handler, _dir) = handler(config(1_000, LargeResponseStrategy::File));
+        let large_text = large_text(2_000);
         let content = Content::Text(TextContent {
             text: large_text.clone(),
             annotations: None,
         });
 
-        let response = Ok(vec![content]);
-
-        // Process the response
-        let processed = process_tool_response(response).unwrap();
+        let processed = handler
+            .process("developer__shell", Ok(vec![content]), None)
+            .await
+            .unwrap();
 
-        // Verify the response contains a message about the file
         assert_eq!(processed.len(), 1);
-        if let Content::Text(text_content) = &processed[0] {
-            assert!(text_content
-                .text
-                .contains("The response returned from the tool call was larger"));
-            assert!(text_content.text.contains("characters"));
-
-            // Extract the file path from the message
-            if let Some(file_path) = text_content.text.split("stored in the file: ").nth(1) {
-                // Verify the file exists and contains the original text
-                let path = Path::new(file_path.trim());
-                if path.exists() {
-                    // Only check content if file exists (may not exist in CI environments)
-                    if let Ok(file_content) = fs::read_to_string(path) {
-                        assert_eq!(file_content, large_text);
-                    }
+        let message = text_of(&processed[0]);
+        assert!(message.contains("The response returned from the tool call was larger"));
+        assert!(message.contains("tokens"));
 
-                    // Clean up the file
-                    let _ = fs::remove_file(path); // Ignore errors on cleanup
-                }
+        let file_path = message.split("search in: ").nth(1).unwrap();
+        assert_eq!(fs::read_to_string(file_path.trim()).unwrap(), large_text);
+    }
+
+    #[tokio::test]
+    async fn test_head_tail_keeps_both_ends() {
+        let (handler, _dir) = handler(config(1_000, LargeResponseStrategy::HeadTail));
+        let large_text = large_text(2_000);
+
+        let processed = handler
+            .process(
+                "developer__shell",
+                Ok(vec![Content::text(large_text)]),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let message = text_of(&processed[0]);
+        assert!(message.starts_with("line 0 of the tool output"));
+        assert!(message.ends_with("line 1999 of the tool output"));
+        assert!(message.contains("middle of the response omitted"));
+        assert!(!message.contains("line 1000 of the tool output"));
+    }
+
+    #[tokio::test]
+    async fn test_summarize_without_provider_falls_back_to_head_tail() {
+        let (handler, _dir) = handler(config(1_000, LargeResponseStrategy::Summarize));
+
+        let processed = handler
+            .process(
+                "developer__shell",
+                Ok(vec![Content::text(large_text(2_000))]),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert!(text_of(&processed[0]).contains("middle of the response omitted"));
+    }
+
+    #[tokio::test]
+    async fn test_page_strategy_and_reading_pages() {
+        let (handler, _dir) = handler(config(1_000, LargeResponseStrategy::Page));
+        let large_text = large_text(2_000);
+
+        let processed = handler
+            .process(
+                "developer__shell",
+                Ok(vec![Content::text(large_text.clone())]),
+                None,
+            )
+            .await
+            .unwrap();
+        let message = text_of(&processed[0]);
+        assert!(message.contains("Showing page 1"));
+        assert!(message.contains("line 0 of the tool output"));
+        assert!(!message.contains("line 1999 of the tool output"));
+
+        let mut reassembled = String::new();
+        let mut page = 1;
+        loop {
+            let (text, pages) = handler.store.read_page("resp_1", page).unwrap();
+            reassembled.push_str(&text);
+            if page == pages {
+                break;
             }
-        } else {
-            panic!("Expected text content");
+            page += 1;
         }
+        assert!(page > 1);
+        assert_eq!(reassembled, large_text);
+
+        let result = handler.read_page(serde_json::json!({"id": "resp_1", "page": page + 1}));
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+        let result = handler.read_page(serde_json::json!({"id": "resp_42"}));
+        assert!(matches!(result, Err(ToolError::InvalidParameters(_))));
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_removed_with_their_session() {
+        let (handler, dir) = handler(config(1_000, LargeResponseStrategy::Page));
+        let stored_path = |message: &str| {
+            PathBuf::from(
+                message
+                    .split("stored in: ")
+                    .nth(1)
+                    .unwrap()
+                    .lines()
+                    .next()
+                    .unwrap(),
+            )
+        };
+
+        handler.begin_session(Some("first"));
+        let processed = handler
+            .process(
+                "developer__shell",
+                Ok(vec![Content::text(large_text(2_000))]),
+                None,
+            )
+            .await
+            .unwrap();
+        let first = stored_path(text_of(&processed[0]));
+        assert!(first.starts_with(dir.path().join("responses").join("first")));
+
+        // Replying in the same session again keeps what was stored
+        handler.begin_session(Some("first"));
+        assert!(handler.store.read_page("resp_1", 1).is_ok());
+
+        handler.begin_session(Some("second"));
+        assert!(!first.exists());
+        assert!(matches!(
+            handler.store.read_page("resp_1", 1),
+            Err(ToolError::InvalidParameters(_))
+        ));
+
+        let processed = handler
+            .process(
+                "developer__shell",
+                Ok(vec![Content::text(large_text(2_000))]),
+                None,
+            )
+            .await
+            .unwrap();
+        let second = stored_path(text_of(&processed[0]));
+        assert!(second.starts_with(dir.path().join("responses").join("second")));
     }
 
     #[test]
-    fn test_image_content_passes_through() {
-        // Create an image content
+    fn test_rule_for_prefers_tool_over_extension() {
+        let mut config = config(1_000, LargeResponseStrategy::File);
+        config.overrides.insert(
+            "developer".to_string(),
+            LargeResponseRule {
+                threshold: Some(500),
+                strategy: Some(LargeResponseStrategy::HeadTail),
+            },
+        );
+        config.overrides.insert(
+            "developer__shell".to_string(),
+            LargeResponseRule {
+                threshold: Some(200),
+                strategy: None,
+            },
+        );
+
+        assert_eq!(
+            config.rule_for("developer__shell"),
+            (200, LargeResponseStrategy::HeadTail)
+        );
+        assert_eq!(
+            config.rule_for("developer__text_editor"),
+            (500, LargeResponseStrategy::HeadTail)
+        );
+        assert_eq!(
+            config.rule_for("memory__remember"),
+            (1_000, LargeResponseStrategy::File)
+        );
+        assert!(!config.paging_enabled());
+    }
+
+    #[test]
+    fn test_config_deserializes_from_yaml() {
+        let config: LargeResponseConfig = serde_yaml::from_str(
+            "threshold: 20000\nstrategy: page\noverrides:\n  developer__shell:\n    strategy: head_tail\n",
+        )
+        .unwrap();
+        assert_eq!(config.threshold, 20_000);
+        assert!(config.paging_enabled());
+        assert_eq!(
+            config.rule_for("developer__shell"),
+            (20_000, LargeResponseStrategy::HeadTail)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_is_removed_on_drop() {
+        let (handler, dir) = handler(config(1_000, LargeResponseStrategy::File));
+        handler
+            .process(
+                "developer__shell",
+                Ok(vec![Content::text(large_text(2_000))]),
+                None,
+            )
+            .await
+            .unwrap();
+        let responses = dir.path().join("responses");
+        assert!(responses.exists());
+
+        drop(handler);
+        assert!(!responses.exists());
+    }
+
+    #[tokio::test]
+    async fn test_image_content_passes_through() {
+        let (handler, _dir) = handler(config(10, LargeResponseStrategy::File));
         let image_content = Content::Image(ImageContent {
-            data: "base64data".to_string(),
+            data: "base64data".repeat(100),
             mime_type: "image/png".to_string(),
             annotations: None,
         });
 
-        let response = Ok(vec![image_content]);
-
-        // Process the response
-        let processed = process_tool_response(response).unwrap();
+        let processed = handler
+            .process("developer__screen_capture", Ok(vec![image_content]), None)
+            .await
+            .unwrap();
 
-        // Verify the response is unchanged
         assert_eq!(processed.len(), 1);
         match &processed[0] {
-            Content::Image(img) => {
-                assert_eq!(img.data, "base64data");
-                assert_eq!(img.mime_type, "image/png");
-            }
+            Content::Image(img) => assert_eq!(img.mime_type, "image/png"),
             _ => panic!("Expected image content"),
         }
     }
 
-    #[test]
-    fn test_mixed_content_handled_correctly() {
-        // Create a response with mixed content types
-        let small_text = Content::text("Small text");
-        let large_text = Content::Text(TextContent {
-            text: "a".repeat(LARGE_TEXT_THRESHOLD + 1000),
-            annotations: None,
-        });
+    #[tokio::test]
+    async fn test_mixed_content_handled_correctly() {
+        let (handler, _dir) = handler(config(1_000, LargeResponseStrategy::File));
         let image = Content::Image(ImageContent {
             data: "image_data".to_string(),
             mime_type: "image/jpeg".to_string(),
             annotations: None,
         });
+        let response = Ok(vec![
+            Content::text("Small text"),
+            Content::text(large_text(2_000)),
+            image,
+        ]);
 
-        let response = Ok(vec![small_text, large_text, image]);
-
-        // Process the response
-        let processed = process_tool_response(response).unwrap();
+        let processed = handler
+            .process("developer__shell", response, None)
+            .await
+            .unwrap();
 
-        // Verify each item is handled correctly
         assert_eq!(processed.len(), 3);
-
-        // First item should be unchanged small text
-        if let Content::Text(text_content) = &processed[0] {
-            assert_eq!(text_content.text, "Small text");
-        } else {
-            panic!("Expected text content");
-        }
-
-        // Second item should be a message about the file
-        if let Content::Text(text_content) = &processed[1] {
-            assert!(text_content
-                .text
-                .contains("The response returned from the tool call was larger"));
-
-            // Extract the file path and clean up
-            if let Some(file_path) = text_content.text.split("stored in the file: ").nth(1) {
-                let path = Path::new(file_path.trim());
-                if path.exists() {
-                    let _ = fs::remove_file(path); // Ignore errors on cleanup
-                }
-            }
-        } else {
-            panic!("Expected text content");
-        }
-
-        // Third item should be unchanged image
-        match &processed[2] {
-            Content::Image(img) => {
-                assert_eq!(img.data, "image_data");
-                assert_eq!(img.mime_type, "image/jpeg");
-            }
-            _ => panic!("Expected image content"),
-        }
+        assert_eq!(text_of(&processed[0]), "Small text");
+        assert!(
+            text_of(&processed[1]).contains("The response returned from the tool call was larger")
+        );
+        assert!(matches!(&processed[2], Content::Image(img) if img.data == "image_data"));
     }
 
-    #[test]
-    fn test_error_response_passes_through() {
-        // Create an error response
-        let error = ToolError::ExecutionError("Test error".to_string());
-        let response: Result<Vec<Content>, ToolError> = Err(error);
+    #[tokio::test]
+    async fn test_error_response_passes_through() {
+        let (handler, _dir) = handler(LargeResponseConfig::default());
+        let response: Result<Vec<Content>, ToolError> =
+            Err(ToolError::ExecutionError("Test error".to_string()));
 
-        // Process the response
-        let processed = process_tool_response(response);
+        let processed = handler.process("developer__shell", response, None).await;
 
-        // Verify the error is passed through unchanged
-        assert!(processed.is_err());
         match processed {
-            Err(ToolError::ExecutionError(msg)) => {
-                assert_eq!(msg, "Test error");
-            }
+            Err(ToolError::ExecutionError(msg)) => assert_eq!(msg, "Test error"),
             _ => panic!("Expected execution error"),
         }
     }
+
+    #[test]
+    fn test_split_pages_respects_char_boundaries() {
+        let text = "héllo wörld\nsecond line\n";
+        let pages = split_pages(text, 5);
+        assert_eq!(pages.concat(), text);
+        assert!(pages.iter().all(|p| p.chars().count() <= 5));
+    }
 }
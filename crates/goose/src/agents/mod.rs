@@ -2,19 +2,23 @@ mod agent;
 mod context;
 pub mod extension;
 pub mod extension_manager;
-mod large_response_handler;
+pub(crate) mod large_response_handler;
 pub mod platform_tools;
 pub mod prompt_manager;
 mod reply_parts;
 mod router_tool_selector;
 mod router_tools;
+mod structured_output;
 mod tool_execution;
 mod tool_router_index_manager;
 pub(crate) mod tool_vectordb;
 mod types;
 
 pub use agent::{Agent, AgentEvent};
+pub use context::{CompactionStrategy, ContextCompaction};
 pub use extension::ExtensionConfig;
 pub use extension_manager::ExtensionManager;
 pub use prompt_manager::PromptManager;
+pub use structured_output::check_schema;
+pub use tool_execution::{DECLINED_RESPONSE, POLICY_DECLINED_PREFIX};
 pub use types::{FrontendTool, SessionConfig};
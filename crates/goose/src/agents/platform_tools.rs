@@ -1,12 +1,14 @@
 use indoc::indoc;
 use mcp_core::tool::{Tool, ToolAnnotations};
-use serde_json::json;
+use serde_json::{json, Value};
 
 pub const PLATFORM_READ_RESOURCE_TOOL_NAME: &str = "platform__read_resource";
 pub const PLATFORM_LIST_RESOURCES_TOOL_NAME: &str = "platform__list_resources";
 pub const PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME: &str =
     "platform__search_available_extensions";
 pub const PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME: &str = "platform__manage_extensions";
+pub const PLATFORM_READ_RESPONSE_PAGE_TOOL_NAME: &str = "platform__read_response_page";
+pub const PLATFORM_FINAL_OUTPUT_TOOL_NAME: &str = "platform__final_output";
 
 pub fn read_resource_tool() -> Tool {
     Tool::new(
@@ -112,3 +114,52 @@ pub fn manage_extensions_tool() -> Tool {
         }),
     )
 }
+
+pub fn read_response_page_tool() -> Tool {
+    Tool::new(
+        PLATFORM_READ_RESPONSE_PAGE_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Read a page of a large tool response.
+
+            When a tool returns more output than fits in the context, only its first page is shown
+            along with a response id. Use this tool with that id to read the following pages.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["id", "page"],
+            "properties": {
+                "id": {"type": "string", "description": "The response id, e.g. resp_1"},
+                "page": {"type": "integer", "minimum": 1, "description": "The 1-based page number to read"}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Read a page of a large response".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn final_output_tool(input_schema: Value) -> Tool {
+    Tool::new(
+        PLATFORM_FINAL_OUTPUT_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Submit the final result of the task.
+
+            Call this once the task is done, with the result as the arguments. The result is
+            checked against the schema and any problems are reported back so they can be fixed.
+        "#}
+        .to_string(),
+        input_schema,
+        Some(ToolAnnotations {
+            title: Some("Submit the final result".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
@@ -64,6 +64,13 @@ impl Agent {
             tool_selection_strategy,
         );
 
+        // Ask for the final result through a tool when the provider has no JSON mode
+        if let Some((tool, instruction)) = self.final_output_tool(&provider).await {
+            tools.push(tool);
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(instruction);
+        }
+
         // Handle toolshim if enabled
         let mut toolshim_tools = vec![];
         if model_config.toolshim {
@@ -247,4 +254,41 @@ impl Agent {
 
         Ok(())
     }
+
+    /// Snapshot the working tree before a new user turn so it can be rewound later. This
+    /// stages the whole working tree, so it is opt-in through `GOOSE_CHECKPOINTS`.
+    /// Failures are logged rather than surfaced; a missing checkpoint only disables rewind.
+    pub(crate) async fn record_turn_checkpoint(
+        session_config: &crate::agents::types::SessionConfig,
+        messages: &[Message],
+    ) {
+        let enabled = Config::global()
+            .get_param::<bool>("GOOSE_CHECKPOINTS")
+            .unwrap_or(false);
+        let starts_turn = messages.last().is_some_and(|m| {
+            m.role == mcp_core::role::Role::User
+                && m.content
+                    .iter()
+                    .any(|c| matches!(c, MessageContent::Text(_)))
+        });
+        if !enabled || !starts_turn {
+            return;
+        }
+
+        let max_mb = Config::global()
+            .get_param::<u64>("GOOSE_CHECKPOINT_MAX_MB")
+            .unwrap_or(session::checkpoint::DEFAULT_MAX_CHECKPOINT_MB);
+        let session_id = session::checkpoint::session_id(&session_config.id);
+        let store = match session::CheckpointStore::new(&session_id, &session_config.working_dir) {
+            Ok(store) if store.is_supported() => store.with_max_bytes(max_mb * 1024 * 1024),
+            Ok(_) => return,
+            Err(e) => {
+                tracing::warn!("Skipping checkpoint: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = store.create(messages.len() - 1).await {
+            tracing::warn!("Failed to record checkpoint: {}", e);
+        }
+    }
 }
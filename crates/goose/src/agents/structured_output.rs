@@ -0,0 +1,416 @@
+use std::sync::Arc;
+
+use jsonschema::paths::{Location, LocationSegment};
+use mcp_core::{Content, Tool, ToolError};
+use serde_json::{json, Value};
+
+use super::platform_tools::{final_output_tool, PLATFORM_FINAL_OUTPUT_TOOL_NAME};
+use super::Agent;
+use crate::message::Message;
+use crate::providers::base::Provider;
+
+/// How many invalid results the model may produce before the reply fails
+pub const MAX_OUTPUT_ATTEMPTS: usize = 3;
+
+/// Property the result is wrapped in when the schema is not an object, since tool
+/// arguments always are
+const WRAPPED_OUTPUT_PROPERTY: &str = "output";
+
+const FINAL_OUTPUT_INSTRUCTION: &str = "This task must end with a structured result. When you \
+are done, call the platform__final_output tool with the result as its arguments. The task is \
+not complete until that tool accepts the result; if it reports errors, fix them and call it again.";
+
+const FINAL_OUTPUT_REMINDER: &str =
+    "You have not called platform__final_output yet. Call it now with the final result of the task.";
+
+const EXTRACT_INSTRUCTION: &str =
+    "Reply with the final result of the task as JSON matching the requested schema.";
+
+/// State of a reply that has to end with a JSON value matching a schema
+#[derive(Debug, Clone)]
+pub struct StructuredOutput {
+    pub schema: Value,
+    pub result: Option<Value>,
+    pub failed_attempts: usize,
+}
+
+impl StructuredOutput {
+    pub fn new(schema: Value) -> Self {
+        Self {
+            schema,
+            result: None,
+            failed_attempts: 0,
+        }
+    }
+
+    fn exhausted(&self) -> bool {
+        self.failed_attempts >= MAX_OUTPUT_ATTEMPTS
+    }
+}
+
+/// What the reply loop should do next while waiting for structured output
+pub(super) enum OutputStep {
+    /// The reply can end
+    Finished,
+    /// Keep going, after sending the model this message if there is one
+    Continue(Option<Message>),
+    /// The model did not produce a valid result, explained by this message
+    Failed(Message),
+}
+
+fn failure_message(errors: &[String]) -> Message {
+    Message::assistant().with_text(format!(
+        "Could not produce output matching the schema after {} attempts:\n{}",
+        MAX_OUTPUT_ATTEMPTS,
+        format_errors(errors)
+    ))
+}
+
+fn format_errors(errors: &[String]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("- {}", error))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_object_schema(schema: &Value) -> bool {
+    schema.get("type").and_then(|t| t.as_str()) == Some("object")
+}
+
+/// Input schema for the final output tool, wrapping schemas that are not objects
+pub fn tool_schema(schema: &Value) -> Value {
+    if is_object_schema(schema) {
+        schema.clone()
+    } else {
+        json!({
+            "type": "object",
+            "required": [WRAPPED_OUTPUT_PROPERTY],
+            "properties": { WRAPPED_OUTPUT_PROPERTY: schema }
+        })
+    }
+}
+
+/// The result passed to the final output tool, undoing the wrapping from [`tool_schema`]
+pub fn output_from_arguments(schema: &Value, arguments: &Value) -> Value {
+    if is_object_schema(schema) {
+        arguments.clone()
+    } else {
+        arguments
+            .get(WRAPPED_OUTPUT_PROPERTY)
+            .cloned()
+            .unwrap_or(Value::Null)
+    }
+}
+
+/// Compile an output schema, with `format` checked like any other keyword
+fn validator(schema: &Value) -> Result<jsonschema::Validator, String> {
+    jsonschema::options()
+        .should_validate_formats(true)
+        .build(schema)
+        .map_err(|e| format!("Invalid output schema: {}", e))
+}
+
+/// Check that an output schema can be used, before any reply depends on it.
+///
+/// Schemas may only refer to their own definitions; `$ref`s to other documents are rejected.
+pub fn check_schema(schema: &Value) -> Result<(), String> {
+    validator(schema).map(|_| ())
+}
+
+/// Check `value` against a JSON schema, returning a description of each problem.
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let validator = match validator(schema) {
+        Ok(validator) => validator,
+        Err(e) => return vec![e],
+    };
+    validator
+        .iter_errors(value)
+        .map(|error| format!("{}: {}", display_path(&error.instance_path), error))
+        .collect()
+}
+
+/// Show where in the value a problem is, as `$.tags[1]`
+fn display_path(location: &Location) -> String {
+    let mut path = "$".to_string();
+    for segment in location {
+        match segment {
+            LocationSegment::Property(name) => {
+                path.push('.');
+                path.push_str(name);
+            }
+            LocationSegment::Index(index) => path.push_str(&format!("[{}]", index)),
+        }
+    }
+    path
+}
+
+impl Agent {
+    /// Require replies to end with a JSON value matching `schema`, available from
+    /// [`Agent::final_output`]. `None` goes back to free-form replies.
+    pub async fn set_output_schema(&self, schema: Option<Value>) {
+        *self.structured_output.lock().await = schema.map(StructuredOutput::new);
+    }
+
+    /// The validated result of the last reply, when an output schema is set
+    pub async fn final_output(&self) -> Option<Value> {
+        self.structured_output
+            .lock()
+            .await
+            .as_ref()
+            .and_then(|state| state.result.clone())
+    }
+
+    /// Forget the result of the previous reply before starting a new one
+    pub(super) async fn reset_final_output(&self) {
+        if let Some(state) = self.structured_output.lock().await.as_mut() {
+            state.result = None;
+            state.failed_attempts = 0;
+        }
+    }
+
+    /// The final output tool and its instructions, when the provider cannot produce
+    /// structured output natively
+    pub(super) async fn final_output_tool(
+        &self,
+        provider: &Arc<dyn Provider>,
+    ) -> Option<(Tool, &'static str)> {
+        let state = self.structured_output.lock().await;
+        let state = state.as_ref()?;
+        if provider.supports_structured_output() {
+            return None;
+        }
+        Some((
+            final_output_tool(tool_schema(&state.schema)),
+            FINAL_OUTPUT_INSTRUCTION,
+        ))
+    }
+
+    pub(super) fn is_final_output_tool(name: &str) -> bool {
+        name == PLATFORM_FINAL_OUTPUT_TOOL_NAME
+    }
+
+    /// Handle a call to the final output tool, keeping the result if it matches the schema
+    pub(super) async fn record_final_output(
+        &self,
+        arguments: &Value,
+    ) -> Result<Vec<Content>, ToolError> {
+        let mut state = self.structured_output.lock().await;
+        let Some(state) = state.as_mut() else {
+            return Err(ToolError::ExecutionError(
+                "No output schema is set for this session".to_string(),
+            ));
+        };
+
+        let output = output_from_arguments(&state.schema, arguments);
+        let errors = validate(&state.schema, &output);
+        if errors.is_empty() {
+            state.result = Some(output);
+            Ok(vec![Content::text("The final output was recorded.")])
+        } else {
+            state.failed_attempts += 1;
+            Err(ToolError::InvalidParameters(format!(
+                "The output does not match the schema:\n{}",
+                format_errors(&errors)
+            )))
+        }
+    }
+
+    /// Decide how to continue after tool calls were handled
+    pub(super) async fn output_step_after_tools(&self) -> OutputStep {
+        match self.structured_output.lock().await.as_ref() {
+            Some(state) if state.result.is_some() => OutputStep::Finished,
+            Some(state) if state.exhausted() => OutputStep::Failed(failure_message(&[
+                "the final output tool was called with invalid results".to_string(),
+            ])),
+            _ => OutputStep::Continue(None),
+        }
+    }
+
+    /// Decide how to continue after the model replied without calling any tools.
+    ///
+    /// Providers with a native JSON mode are asked for the result directly, retrying with
+    /// the validation errors. Otherwise the model is reminded to call the final output tool.
+    pub(super) async fn output_step_after_reply(
+        &self,
+        provider: Arc<dyn Provider>,
+        system_prompt: &str,
+        messages: &[Message],
+    ) -> OutputStep {
+        let schema = match self.structured_output.lock().await.as_mut() {
+            None => return OutputStep::Finished,
+            Some(state) if state.result.is_some() => return OutputStep::Finished,
+            Some(state) if !provider.supports_structured_output() => {
+                state.failed_attempts += 1;
+                return if state.exhausted() {
+                    OutputStep::Failed(failure_message(&[
+                        "the final output tool was never called".to_string()
+                    ]))
+                } else {
+                    OutputStep::Continue(Some(Message::user().with_text(FINAL_OUTPUT_REMINDER)))
+                };
+            }
+            Some(state) => state.schema.clone(),
+        };
+
+        let mut conversation = messages.to_vec();
+        conversation.push(Message::user().with_text(EXTRACT_INSTRUCTION));
+        let mut errors = Vec::new();
+        for _ in 0..MAX_OUTPUT_ATTEMPTS {
+            let output = match provider
+                .extract(system_prompt, &conversation, &schema)
+                .await
+            {
+                Ok((output, _usage)) => output,
+                Err(e) => {
+                    tracing::warn!("Structured output request failed: {}", e);
+                    errors = vec![e.to_string()];
+                    continue;
+                }
+            };
+
+            errors = validate(&schema, &output);
+            if errors.is_empty() {
+                if let Some(state) = self.structured_output.lock().await.as_mut() {
+                    state.result = Some(output);
+                }
+                return OutputStep::Finished;
+            }
+
+            conversation.push(Message::assistant().with_text(output.to_string()));
+            conversation.push(Message::user().with_text(format!(
+                "That result does not match the schema:\n{}\n{}",
+                format_errors(&errors),
+                EXTRACT_INSTRUCTION
+            )));
+        }
+
+        if let Some(state) = self.structured_output.lock().await.as_mut() {
+            state.failed_attempts = MAX_OUTPUT_ATTEMPTS;
+        }
+        OutputStep::Failed(failure_message(&errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["title", "severity"],
+            "additionalProperties": false,
+            "properties": {
+                "title": {"type": "string", "minLength": 1},
+                "severity": {"enum": ["low", "medium", "high"]},
+                "score": {"type": "integer", "minimum": 0, "maximum": 10},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "owner": {"anyOf": [{"type": "string"}, {"type": "null"}]}
+            }
+        })
+    }
+
+    #[test]
+    fn test_valid_output() {
+        let output = json!({
+            "title": "Crash on start",
+            "severity": "high",
+            "score": 7,
+            "tags": ["startup"],
+            "owner": null
+        });
+        assert!(validate(&report_schema(), &output).is_empty());
+    }
+
+    #[test]
+    fn test_reports_each_problem_with_its_path() {
+        let output = json!({
+            "title": "",
+            "severity": "urgent",
+            "score": 7.5,
+            "tags": ["startup", 3],
+            "owner": false,
+            "extra": true
+        });
+        let mut paths: Vec<String> = validate(&report_schema(), &output)
+            .into_iter()
+            .map(|error| error.split(':').next().unwrap().to_string())
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "$",
+                "$.owner",
+                "$.score",
+                "$.severity",
+                "$.tags[1]",
+                "$.title"
+            ]
+        );
+
+        let errors = validate(&report_schema(), &json!({"title": "x"}));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("$: ") && errors[0].contains("severity"));
+    }
+
+    #[test]
+    fn test_type_lists_and_integers() {
+        let schema = json!({"type": ["integer", "null"]});
+        assert!(validate(&schema, &json!(3)).is_empty());
+        assert!(validate(&schema, &json!(3.0)).is_empty());
+        assert!(validate(&schema, &Value::Null).is_empty());
+        assert_eq!(validate(&schema, &json!("3")).len(), 1);
+    }
+
+    #[test]
+    fn test_refs_patterns_all_of_and_formats_are_checked() {
+        let schema = json!({
+            "$defs": {
+                "id": {"type": "string", "pattern": "^[A-Z]+-[0-9]+$"}
+            },
+            "type": "object",
+            "properties": {
+                "ticket": {"$ref": "#/$defs/id"},
+                "reporter": {"type": "string", "format": "email"},
+                "priority": {"allOf": [{"type": "integer"}, {"minimum": 1}]}
+            }
+        });
+        let valid = json!({"ticket": "GOOSE-12", "reporter": "a@example.com", "priority": 2});
+        assert!(validate(&schema, &valid).is_empty());
+
+        let invalid = json!({"ticket": "goose 12", "reporter": "nobody", "priority": 0});
+        let mut paths: Vec<String> = validate(&schema, &invalid)
+            .into_iter()
+            .map(|error| error.split(':').next().unwrap().to_string())
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["$.priority", "$.reporter", "$.ticket"]);
+    }
+
+    #[test]
+    fn test_unusable_schemas_are_rejected() {
+        assert!(check_schema(&report_schema()).is_ok());
+        assert!(check_schema(&json!({"type": "not-a-type"})).is_err());
+        assert!(check_schema(&json!({"$ref": "https://example.com/schema.json"})).is_err());
+        assert!(!validate(&json!({"type": 5}), &json!(1)).is_empty());
+    }
+
+    #[test]
+    fn test_non_object_schemas_are_wrapped_for_the_tool() {
+        let schema = json!({"type": "array", "items": {"type": "string"}});
+        let input_schema = tool_schema(&schema);
+        assert_eq!(input_schema["type"], "object");
+        assert_eq!(input_schema["properties"]["output"], schema);
+        assert_eq!(
+            output_from_arguments(&schema, &json!({"output": ["a", "b"]})),
+            json!(["a", "b"])
+        );
+
+        let schema = report_schema();
+        assert_eq!(tool_schema(&schema), schema);
+        let arguments = json!({"title": "x", "severity": "low"});
+        assert_eq!(output_from_arguments(&schema, &arguments), arguments);
+    }
+}
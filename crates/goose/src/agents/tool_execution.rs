@@ -7,11 +7,13 @@ use futures::{Stream, StreamExt};
 use mcp_core::protocol::JsonRpcMessage;
 use tokio::sync::Mutex;
 
+use crate::audit::{Approver, AuditScope, Decision};
 use crate::config::permission::PermissionLevel;
 use crate::config::PermissionManager;
 use crate::message::{Message, ToolRequest};
-use crate::permission::Permission;
-use mcp_core::{Content, ToolResult};
+use crate::permission::{ApprovalPolicy, Permission, PolicyDecision};
+use mcp_core::{tool::ToolCall, Content, ToolError, ToolResult};
+use std::time::Instant;
 
 // ToolCallResult combines the result of a tool call with an optional notification stream that
 // can be used to receive notifications from the tool.
@@ -32,10 +34,53 @@ impl From<ToolResult<Vec<Content>>> for ToolCallResult {
 use super::agent::{tool_stream, ToolStream};
 use crate::agents::Agent;
 
+/// Turn a dispatched tool call into a stream, recording it in the audit log once it completes
+pub(crate) fn audited_tool_stream(
+    audit: &AuditScope,
+    request_id: &str,
+    tool_call: ToolCall,
+    approver: Approver,
+    started: Instant,
+    tool_result: Result<ToolCallResult, ToolError>,
+) -> ToolStream {
+    let ToolCallResult {
+        result,
+        notification_stream,
+    } = tool_result.unwrap_or_else(|e| ToolCallResult::from(Err(e)));
+    let notifications = notification_stream.unwrap_or_else(|| Box::new(stream::empty()));
+
+    let audit = audit.clone();
+    let request_id = request_id.to_string();
+    tool_stream(notifications, async move {
+        let output = result.await;
+        audit.record(
+            &request_id,
+            &tool_call,
+            Decision::Approved,
+            approver,
+            started.elapsed(),
+            Some(&output),
+        );
+        output
+    })
+}
+
 pub const DECLINED_RESPONSE: &str = "The user has declined to run this tool. \
     DO NOT attempt to call this tool again. \
     If there are no alternative methods to proceed, clearly explain the situation and STOP.";
 
+/// Start of the tool result when an approval policy refuses a call, followed by the reason
+pub const POLICY_DECLINED_PREFIX: &str = "The approval policy has declined to run this tool: ";
+
+fn policy_declined_response(reason: &str) -> String {
+    format!(
+        "{}{}. Do not call this tool again with the same arguments. \
+        If there are no alternative methods to proceed, clearly explain the situation and STOP.",
+        POLICY_DECLINED_PREFIX,
+        reason.trim_end_matches('.')
+    )
+}
+
 pub const CHAT_MODE_TOOL_SKIPPED_RESPONSE: &str = "Let the user know the tool call was skipped in Goose chat mode. \
                                         DO NOT apologize for skipping the tool call. DO NOT say sorry. \
                                         Provide an explanation of what the tool call would do, structured as a \
@@ -52,6 +97,7 @@ impl Agent {
         tool_futures: Arc<Mutex<Vec<(String, ToolStream)>>>,
         permission_manager: &'a mut PermissionManager,
         message_tool_response: Arc<Mutex<Message>>,
+        audit: &'a AuditScope,
     ) -> BoxStream<'a, anyhow::Result<Message>> {
         try_stream! {
             for request in tool_requests {
@@ -68,24 +114,31 @@ impl Agent {
                     while let Some((req_id, confirmation)) = rx.recv().await {
                         if req_id == request.id {
                             if confirmation.permission == Permission::AllowOnce || confirmation.permission == Permission::AlwaysAllow {
-                                let (req_id, tool_result) = self.dispatch_tool_call(tool_call.clone(), request.id.clone()).await;
+                                let started = Instant::now();
+                                let (req_id, tool_result) = self.dispatch_tool_call(tool_call.clone(), request.id.clone(), audit.redactor.as_ref()).await;
                                 let mut futures = tool_futures.lock().await;
 
-                                futures.push((req_id, match tool_result {
-                                    Ok(result) => tool_stream(
-                                        result.notification_stream.unwrap_or_else(|| Box::new(stream::empty())),
-                                        result.result,
-                                    ),
-                                    Err(e) => tool_stream(
-                                        Box::new(stream::empty()),
-                                        futures::future::ready(Err(e)),
-                                    ),
-                                }));
+                                futures.push((req_id.clone(), audited_tool_stream(
+                                    audit,
+                                    &req_id,
+                                    tool_call.clone(),
+                                    Approver::User,
+                                    started,
+                                    tool_result,
+                                )));
 
                                 if confirmation.permission == Permission::AlwaysAllow {
                                     permission_manager.update_user_permission(&tool_call.name, PermissionLevel::AlwaysAllow);
                                 }
                             } else {
+                                audit.record(
+                                    &request.id,
+                                    &tool_call,
+                                    Decision::Denied,
+                                    Approver::User,
+                                    Default::default(),
+                                    None,
+                                );
                                 // User declined - add declined response
                                 let mut response = message_tool_response.lock().await;
                                 *response = response.clone().with_tool_response(
@@ -101,15 +154,77 @@ impl Agent {
         }.boxed()
     }
 
+    /// Answer the confirmations for `tool_requests` from an approval policy instead of asking.
+    /// Returns the requests the policy leaves to be asked about as usual, and the ids of the
+    /// requests it denied.
+    pub(crate) async fn handle_policy_tool_requests(
+        &self,
+        policy: &ApprovalPolicy,
+        tool_requests: &[ToolRequest],
+        tool_futures: &mut Vec<(String, ToolStream)>,
+        message_tool_response: Arc<Mutex<Message>>,
+        audit: &AuditScope,
+    ) -> (Vec<ToolRequest>, Vec<String>) {
+        let mut undecided = Vec::new();
+        let mut denied = Vec::new();
+        for request in tool_requests {
+            if let Ok(tool_call) = request.tool_call.clone() {
+                match policy.decide(&request.id, &tool_call).await {
+                    None => undecided.push(request.clone()),
+                    Some(PolicyDecision::Allow) => {
+                        let started = Instant::now();
+                        let (req_id, tool_result) = self
+                            .dispatch_tool_call(
+                                tool_call.clone(),
+                                request.id.clone(),
+                                audit.redactor.as_ref(),
+                            )
+                            .await;
+                        tool_futures.push((
+                            req_id.clone(),
+                            audited_tool_stream(
+                                audit,
+                                &req_id,
+                                tool_call,
+                                Approver::ApprovalPolicy,
+                                started,
+                                tool_result,
+                            ),
+                        ));
+                    }
+                    Some(PolicyDecision::Deny(reason)) => {
+                        audit.record(
+                            &request.id,
+                            &tool_call,
+                            Decision::Denied,
+                            Approver::ApprovalPolicy,
+                            Default::default(),
+                            None,
+                        );
+                        let mut response = message_tool_response.lock().await;
+                        *response = response.clone().with_tool_response(
+                            request.id.clone(),
+                            Ok(vec![Content::text(policy_declined_response(&reason))]),
+                        );
+                        denied.push(request.id.clone());
+                    }
+                }
+            }
+        }
+        (undecided, denied)
+    }
+
     pub(crate) fn handle_frontend_tool_requests<'a>(
         &'a self,
         tool_requests: &'a [ToolRequest],
         message_tool_response: Arc<Mutex<Message>>,
+        audit: &'a AuditScope,
     ) -> BoxStream<'a, anyhow::Result<Message>> {
         try_stream! {
             for request in tool_requests {
                 if let Ok(tool_call) = request.tool_call.clone() {
                     if self.is_frontend_tool(&tool_call.name).await {
+                        let started = Instant::now();
                         // Send frontend tool request and wait for response
                         yield Message::assistant().with_frontend_tool_request(
                             request.id.clone(),
@@ -117,6 +232,14 @@ impl Agent {
                         );
 
                         if let Some((id, result)) = self.tool_result_rx.lock().await.recv().await {
+                            audit.record(
+                                &id,
+                                &tool_call,
+                                Decision::Approved,
+                                Approver::Frontend,
+                                started.elapsed(),
+                                Some(&result),
+                            );
                             let mut response = message_tool_response.lock().await;
                             *response = response.clone().with_tool_response(id, result);
                         }
@@ -22,6 +22,9 @@ pub struct SessionConfig {
     pub id: session::Identifier,
     /// Working directory for the session
     pub working_dir: PathBuf,
+    /// Project configuration layered over the user's for this session, if any
+    #[serde(default)]
+    pub project_config: Option<PathBuf>,
     /// ID of the schedule that triggered this session, if any
     pub schedule_id: Option<String>, // NEW
 }
@@ -0,0 +1,231 @@
+//! Files attached to user messages.
+//!
+//! Images are downscaled to a size every provider accepts, PDFs keep their extracted text
+//! for providers that cannot read them directly, and text files are inlined.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::{imageops::FilterType, GenericImageView, ImageOutputFormat};
+
+use crate::config::Config;
+use crate::message::MessageContent;
+
+/// Config key for the longest side, in pixels, of attached images
+pub const IMAGE_MAX_DIMENSION_KEY: &str = "GOOSE_IMAGE_MAX_DIMENSION";
+/// Larger images are scaled down by the providers anyway, so sending them only costs tokens
+pub const DEFAULT_IMAGE_MAX_DIMENSION: u32 = 1568;
+/// Providers reject requests much larger than this
+pub const MAX_ATTACHMENT_BYTES: usize = 32 * 1024 * 1024;
+
+const JPEG_QUALITY: u8 = 85;
+
+fn image_max_dimension() -> u32 {
+    Config::global()
+        .get_param(IMAGE_MAX_DIMENSION_KEY)
+        .unwrap_or(DEFAULT_IMAGE_MAX_DIMENSION)
+}
+
+/// Work out the type of a file from its contents, falling back to the declared type
+pub fn detect_mime_type(bytes: &[u8], declared: Option<&str>) -> Option<String> {
+    let sniffed = if bytes.starts_with(&[0x89, b'P', b'N', b'G']) {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF8") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if bytes.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else {
+        None
+    };
+
+    sniffed
+        .map(str::to_string)
+        .or_else(|| declared.map(str::to_string))
+        .or_else(|| {
+            std::str::from_utf8(bytes)
+                .is_ok()
+                .then(|| "text/plain".to_string())
+        })
+}
+
+/// Read a file from disk as message content
+pub fn load_attachment(path: &Path) -> Result<MessageContent> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string());
+    attachment_from_bytes(&bytes, name.as_deref(), None)
+}
+
+/// Turn the contents of an uploaded or pasted file into message content
+pub fn attachment_from_bytes(
+    bytes: &[u8],
+    name: Option<&str>,
+    mime_type: Option<&str>,
+) -> Result<MessageContent> {
+    if bytes.len() > MAX_ATTACHMENT_BYTES {
+        bail!(
+            "Attachment is too large ({} MB, the limit is {} MB)",
+            bytes.len() / (1024 * 1024),
+            MAX_ATTACHMENT_BYTES / (1024 * 1024)
+        );
+    }
+
+    let mime_type = detect_mime_type(bytes, mime_type)
+        .ok_or_else(|| anyhow!("Could not tell what kind of file this is"))?;
+    match mime_type.as_str() {
+        "image/png" | "image/jpeg" | "image/gif" | "image/webp" => {
+            let (data, mime_type) = downscale_image(bytes, &mime_type, image_max_dimension())?;
+            Ok(MessageContent::image(BASE64.encode(data), mime_type))
+        }
+        "application/pdf" => {
+            let text = match pdf_text(bytes) {
+                Ok(text) => Some(text),
+                Err(e) => {
+                    tracing::warn!("Failed to extract text from PDF: {}", e);
+                    None
+                }
+            };
+            Ok(MessageContent::document(
+                BASE64.encode(bytes),
+                mime_type,
+                name.map(str::to_string),
+                text,
+            ))
+        }
+        mime if mime.starts_with("text/") || mime == "application/json" => {
+            let text = std::str::from_utf8(bytes).context("Text file is not valid UTF-8")?;
+            Ok(MessageContent::text(format!(
+                "<file name=\"{}\">\n{}\n</file>",
+                name.unwrap_or("attachment"),
+                text
+            )))
+        }
+        other => bail!("Unsupported attachment type {}", other),
+    }
+}
+
+/// Scale an image down so its longest side is at most `max_dimension` pixels. Images that
+/// are already small enough are returned unchanged.
+pub fn downscale_image(
+    bytes: &[u8],
+    mime_type: &str,
+    max_dimension: u32,
+) -> Result<(Vec<u8>, String)> {
+    let image = image::load_from_memory(bytes).context("Failed to decode image")?;
+    let (width, height) = image.dimensions();
+    if width.max(height) <= max_dimension {
+        return Ok((bytes.to_vec(), mime_type.to_string()));
+    }
+
+    let resized = image.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+    tracing::debug!(
+        "Downscaled image from {}x{} to {}x{}",
+        width,
+        height,
+        resized.width(),
+        resized.height()
+    );
+
+    // Photos stay JPEG; everything else becomes PNG, which every provider accepts
+    let (format, mime_type) = if mime_type == "image/jpeg" {
+        (ImageOutputFormat::Jpeg(JPEG_QUALITY), "image/jpeg")
+    } else {
+        (ImageOutputFormat::Png, "image/png")
+    };
+    let mut output = Cursor::new(Vec::new());
+    resized
+        .write_to(&mut output, format)
+        .context("Failed to encode image")?;
+    Ok((output.into_inner(), mime_type.to_string()))
+}
+
+/// Extract the text of every page of a PDF
+pub fn pdf_text(bytes: &[u8]) -> Result<String> {
+    let document = lopdf::Document::load_mem(bytes).context("Failed to parse PDF")?;
+    let pages: Vec<u32> = document.get_pages().keys().copied().collect();
+    document
+        .extract_text(&pages)
+        .context("Failed to extract text from PDF")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, RgbImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(RgbImage::new(width, height));
+        let mut output = Cursor::new(Vec::new());
+        image.write_to(&mut output, ImageOutputFormat::Png).unwrap();
+        output.into_inner()
+    }
+
+    #[test]
+    fn test_detect_mime_type() {
+        assert_eq!(
+            detect_mime_type(&png(1, 1), Some("application/octet-stream")).as_deref(),
+            Some("image/png")
+        );
+        assert_eq!(
+            detect_mime_type(b"%PDF-1.7\n", None).as_deref(),
+            Some("application/pdf")
+        );
+        assert_eq!(
+            detect_mime_type(b"fn main() {}", None).as_deref(),
+            Some("text/plain")
+        );
+        assert_eq!(
+            detect_mime_type(b"a,b\n1,2", Some("text/csv")).as_deref(),
+            Some("text/csv")
+        );
+        assert_eq!(detect_mime_type(&[0xFF, 0xFE, 0x00, 0xC3], None), None);
+    }
+
+    #[test]
+    fn test_large_images_are_downscaled() {
+        let (data, mime_type) = downscale_image(&png(3000, 1500), "image/png", 1000).unwrap();
+        assert_eq!(mime_type, "image/png");
+        let image = image::load_from_memory(&data).unwrap();
+        assert_eq!(image.dimensions(), (1000, 500));
+
+        let small = png(200, 100);
+        let (data, _) = downscale_image(&small, "image/png", 1000).unwrap();
+        assert_eq!(data, small);
+    }
+
+    #[test]
+    fn test_attachment_kinds() {
+        match attachment_from_bytes(&png(10, 10), Some("shot.png"), None).unwrap() {
+            MessageContent::Image(image) => assert_eq!(image.mime_type, "image/png"),
+            other => panic!("Expected an image, got {:?}", other),
+        }
+
+        match attachment_from_bytes(b"hello", Some("notes.md"), None).unwrap() {
+            MessageContent::Text(text) => {
+                assert_eq!(text.text, "<file name=\"notes.md\">\nhello\n</file>")
+            }
+            other => panic!("Expected text, got {:?}", other),
+        }
+
+        // A PDF whose text can't be extracted is still attached for providers that read PDFs
+        match attachment_from_bytes(b"%PDF-1.7\nnot really", Some("report.pdf"), None).unwrap() {
+            MessageContent::Document(document) => {
+                assert_eq!(document.mime_type, "application/pdf");
+                assert_eq!(document.name.as_deref(), Some("report.pdf"));
+                assert!(document.text.is_none());
+                assert!(document
+                    .as_fallback_text()
+                    .contains("could not be extracted"));
+            }
+            other => panic!("Expected a document, got {:?}", other),
+        }
+
+        assert!(attachment_from_bytes(&[0xFF, 0xFE, 0x00, 0xC3], None, None).is_err());
+    }
+}
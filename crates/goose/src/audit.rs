@@ -0,0 +1,738 @@
+//! Append-only audit log of tool calls.
+//!
+//! Every tool call the agent dispatches (or refuses to dispatch) is written as one JSON line to
+//! `audit.jsonl` in the goose data directory. Each entry carries the SHA-256 hash of the previous
+//! entry and a hash over its own contents, so editing, removing or reordering entries breaks the
+//! chain and is reported by [`AuditLog::verify`].
+//!
+//! Configuration:
+//! - `GOOSE_AUDIT_LOG`: set to `false` to disable the log (enabled by default)
+//! - `GOOSE_AUDIT_LOG_PATH`: write the log somewhere other than the default location
+//!
+//! Arguments are redacted by the session's [`SessionRedactor`] before they are written, so the
+//! log holds the same placeholders as the conversation. See [`crate::redaction`] for its
+//! settings.
+
+use crate::config::Config;
+use crate::redaction::SessionRedactor;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use etcetera::{choose_app_strategy, AppStrategy, AppStrategyArgs};
+use fs2::FileExt;
+use mcp_core::{tool::ToolCall, Content, ToolResult};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// `prev_hash` of the first entry in a log
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+const MAX_ARGUMENT_CHARS: usize = 4096;
+
+/// Whether a tool call was allowed to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Approved,
+    Denied,
+    /// Not run because goose is in chat mode
+    Skipped,
+}
+
+/// Who made the permission decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Approver {
+    /// The goose mode alone decided, e.g. everything runs in `auto`
+    Mode,
+    /// A permission stored in the user or project configuration
+    StoredPermission,
+    /// The tool is annotated as read-only, so smart_approve ran it without asking
+    ReadOnly,
+    /// smart_approve judged the call read-only, now or earlier in the session
+    SmartApprove,
+    /// The approval policy answered the confirmation in an unattended run
+    ApprovalPolicy,
+    /// The user answered a confirmation prompt
+    User,
+    /// The call was handed to the frontend that registered the tool
+    Frontend,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    Error,
+    NotRun,
+}
+
+/// The hashed contents of an audit entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub host: String,
+    pub user: String,
+    pub session_id: Option<String>,
+    pub working_dir: Option<PathBuf>,
+    pub mode: String,
+    pub request_id: String,
+    pub extension: String,
+    pub tool: String,
+    pub arguments: Value,
+    pub decision: Decision,
+    pub approver: Approver,
+    pub duration_ms: u64,
+    pub outcome: Outcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unreadable lines between this entry and the one it chains from, left by a write that
+    /// was cut short
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped_lines: Option<u64>,
+    pub prev_hash: String,
+}
+
+impl AuditRecord {
+    fn compute_hash(&self) -> Result<String> {
+        let bytes = serde_json::to_vec(self)?;
+        Ok(Sha256::digest(&bytes)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect())
+    }
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    #[serde(flatten)]
+    pub record: AuditRecord,
+    pub hash: String,
+}
+
+/// Session level context shared by every tool call in a reply
+#[derive(Debug, Clone, Default)]
+pub struct AuditScope {
+    pub session_id: Option<String>,
+    /// Redacts tool call arguments with the session's placeholders, None when redaction is off
+    pub redactor: Option<SessionRedactor>,
+    pub working_dir: Option<PathBuf>,
+    pub mode: String,
+}
+
+impl AuditScope {
+    /// Record a tool call in the global audit log, if enabled. Failures are logged, never raised,
+    /// so a broken log cannot stop the agent.
+    pub fn record(
+        &self,
+        request_id: &str,
+        tool_call: &ToolCall,
+        decision: Decision,
+        approver: Approver,
+        duration: Duration,
+        result: Option<&ToolResult<Vec<Content>>>,
+    ) {
+        let Some(log) = AuditLog::global() else {
+            return;
+        };
+
+        let (outcome, error) = match result {
+            None => (Outcome::NotRun, None),
+            Some(Ok(_)) => (Outcome::Success, None),
+            Some(Err(e)) => (Outcome::Error, Some(e.to_string())),
+        };
+        let (extension, tool) = match tool_call.name.split_once("__") {
+            Some((extension, tool)) => (extension.to_string(), tool.to_string()),
+            None => (String::new(), tool_call.name.clone()),
+        };
+
+        let record = AuditRecord {
+            seq: 0,
+            timestamp: Utc::now(),
+            host: host_name(),
+            user: user_name(),
+            session_id: self.session_id.clone(),
+            working_dir: self.working_dir.clone(),
+            mode: self.mode.clone(),
+            request_id: request_id.to_string(),
+            extension,
+            tool,
+            arguments: match &self.redactor {
+                Some(redactor) => redactor.redact_value(&tool_call.arguments),
+                None => tool_call.arguments.clone(),
+            },
+            decision,
+            approver,
+            duration_ms: duration.as_millis() as u64,
+            outcome,
+            error,
+            skipped_lines: None,
+            prev_hash: String::new(),
+        };
+
+        if let Err(e) = log.append(record) {
+            tracing::warn!("Failed to write audit log entry: {}", e);
+        }
+    }
+}
+
+/// Shorten long string arguments, such as whole files passed to an editor tool
+fn truncate_arguments(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), truncate_arguments(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(truncate_arguments).collect()),
+        Value::String(text) => {
+            let total = text.chars().count();
+            if total <= MAX_ARGUMENT_CHARS {
+                return value.clone();
+            }
+            Value::String(format!(
+                "{}... [{} more characters]",
+                text.chars().take(MAX_ARGUMENT_CHARS).collect::<String>(),
+                total - MAX_ARGUMENT_CHARS
+            ))
+        }
+        other => other.clone(),
+    }
+}
+
+/// Entry selection for `goose audit list` and `goose audit export`
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub session_id: Option<String>,
+    /// Matches either the tool name or the full `extension__tool` name
+    pub tool: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub decision: Option<Decision>,
+    pub errors_only: bool,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        let record = &entry.record;
+        if let Some(session_id) = &self.session_id {
+            if record.session_id.as_ref() != Some(session_id) {
+                return false;
+            }
+        }
+        if let Some(tool) = &self.tool {
+            let full_name = format!("{}__{}", record.extension, record.tool);
+            if &record.tool != tool && &full_name != tool && &record.extension != tool {
+                return false;
+            }
+        }
+        if let Some(since) = self.since {
+            if record.timestamp < since {
+                return false;
+            }
+        }
+        if let Some(decision) = self.decision {
+            if record.decision != decision {
+                return false;
+            }
+        }
+        !self.errors_only || record.outcome == Outcome::Error
+    }
+}
+
+/// Result of checking the hash chain of a log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    Valid {
+        entries: usize,
+    },
+    /// The chain holds across lines that could not be read, which the entry written after
+    /// them flagged as skipped
+    Recovered {
+        entries: usize,
+        /// Line numbers (1 based) of the skipped lines
+        skipped: Vec<usize>,
+    },
+    /// The first line (1 based) where the chain no longer holds
+    Broken {
+        line: usize,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+static GLOBAL_AUDIT_LOG: OnceCell<Option<AuditLog>> = OnceCell::new();
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// The audit log configured for this process, or None when it has been disabled
+    pub fn global() -> Option<&'static AuditLog> {
+        GLOBAL_AUDIT_LOG
+            .get_or_init(|| {
+                let config = Config::global();
+                if !config.get_param::<bool>("GOOSE_AUDIT_LOG").unwrap_or(true) {
+                    return None;
+                }
+                Some(Self::new(Self::configured_path()))
+            })
+            .as_ref()
+    }
+
+    /// Location of the log, honouring `GOOSE_AUDIT_LOG_PATH`
+    pub fn configured_path() -> PathBuf {
+        if let Ok(path) = Config::global().get_param::<String>("GOOSE_AUDIT_LOG_PATH") {
+            return PathBuf::from(path);
+        }
+        let app_strategy = AppStrategyArgs {
+            top_level_domain: "Block".to_string(),
+            author: "Block".to_string(),
+            app_name: "goose".to_string(),
+        };
+        choose_app_strategy(app_strategy)
+            .expect("goose requires a home dir")
+            .data_dir()
+            .join("audit.jsonl")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Truncate, chain and append a record. `seq` and `prev_hash` are assigned here.
+    pub fn append(&self, mut record: AuditRecord) -> Result<AuditEntry> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open audit log {}", self.path.display()))?;
+
+        // Other goose processes may be appending too; hold the lock across read and write so
+        // the chain stays linear
+        file.lock_exclusive()?;
+        let result = (|| -> Result<AuditEntry> {
+            let (previous, skipped) = match last_line(&mut file)? {
+                None => (None, 0),
+                Some(line) => match serde_json::from_str::<AuditEntry>(&line) {
+                    Ok(entry) => (Some(entry), 0),
+                    Err(e) => {
+                        let (previous, skipped) = last_readable_entry(&mut file)?;
+                        tracing::warn!(
+                            "Last audit log entry is unreadable ({}), chaining from entry {} and skipping {} line(s)",
+                            e,
+                            previous.as_ref().map_or(0, |p| p.record.seq),
+                            skipped
+                        );
+                        (previous, skipped)
+                    }
+                },
+            };
+
+            record.arguments = truncate_arguments(&record.arguments);
+            record.seq = previous.as_ref().map_or(0, |p| p.record.seq + 1);
+            record.skipped_lines = (skipped > 0).then_some(skipped);
+            record.prev_hash = previous.map_or_else(|| GENESIS_HASH.to_string(), |p| p.hash);
+            let hash = record.compute_hash()?;
+            let entry = AuditEntry { record, hash };
+
+            let mut line = serde_json::to_string(&entry)?;
+            line.push('\n');
+            // A write that was cut short leaves no newline, don't run on from it
+            if !ends_with_newline(&mut file)? {
+                line.insert(0, '\n');
+            }
+            file.write_all(line.as_bytes())?;
+            file.flush()?;
+            Ok(entry)
+        })();
+        let _ = FileExt::unlock(&file);
+        result
+    }
+
+    /// All entries in the log, oldest first. Blank lines and unreadable lines flagged by the
+    /// entry after them are skipped, as in [`AuditLog::verify`].
+    pub fn read(&self) -> Result<Vec<AuditEntry>> {
+        let (lines, trailing) = self.lines()?;
+        let mut entries = Vec::new();
+        for line in lines {
+            if let Some((number, error)) = line.unflagged_unreadable() {
+                return Err(anyhow!(
+                    "Invalid audit log entry on line {}: {}",
+                    number,
+                    error
+                ));
+            }
+            entries.push(line.entry);
+        }
+        if let Some((number, error)) = trailing.first() {
+            return Err(anyhow!(
+                "Invalid audit log entry on line {}: {}",
+                number,
+                error
+            ));
+        }
+        Ok(entries)
+    }
+
+    /// Check that every entry hashes to its recorded hash and links to the one before it
+    pub fn verify(&self) -> Result<Verification> {
+        let (lines, trailing) = self.lines()?;
+        let mut expected_prev = GENESIS_HASH.to_string();
+        let mut count = 0;
+        let mut skipped = Vec::new();
+
+        for line in lines {
+            if let Some((number, error)) = line.unflagged_unreadable() {
+                return Ok(Verification::Broken {
+                    line: number,
+                    reason: format!("unparseable entry: {}", error),
+                });
+            }
+            skipped.extend(line.unreadable.iter().map(|(number, _)| *number));
+
+            let broken = |reason: String| Verification::Broken {
+                line: line.number,
+                reason,
+            };
+            let entry = line.entry;
+            if entry.record.seq != count as u64 {
+                return Ok(broken(format!(
+                    "expected sequence number {}, found {}",
+                    count, entry.record.seq
+                )));
+            }
+            if entry.record.prev_hash != expected_prev {
+                return Ok(broken("previous hash does not match".to_string()));
+            }
+            if entry.record.compute_hash()? != entry.hash {
+                return Ok(broken("entry contents do not match its hash".to_string()));
+            }
+            expected_prev = entry.hash;
+            count += 1;
+        }
+        if let Some((number, error)) = trailing.first() {
+            return Ok(Verification::Broken {
+                line: *number,
+                reason: format!("unparseable entry: {}", error),
+            });
+        }
+
+        if skipped.is_empty() {
+            Ok(Verification::Valid { entries: count })
+        } else {
+            Ok(Verification::Recovered {
+                entries: count,
+                skipped,
+            })
+        }
+    }
+
+    /// The entries of the log with the unreadable lines before each, and the unreadable lines
+    /// after the last entry. Blank lines are left out.
+    fn lines(&self) -> Result<(Vec<LogLine>, Vec<(usize, String)>)> {
+        if !self.path.exists() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let reader = BufReader::new(File::open(&self.path)?);
+        let mut lines = Vec::new();
+        let mut unreadable = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<AuditEntry>(&line) {
+                Ok(entry) => lines.push(LogLine {
+                    number: index + 1,
+                    entry,
+                    unreadable: std::mem::take(&mut unreadable),
+                }),
+                Err(e) => unreadable.push((index + 1, e.to_string())),
+            }
+        }
+        Ok((lines, unreadable))
+    }
+}
+
+/// An entry of the log, with the unreadable lines (number and parse error) right before it
+struct LogLine {
+    number: usize,
+    entry: AuditEntry,
+    unreadable: Vec<(usize, String)>,
+}
+
+impl LogLine {
+    /// The first unreadable line before this entry, unless the entry flagged them as skipped
+    fn unflagged_unreadable(&self) -> Option<(usize, &str)> {
+        let (number, error) = self.unreadable.first()?;
+        if self.entry.record.skipped_lines == Some(self.unreadable.len() as u64) {
+            return None;
+        }
+        Some((*number, error))
+    }
+}
+
+/// The last entry that can be read and the number of non-blank lines after it
+fn last_readable_entry(file: &mut File) -> Result<(Option<AuditEntry>, u64)> {
+    file.seek(SeekFrom::Start(0))?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
+    let mut last = None;
+    let mut skipped = 0;
+    for line in String::from_utf8_lossy(&buffer).lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<AuditEntry>(line) {
+            Ok(entry) => {
+                last = Some(entry);
+                skipped = 0;
+            }
+            Err(_) => skipped += 1,
+        }
+    }
+    Ok((last, skipped))
+}
+
+fn ends_with_newline(file: &mut File) -> Result<bool> {
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return Ok(true);
+    }
+    file.seek(SeekFrom::Start(len - 1))?;
+    let mut last = [0u8; 1];
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+/// Read the last non-empty line of a file without scanning the whole file
+fn last_line(file: &mut File) -> Result<Option<String>> {
+    let len = file.metadata()?.len();
+    let mut window: u64 = 16 * 1024;
+    loop {
+        let start = len.saturating_sub(window);
+        file.seek(SeekFrom::Start(start))?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        let text = String::from_utf8_lossy(&buffer);
+        let trimmed = text.trim_end();
+        if trimmed.is_empty() {
+            return Ok(None);
+        }
+        match trimmed.rfind('\n') {
+            Some(idx) => return Ok(Some(trimmed[idx + 1..].to_string())),
+            None if start == 0 => return Ok(Some(trimmed.to_string())),
+            None => {
+                window = window
+                    .checked_mul(2)
+                    .ok_or_else(|| anyhow!("line too long"))?
+            }
+        }
+    }
+}
+
+fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| {
+            fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+        })
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn user_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn record(tool: &str, arguments: Value) -> AuditRecord {
+        AuditRecord {
+            seq: 0,
+            timestamp: Utc::now(),
+            host: "host".to_string(),
+            user: "user".to_string(),
+            session_id: Some("session".to_string()),
+            working_dir: None,
+            mode: "auto".to_string(),
+            request_id: "req".to_string(),
+            extension: "developer".to_string(),
+            tool: tool.to_string(),
+            arguments,
+            decision: Decision::Approved,
+            approver: Approver::Mode,
+            duration_ms: 5,
+            outcome: Outcome::Success,
+            error: None,
+            skipped_lines: None,
+            prev_hash: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_append_chains_entries() -> Result<()> {
+        let dir = tempdir()?;
+        let log = AuditLog::new(dir.path().join("audit.jsonl"));
+
+        let first = log.append(record("shell", json!({"command": "ls"})))?;
+        let second = log.append(record("text_editor", json!({"path": "a.txt"})))?;
+
+        assert_eq!(first.record.seq, 0);
+        assert_eq!(first.record.prev_hash, GENESIS_HASH);
+        assert_eq!(second.record.seq, 1);
+        assert_eq!(second.record.prev_hash, first.hash);
+        assert_eq!(log.read()?, vec![first, second]);
+        assert_eq!(log.verify()?, Verification::Valid { entries: 2 });
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_detects_tampering() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("audit.jsonl");
+        let log = AuditLog::new(path.clone());
+        for command in ["ls", "rm -rf build", "cargo test"] {
+            log.append(record("shell", json!({"command": command})))?;
+        }
+
+        let original = fs::read_to_string(&path)?;
+        fs::write(&path, original.replace("rm -rf build", "echo hello"))?;
+        assert!(matches!(
+            log.verify()?,
+            Verification::Broken { line: 2, .. }
+        ));
+
+        // Dropping a line breaks the link from the one after it
+        let lines: Vec<&str> = original.lines().collect();
+        fs::write(&path, format!("{}\n{}\n", lines[0], lines[2]))?;
+        assert!(matches!(
+            log.verify()?,
+            Verification::Broken { line: 2, .. }
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_blank_lines_are_ignored() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("audit.jsonl");
+        let log = AuditLog::new(path.clone());
+        log.append(record("shell", json!({"command": "ls"})))?;
+        log.append(record("shell", json!({"command": "pwd"})))?;
+
+        let lines: Vec<String> = fs::read_to_string(&path)?
+            .lines()
+            .map(str::to_string)
+            .collect();
+        fs::write(&path, format!("{}\n\n  \n{}\n\n", lines[0], lines[1]))?;
+        assert_eq!(log.read()?.len(), 2);
+        assert_eq!(log.verify()?, Verification::Valid { entries: 2 });
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_recovers_from_a_cut_short_entry() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("audit.jsonl");
+        let log = AuditLog::new(path.clone());
+        log.append(record("shell", json!({"command": "ls"})))?;
+        let second = log.append(record("shell", json!({"command": "pwd"})))?;
+
+        // A crash in the middle of a write leaves half a line behind
+        let mut file = OpenOptions::new().append(true).open(&path)?;
+        file.write_all(br#"{"seq":2,"timestamp":"2025-"#)?;
+        drop(file);
+        assert!(log.read().is_err());
+        assert!(matches!(
+            log.verify()?,
+            Verification::Broken { line: 3, .. }
+        ));
+
+        let third = log.append(record("shell", json!({"command": "whoami"})))?;
+        assert_eq!(third.record.seq, 2);
+        assert_eq!(third.record.prev_hash, second.hash);
+        assert_eq!(third.record.skipped_lines, Some(1));
+        assert_eq!(log.read()?.len(), 3);
+        assert_eq!(
+            log.verify()?,
+            Verification::Recovered {
+                entries: 3,
+                skipped: vec![3]
+            }
+        );
+
+        // Later entries chain on as usual
+        let fourth = log.append(record("shell", json!({"command": "date"})))?;
+        assert_eq!(fourth.record.prev_hash, third.hash);
+        assert_eq!(fourth.record.skipped_lines, None);
+        assert!(matches!(
+            log.verify()?,
+            Verification::Recovered { entries: 4, .. }
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_arguments_are_truncated() {
+        let truncated = truncate_arguments(&json!({
+            "command": "ls",
+            "nested": [{"content": "x".repeat(MAX_ARGUMENT_CHARS + 10)}]
+        }));
+
+        assert_eq!(truncated["command"], "ls");
+        assert!(truncated["nested"][0]["content"]
+            .as_str()
+            .unwrap()
+            .ends_with("[10 more characters]"));
+    }
+
+    #[test]
+    fn test_filter() {
+        let entry = AuditEntry {
+            record: record("shell", json!({})),
+            hash: String::new(),
+        };
+        assert!(AuditFilter::default().matches(&entry));
+        assert!(AuditFilter {
+            tool: Some("developer__shell".to_string()),
+            ..Default::default()
+        }
+        .matches(&entry));
+        assert!(!AuditFilter {
+            session_id: Some("other".to_string()),
+            ..Default::default()
+        }
+        .matches(&entry));
+        assert!(!AuditFilter {
+            errors_only: true,
+            ..Default::default()
+        }
+        .matches(&entry));
+    }
+}
@@ -85,7 +85,7 @@ impl ExtensionConfigManager {
         let config = Config::global();
 
         let mut extensions: HashMap<String, ExtensionEntry> = config
-            .get_param("extensions")
+            .get_user_param("extensions")
             .unwrap_or_else(|_| HashMap::new());
 
         let key = entry.config.key();
@@ -100,7 +100,7 @@ impl ExtensionConfigManager {
         let config = Config::global();
 
         let mut extensions: HashMap<String, ExtensionEntry> = config
-            .get_param("extensions")
+            .get_user_param("extensions")
             .unwrap_or_else(|_| HashMap::new());
 
         extensions.remove(key);
@@ -113,7 +113,7 @@ impl ExtensionConfigManager {
         let config = Config::global();
 
         let mut extensions: HashMap<String, ExtensionEntry> = config
-            .get_param("extensions")
+            .get_user_param("extensions")
             .unwrap_or_else(|_| HashMap::new());
 
         if let Some(entry) = extensions.get_mut(key) {
@@ -125,7 +125,12 @@ impl ExtensionConfigManager {
 
     /// Get all extensions and their configurations
     pub fn get_all() -> Result<Vec<ExtensionEntry>> {
-        let config = Config::global();
+        Self::get_all_in(Config::global())
+    }
+
+    /// Get all extensions and their configurations as a given configuration sees them, such as
+    /// one with a session's project configuration layered over the user's
+    pub fn get_all_in(config: &Config) -> Result<Vec<ExtensionEntry>> {
         let extensions: HashMap<String, ExtensionEntry> = match config.get_param("extensions") {
             Ok(exts) => exts,
             Err(super::ConfigError::NotFound(_)) => HashMap::new(),
@@ -6,8 +6,9 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use utoipa::ToSchema;
 
-/// Enum representing the possible permission levels for a tool.
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, ToSchema)]
+/// Enum representing the possible permission levels for a tool, from the least to the most
+/// restrictive.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum PermissionLevel {
     AlwaysAllow, // Tool can always be used without prompt
@@ -17,6 +18,7 @@ pub enum PermissionLevel {
 
 /// Struct representing the configuration of permissions, categorized by level.
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(default)]
 pub struct PermissionConfig {
     pub always_allow: Vec<String>, // List of tools that are always allowed
     pub ask_before: Vec<String>,   // List of tools that require user consent
@@ -28,6 +30,8 @@ pub struct PermissionConfig {
 pub struct PermissionManager {
     config_path: PathBuf, // Path to the permission configuration file
     permission_map: HashMap<String, PermissionConfig>, // Mapping of permission names to configurations
+    project_permissions: PermissionConfig, // Tool permissions set by the project configuration
+    project_trusted: bool,                 // Whether project permissions may also loosen the user's
 }
 
 // Constants representing specific permission categories
@@ -59,6 +63,8 @@ impl Default for PermissionManager {
         PermissionManager {
             config_path,
             permission_map,
+            project_permissions: PermissionConfig::default(),
+            project_trusted: false,
         }
     }
 }
@@ -81,17 +87,44 @@ impl PermissionManager {
         PermissionManager {
             config_path,
             permission_map,
+            project_permissions: PermissionConfig::default(),
+            project_trusted: false,
         }
     }
 
+    /// Uses the given tool permissions of a project, which take precedence over the user's.
+    /// Unless the project is trusted, they can only make a tool more restricted.
+    pub fn with_project_permissions(
+        mut self,
+        permissions: PermissionConfig,
+        trusted: bool,
+    ) -> Self {
+        self.project_permissions = permissions;
+        self.project_trusted = trusted;
+        self
+    }
+
     /// Returns a list of all the names (keys) in the permission map.
     pub fn get_permission_names(&self) -> Vec<String> {
         self.permission_map.keys().cloned().collect()
     }
 
     /// Retrieves the user permission level for a specific tool.
+    ///
+    /// A permission set by the project configuration takes precedence over the user's own,
+    /// unless the project is untrusted and its permission is the less restrictive one.
     pub fn get_user_permission(&self, principal_name: &str) -> Option<PermissionLevel> {
-        self.get_permission(USER_PERMISSION, principal_name)
+        let project = self.get_project_permission(principal_name);
+        let user = self.get_permission(USER_PERMISSION, principal_name);
+        match (project, user) {
+            (Some(project), Some(user)) if !self.project_trusted && project < user => Some(user),
+            (project, user) => project.or(user),
+        }
+    }
+
+    /// Retrieves the permission level the project configuration sets for a specific tool.
+    pub fn get_project_permission(&self, principal_name: &str) -> Option<PermissionLevel> {
+        level_in(&self.project_permissions, principal_name)
     }
 
     /// Retrieves the smart approve permission level for a specific tool.
@@ -102,26 +135,9 @@ impl PermissionManager {
     /// Helper function to retrieve the permission level for a specific permission category and tool.
     fn get_permission(&self, name: &str, principal_name: &str) -> Option<PermissionLevel> {
         // Check if the permission category exists in the map
-        if let Some(permission_config) = self.permission_map.get(name) {
-            // Check the permission levels for the given tool
-            if permission_config
-                .always_allow
-                .contains(&principal_name.to_string())
-            {
-                return Some(PermissionLevel::AlwaysAllow);
-            } else if permission_config
-                .ask_before
-                .contains(&principal_name.to_string())
-            {
-                return Some(PermissionLevel::AskBefore);
-            } else if permission_config
-                .never_allow
-                .contains(&principal_name.to_string())
-            {
-                return Some(PermissionLevel::NeverAllow);
-            }
-        }
-        None // Return None if no matching permission level is found
+        self.permission_map
+            .get(name)
+            .and_then(|permission_config| level_in(permission_config, principal_name))
     }
 
     /// Updates the user permission level for a specific tool.
@@ -191,6 +207,29 @@ impl PermissionManager {
     }
 }
 
+/// Finds the permission level of a tool in one permission configuration.
+fn level_in(permission_config: &PermissionConfig, principal_name: &str) -> Option<PermissionLevel> {
+    // Check the permission levels for the given tool
+    if permission_config
+        .always_allow
+        .contains(&principal_name.to_string())
+    {
+        Some(PermissionLevel::AlwaysAllow)
+    } else if permission_config
+        .ask_before
+        .contains(&principal_name.to_string())
+    {
+        Some(PermissionLevel::AskBefore)
+    } else if permission_config
+        .never_allow
+        .contains(&principal_name.to_string())
+    {
+        Some(PermissionLevel::NeverAllow)
+    } else {
+        None // Return None if no matching permission level is found
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -305,4 +344,49 @@ mod tests {
             .always_allow
             .contains(&"nonprefix__tool2".to_string()));
     }
+
+    #[test]
+    fn test_project_permissions_take_precedence() {
+        let mut manager = create_test_permission_manager().with_project_permissions(
+            PermissionConfig {
+                never_allow: vec!["developer__shell".to_string()],
+                ..Default::default()
+            },
+            false,
+        );
+        manager.update_user_permission("developer__shell", PermissionLevel::AlwaysAllow);
+        manager.update_user_permission("developer__text_editor", PermissionLevel::AlwaysAllow);
+
+        assert_eq!(
+            manager.get_user_permission("developer__shell"),
+            Some(PermissionLevel::NeverAllow)
+        );
+        assert_eq!(
+            manager.get_user_permission("developer__text_editor"),
+            Some(PermissionLevel::AlwaysAllow)
+        );
+    }
+
+    #[test]
+    fn test_untrusted_project_permissions_only_tighten() {
+        let project_permissions = PermissionConfig {
+            ask_before: vec!["developer__shell".to_string()],
+            ..Default::default()
+        };
+        let mut manager = create_test_permission_manager()
+            .with_project_permissions(project_permissions.clone(), false);
+        manager.update_user_permission("developer__shell", PermissionLevel::NeverAllow);
+        assert_eq!(
+            manager.get_user_permission("developer__shell"),
+            Some(PermissionLevel::NeverAllow)
+        );
+
+        let mut manager =
+            create_test_permission_manager().with_project_permissions(project_permissions, true);
+        manager.update_user_permission("developer__shell", PermissionLevel::NeverAllow);
+        assert_eq!(
+            manager.get_user_permission("developer__shell"),
+            Some(PermissionLevel::AskBefore)
+        );
+    }
 }
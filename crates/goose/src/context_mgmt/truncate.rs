@@ -1,12 +1,27 @@
+use crate::config::Config;
 use crate::message::{Message, MessageContent};
+use crate::providers::base::Provider;
 use anyhow::{anyhow, Result};
 use mcp_core::{Content, ResourceContents, Role};
-use std::collections::HashSet;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 use tracing::{debug, warn};
 
 /// Maximum size for truncated content in characters
 const MAX_TRUNCATED_CONTENT_SIZE: usize = 5000;
 
+/// Config key selecting the truncation strategy
+pub const TRUNCATION_STRATEGY_KEY: &str = "GOOSE_TRUNCATION_STRATEGY";
+/// Config key with regexes that pin matching messages for `PinnedTruncation`
+pub const PINNED_PATTERNS_KEY: &str = "GOOSE_TRUNCATION_PINNED_PATTERNS";
+const DEFAULT_PINNED_PATTERN: &str = r"(?i)#important\b";
+/// Characters of each message that are embedded for `RelevanceTruncation`
+const MAX_EMBEDDING_TEXT_SIZE: usize = 2000;
+
 /// Handles messages that are individually larger than the context limit
 /// by truncating their content rather than removing them entirely
 fn handle_oversized_messages(
@@ -218,7 +233,14 @@ pub fn truncate_messages(
         return Ok((messages, token_counts)); // No truncation needed
     }
 
-    // Step 2: Determine indices to remove based on strategy
+    // Step 2: Give the strategy a chance to shrink contents before whole messages are removed
+    strategy.elide_contents(&mut messages, &mut token_counts, context_limit);
+    total_tokens = token_counts.iter().sum();
+    if total_tokens <= context_limit {
+        return Ok((messages, token_counts));
+    }
+
+    // Step 3: Determine indices to remove based on strategy
     let indices_to_remove =
         strategy.determine_indices_to_remove(&messages, &token_counts, context_limit)?;
 
@@ -244,7 +266,7 @@ pub fn truncate_messages(
         ));
     }
 
-    // Step 3: Remove the marked messages
+    // Step 4: Remove the marked messages
     // Vectorize the set and sort in reverse order to avoid shifting indices when removing
     let mut indices_to_remove = indices_to_remove.iter().cloned().collect::<Vec<usize>>();
     indices_to_remove.sort_unstable_by(|a, b| b.cmp(a));
@@ -257,7 +279,7 @@ pub fn truncate_messages(
         }
     }
 
-    // Step 4: Ensure the last message is a user message with TextContent only
+    // Step 5: Ensure the last message is a user message with TextContent only
     while let Some(last_msg) = messages.last() {
         if last_msg.role != Role::User || !last_msg.has_only_text_content() {
             let _ = messages.pop().ok_or(anyhow!("Failed to pop message"))?;
@@ -270,7 +292,7 @@ pub fn truncate_messages(
         }
     }
 
-    // Step 5: Check first msg is a User message with TextContent only
+    // Step 6: Check first msg is a User message with TextContent only
     while let Some(first_msg) = messages.first() {
         if first_msg.role != Role::User || !first_msg.has_only_text_content() {
             let _ = messages.remove(0);
@@ -315,6 +337,114 @@ pub trait TruncationStrategy {
         token_counts: &[usize],
         context_limit: usize,
     ) -> Result<HashSet<usize>>;
+
+    /// Shrinks message contents in place before any message is removed, updating
+    /// `token_counts` to match. Strategies that only remove whole messages keep the default.
+    fn elide_contents(
+        &self,
+        _messages: &mut [Message],
+        _token_counts: &mut [usize],
+        _context_limit: usize,
+    ) {
+    }
+}
+
+/// The truncation strategies a user can pick in config or in a recipe
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationStrategyKind {
+    /// Remove the oldest messages first
+    #[default]
+    OldestFirst,
+    /// Elide the largest tool outputs first and keep the assistant's reasoning
+    ToolOutputFirst,
+    /// Keep the first user message and messages marked important
+    Pinned,
+    /// Remove the messages least similar to the latest request, using embeddings
+    Relevance,
+}
+
+impl TruncationStrategyKind {
+    pub fn from_config() -> Self {
+        Config::global()
+            .get_param(TRUNCATION_STRATEGY_KEY)
+            .unwrap_or_default()
+    }
+}
+
+/// Build the strategy for `kind`. Relevance needs embeddings from the provider and falls back
+/// to oldest first when they are unavailable.
+pub async fn create_truncation_strategy(
+    kind: TruncationStrategyKind,
+    provider: &Arc<dyn Provider>,
+    messages: &[Message],
+) -> Box<dyn TruncationStrategy + Send + Sync> {
+    match kind {
+        TruncationStrategyKind::OldestFirst => Box::new(OldestFirstTruncation),
+        TruncationStrategyKind::ToolOutputFirst => Box::new(ToolOutputFirstTruncation),
+        TruncationStrategyKind::Pinned => Box::new(PinnedTruncation::from_config()),
+        TruncationStrategyKind::Relevance => {
+            match RelevanceTruncation::from_provider(provider, messages).await {
+                Ok(strategy) => Box::new(strategy),
+                Err(e) => {
+                    warn!(
+                        "Relevance truncation unavailable, removing oldest messages instead: {}",
+                        e
+                    );
+                    Box::new(OldestFirstTruncation)
+                }
+            }
+        }
+    }
+}
+
+/// Indices of the messages that hold the other half of the tool calls in `messages[index]`
+fn tool_pair_indices(messages: &[Message], index: usize) -> Vec<usize> {
+    let ids = messages[index].get_tool_ids();
+    if ids.is_empty() {
+        return Vec::new();
+    }
+    messages
+        .iter()
+        .enumerate()
+        .filter(|(i, message)| {
+            *i != index && message.get_tool_ids().iter().any(|id| ids.contains(id))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Remove candidates in the given order, together with their tool pairs, until the
+/// conversation fits. Candidates whose pair is protected are skipped.
+fn remove_in_order(
+    messages: &[Message],
+    token_counts: &[usize],
+    context_limit: usize,
+    candidates: impl IntoIterator<Item = usize>,
+    protected: &HashSet<usize>,
+) -> HashSet<usize> {
+    let mut indices_to_remove = HashSet::new();
+    let mut total_tokens: usize = token_counts.iter().sum();
+
+    for i in candidates {
+        if total_tokens <= context_limit {
+            break;
+        }
+        if protected.contains(&i) || indices_to_remove.contains(&i) {
+            continue;
+        }
+        let pairs = tool_pair_indices(messages, i);
+        if pairs.iter().any(|pair| protected.contains(pair)) {
+            continue;
+        }
+        for index in std::iter::once(i).chain(pairs) {
+            if indices_to_remove.insert(index) {
+                total_tokens -= token_counts[index];
+            }
+        }
+    }
+
+    indices_to_remove
 }
 
 /// Strategy to truncate messages by removing the oldest first
@@ -369,6 +499,262 @@ impl TruncationStrategy for OldestFirstTruncation {
     }
 }
 
+/// Strategy that elides the largest tool outputs first, keeping the requests and the
+/// assistant's reasoning around them, and only then removes the oldest messages
+pub struct ToolOutputFirstTruncation;
+
+impl TruncationStrategy for ToolOutputFirstTruncation {
+    fn determine_indices_to_remove(
+        &self,
+        messages: &[Message],
+        token_counts: &[usize],
+        context_limit: usize,
+    ) -> Result<HashSet<usize>> {
+        OldestFirstTruncation.determine_indices_to_remove(messages, token_counts, context_limit)
+    }
+
+    fn elide_contents(
+        &self,
+        messages: &mut [Message],
+        token_counts: &mut [usize],
+        context_limit: usize,
+    ) {
+        let mut total_tokens: usize = token_counts.iter().sum();
+
+        // The latest tool output is what the model is working on, so leave it alone
+        let last = messages.len().saturating_sub(1);
+        let mut candidates: Vec<usize> = (0..last)
+            .filter(|&i| messages[i].is_tool_response())
+            .collect();
+        candidates.sort_by_key(|&i| std::cmp::Reverse(token_counts[i]));
+
+        let estimate_tokens = |text: &str| -> usize { (text.len() / 4).max(1) };
+        for i in candidates {
+            if total_tokens <= context_limit {
+                break;
+            }
+            let elided = elide_tool_outputs(&messages[i], token_counts[i]);
+            let elided_tokens = estimate_message_tokens(&elided, &estimate_tokens);
+            if elided_tokens < token_counts[i] {
+                debug!(
+                    "ToolOutputFirst: Eliding tool output at index {}. Tokens saved: {}",
+                    i,
+                    token_counts[i] - elided_tokens
+                );
+                total_tokens -= token_counts[i] - elided_tokens;
+                messages[i] = elided;
+                token_counts[i] = elided_tokens;
+            }
+        }
+    }
+}
+
+/// Replace the successful outputs in a tool response message with a short placeholder
+fn elide_tool_outputs(message: &Message, tokens: usize) -> Message {
+    let mut new_message = message.clone();
+    for content in &mut new_message.content {
+        if let MessageContent::ToolResponse(tool_response) = content {
+            if let Ok(result) = &mut tool_response.tool_result {
+                *result = vec![Content::text(format!(
+                    "[tool output elided to save context, it was about {} tokens]",
+                    tokens
+                ))];
+            }
+        }
+    }
+    new_message
+}
+
+/// Strategy that removes the oldest messages first but never the first user message,
+/// messages matching one of the pin patterns, or the tool calls paired with them
+pub struct PinnedTruncation {
+    patterns: Vec<Regex>,
+}
+
+impl PinnedTruncation {
+    pub fn new(patterns: Vec<Regex>) -> Self {
+        Self { patterns }
+    }
+
+    /// Reads pin patterns from config, defaulting to messages that contain `#important`
+    pub fn from_config() -> Self {
+        let patterns: Vec<String> = Config::global()
+            .get_param(PINNED_PATTERNS_KEY)
+            .unwrap_or_else(|_| vec![DEFAULT_PINNED_PATTERN.to_string()]);
+        let patterns = patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    warn!("Ignoring invalid pin pattern '{}': {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self::new(patterns)
+    }
+
+    fn pinned_indices(&self, messages: &[Message]) -> HashSet<usize> {
+        let mut pinned: HashSet<usize> = messages
+            .iter()
+            .position(|m| m.role == Role::User && m.has_only_text_content())
+            .into_iter()
+            .collect();
+        for (i, message) in messages.iter().enumerate() {
+            let text = embedding_text(message);
+            if self.patterns.iter().any(|pattern| pattern.is_match(&text)) {
+                pinned.insert(i);
+            }
+        }
+        let pairs: Vec<usize> = pinned
+            .iter()
+            .flat_map(|&i| tool_pair_indices(messages, i))
+            .collect();
+        pinned.extend(pairs);
+        pinned
+    }
+}
+
+impl TruncationStrategy for PinnedTruncation {
+    fn determine_indices_to_remove(
+        &self,
+        messages: &[Message],
+        token_counts: &[usize],
+        context_limit: usize,
+    ) -> Result<HashSet<usize>> {
+        let pinned = self.pinned_indices(messages);
+        Ok(remove_in_order(
+            messages,
+            token_counts,
+            context_limit,
+            0..messages.len(),
+            &pinned,
+        ))
+    }
+}
+
+/// Strategy that removes the messages least related to the latest request first, ranked by
+/// the cosine similarity of their embeddings
+pub struct RelevanceTruncation {
+    scores: HashMap<u64, f32>,
+}
+
+impl RelevanceTruncation {
+    pub fn new(scores: HashMap<u64, f32>) -> Self {
+        Self { scores }
+    }
+
+    /// Embed every message and the latest user request with the provider
+    pub async fn from_provider(provider: &Arc<dyn Provider>, messages: &[Message]) -> Result<Self> {
+        if !provider.supports_embeddings() {
+            return Err(anyhow!("the provider does not support embeddings"));
+        }
+        let query = messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::User && m.has_only_text_content())
+            .map(embedding_text)
+            .ok_or_else(|| anyhow!("there is no user request to compare against"))?;
+
+        let mut texts: Vec<String> = messages.iter().map(embedding_text).collect();
+        texts.push(query);
+        let mut embeddings = provider.create_embeddings(texts).await?;
+        let query_embedding = embeddings
+            .pop()
+            .ok_or_else(|| anyhow!("the provider returned no embeddings"))?;
+        if embeddings.len() != messages.len() {
+            return Err(anyhow!(
+                "expected {} embeddings, got {}",
+                messages.len(),
+                embeddings.len()
+            ));
+        }
+
+        let scores = messages
+            .iter()
+            .zip(embeddings.iter())
+            .map(|(message, embedding)| {
+                (
+                    message_fingerprint(message),
+                    cosine_similarity(embedding, &query_embedding),
+                )
+            })
+            .collect();
+        Ok(Self::new(scores))
+    }
+}
+
+impl TruncationStrategy for RelevanceTruncation {
+    fn determine_indices_to_remove(
+        &self,
+        messages: &[Message],
+        token_counts: &[usize],
+        context_limit: usize,
+    ) -> Result<HashSet<usize>> {
+        // Messages we have no score for, e.g. ones shrunk since embedding, rank as unrelated
+        let score = |i: usize| -> f32 {
+            *self
+                .scores
+                .get(&message_fingerprint(&messages[i]))
+                .unwrap_or(&0.0)
+        };
+        let mut candidates: Vec<usize> = (0..messages.len()).collect();
+        candidates.sort_by(|&a, &b| score(a).total_cmp(&score(b)).then(a.cmp(&b)));
+
+        let latest: HashSet<usize> = messages.len().checked_sub(1).into_iter().collect();
+        Ok(remove_in_order(
+            messages,
+            token_counts,
+            context_limit,
+            candidates,
+            &latest,
+        ))
+    }
+}
+
+/// The text of a message as it is compared and embedded
+fn embedding_text(message: &Message) -> String {
+    let text = message
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            MessageContent::Text(text) => Some(text.text.clone()),
+            MessageContent::ToolRequest(request) => Some(request.to_readable_string()),
+            MessageContent::ToolResponse(response) => {
+                response.tool_result.as_ref().ok().map(|result| {
+                    result
+                        .iter()
+                        .filter_map(|c| c.as_text())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    text.chars().take(MAX_EMBEDDING_TEXT_SIZE).collect()
+}
+
+fn message_fingerprint(message: &Message) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(message)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -711,4 +1097,91 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_tool_output_first_elides_before_removing() -> Result<()> {
+        let large = "x".repeat(4000);
+        let messages = vec![
+            user_text(1, 10).0,
+            assistant_tool_request("t1", ToolCall::new("read_file", json!({"path": "a"})), 10).0,
+            large_tool_response("t1", large.clone(), 1000).0,
+            assistant_text(2, 10).0,
+            user_text(3, 10).0,
+            assistant_tool_request("t2", ToolCall::new("read_file", json!({"path": "b"})), 10).0,
+            large_tool_response("t2", large, 1000).0,
+        ];
+        let token_counts = vec![10, 10, 1000, 10, 10, 10, 1000];
+
+        let (result, counts) =
+            truncate_messages(&messages, &token_counts, 1100, &ToolOutputFirstTruncation)?;
+
+        // Nothing is removed, only the older output is elided
+        assert_eq!(result.len(), messages.len());
+        assert!(counts.iter().sum::<usize>() <= 1100);
+        let elided = result[2].content[0].as_tool_response_text().unwrap();
+        assert!(elided.contains("tool output elided"));
+        assert_eq!(result[6], messages[6]);
+        assert_eq!(result[3], messages[3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pinned_keeps_first_and_marked_messages() -> Result<()> {
+        let messages = vec![
+            user_text(1, 10).0,
+            assistant_text(1, 10).0,
+            Message::user().with_text("Remember: deploy to staging only #important"),
+            assistant_text(2, 10).0,
+            assistant_tool_request("t1", ToolCall::new("read_file", json!({})), 10).0,
+            user_tool_response("t1", vec![Content::text("data")], 10).0,
+            user_text(3, 10).0,
+            assistant_text(3, 10).0,
+            user_text(4, 10).0,
+        ];
+        let token_counts = vec![10; messages.len()];
+        let strategy = PinnedTruncation::new(vec![Regex::new(DEFAULT_PINNED_PATTERN)?]);
+
+        let removed = strategy.determine_indices_to_remove(&messages, &token_counts, 50)?;
+
+        assert!(!removed.contains(&0));
+        assert!(!removed.contains(&2));
+        // Tool pairs are removed together
+        assert_eq!(removed.contains(&4), removed.contains(&5));
+        assert!(token_counts.iter().sum::<usize>() - removed.len() * 10 <= 50);
+        Ok(())
+    }
+
+    #[test]
+    fn test_relevance_removes_least_similar_first() -> Result<()> {
+        let messages = vec![
+            Message::user().with_text("tell me about rust lifetimes"),
+            Message::assistant().with_text("unrelated chatter"),
+            Message::user().with_text("more rust borrow checker details"),
+            Message::assistant().with_text("weather talk"),
+            Message::user().with_text("how do rust lifetimes work"),
+        ];
+        let scores = [0.9, 0.1, 0.8, 0.2, 1.0];
+        let strategy = RelevanceTruncation::new(
+            messages
+                .iter()
+                .zip(scores)
+                .map(|(m, s)| (message_fingerprint(m), s))
+                .collect(),
+        );
+
+        let removed = strategy.determine_indices_to_remove(&messages, &[10; 5], 30)?;
+
+        assert_eq!(removed, HashSet::from([1, 3]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncation_strategy_kind_names() {
+        let kind: TruncationStrategyKind = serde_json::from_str("\"tool_output_first\"").unwrap();
+        assert_eq!(kind, TruncationStrategyKind::ToolOutputFirst);
+        assert_eq!(
+            TruncationStrategyKind::default(),
+            TruncationStrategyKind::OldestFirst
+        );
+    }
 }
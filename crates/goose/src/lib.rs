@@ -1,4 +1,6 @@
 pub mod agents;
+pub mod attachments;
+pub mod audit;
 pub mod config;
 pub mod context_mgmt;
 pub mod message;
@@ -7,6 +9,7 @@ pub mod permission;
 pub mod prompt_template;
 pub mod providers;
 pub mod recipe;
+pub mod redaction;
 pub mod scheduler;
 pub mod scheduler_factory;
 pub mod scheduler_trait;
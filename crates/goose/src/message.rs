@@ -96,12 +96,43 @@ pub struct SummarizationRequested {
     pub msg: String,
 }
 
+/// A file such as a PDF attached to a message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentContent {
+    /// Base64 encoded contents of the file
+    pub data: String,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Text extracted from the file, sent to providers that cannot read the file itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl DocumentContent {
+    /// The document as plain text, for providers without native document support
+    pub fn as_fallback_text(&self) -> String {
+        let name = self.name.as_deref().unwrap_or("attachment");
+        match &self.text {
+            Some(text) if !text.trim().is_empty() => {
+                format!("<document name=\"{}\">\n{}\n</document>", name, text.trim())
+            }
+            _ => format!(
+                "[The document {} ({}) was attached, but its text could not be extracted]",
+                name, self.mime_type
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 /// Content passed inside a message, which can be both simple content and tool content
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum MessageContent {
     Text(TextContent),
     Image(ImageContent),
+    Document(DocumentContent),
     ToolRequest(ToolRequest),
     ToolResponse(ToolResponse),
     ToolConfirmationRequest(ToolConfirmationRequest),
@@ -128,6 +159,20 @@ impl MessageContent {
         })
     }
 
+    pub fn document<S: Into<String>, T: Into<String>>(
+        data: S,
+        mime_type: T,
+        name: Option<String>,
+        text: Option<String>,
+    ) -> Self {
+        MessageContent::Document(DocumentContent {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            name,
+            text,
+        })
+    }
+
     pub fn tool_request<S: Into<String>>(id: S, tool_call: ToolResult<ToolCall>) -> Self {
         MessageContent::ToolRequest(ToolRequest {
             id: id.into(),
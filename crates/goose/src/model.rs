@@ -1,9 +1,20 @@
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::config::Config;
 
 const DEFAULT_CONTEXT_LIMIT: usize = 128_000;
 
+/// Config key for the number of tokens a model may spend thinking before it answers
+pub const THINKING_BUDGET_CONFIG_KEY: &str = "GOOSE_THINKING_BUDGET";
+/// Config key for how hard a reasoning model should think (low, medium or high)
+pub const REASONING_EFFORT_CONFIG_KEY: &str = "GOOSE_REASONING_EFFORT";
+/// Anthropic rejects thinking budgets below this
+pub const MIN_THINKING_BUDGET: u32 = 1024;
+
 // Tokenizer names, used to infer from model name
 pub const GPT_4O_TOKENIZER: &str = "Xenova--gpt-4o";
 pub const CLAUDE_TOKENIZER: &str = "Xenova--claude-tokenizer";
@@ -33,6 +44,66 @@ static MODEL_SPECIFIC_LIMITS: Lazy<HashMap<&'static str, usize>> = Lazy::new(||
     map
 });
 
+/// How much a reasoning model should think before it answers. Providers that take a token
+/// budget instead get [`ReasoningEffort::budget_tokens`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+        }
+    }
+
+    /// Thinking budget used for providers that don't have effort levels
+    pub fn budget_tokens(&self) -> u32 {
+        match self {
+            ReasoningEffort::Low => 4_000,
+            ReasoningEffort::Medium => 16_000,
+            ReasoningEffort::High => 32_000,
+        }
+    }
+}
+
+impl fmt::Display for ReasoningEffort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReasoningEffort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "low" => Ok(ReasoningEffort::Low),
+            "medium" => Ok(ReasoningEffort::Medium),
+            "high" => Ok(ReasoningEffort::High),
+            other => Err(format!(
+                "Unknown reasoning effort '{}', expected low, medium or high",
+                other
+            )),
+        }
+    }
+}
+
+/// Reasoning settings that override the configured ones, as set by a recipe
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReasoningConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort: Option<ReasoningEffort>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_tokens: Option<u32>,
+}
+
 /// Configuration for model-specific settings and limits
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelConfig {
@@ -52,6 +123,12 @@ pub struct ModelConfig {
     pub toolshim: bool,
     /// Model to use for toolshim (optional as a default exists)
     pub toolshim_model: Option<String>,
+    /// Tokens the model may spend thinking, for providers with extended thinking
+    #[serde(default)]
+    pub thinking_budget: Option<u32>,
+    /// Effort level for providers with reasoning effort settings
+    #[serde(default)]
+    pub reasoning_effort: Option<ReasoningEffort>,
 }
 
 /// Struct to represent model pattern matches and their limits
@@ -66,10 +143,14 @@ impl ModelConfig {
     ///
     /// The context limit is set with the following precedence:
     /// 1. Explicit context_limit if provided in config
-    /// 2. Model-specific default based on model name
-    /// 3. Global default (128_000) (in get_context_limit)
+    /// 2. GOOSE_CONTEXT_LIMIT environment variable
+    /// 3. Model-specific default based on model name
+    /// 4. Global default (128_000) (in get_context_limit)
     pub fn new(model_name: String) -> Self {
-        let context_limit = Self::get_model_specific_limit(&model_name);
+        let context_limit = std::env::var("GOOSE_CONTEXT_LIMIT")
+            .ok()
+            .and_then(|val| val.parse::<usize>().ok())
+            .or_else(|| Self::get_model_specific_limit(&model_name));
         let tokenizer_name = Self::infer_tokenizer_name(&model_name);
 
         let toolshim = std::env::var("GOOSE_TOOLSHIM")
@@ -90,7 +171,49 @@ impl ModelConfig {
             max_tokens: None,
             toolshim,
             toolshim_model,
+            thinking_budget: None,
+            reasoning_effort: None,
+        }
+    }
+
+    /// Create a ModelConfig for the user's session, with the reasoning effort and thinking
+    /// budget from config.yaml or the environment
+    pub fn from_config(model_name: String) -> Self {
+        let config = Config::global();
+        let reasoning_effort = config
+            .get_param::<String>(REASONING_EFFORT_CONFIG_KEY)
+            .ok()
+            .and_then(|val| match val.parse::<ReasoningEffort>() {
+                Ok(effort) => Some(effort),
+                Err(e) => {
+                    tracing::warn!("Ignoring {}: {}", REASONING_EFFORT_CONFIG_KEY, e);
+                    None
+                }
+            });
+        let thinking_budget = config
+            .get_param::<u32>(THINKING_BUDGET_CONFIG_KEY)
+            .ok()
+            .or_else(|| Self::legacy_claude_thinking_budget(&model_name));
+
+        Self::new(model_name)
+            .with_thinking_budget(thinking_budget)
+            .with_reasoning_effort(reasoning_effort)
+    }
+
+    /// Before the thinking budget could be configured for every provider, Claude 3.7 Sonnet
+    /// thinking was turned on with CLAUDE_THINKING_ENABLED and CLAUDE_THINKING_BUDGET
+    fn legacy_claude_thinking_budget(model_name: &str) -> Option<u32> {
+        if !model_name.contains("claude-3-7-sonnet")
+            || std::env::var("CLAUDE_THINKING_ENABLED").is_err()
+        {
+            return None;
         }
+        Some(
+            std::env::var("CLAUDE_THINKING_BUDGET")
+                .ok()
+                .and_then(|val| val.parse().ok())
+                .unwrap_or(16_000),
+        )
     }
 
     fn infer_tokenizer_name(model_name: &str) -> &'static str {
@@ -158,6 +281,39 @@ impl ModelConfig {
         self
     }
 
+    /// Set the thinking budget in tokens
+    pub fn with_thinking_budget(mut self, budget: Option<u32>) -> Self {
+        self.thinking_budget = budget;
+        self
+    }
+
+    /// Set the reasoning effort
+    pub fn with_reasoning_effort(mut self, effort: Option<ReasoningEffort>) -> Self {
+        self.reasoning_effort = effort;
+        self
+    }
+
+    /// Apply the settings of a recipe, keeping the configured ones it doesn't set
+    pub fn with_reasoning(mut self, reasoning: Option<ReasoningConfig>) -> Self {
+        if let Some(reasoning) = reasoning {
+            if reasoning.effort.is_some() {
+                self.reasoning_effort = reasoning.effort;
+            }
+            if reasoning.budget_tokens.is_some() {
+                self.thinking_budget = reasoning.budget_tokens;
+            }
+        }
+        self
+    }
+
+    /// Tokens the model should spend thinking, if thinking was asked for either as a budget
+    /// or as an effort level. Never below [`MIN_THINKING_BUDGET`].
+    pub fn thinking_budget(&self) -> Option<u32> {
+        self.thinking_budget
+            .or_else(|| self.reasoning_effort.map(|effort| effort.budget_tokens()))
+            .map(|budget| budget.max(MIN_THINKING_BUDGET))
+    }
+
     /// Get the tokenizer name
     pub fn tokenizer_name(&self) -> &str {
         &self.tokenizer_name
@@ -244,6 +400,81 @@ mod tests {
         assert_eq!(config.temperature, None);
     }
 
+    #[test]
+    fn test_model_config_reasoning() {
+        let config = ModelConfig::new("test-model".to_string());
+        assert_eq!(config.thinking_budget(), None);
+
+        // An effort level alone implies a budget
+        let config = config.with_reasoning_effort(Some(ReasoningEffort::High));
+        assert_eq!(config.thinking_budget(), Some(32_000));
+
+        // An explicit budget wins, but is never below the minimum
+        let config = config.with_thinking_budget(Some(500));
+        assert_eq!(config.thinking_budget(), Some(MIN_THINKING_BUDGET));
+
+        // Recipes only override what they set
+        let config = config.with_reasoning(Some(ReasoningConfig {
+            effort: Some(ReasoningEffort::Low),
+            budget_tokens: None,
+        }));
+        assert_eq!(config.reasoning_effort, Some(ReasoningEffort::Low));
+        assert_eq!(config.thinking_budget, Some(500));
+
+        assert_eq!("High".parse::<ReasoningEffort>(), Ok(ReasoningEffort::High));
+        assert!("extreme".parse::<ReasoningEffort>().is_err());
+    }
+
+    #[test]
+    fn test_reasoning_settings_come_only_from_config() {
+        use temp_env::with_vars;
+
+        with_vars(
+            [
+                (REASONING_EFFORT_CONFIG_KEY, Some("medium")),
+                (THINKING_BUDGET_CONFIG_KEY, Some("8000")),
+            ],
+            || {
+                let config = ModelConfig::new("test-model".to_string());
+                assert_eq!(config.reasoning_effort, None);
+                assert_eq!(config.thinking_budget, None);
+
+                let config = ModelConfig::from_config("test-model".to_string());
+                assert_eq!(config.reasoning_effort, Some(ReasoningEffort::Medium));
+                assert_eq!(config.thinking_budget, Some(8000));
+            },
+        );
+    }
+
+    #[test]
+    fn test_legacy_claude_thinking_env_vars() {
+        use temp_env::with_vars;
+
+        with_vars(
+            [
+                ("CLAUDE_THINKING_ENABLED", Some("true")),
+                ("CLAUDE_THINKING_BUDGET", Some("2048")),
+            ],
+            || {
+                assert_eq!(
+                    ModelConfig::legacy_claude_thinking_budget("claude-3-7-sonnet-latest"),
+                    Some(2048)
+                );
+                assert_eq!(
+                    ModelConfig::legacy_claude_thinking_budget("claude-3-5-sonnet-latest"),
+                    None
+                );
+            },
+        );
+
+        with_vars([("CLAUDE_THINKING_ENABLED", None::<&str>)], || {
+            assert_eq!(
+                ModelConfig::legacy_claude_thinking_budget("claude-3-7-sonnet-latest"),
+                None
+            );
+        });
+    }
+
     #[test]
     fn test_get_all_model_limits() {
         let limits = ModelConfig::get_all_model_limits();
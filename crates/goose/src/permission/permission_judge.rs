@@ -1,4 +1,5 @@
 use crate::agents::platform_tools::PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME;
+use crate::audit::Approver;
 use crate::config::permission::PermissionLevel;
 use crate::config::PermissionManager;
 use crate::message::{Message, MessageContent, ToolRequest};
@@ -9,7 +10,7 @@ use mcp_core::tool::ToolAnnotations;
 use mcp_core::{tool::Tool, TextContent};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 /// Creates the tool definition for checking read-only permissions.
@@ -158,6 +159,32 @@ pub struct PermissionCheckResult {
     pub approved: Vec<ToolRequest>,
     pub needs_approval: Vec<ToolRequest>,
     pub denied: Vec<ToolRequest>,
+    /// What approved or denied each request in `approved` and `denied`, by request id
+    #[serde(default)]
+    pub approvers: HashMap<String, Approver>,
+}
+
+impl PermissionCheckResult {
+    /// What approved or denied the request with id `request_id`
+    pub fn approver(&self, request_id: &str) -> Approver {
+        self.approvers
+            .get(request_id)
+            .copied()
+            .unwrap_or(Approver::Mode)
+    }
+
+    /// Sort `request` by a permission level, recording what decided it
+    fn push(&mut self, request: &ToolRequest, level: PermissionLevel, approver: Approver) {
+        match level {
+            PermissionLevel::AlwaysAllow => self.approved.push(request.clone()),
+            PermissionLevel::AskBefore => {
+                self.needs_approval.push(request.clone());
+                return;
+            }
+            PermissionLevel::NeverAllow => self.denied.push(request.clone()),
+        }
+        self.approvers.insert(request.id.clone(), approver);
+    }
 }
 
 pub async fn check_tool_permissions(
@@ -168,9 +195,12 @@ pub async fn check_tool_permissions(
     permission_manager: &mut PermissionManager,
     provider: Arc<dyn Provider>,
 ) -> (PermissionCheckResult, Vec<String>) {
-    let mut approved = vec![];
-    let mut needs_approval = vec![];
-    let mut denied = vec![];
+    let mut result = PermissionCheckResult {
+        approved: vec![],
+        needs_approval: vec![],
+        denied: vec![],
+        approvers: HashMap::new(),
+    };
     let mut llm_detect_candidates = vec![];
     let mut extension_request_ids = vec![];
 
@@ -179,7 +209,13 @@ pub async fn check_tool_permissions(
             if mode == "chat" {
                 continue;
             } else if mode == "auto" {
-                approved.push(request.clone());
+                // A project's own permissions still hold when everything else is allowed
+                match permission_manager.get_project_permission(&tool_call.name) {
+                    Some(level @ (PermissionLevel::AskBefore | PermissionLevel::NeverAllow)) => {
+                        result.push(request, level, Approver::StoredPermission)
+                    }
+                    _ => result.push(request, PermissionLevel::AlwaysAllow, Approver::Mode),
+                }
             } else {
                 if tool_call.name == PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME {
                     extension_request_ids.push(request.id.clone());
@@ -187,41 +223,33 @@ pub async fn check_tool_permissions(
 
                 // 1. Check user-defined permission
                 if let Some(level) = permission_manager.get_user_permission(&tool_call.name) {
-                    match level {
-                        PermissionLevel::AlwaysAllow => approved.push(request.clone()),
-                        PermissionLevel::AskBefore => needs_approval.push(request.clone()),
-                        PermissionLevel::NeverAllow => denied.push(request.clone()),
-                    }
+                    result.push(request, level, Approver::StoredPermission);
                     continue;
                 }
 
                 // 2. Fallback based on mode
                 match mode {
                     "approve" => {
-                        needs_approval.push(request.clone());
+                        result.needs_approval.push(request.clone());
                     }
                     "smart_approve" => {
                         if let Some(level) =
                             permission_manager.get_smart_approve_permission(&tool_call.name)
                         {
-                            match level {
-                                PermissionLevel::AlwaysAllow => approved.push(request.clone()),
-                                PermissionLevel::AskBefore => needs_approval.push(request.clone()),
-                                PermissionLevel::NeverAllow => denied.push(request.clone()),
-                            }
+                            result.push(request, level, Approver::SmartApprove);
                             continue;
                         }
 
                         if tools_with_readonly_annotation.contains(&tool_call.name) {
-                            approved.push(request.clone());
+                            result.push(request, PermissionLevel::AlwaysAllow, Approver::ReadOnly);
                         } else if tools_without_annotation.contains(&tool_call.name) {
                             llm_detect_candidates.push(request.clone());
                         } else {
-                            needs_approval.push(request.clone());
+                            result.needs_approval.push(request.clone());
                         }
                     }
                     _ => {
-                        needs_approval.push(request.clone());
+                        result.needs_approval.push(request.clone());
                     }
                 }
             }
@@ -235,13 +263,17 @@ pub async fn check_tool_permissions(
         for request in llm_detect_candidates {
             if let Ok(tool_call) = request.tool_call.clone() {
                 if detected_readonly_tools.contains(&tool_call.name) {
-                    approved.push(request.clone());
+                    result.push(
+                        &request,
+                        PermissionLevel::AlwaysAllow,
+                        Approver::SmartApprove,
+                    );
                     permission_manager.update_smart_approve_permission(
                         &tool_call.name,
                         PermissionLevel::AlwaysAllow,
                     );
                 } else {
-                    needs_approval.push(request.clone());
+                    result.needs_approval.push(request.clone());
                     permission_manager.update_smart_approve_permission(
                         &tool_call.name,
                         PermissionLevel::AskBefore,
@@ -251,14 +283,7 @@ pub async fn check_tool_permissions(
         }
     }
 
-    (
-        PermissionCheckResult {
-            approved,
-            needs_approval,
-            denied,
-        },
-        extension_request_ids,
-    )
+    (result, extension_request_ids)
 }
 
 #[cfg(test)]
@@ -459,6 +484,7 @@ mod tests {
 
         // Ensure the right tools are in the approved and needs_approval lists
         assert!(result.approved.iter().any(|req| req.id == "tool_1"));
+        assert_eq!(result.approver("tool_1"), Approver::StoredPermission);
         assert!(result.needs_approval.iter().any(|req| req.id == "tool_2"));
         assert!(result.needs_approval.iter().any(|req| req.id == "tool_3"));
         assert!(enable_extension_request_ids.iter().any(|id| id == "tool_3"));
@@ -514,5 +540,6 @@ mod tests {
         assert_eq!(result.approved.len(), 2); // file_reader should be approved
         assert_eq!(result.needs_approval.len(), 0); // data_fetcher should need approval
         assert_eq!(result.denied.len(), 0); // No tool should be denied in this test
+        assert_eq!(result.approver("tool_1"), Approver::Mode);
     }
 }
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use super::errors::ProviderError;
 use crate::message::Message;
@@ -8,7 +9,7 @@ use mcp_core::tool::Tool;
 use utoipa::ToSchema;
 
 use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 /// A global store for the current model being used, we use this as when a provider returns, it tells us the real model, not an alias
 pub static CURRENT_MODEL: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
@@ -152,6 +153,9 @@ use async_trait::async_trait;
 pub trait LeadWorkerProviderTrait {
     /// Get information about the lead and worker models for logging
     fn get_model_info(&self) -> (String, String);
+
+    /// Get the provider used for worker turns, for background work that doesn't need the lead model
+    fn worker_provider(&self) -> Arc<dyn Provider>;
 }
 
 /// Base trait for AI providers (OpenAI, Anthropic, etc)
@@ -202,6 +206,24 @@ pub trait Provider: Send + Sync {
         ))
     }
 
+    /// Check if this provider can force a response that matches a JSON schema
+    fn supports_structured_output(&self) -> bool {
+        false
+    }
+
+    /// Generate a JSON value matching `schema` using the provider's native JSON mode, if
+    /// supported. Default implementation returns an error.
+    async fn extract(
+        &self,
+        _system: &str,
+        _messages: &[Message],
+        _schema: &Value,
+    ) -> Result<(Value, ProviderUsage), ProviderError> {
+        Err(ProviderError::ExecutionError(
+            "This provider does not support structured output".to_string(),
+        ))
+    }
+
     /// Check if this provider is a LeadWorkerProvider
     /// This is used for logging model information at startup
     fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
@@ -1,14 +1,13 @@
 use std::collections::HashMap;
-use std::time::Duration;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use aws_sdk_bedrockruntime::config::ProvideCredentials;
+use aws_sdk_bedrockruntime::error::SdkError;
 use aws_sdk_bedrockruntime::operation::converse::ConverseError;
 use aws_sdk_bedrockruntime::{types as bedrock, Client};
 use mcp_core::Tool;
 use serde_json::Value;
-use tokio::time::sleep;
 
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
 use super::errors::ProviderError;
@@ -18,7 +17,8 @@ use crate::providers::utils::emit_debug_trace;
 
 // Import the migrated helper functions from providers/formats/bedrock.rs
 use super::formats::bedrock::{
-    from_bedrock_message, from_bedrock_usage, to_bedrock_message, to_bedrock_tool_config,
+    from_bedrock_message, from_bedrock_usage, to_bedrock_message, to_bedrock_thinking_fields,
+    to_bedrock_tool_config,
 };
 
 pub const BEDROCK_DOC_LINK: &str =
@@ -123,116 +123,136 @@ impl Provider for BedrockProvider {
             request = request.tool_config(to_bedrock_tool_config(tools)?);
         }
 
-        // Retry configuration
-        const MAX_RETRIES: u32 = 10;
-        const INITIAL_BACKOFF_MS: u64 = 20_000; // 20 seconds
-        const MAX_BACKOFF_MS: u64 = 120_000; // 120 seconds (2 minutes)
-
-        let mut attempts = 0;
-        let mut backoff_ms = INITIAL_BACKOFF_MS;
-
-        loop {
-            attempts += 1;
-
-            match request.clone().send().await {
-                Ok(response) => {
-                    // Successful response, process it and return
-                    return match response.output {
-                        Some(bedrock::ConverseOutput::Message(message)) => {
-                            let usage = response
-                                .usage
-                                .as_ref()
-                                .map(from_bedrock_usage)
-                                .unwrap_or_default();
-
-                            let message = from_bedrock_message(&message)?;
-
-                            // Add debug trace with input context
-                            let debug_payload = serde_json::json!({
-                                "system": system,
-                                "messages": messages,
-                                "tools": tools
-                            });
-                            emit_debug_trace(
-                                &self.model,
-                                &debug_payload,
-                                &serde_json::to_value(&message).unwrap_or_default(),
-                                &usage,
-                            );
-
-                            let provider_usage = ProviderUsage::new(model_name.to_string(), usage);
-                            Ok((message, provider_usage))
-                        }
-                        _ => Err(ProviderError::RequestFailed(
-                            "No output from Bedrock".to_string(),
-                        )),
-                    };
-                }
-                Err(err) => {
-                    match err.into_service_error() {
-                        ConverseError::ThrottlingException(throttle_err) => {
-                            if attempts > MAX_RETRIES {
-                                // We've exhausted our retries
-                                tracing::error!(
-                                    "Failed after {MAX_RETRIES} retries: {:?}",
-                                    throttle_err
-                                );
-                                return Err(ProviderError::RateLimitExceeded(format!(
-                                    "Failed to call Bedrock after {MAX_RETRIES} retries: {:?}",
-                                    throttle_err
-                                )));
-                            }
-
-                            // Log retry attempt
-                            tracing::warn!(
-                                "Bedrock throttling error (attempt {}/{}), retrying in {} ms: {:?}",
-                                attempts,
-                                MAX_RETRIES,
-                                backoff_ms,
-                                throttle_err
-                            );
-
-                            // Wait before retry with exponential backoff
-                            sleep(Duration::from_millis(backoff_ms)).await;
-
-                            // Calculate next backoff with exponential growth, capped at max
-                            backoff_ms = (backoff_ms * 2).min(MAX_BACKOFF_MS);
-
-                            // Continue to the next retry attempt
-                            continue;
-                        }
-                        ConverseError::AccessDeniedException(err) => {
-                            return Err(ProviderError::Authentication(format!(
-                                "Failed to call Bedrock: {:?}",
-                                err
-                            )));
-                        }
-                        ConverseError::ValidationException(err)
-                            if err
-                                .message()
-                                .unwrap_or_default()
-                                .contains("Input is too long for requested model.") =>
-                        {
-                            return Err(ProviderError::ContextLengthExceeded(format!(
-                                "Failed to call Bedrock: {:?}",
-                                err
-                            )));
-                        }
-                        ConverseError::ModelErrorException(err) => {
-                            return Err(ProviderError::ExecutionError(format!(
-                                "Failed to call Bedrock: {:?}",
-                                err
-                            )));
-                        }
-                        err => {
-                            return Err(ProviderError::ServerError(format!(
-                                "Failed to call Bedrock: {:?}",
-                                err
-                            )));
-                        }
+        if let Some(fields) = to_bedrock_thinking_fields(&self.model) {
+            // The thinking budget counts towards the output tokens
+            let max_tokens = self.model.max_tokens.unwrap_or(8192)
+                + self.model.thinking_budget().unwrap_or_default() as i32;
+            request = request
+                .additional_model_request_fields(fields)
+                .inference_config(
+                    bedrock::InferenceConfiguration::builder()
+                        .max_tokens(max_tokens)
+                        .build(),
+                );
+        }
+
+        match request.send().await {
+            Ok(response) => {
+                // Successful response, process it and return
+                match response.output {
+                    Some(bedrock::ConverseOutput::Message(message)) => {
+                        let usage = response
+                            .usage
+                            .as_ref()
+                            .map(from_bedrock_usage)
+                            .unwrap_or_default();
+
+                        let message = from_bedrock_message(&message)?;
+
+                        // Add debug trace with input context
+                        let debug_payload = serde_json::json!({
+                            "system": system,
+                            "messages": messages,
+                            "tools": tools
+                        });
+                        emit_debug_trace(
+                            &self.model,
+                            &debug_payload,
+                            &serde_json::to_value(&message).unwrap_or_default(),
+                            &usage,
+                        );
+
+                        let provider_usage = ProviderUsage::new(model_name.to_string(), usage);
+                        Ok((message, provider_usage))
                     }
+                    _ => Err(ProviderError::RequestFailed(
+                        "No output from Bedrock".to_string(),
+                    )),
                 }
             }
+            // Failures to reach Bedrock at all are worth another try
+            Err(err @ (SdkError::DispatchFailure(_) | SdkError::TimeoutError(_))) => Err(
+                ProviderError::server_error(format!("Failed to call Bedrock: {:?}", err)),
+            ),
+            Err(err) => Err(converse_error(err.into_service_error())),
+        }
+    }
+}
+
+/// Map a Bedrock error to a provider error. Only errors that can go away on their own are
+/// retryable: a missing model or a rejected request fails the same way every time.
+fn converse_error(err: ConverseError) -> ProviderError {
+    let details = format!("Failed to call Bedrock: {:?}", err);
+    match err {
+        ConverseError::ThrottlingException(_) => ProviderError::rate_limited(details),
+        ConverseError::ServiceUnavailableException(_)
+        | ConverseError::InternalServerException(_)
+        | ConverseError::ModelNotReadyException(_) => ProviderError::server_error(details),
+        ConverseError::AccessDeniedException(_) => ProviderError::Authentication(details),
+        ConverseError::ValidationException(ref err)
+            if err
+                .message()
+                .unwrap_or_default()
+                .contains("Input is too long for requested model.") =>
+        {
+            ProviderError::ContextLengthExceeded(details)
+        }
+        ConverseError::ModelErrorException(_) => ProviderError::ExecutionError(details),
+        _ => ProviderError::RequestFailed(details),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_bedrockruntime::types::error::{
+        InternalServerException, ModelNotReadyException, ResourceNotFoundException,
+        ServiceUnavailableException, ThrottlingException, ValidationException,
+    };
+
+    fn is_retryable(err: &ProviderError) -> bool {
+        matches!(
+            err,
+            ProviderError::RateLimitExceeded { .. } | ProviderError::ServerError { .. }
+        )
+    }
+
+    #[test]
+    fn test_transient_errors_are_retryable() {
+        let errors = [
+            ConverseError::ThrottlingException(ThrottlingException::builder().build()),
+            ConverseError::ServiceUnavailableException(
+                ServiceUnavailableException::builder().build(),
+            ),
+            ConverseError::InternalServerException(InternalServerException::builder().build()),
+            ConverseError::ModelNotReadyException(ModelNotReadyException::builder().build()),
+        ];
+        for err in errors {
+            assert!(is_retryable(&converse_error(err)));
         }
     }
+
+    #[test]
+    fn test_permanent_errors_are_not_retryable() {
+        let not_found = converse_error(ConverseError::ResourceNotFoundException(
+            ResourceNotFoundException::builder()
+                .message("model not found")
+                .build(),
+        ));
+        assert!(matches!(not_found, ProviderError::RequestFailed(_)));
+
+        let invalid = converse_error(ConverseError::ValidationException(
+            ValidationException::builder()
+                .message("The provided model identifier is invalid.")
+                .build(),
+        ));
+        assert!(matches!(invalid, ProviderError::RequestFailed(_)));
+
+        let too_long = converse_error(ConverseError::ValidationException(
+            ValidationException::builder()
+                .message("Input is too long for requested model.")
+                .build(),
+        ));
+        assert!(matches!(too_long, ProviderError::ContextLengthExceeded(_)));
+    }
 }
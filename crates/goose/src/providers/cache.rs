@@ -0,0 +1,600 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use etcetera::{choose_app_strategy, AppStrategy};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::base::{LeadWorkerProviderTrait, Provider, ProviderMetadata, ProviderUsage};
+use super::errors::ProviderError;
+use crate::config::Config;
+use crate::message::Message;
+use crate::model::ModelConfig;
+use mcp_core::tool::Tool;
+
+const DEFAULT_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+const DEFAULT_MAX_SIZE_MB: u64 = 512;
+
+/// How the response cache is used
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheMode {
+    /// No caching, every request goes to the provider
+    #[default]
+    Off,
+    /// Serve fresh cached responses and store new ones
+    ReadWrite,
+    /// Always call the provider and overwrite the cached response
+    Record,
+    /// Only serve cached responses, ignoring their age; a request that was not recorded
+    /// is an error
+    Replay,
+}
+
+/// Settings for [`CachingProvider`], read from `GOOSE_PROVIDER_CACHE*`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheConfig {
+    pub mode: CacheMode,
+    pub dir: PathBuf,
+    /// Entries older than this are not served in read_write mode
+    pub ttl: Duration,
+    /// Oldest entries are evicted once the cache grows past this many bytes
+    pub max_size_bytes: u64,
+}
+
+impl CacheConfig {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let dir = config
+            .get_param::<String>("GOOSE_PROVIDER_CACHE_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| default_cache_dir());
+        let ttl_secs = config
+            .get_param::<u64>("GOOSE_PROVIDER_CACHE_TTL_SECS")
+            .unwrap_or(DEFAULT_TTL_SECS);
+        let max_size_mb = config
+            .get_param::<u64>("GOOSE_PROVIDER_CACHE_MAX_SIZE_MB")
+            .unwrap_or(DEFAULT_MAX_SIZE_MB);
+
+        Self {
+            mode: config
+                .get_param::<CacheMode>("GOOSE_PROVIDER_CACHE")
+                .unwrap_or_default(),
+            dir,
+            ttl: Duration::from_secs(ttl_secs),
+            max_size_bytes: max_size_mb * 1024 * 1024,
+        }
+    }
+}
+
+fn default_cache_dir() -> PathBuf {
+    choose_app_strategy(crate::config::APP_STRATEGY.clone())
+        .expect("goose requires a home dir")
+        .in_cache_dir("provider_cache")
+}
+
+/// Timestamps such as the current date in the system prompt, which would otherwise make
+/// every session's requests unique
+static TIMESTAMP_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\d{4}-\d{2}-\d{2}[ T]\d{2}:\d{2}:\d{2}").unwrap());
+
+/// Stable key for a completion request to the provider registered as `provider`. Only the
+/// model settings that change what is sent are part of the key, so new [`ModelConfig`]
+/// fields don't invalidate stored entries. Message timestamps and timestamps in the system
+/// prompt are left out so the same conversation replayed later maps to the same key.
+pub fn request_fingerprint(
+    provider: &str,
+    model_config: &ModelConfig,
+    system: &str,
+    messages: &[Message],
+    tools: &[Tool],
+) -> String {
+    let system = TIMESTAMP_RE.replace_all(system, "<timestamp>");
+    hash_request(json!({
+        "provider": provider,
+        "model": {
+            "name": model_config.model_name,
+            "temperature": model_config.temperature,
+            "max_tokens": model_config.max_tokens,
+            "toolshim": model_config.toolshim,
+            "toolshim_model": model_config.toolshim_model,
+            "thinking_budget": model_config.thinking_budget,
+            "reasoning_effort": model_config.reasoning_effort,
+        },
+        "system": system,
+        "messages": normalized_messages(messages),
+        "tools": tools,
+    }))
+}
+
+fn normalized_messages(messages: &[Message]) -> Vec<Value> {
+    messages
+        .iter()
+        .map(|message| {
+            let mut value = serde_json::to_value(message).unwrap_or(Value::Null);
+            if let Some(object) = value.as_object_mut() {
+                object.remove("created");
+            }
+            value
+        })
+        .collect()
+}
+
+fn hash_request(request: Value) -> String {
+    Sha256::digest(request.to_string().as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Unix timestamp of when the response was stored
+    stored_at: u64,
+    message: Message,
+    usage: ProviderUsage,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// A provider that caches completions on disk, keyed by [`request_fingerprint`]
+pub struct CachingProvider {
+    name: String,
+    inner: Arc<dyn Provider>,
+    config: CacheConfig,
+}
+
+impl CachingProvider {
+    /// Caches the responses of `inner`, the provider registered as `name`
+    pub fn new(name: &str, inner: Arc<dyn Provider>, config: CacheConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            inner,
+            config,
+        }
+    }
+
+    /// Wraps `inner` when caching is enabled in config, otherwise returns it unchanged
+    pub fn from_config(name: &str, inner: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        let config = CacheConfig::from_config();
+        if config.mode == CacheMode::Off {
+            return inner;
+        }
+        tracing::info!(
+            "Caching provider responses in {} ({:?} mode)",
+            config.dir.display(),
+            config.mode
+        );
+        Arc::new(Self::new(name, inner, config))
+    }
+
+    /// Answers only from the responses recorded in `dir` for the provider registered as
+    /// `name`, with no real provider behind it. Nothing is sent over the network and no
+    /// credentials are needed, so recorded sessions can run in CI.
+    pub fn replay(name: &str, model: ModelConfig, dir: impl Into<PathBuf>) -> Self {
+        let config = CacheConfig {
+            mode: CacheMode::Replay,
+            dir: dir.into(),
+            ttl: Duration::MAX,
+            max_size_bytes: u64::MAX,
+        };
+        Self::new(name, Arc::new(NotRecorded { model }), config)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.config.dir.join(format!("{}.json", key))
+    }
+
+    async fn load(&self, key: &str) -> Option<CacheEntry> {
+        let contents = tokio::fs::read_to_string(self.entry_path(key)).await.ok()?;
+        match serde_json::from_str::<CacheEntry>(&contents) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable cache entry {}: {}", key, e);
+                None
+            }
+        }
+    }
+
+    async fn store(&self, key: &str, message: &Message, usage: &ProviderUsage) -> Result<()> {
+        tokio::fs::create_dir_all(&self.config.dir).await?;
+        let entry = CacheEntry {
+            stored_at: now_secs(),
+            message: message.clone(),
+            usage: usage.clone(),
+        };
+        tokio::fs::write(self.entry_path(key), serde_json::to_vec_pretty(&entry)?).await?;
+        evict_to_size(&self.config.dir, self.config.max_size_bytes).await
+    }
+
+    fn is_fresh(&self, entry: &CacheEntry) -> bool {
+        now_secs().saturating_sub(entry.stored_at) <= self.config.ttl.as_secs()
+    }
+}
+
+/// Removes the least recently written entries until the cache fits in `max_size_bytes`.
+/// Only cache entries count towards the size, other files in `dir` are left alone.
+async fn evict_to_size(dir: &Path, max_size_bytes: u64) -> Result<()> {
+    let mut entries = Vec::new();
+    let mut total = 0;
+    let mut read_dir = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        if !is_cache_entry(&entry.path()) {
+            continue;
+        }
+        let metadata = entry.metadata().await?;
+        if !metadata.is_file() {
+            continue;
+        }
+        total += metadata.len();
+        entries.push((
+            metadata.modified().unwrap_or(UNIX_EPOCH),
+            metadata.len(),
+            entry.path(),
+        ));
+    }
+
+    entries.sort_by_key(|(modified, _, _)| *modified);
+    for (_, len, path) in entries {
+        if total <= max_size_bytes {
+            break;
+        }
+        tokio::fs::remove_file(&path).await?;
+        total -= len;
+    }
+    Ok(())
+}
+
+/// Whether `path` is named like an entry this cache wrote, a hex fingerprint with a .json
+/// extension
+pub(crate) fn is_cache_entry(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+        && path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| stem.len() == 64 && stem.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Stands in for the real provider behind [`CachingProvider::replay`]. Replay mode never
+/// calls the inner provider, this only supplies the model config.
+struct NotRecorded {
+    model: ModelConfig,
+}
+
+#[async_trait]
+impl Provider for NotRecorded {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::empty()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.model.clone()
+    }
+
+    async fn complete(
+        &self,
+        _system: &str,
+        _messages: &[Message],
+        _tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        Err(ProviderError::ExecutionError(
+            "Responses are only replayed, there is no provider to call".to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+impl Provider for CachingProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "cache",
+            "Caching Provider",
+            "Caches responses of another provider on disk",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let key = request_fingerprint(
+            &self.name,
+            &self.inner.get_model_config(),
+            system,
+            messages,
+            tools,
+        );
+
+        match self.config.mode {
+            CacheMode::Replay => {
+                return match self.load(&key).await {
+                    Some(entry) => Ok((entry.message, entry.usage)),
+                    None => Err(ProviderError::ExecutionError(format!(
+                        "No recorded response for request {} in {} (last message: {:?}). \
+                         Record it again with GOOSE_PROVIDER_CACHE=record.",
+                        key,
+                        self.config.dir.display(),
+                        messages
+                            .last()
+                            .map(|message| message.as_concat_text())
+                            .unwrap_or_default()
+                    ))),
+                };
+            }
+            CacheMode::ReadWrite => {
+                if let Some(entry) = self.load(&key).await {
+                    if self.is_fresh(&entry) {
+                        tracing::debug!("Serving cached response {}", key);
+                        return Ok((entry.message, entry.usage));
+                    }
+                }
+            }
+            CacheMode::Record | CacheMode::Off => {}
+        }
+
+        let (message, usage) = self.inner.complete(system, messages, tools).await?;
+        if self.config.mode != CacheMode::Off {
+            if let Err(e) = self.store(&key, &message, &usage).await {
+                tracing::warn!("Failed to cache response {}: {}", key, e);
+            }
+        }
+        Ok((message, usage))
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.inner.create_embeddings(texts).await
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.inner.supports_structured_output()
+    }
+
+    /// Structured output requests are not cached
+    async fn extract(
+        &self,
+        system: &str,
+        messages: &[Message],
+        schema: &Value,
+    ) -> Result<(Value, ProviderUsage), ProviderError> {
+        self.inner.extract(system, messages, schema).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    struct CountingProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Provider for CountingProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("mock".to_string())
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok((
+                Message::assistant().with_text(format!("response {}", call)),
+                ProviderUsage::new("mock".to_string(), Usage::default()),
+            ))
+        }
+    }
+
+    fn caching(dir: &TempDir, mode: CacheMode) -> (Arc<CountingProvider>, CachingProvider) {
+        let inner = Arc::new(CountingProvider {
+            calls: AtomicUsize::new(0),
+        });
+        let config = CacheConfig {
+            mode,
+            dir: dir.path().to_path_buf(),
+            ttl: Duration::from_secs(60),
+            max_size_bytes: 1024 * 1024,
+        };
+        (inner.clone(), CachingProvider::new("mock", inner, config))
+    }
+
+    async fn reply(provider: &CachingProvider, text: &str) -> Result<String, ProviderError> {
+        let messages = vec![Message::user().with_text(text)];
+        let (message, _) = provider.complete("system", &messages, &[]).await?;
+        Ok(message.as_concat_text())
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_timestamps() {
+        let model = ModelConfig::new("mock".to_string());
+        let mut first = Message::user().with_text("hello");
+        first.created = 1;
+        let mut second = Message::user().with_text("hello");
+        second.created = 2;
+        let other = Message::user().with_text("goodbye");
+
+        let key = |m: &Message| {
+            request_fingerprint("mock", &model, "system", std::slice::from_ref(m), &[])
+        };
+        assert_eq!(key(&first), key(&second));
+        assert_ne!(key(&first), key(&other));
+        assert_ne!(
+            key(&first),
+            request_fingerprint("mock", &model, "other system", &[first.clone()], &[])
+        );
+        assert_eq!(
+            request_fingerprint(
+                "mock",
+                &model,
+                "It is 2025-01-01 09:00:00.",
+                &[first.clone()],
+                &[]
+            ),
+            request_fingerprint(
+                "mock",
+                &model,
+                "It is 2025-06-30 17:45:12.",
+                &[first.clone()],
+                &[]
+            )
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_covers_provider_and_request_settings() {
+        let model = ModelConfig::new("mock".to_string());
+        let messages = [Message::user().with_text("hello")];
+        let key = |provider: &str, model: &ModelConfig| {
+            request_fingerprint(provider, model, "system", &messages, &[])
+        };
+
+        assert_ne!(key("openai", &model), key("openrouter", &model));
+        assert_ne!(
+            key("mock", &model),
+            key("mock", &model.clone().with_temperature(Some(0.5)))
+        );
+        // Settings that never reach the provider don't change the key
+        assert_eq!(
+            key("mock", &model),
+            key("mock", &model.clone().with_context_limit(Some(1000)))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_write_serves_cached_response() {
+        let dir = TempDir::new().unwrap();
+        let (inner, provider) = caching(&dir, CacheMode::ReadWrite);
+
+        assert_eq!(reply(&provider, "hi").await.unwrap(), "response 1");
+        assert_eq!(reply(&provider, "hi").await.unwrap(), "response 1");
+        assert_eq!(reply(&provider, "other").await.unwrap(), "response 2");
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_refreshed() {
+        let dir = TempDir::new().unwrap();
+        let (inner, mut provider) = caching(&dir, CacheMode::ReadWrite);
+        provider.config.ttl = Duration::ZERO;
+        let key = request_fingerprint(
+            "mock",
+            &provider.get_model_config(),
+            "system",
+            &[Message::user().with_text("hi")],
+            &[],
+        );
+        let stale = CacheEntry {
+            stored_at: 0,
+            message: Message::assistant().with_text("stale"),
+            usage: ProviderUsage::new("mock".to_string(), Usage::default()),
+        };
+        std::fs::write(
+            provider.entry_path(&key),
+            serde_json::to_vec(&stale).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(reply(&provider, "hi").await.unwrap(), "response 1");
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_record_then_strict_replay() {
+        let dir = TempDir::new().unwrap();
+        let (_, recorder) = caching(&dir, CacheMode::Record);
+        assert_eq!(reply(&recorder, "hi").await.unwrap(), "response 1");
+        assert_eq!(reply(&recorder, "hi").await.unwrap(), "response 2");
+
+        let (inner, replay) = caching(&dir, CacheMode::Replay);
+        assert_eq!(reply(&replay, "hi").await.unwrap(), "response 2");
+        let err = reply(&replay, "never recorded").await.unwrap_err();
+        assert!(err.to_string().contains("No recorded response"));
+        assert!(err.to_string().contains("never recorded"));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 0);
+
+        // Replaying needs no provider at all
+        let replay =
+            CachingProvider::replay("mock", ModelConfig::new("mock".to_string()), dir.path());
+        assert_eq!(reply(&replay, "hi").await.unwrap(), "response 2");
+        assert!(reply(&replay, "never recorded").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_evicts_oldest_entries_over_size_limit() {
+        let dir = TempDir::new().unwrap();
+        let (_, mut provider) = caching(&dir, CacheMode::ReadWrite);
+
+        // Files the cache didn't write are neither counted nor removed
+        std::fs::write(dir.path().join("notes.txt"), "x".repeat(64 * 1024)).unwrap();
+
+        reply(&provider, "first").await.unwrap();
+        let entry_size = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|path| is_cache_entry(path))
+            .map(|path| std::fs::metadata(path).unwrap().len())
+            .sum::<u64>();
+        // Room for two entries, with some slack for differences in size
+        provider.config.max_size_bytes = entry_size * 5 / 2;
+
+        // Make sure modification times differ between entries
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        reply(&provider, "second").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        reply(&provider, "third").await.unwrap();
+
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 3);
+        assert!(dir.path().join("notes.txt").exists());
+        let first_key = request_fingerprint(
+            "mock",
+            &provider.get_model_config(),
+            "system",
+            &[Message::user().with_text("first")],
+            &[],
+        );
+        assert!(!provider.entry_path(&first_key).exists());
+    }
+}
@@ -1,6 +1,26 @@
 use reqwest::StatusCode;
+use std::fmt;
+use std::time::Duration;
 use thiserror::Error;
 
+/// Retry details carried by transient provider errors
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetryInfo {
+    /// How long the provider asked us to wait, from a Retry-After header
+    pub retry_after: Option<Duration>,
+    /// Number of attempts made before giving up, filled in by the retry layer
+    pub attempts: usize,
+}
+
+impl fmt::Display for RetryInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.attempts > 1 {
+            write!(f, " (gave up after {} attempts)", self.attempts)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ProviderError {
     #[error("Authentication error: {0}")]
@@ -9,11 +29,17 @@ pub enum ProviderError {
     #[error("Context length exceeded: {0}")]
     ContextLengthExceeded(String),
 
-    #[error("Rate limit exceeded: {0}")]
-    RateLimitExceeded(String),
+    #[error("Rate limit exceeded: {details}{retry}")]
+    RateLimitExceeded { details: String, retry: RetryInfo },
 
-    #[error("Server error: {0}")]
-    ServerError(String),
+    #[error("Server error: {details}{retry}")]
+    ServerError { details: String, retry: RetryInfo },
+
+    #[error("Provider {provider} is unavailable after repeated failures, try again in {}s", retry_in.as_secs())]
+    CircuitOpen {
+        provider: String,
+        retry_in: Duration,
+    },
 
     #[error("Request failed: {0}")]
     RequestFailed(String),
@@ -25,6 +51,59 @@ pub enum ProviderError {
     UsageError(String),
 }
 
+impl ProviderError {
+    pub fn rate_limited(details: impl Into<String>) -> Self {
+        ProviderError::RateLimitExceeded {
+            details: details.into(),
+            retry: RetryInfo::default(),
+        }
+    }
+
+    pub fn server_error(details: impl Into<String>) -> Self {
+        ProviderError::ServerError {
+            details: details.into(),
+            retry: RetryInfo::default(),
+        }
+    }
+
+    /// Attach the delay the provider asked for, if any
+    pub fn with_retry_after(mut self, delay: Option<Duration>) -> Self {
+        if let Some(retry) = self.retry_info_mut() {
+            retry.retry_after = delay;
+        }
+        self
+    }
+
+    /// Record how many attempts were made before this error was returned
+    pub fn with_attempts(mut self, attempts: usize) -> Self {
+        if let Some(retry) = self.retry_info_mut() {
+            retry.attempts = attempts;
+        }
+        self
+    }
+
+    /// Retry details, present only for errors worth retrying
+    pub fn retry_info(&self) -> Option<&RetryInfo> {
+        match self {
+            ProviderError::RateLimitExceeded { retry, .. }
+            | ProviderError::ServerError { retry, .. } => Some(retry),
+            _ => None,
+        }
+    }
+
+    fn retry_info_mut(&mut self) -> Option<&mut RetryInfo> {
+        match self {
+            ProviderError::RateLimitExceeded { retry, .. }
+            | ProviderError::ServerError { retry, .. } => Some(retry),
+            _ => None,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.retry_info().is_some()
+    }
+}
+
 impl From<anyhow::Error> for ProviderError {
     fn from(error: anyhow::Error) -> Self {
         ProviderError::ExecutionError(error.to_string())
@@ -33,7 +112,12 @@ impl From<anyhow::Error> for ProviderError {
 
 impl From<reqwest::Error> for ProviderError {
     fn from(error: reqwest::Error) -> Self {
-        ProviderError::ExecutionError(error.to_string())
+        // Transport failures mean the provider could not be reached at all
+        if error.is_connect() || error.is_timeout() || error.is_request() {
+            ProviderError::RequestFailed(error.to_string())
+        } else {
+            ProviderError::ExecutionError(error.to_string())
+        }
     }
 }
 
@@ -5,6 +5,7 @@ use super::{
     azure::AzureProvider,
     base::{Provider, ProviderMetadata},
     bedrock::BedrockProvider,
+    cache::{CacheConfig, CacheMode, CachingProvider},
     databricks::DatabricksProvider,
     gcpvertexai::GcpVertexAIProvider,
     githubcopilot::GithubCopilotProvider,
@@ -13,7 +14,11 @@ use super::{
     lead_worker::LeadWorkerProvider,
     ollama::OllamaProvider,
     openai::OpenAiProvider,
+    openai_compatible::OpenAiCompatibleProvider,
     openrouter::OpenRouterProvider,
+    replay::ReplayProvider,
+    retry::RetryProvider,
+    router::{Route, RouterConfig, RouterProvider, ROUTER_CONFIG_KEY},
     snowflake::SnowflakeProvider,
     venice::VeniceProvider,
 };
@@ -47,6 +52,7 @@ pub fn providers() -> Vec<ProviderMetadata> {
         GroqProvider::metadata(),
         OllamaProvider::metadata(),
         OpenAiProvider::metadata(),
+        OpenAiCompatibleProvider::metadata(),
         OpenRouterProvider::metadata(),
         VeniceProvider::metadata(),
         SnowflakeProvider::metadata(),
@@ -56,6 +62,16 @@ pub fn providers() -> Vec<ProviderMetadata> {
 pub fn create(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
     let config = crate::config::Config::global();
 
+    // A routing setup takes over provider selection entirely
+    if let Ok(router_config) = config.get_param::<RouterConfig>(ROUTER_CONFIG_KEY) {
+        tracing::info!(
+            "Creating router provider with {} routes",
+            router_config.routes.len()
+        );
+
+        return create_router(router_config, &model);
+    }
+
     // Check for lead model environment variables
     if let Ok(lead_model_name) = config.get_param::<String>("GOOSE_LEAD_MODEL") {
         tracing::info!("Creating lead/worker provider from environment variables");
@@ -67,6 +83,28 @@ pub fn create(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
     create_provider(name, model)
 }
 
+/// Create a router provider, building each route with its own retry wrapper. Routes share
+/// the reasoning settings of `model`.
+fn create_router(router_config: RouterConfig, model: &ModelConfig) -> Result<Arc<dyn Provider>> {
+    let routes = router_config
+        .routes
+        .into_iter()
+        .map(|route| {
+            let route_model = ModelConfig::new(route.model)
+                .with_context_limit(route.context_limit)
+                .with_thinking_budget(model.thinking_budget)
+                .with_reasoning_effort(model.reasoning_effort);
+            let provider = create_provider(&route.provider, route_model)?;
+            Ok(Route::new(&route.provider, provider, route.when))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Arc::new(RouterProvider::new(
+        routes,
+        router_config.fallback_on,
+    )?))
+}
+
 /// Create a lead/worker provider from environment variables
 fn create_lead_worker_from_env(
     default_provider_name: &str,
@@ -92,7 +130,9 @@ fn create_lead_worker_from_env(
         .unwrap_or(default_fallback_turns());
 
     // Create model configs
-    let lead_model_config = ModelConfig::new(lead_model_name.to_string());
+    let lead_model_config = ModelConfig::new(lead_model_name.to_string())
+        .with_thinking_budget(default_model.thinking_budget)
+        .with_reasoning_effort(default_model.reasoning_effort);
     let worker_model_config = default_model.clone();
 
     // Create the providers
@@ -109,10 +149,25 @@ fn create_lead_worker_from_env(
     )))
 }
 
+/// Create a provider wrapped with the shared retry and circuit breaker policy, and the
+/// response cache when one is configured
 fn create_provider(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
+    // Replayed responses need no real provider, and so no credentials
+    let cache_config = CacheConfig::from_config();
+    if cache_config.mode == CacheMode::Replay {
+        return Ok(Arc::new(ReplayProvider::new(name, model, cache_config.dir)));
+    }
+
+    let provider = create_base_provider(name, model)?;
+    let provider = Arc::new(RetryProvider::from_config(name, provider));
+    Ok(CachingProvider::from_config(name, provider))
+}
+
+fn create_base_provider(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
     // We use Arc instead of Box to be able to clone for multiple async tasks
     match name {
         "openai" => Ok(Arc::new(OpenAiProvider::from_env(model)?)),
+        "openai_compatible" => Ok(Arc::new(OpenAiCompatibleProvider::from_env(model)?)),
         "anthropic" => Ok(Arc::new(AnthropicProvider::from_env(model)?)),
         "azure_openai" => Ok(Arc::new(AzureProvider::from_env(model)?)),
         "aws_bedrock" => Ok(Arc::new(BedrockProvider::from_env(model)?)),
@@ -136,6 +191,7 @@ mod tests {
     use crate::providers::base::{ProviderMetadata, ProviderUsage, Usage};
     use chrono::Utc;
     use mcp_core::{content::TextContent, Role};
+    use serial_test::serial;
     use std::env;
 
     #[derive(Clone)]
@@ -186,6 +242,7 @@ mod tests {
     }
 
     #[test]
+    #[serial]
     fn test_create_lead_worker_provider() {
         // Save current env vars
         let saved_lead = env::var("GOOSE_LEAD_MODEL").ok();
@@ -234,6 +291,7 @@ mod tests {
     }
 
     #[test]
+    #[serial]
     fn test_lead_model_env_vars_with_defaults() {
         // Save current env vars
         let saved_vars = [
@@ -291,6 +349,7 @@ mod tests {
     }
 
     #[test]
+    #[serial]
     fn test_create_regular_provider_without_lead_config() {
         // Save current env vars
         let saved_lead = env::var("GOOSE_LEAD_MODEL").ok();
@@ -339,4 +398,24 @@ mod tests {
             env::set_var("GOOSE_LEAD_FALLBACK_TURNS", val);
         }
     }
+
+    #[test]
+    #[serial]
+    fn test_router_config_builds_each_route() {
+        let saved_router = env::var("GOOSE_ROUTER").ok();
+
+        // Routes are created through the regular factory, so an unknown provider fails
+        env::set_var(
+            "GOOSE_ROUTER",
+            r#"{"routes": [{"provider": "not_a_provider", "model": "some-model"}]}"#,
+        );
+        let result = create("openai", ModelConfig::new("gpt-4o-mini".to_string()));
+        let error_msg = result.err().expect("unknown route provider").to_string();
+        assert!(error_msg.contains("Unknown provider: not_a_provider"));
+
+        match saved_router {
+            Some(val) => env::set_var("GOOSE_ROUTER", val),
+            None => env::remove_var("GOOSE_ROUTER"),
+        }
+    }
 }
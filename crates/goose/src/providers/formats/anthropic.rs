@@ -2,6 +2,7 @@ use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use crate::providers::base::Usage;
 use crate::providers::errors::ProviderError;
+use crate::providers::utils::{convert_image, ImageFormat};
 use anyhow::{anyhow, Result};
 use mcp_core::content::Content;
 use mcp_core::role::Role;
@@ -66,6 +67,8 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                 MessageContent::SummarizationRequested(_) => {
                     // Skip
                 }
+                // Thinking without a signature came from another provider and would be rejected
+                MessageContent::Thinking(thinking) if thinking.signature.is_empty() => {}
                 MessageContent::Thinking(thinking) => {
                     content.push(json!({
                         "type": "thinking",
@@ -79,7 +82,26 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                         "data": redacted.data
                     }));
                 }
-                MessageContent::Image(_) => continue, // Anthropic doesn't support image content yet
+                MessageContent::Image(image) => {
+                    content.push(convert_image(image, &ImageFormat::Anthropic));
+                }
+                MessageContent::Document(document) => {
+                    if document.mime_type == "application/pdf" {
+                        content.push(json!({
+                            "type": "document",
+                            "source": {
+                                "type": "base64",
+                                "media_type": document.mime_type,
+                                "data": document.data
+                            }
+                        }));
+                    } else {
+                        content.push(json!({
+                            "type": "text",
+                            "text": document.as_fallback_text()
+                        }));
+                    }
+                }
                 MessageContent::FrontendToolRequest(tool_request) => {
                     if let Ok(tool_call) = &tool_request.tool_call {
                         content.push(json!({
@@ -315,10 +337,14 @@ pub fn create_request(
             .insert("tools".to_string(), json!(tool_specs));
     }
 
+    let thinking_budget = model_config
+        .thinking_budget()
+        .filter(|_| supports_thinking(&model_config.model_name));
+
     // Add temperature if specified and not using extended thinking model
     if let Some(temp) = model_config.temperature {
-        // Claude 3.7 models with thinking enabled don't support temperature
-        if !model_config.model_name.starts_with("claude-3-7-sonnet-") {
+        // Models with thinking enabled don't support temperature
+        if thinking_budget.is_none() && !model_config.model_name.starts_with("claude-3-7-sonnet-") {
             payload
                 .as_object_mut()
                 .unwrap()
@@ -326,19 +352,13 @@ pub fn create_request(
         }
     }
 
-    // Add thinking parameters for claude-3-7-sonnet model
-    let is_thinking_enabled = std::env::var("CLAUDE_THINKING_ENABLED").is_ok();
-    if model_config.model_name.starts_with("claude-3-7-sonnet-") && is_thinking_enabled {
-        // Minimum budget_tokens is 1024
-        let budget_tokens = std::env::var("CLAUDE_THINKING_BUDGET")
-            .unwrap_or_else(|_| "16000".to_string())
-            .parse()
-            .unwrap_or(16000);
-
-        payload
-            .as_object_mut()
-            .unwrap()
-            .insert("max_tokens".to_string(), json!(max_tokens + budget_tokens));
+    // Add extended thinking parameters. The budget counts towards max_tokens, so it is added
+    // on top of the tokens for the answer itself.
+    if let Some(budget_tokens) = thinking_budget {
+        payload.as_object_mut().unwrap().insert(
+            "max_tokens".to_string(),
+            json!(max_tokens + budget_tokens as i32),
+        );
 
         payload.as_object_mut().unwrap().insert(
             "thinking".to_string(),
@@ -352,6 +372,23 @@ pub fn create_request(
     Ok(payload)
 }
 
+/// Claude models with extended thinking
+const THINKING_MODELS: &[&str] = &[
+    "claude-3-7-sonnet",
+    "claude-sonnet-4",
+    "claude-opus-4",
+    "claude-haiku-4",
+    "claude-4",
+];
+
+/// Whether a Claude model supports extended thinking. The name can be prefixed, as in
+/// `databricks-claude-3-7-sonnet`.
+pub fn supports_thinking(model_name: &str) -> bool {
+    THINKING_MODELS
+        .iter()
+        .any(|prefix| model_name.contains(prefix))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -531,6 +568,29 @@ mod tests {
         assert_eq!(spec[2]["content"][0]["text"], "How are you?");
     }
 
+    #[test]
+    fn test_attachments_to_anthropic_spec() {
+        let message = Message::user()
+            .with_text("Summarize these")
+            .with_image("aGVsbG8=", "image/png")
+            .with_content(MessageContent::document(
+                "JVBERi0=",
+                "application/pdf",
+                Some("report.pdf".to_string()),
+                None,
+            ));
+
+        let spec = format_messages(&[message]);
+
+        let content = spec[0]["content"].as_array().unwrap();
+        assert_eq!(content.len(), 3);
+        assert_eq!(content[1]["type"], "image");
+        assert_eq!(content[1]["source"]["media_type"], "image/png");
+        assert_eq!(content[2]["type"], "document");
+        assert_eq!(content[2]["source"]["media_type"], "application/pdf");
+        assert_eq!(content[2]["source"]["data"], "JVBERi0=");
+    }
+
     #[test]
     fn test_tools_to_anthropic_spec() {
         let tools = vec![
@@ -599,7 +659,7 @@ mod tests {
 
         // Execute the test
         let result = (|| {
-            let model_config = ModelConfig::new("claude-3-7-sonnet-20250219".to_string());
+            let model_config = ModelConfig::from_config("claude-3-7-sonnet-20250219".to_string());
             let system = "You are a helpful assistant.";
             let messages = vec![Message::user().with_text("Hello")];
             let tools = vec![];
@@ -631,4 +691,60 @@ mod tests {
         // Return the test result
         result
     }
+
+    #[test]
+    fn test_create_request_with_thinking_budget() -> Result<()> {
+        let model_config = ModelConfig::new("claude-sonnet-4-20250514".to_string())
+            .with_temperature(Some(0.5))
+            .with_max_tokens(Some(4000))
+            .with_thinking_budget(Some(10_000));
+        let messages = vec![
+            Message::user().with_text("Hello"),
+            Message::assistant()
+                .with_thinking("Let me think", "sig")
+                .with_thinking("From another provider", "")
+                .with_text("Hi"),
+        ];
+
+        let payload = create_request(&model_config, "system", &messages, &[])?;
+
+        assert_eq!(payload["thinking"]["type"], "enabled");
+        assert_eq!(payload["thinking"]["budget_tokens"], 10_000);
+        assert_eq!(payload["max_tokens"], 14_000);
+        assert!(payload.get("temperature").is_none());
+
+        // Only signed thinking is sent back
+        let assistant = payload["messages"][1]["content"].as_array().unwrap();
+        assert_eq!(assistant.len(), 2);
+        assert_eq!(assistant[0]["type"], "thinking");
+        assert_eq!(assistant[0]["signature"], "sig");
+
+        // Without a budget there is no thinking and temperature is kept
+        let model_config = model_config
+            .with_thinking_budget(None)
+            .with_reasoning_effort(None);
+        let payload = create_request(&model_config, "system", &messages, &[])?;
+        assert!(payload.get("thinking").is_none());
+        assert_eq!(payload["temperature"], 0.5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_thinking_unsupported_model() -> Result<()> {
+        let model_config = ModelConfig::new("claude-3-5-sonnet-latest".to_string())
+            .with_temperature(Some(0.5))
+            .with_max_tokens(Some(4000))
+            .with_reasoning_effort(Some(crate::model::ReasoningEffort::High));
+
+        let payload = create_request(&model_config, "system", &[], &[])?;
+        assert!(payload.get("thinking").is_none());
+        assert_eq!(payload["max_tokens"], 4000);
+        assert_eq!(payload["temperature"], 0.5);
+
+        assert!(supports_thinking("claude-opus-4-1-20250805"));
+        assert!(supports_thinking("databricks-claude-3-7-sonnet"));
+        assert!(!supports_thinking("claude-3-5-haiku-latest"));
+        Ok(())
+    }
 }
@@ -4,12 +4,14 @@ use std::path::Path;
 use anyhow::{anyhow, bail, Result};
 use aws_sdk_bedrockruntime::types as bedrock;
 use aws_smithy_types::{Document, Number};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use chrono::Utc;
 use mcp_core::{Content, ResourceContents, Role, Tool, ToolCall, ToolError, ToolResult};
 use serde_json::Value;
 
 use super::super::base::Usage;
 use crate::message::{Message, MessageContent};
+use crate::model::ModelConfig;
 
 pub fn to_bedrock_message(message: &Message) -> Result<bedrock::Message> {
     bedrock::Message::builder()
@@ -31,17 +33,65 @@ pub fn to_bedrock_message_content(content: &MessageContent) -> Result<bedrock::C
         MessageContent::ToolConfirmationRequest(_tool_confirmation_request) => {
             bedrock::ContentBlock::Text("".to_string())
         }
-        MessageContent::Image(_) => {
-            bail!("Image content is not supported by Bedrock provider yet")
+        MessageContent::Image(image) => {
+            let format = match image.mime_type.as_str() {
+                "image/png" => bedrock::ImageFormat::Png,
+                "image/jpeg" => bedrock::ImageFormat::Jpeg,
+                "image/gif" => bedrock::ImageFormat::Gif,
+                "image/webp" => bedrock::ImageFormat::Webp,
+                other => bail!("Image type {} is not supported by Bedrock", other),
+            };
+            let bytes = BASE64
+                .decode(&image.data)
+                .map_err(|err| anyhow!("Invalid image data: {}", err))?;
+            bedrock::ContentBlock::Image(
+                bedrock::ImageBlock::builder()
+                    .format(format)
+                    .source(bedrock::ImageSource::Bytes(bytes.into()))
+                    .build()
+                    .map_err(|err| anyhow!("Failed to construct Bedrock image: {}", err))?,
+            )
         }
-        MessageContent::Thinking(_) => {
-            // Thinking blocks are not supported in Bedrock - skip
-            bedrock::ContentBlock::Text("".to_string())
+        MessageContent::Document(document) => {
+            if document.mime_type != "application/pdf" {
+                return Ok(bedrock::ContentBlock::Text(document.as_fallback_text()));
+            }
+            let bytes = BASE64
+                .decode(&document.data)
+                .map_err(|err| anyhow!("Invalid document data: {}", err))?;
+            bedrock::ContentBlock::Document(
+                bedrock::DocumentBlock::builder()
+                    .format(bedrock::DocumentFormat::Pdf)
+                    .name(to_bedrock_document_name(
+                        document.name.as_deref().unwrap_or("attachment"),
+                    ))
+                    .source(bedrock::DocumentSource::Bytes(bytes.into()))
+                    .build()
+                    .map_err(|err| anyhow!("Failed to construct Bedrock document: {}", err))?,
+            )
         }
-        MessageContent::RedactedThinking(_) => {
-            // Redacted thinking blocks are not supported in Bedrock - skip
+        MessageContent::Thinking(thinking) if thinking.signature.is_empty() => {
+            // Unsigned thinking came from another provider and can't be sent back - skip
             bedrock::ContentBlock::Text("".to_string())
         }
+        MessageContent::Thinking(thinking) => {
+            // Claude needs its signed reasoning back in turns that used tools
+            bedrock::ContentBlock::ReasoningContent(bedrock::ReasoningContentBlock::ReasoningText(
+                bedrock::ReasoningTextBlock::builder()
+                    .text(&thinking.thinking)
+                    .signature(&thinking.signature)
+                    .build()
+                    .map_err(|err| anyhow!("Failed to construct Bedrock reasoning: {}", err))?,
+            ))
+        }
+        MessageContent::RedactedThinking(redacted) => {
+            let data = BASE64
+                .decode(&redacted.data)
+                .map_err(|err| anyhow!("Invalid redacted reasoning data: {}", err))?;
+            bedrock::ContentBlock::ReasoningContent(
+                bedrock::ReasoningContentBlock::RedactedContent(data.into()),
+            )
+        }
         MessageContent::ContextLengthExceeded(_) => {
             bail!("ContextLengthExceeded should not get passed to the provider")
         }
@@ -180,6 +230,27 @@ pub fn to_bedrock_json(value: &Value) -> Document {
     }
 }
 
+/// Bedrock only accepts alphanumerics, single spaces, hyphens, parentheses and square
+/// brackets in document names
+fn to_bedrock_document_name(filename: &str) -> String {
+    let stem = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+    let name = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '(' | ')' | '[' | ']') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    if name.is_empty() {
+        "attachment".to_string()
+    } else {
+        name
+    }
+}
+
 fn to_bedrock_document(
     tool_use_id: &str,
     content: &ResourceContents,
@@ -260,10 +331,38 @@ pub fn from_bedrock_content_block(block: &bedrock::ContentBlock) -> Result<Messa
                     .collect::<ToolResult<Vec<_>>>()
             },
         ),
+        bedrock::ContentBlock::ReasoningContent(reasoning) => match reasoning {
+            bedrock::ReasoningContentBlock::ReasoningText(text) => {
+                MessageContent::thinking(text.text(), text.signature().unwrap_or_default())
+            }
+            bedrock::ReasoningContentBlock::RedactedContent(data) => {
+                MessageContent::redacted_thinking(BASE64.encode(data.as_ref()))
+            }
+            _ => bail!("Unsupported reasoning content type from Bedrock"),
+        },
         _ => bail!("Unsupported content block type from Bedrock"),
     })
 }
 
+/// Request fields that turn on extended thinking for Claude models, which take them as
+/// model specific fields rather than as part of the Converse API
+pub fn to_bedrock_thinking_fields(model_config: &ModelConfig) -> Option<Document> {
+    if !model_config.model_name.contains("claude") {
+        return None;
+    }
+    let budget_tokens = model_config.thinking_budget()?;
+    Some(Document::Object(HashMap::from([(
+        "thinking".to_string(),
+        Document::Object(HashMap::from([
+            ("type".to_string(), Document::String("enabled".to_string())),
+            (
+                "budget_tokens".to_string(),
+                Document::Number(Number::PosInt(budget_tokens as u64)),
+            ),
+        ])),
+    )])))
+}
+
 pub fn from_bedrock_tool_result_content_block(
     content: &bedrock::ToolResultContentBlock,
 ) -> ToolResult<Content> {
@@ -2,6 +2,7 @@ use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use crate::providers::base::Usage;
 use crate::providers::errors::ProviderError;
+use crate::providers::formats::anthropic::supports_thinking;
 use crate::providers::utils::{
     convert_image, detect_image_path, is_valid_function_name, load_image_file,
     sanitize_function_name, ImageFormat,
@@ -187,11 +188,15 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                 }
                 MessageContent::Image(image) => {
                     // Handle direct image content
+                    has_multiple_content = true;
+                    content_array.push(convert_image(image, image_format));
+                }
+                MessageContent::Document(document) => {
+                    // Databricks has no document part, so send the extracted text
+                    has_multiple_content = true;
                     content_array.push(json!({
-                        "type": "image_url",
-                        "image_url": {
-                            "url": convert_image(image, image_format)
-                        }
+                        "type": "text",
+                        "text": document.as_fallback_text()
                     }));
                 }
                 MessageContent::FrontendToolRequest(req) => {
@@ -454,21 +459,24 @@ pub fn create_request(
     let model_name = model_config.model_name.to_string();
     let is_o1 = model_name.starts_with("o1") || model_name.starts_with("goose-o1");
     let is_o3 = model_name.starts_with("o3") || model_name.starts_with("goose-o3");
-    let is_claude_3_7_sonnet = model_name.contains("claude-3-7-sonnet"); // can be goose- or databricks-
 
-    // Only extract reasoning effort for O1/O3 models
+    // Only extract reasoning effort for O1/O3 models. A configured effort wins over one in
+    // the model name.
     let (model_name, reasoning_effort) = if is_o1 || is_o3 {
         let parts: Vec<&str> = model_config.model_name.split('-').collect();
         let last_part = parts.last().unwrap();
+        let configured = model_config
+            .reasoning_effort
+            .map(|effort| effort.to_string());
 
         match *last_part {
             "low" | "medium" | "high" => {
                 let base_name = parts[..parts.len() - 1].join("-");
-                (base_name, Some(last_part.to_string()))
+                (base_name, configured.or(Some(last_part.to_string())))
             }
             _ => (
                 model_config.model_name.to_string(),
-                Some("medium".to_string()),
+                configured.or(Some("medium".to_string())),
             ),
         }
     } else {
@@ -513,21 +521,17 @@ pub fn create_request(
             .insert("tools".to_string(), json!(tools_spec));
     }
 
-    // Add thinking parameters for Claude 3.7 Sonnet model when requested
-    let is_thinking_enabled = std::env::var("CLAUDE_THINKING_ENABLED").is_ok();
-    if is_claude_3_7_sonnet && is_thinking_enabled {
-        // Minimum budget_tokens is 1024
-        let budget_tokens = std::env::var("CLAUDE_THINKING_BUDGET")
-            .unwrap_or_else(|_| "16000".to_string())
-            .parse()
-            .unwrap_or(16000);
-
+    // Add thinking parameters for Claude models with extended thinking, when requested
+    let thinking_budget = model_config
+        .thinking_budget()
+        .filter(|_| supports_thinking(&model_name));
+    if let Some(budget_tokens) = thinking_budget {
         // For Claude models with thinking enabled, we need to add max_tokens + budget_tokens
         // Default to 8192 (Claude max output) + budget if not specified
         let max_completion_tokens = model_config.max_tokens.unwrap_or(8192);
         payload.as_object_mut().unwrap().insert(
             "max_tokens".to_string(),
-            json!(max_completion_tokens + budget_tokens),
+            json!(max_completion_tokens + budget_tokens as i32),
         );
 
         payload.as_object_mut().unwrap().insert(
@@ -980,6 +984,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            reasoning_effort: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
@@ -1001,6 +1007,24 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_create_request_thinking_unsupported_model() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new("databricks-claude-3-5-sonnet".to_string())
+            .with_temperature(Some(0.5))
+            .with_max_tokens(Some(1024))
+            .with_thinking_budget(Some(8000));
+        let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
+        assert!(request.get("thinking").is_none());
+        assert_eq!(request["temperature"], 0.5);
+        assert_eq!(request["max_tokens"], 1024);
+
+        let model_config = ModelConfig::new("databricks-claude-3-7-sonnet".to_string())
+            .with_thinking_budget(Some(8000));
+        let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
+        assert_eq!(request["thinking"]["budget_tokens"], 8000);
+        Ok(())
+    }
+
     #[test]
     fn test_create_request_o1_default() -> anyhow::Result<()> {
         // Test default medium reasoning effort for O1 model
@@ -1012,6 +1036,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            reasoning_effort: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
@@ -1045,6 +1071,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            reasoning_effort: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
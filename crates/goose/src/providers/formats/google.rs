@@ -110,6 +110,27 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                             }
                         }
                     }
+                    MessageContent::Image(image) => {
+                        parts.push(json!({
+                            "inline_data": {
+                                "mime_type": image.mime_type,
+                                "data": image.data,
+                            }
+                        }));
+                    }
+                    MessageContent::Document(document) => {
+                        // Gemini reads PDFs directly
+                        if document.mime_type == "application/pdf" {
+                            parts.push(json!({
+                                "inline_data": {
+                                    "mime_type": document.mime_type,
+                                    "data": document.data,
+                                }
+                            }));
+                        } else {
+                            parts.push(json!({"text": document.as_fallback_text()}));
+                        }
+                    }
 
                     _ => {}
                 }
@@ -223,8 +244,14 @@ pub fn response_to_message(response: Value) -> Result<Message> {
         .unwrap_or(&binding);
 
     for part in parts {
+        let is_thought = part.get("thought").and_then(|v| v.as_bool()) == Some(true);
         if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
-            content.push(MessageContent::text(text.to_string()));
+            if is_thought {
+                // Thought summaries are not signed, and Gemini doesn't need them sent back
+                content.push(MessageContent::thinking(text.to_string(), String::new()));
+            } else {
+                content.push(MessageContent::text(text.to_string()));
+            }
         } else if let Some(function_call) = part.get("functionCall") {
             let id: String = rand::thread_rng()
                 .sample_iter(&Alphanumeric)
@@ -311,6 +338,17 @@ pub fn create_request(
     if let Some(tokens) = model_config.max_tokens {
         generation_config.insert("maxOutputTokens".to_string(), json!(tokens));
     }
+    // Gemini has no effort levels, so an effort is turned into a budget. Thought summaries
+    // are included so they can be shown like other providers' thinking.
+    let thinking_budget = model_config
+        .thinking_budget()
+        .filter(|_| supports_thinking(&model_config.model_name));
+    if let Some(budget) = thinking_budget {
+        generation_config.insert(
+            "thinkingConfig".to_string(),
+            json!({"thinkingBudget": budget, "includeThoughts": true}),
+        );
+    }
     if !generation_config.is_empty() {
         payload.insert("generationConfig".to_string(), json!(generation_config));
     }
@@ -318,6 +356,21 @@ pub fn create_request(
     Ok(Value::Object(payload))
 }
 
+/// Whether a Gemini model takes a thinking budget, which started with Gemini 2.5
+fn supports_thinking(model_name: &str) -> bool {
+    let Some((_, version)) = model_name.split_once("gemini-") else {
+        return false;
+    };
+    let version: String = version
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    version
+        .trim_end_matches('.')
+        .parse::<f64>()
+        .is_ok_and(|version| version >= 2.5)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -552,6 +605,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_response_to_message_with_thought_part() {
+        let response = json!({
+            "candidates": [{
+                "content": {
+                    "parts": [
+                        {"text": "Considering the greeting", "thought": true},
+                        {"text": "Hello, world!"}
+                    ]
+                }
+            }]
+        });
+        let message = response_to_message(response).unwrap();
+        assert_eq!(message.content.len(), 2);
+        match &message.content[0] {
+            MessageContent::Thinking(thinking) => {
+                assert_eq!(thinking.thinking, "Considering the greeting")
+            }
+            other => panic!("Expected thinking, got {:?}", other),
+        }
+        assert_eq!(message.as_concat_text(), "Hello, world!");
+    }
+
+    #[test]
+    fn test_create_request_with_thinking_budget() {
+        let model_config = ModelConfig::new("gemini-2.5-flash".to_string())
+            .with_thinking_budget(None)
+            .with_reasoning_effort(Some(crate::model::ReasoningEffort::Low));
+        let request = create_request(&model_config, "system", &[], &[]).unwrap();
+        assert_eq!(
+            request["generationConfig"]["thinkingConfig"],
+            json!({"thinkingBudget": 4000, "includeThoughts": true})
+        );
+
+        let model_config = model_config.with_reasoning_effort(None);
+        let request = create_request(&model_config, "system", &[], &[]).unwrap();
+        assert!(request.get("generationConfig").is_none());
+    }
+
+    #[test]
+    fn test_create_request_thinking_unsupported_model() {
+        for model in ["gemini-1.5-pro", "gemini-2.0-flash"] {
+            let model_config = ModelConfig::new(model.to_string())
+                .with_reasoning_effort(Some(crate::model::ReasoningEffort::High));
+            let request = create_request(&model_config, "system", &[], &[]).unwrap();
+            assert!(request.get("generationConfig").is_none(), "{}", model);
+        }
+        assert!(supports_thinking("gemini-2.5-pro"));
+        assert!(supports_thinking("gemini-3-pro-preview"));
+        assert!(!supports_thinking("gemini-exp-1206"));
+    }
+
     #[test]
     fn test_response_to_message_with_invalid_function_name() {
         let response = json!({
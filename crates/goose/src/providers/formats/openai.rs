@@ -11,6 +11,19 @@ use mcp_core::ToolError;
 use mcp_core::{Content, Role, Tool, ToolCall};
 use serde_json::{json, Value};
 
+/// Add a part to the content of a message, turning plain string content into a list of
+/// parts once there is more than one
+fn append_content(converted: &mut Value, part: Value) {
+    match converted.get_mut("content") {
+        Some(Value::Array(parts)) => parts.push(part),
+        Some(Value::String(text)) => {
+            let text = std::mem::take(text);
+            converted["content"] = json!([{"type": "text", "text": text}, part]);
+        }
+        _ => converted["content"] = json!([part]),
+    }
+}
+
 /// Convert internal Message format to OpenAI's API message specification
 ///   some openai compatible endpoints use the anthropic image spec at the content level
 ///   even though the message structure is otherwise following openai, the enum switches this
@@ -27,20 +40,20 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
             match content {
                 MessageContent::Text(text) => {
                     if !text.text.is_empty() {
+                        if converted.get("content").is_none() {
+                            converted["content"] = json!(text.text);
+                        } else {
+                            append_content(
+                                &mut converted,
+                                json!({"type": "text", "text": text.text}),
+                            );
+                        }
                         // Check for image paths in the text
                         if let Some(image_path) = detect_image_path(&text.text) {
-                            // Try to load and convert the image
+                            // Try to load and convert the image; if that fails, just use the text
                             if let Ok(image) = load_image_file(image_path) {
-                                converted["content"] = json!([
-                                    {"type": "text", "text": text.text},
-                                    convert_image(&image, image_format)
-                                ]);
-                            } else {
-                                // If image loading fails, just use the text
-                                converted["content"] = json!(text.text);
+                                append_content(&mut converted, convert_image(&image, image_format));
                             }
-                        } else {
-                            converted["content"] = json!(text.text);
                         }
                     }
                 }
@@ -155,7 +168,14 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                 }
                 MessageContent::Image(image) => {
                     // Handle direct image content
-                    converted["content"] = json!([convert_image(image, image_format)]);
+                    append_content(&mut converted, convert_image(image, image_format));
+                }
+                MessageContent::Document(document) => {
+                    // Chat completions has no document part, so send the extracted text
+                    append_content(
+                        &mut converted,
+                        json!({"type": "text", "text": document.as_fallback_text()}),
+                    );
                 }
                 MessageContent::FrontendToolRequest(request) => match &request.tool_call {
                     Ok(tool_call) => {
@@ -369,19 +389,23 @@ pub fn create_request(
 
     let is_ox_model = model_config.model_name.starts_with("o");
 
-    // Only extract reasoning effort for O1/O3 models
+    // Only extract reasoning effort for O1/O3 models. A configured effort wins over one in
+    // the model name.
     let (model_name, reasoning_effort) = if is_ox_model {
         let parts: Vec<&str> = model_config.model_name.split('-').collect();
         let last_part = parts.last().unwrap();
+        let configured = model_config
+            .reasoning_effort
+            .map(|effort| effort.to_string());
 
         match *last_part {
             "low" | "medium" | "high" => {
                 let base_name = parts[..parts.len() - 1].join("-");
-                (base_name, Some(last_part.to_string()))
+                (base_name, configured.or(Some(last_part.to_string())))
             }
             _ => (
                 model_config.model_name.to_string(),
-                Some("medium".to_string()),
+                configured.or(Some("medium".to_string())),
             ),
         }
     } else {
@@ -450,9 +474,58 @@ pub fn create_request(
     Ok(payload)
 }
 
+/// Create a request that asks the model to answer with JSON matching `schema`, using the
+/// `response_format` field instead of tools
+pub fn create_structured_request(
+    model_config: &ModelConfig,
+    system: &str,
+    messages: &[Message],
+    schema: &Value,
+    image_format: &ImageFormat,
+) -> anyhow::Result<Value, Error> {
+    let mut payload = create_request(model_config, system, messages, &[], image_format)?;
+    payload.as_object_mut().unwrap().insert(
+        "response_format".to_string(),
+        json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "final_output",
+                "schema": schema,
+                // Strict mode rejects common schema features such as optional properties,
+                // so we validate the result ourselves instead
+                "strict": false,
+            }
+        }),
+    );
+    Ok(payload)
+}
+
+/// Parse the JSON document out of the response to a request from [`create_structured_request`]
+pub fn structured_output_from_response(response: &Value) -> Result<Value, ProviderError> {
+    let content = response
+        .get("choices")
+        .and_then(|choices| choices.get(0))
+        .and_then(|choice| choice.get("message"))
+        .and_then(|message| message.get("content"))
+        .ok_or_else(|| {
+            ProviderError::RequestFailed("Response is missing message content".to_string())
+        })?;
+
+    match content {
+        Value::String(text) => serde_json::from_str(text).map_err(|e| {
+            ProviderError::RequestFailed(format!("Response is not valid JSON: {}", e))
+        }),
+        Value::Object(_) | Value::Array(_) => Ok(content.clone()),
+        _ => Err(ProviderError::RequestFailed(
+            "Response content is not a JSON document".to_string(),
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::ReasoningEffort;
     use mcp_core::content::Content;
     use serde_json::json;
 
@@ -740,6 +813,31 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_format_messages_with_attachments() {
+        let message = Message::user()
+            .with_text("What is in these?")
+            .with_image("aGVsbG8=", "image/png")
+            .with_content(MessageContent::document(
+                "JVBERi0=",
+                "application/pdf",
+                Some("report.pdf".to_string()),
+                Some("Quarterly numbers".to_string()),
+            ));
+        let spec = format_messages(&[message], &ImageFormat::OpenAi);
+
+        assert_eq!(spec.len(), 1);
+        let content = spec[0]["content"].as_array().unwrap();
+        assert_eq!(content.len(), 3);
+        assert_eq!(content[0]["text"], "What is in these?");
+        assert_eq!(content[1]["type"], "image_url");
+        assert_eq!(content[2]["type"], "text");
+        assert_eq!(
+            content[2]["text"],
+            "<document name=\"report.pdf\">\nQuarterly numbers\n</document>"
+        );
+    }
+
     #[test]
     fn test_response_to_message_text() -> anyhow::Result<()> {
         let response = json!({
@@ -859,6 +957,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            reasoning_effort: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
@@ -891,6 +991,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            reasoning_effort: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
@@ -924,6 +1026,8 @@ mod tests {
             max_tokens: Some(1024),
             toolshim: false,
             toolshim_model: None,
+            thinking_budget: None,
+            reasoning_effort: None,
         };
         let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
         let obj = request.as_object().unwrap();
@@ -945,4 +1049,60 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_create_request_configured_reasoning_effort() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new("o3-mini-high".to_string())
+            .with_reasoning_effort(Some(ReasoningEffort::Low));
+        let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
+        assert_eq!(request["model"], "o3-mini");
+        assert_eq!(request["reasoning_effort"], "low");
+
+        // Models without effort levels don't get one
+        let model_config = ModelConfig::new("gpt-4o".to_string())
+            .with_reasoning_effort(Some(ReasoningEffort::Low));
+        let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
+        assert!(request.get("reasoning_effort").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_structured_request() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new("gpt-4o".to_string());
+        let schema = json!({"type": "object", "properties": {"answer": {"type": "string"}}});
+        let messages = vec![Message::user().with_text("What is 2+2?")];
+        let request = create_structured_request(
+            &model_config,
+            "system",
+            &messages,
+            &schema,
+            &ImageFormat::OpenAi,
+        )?;
+
+        assert!(request.get("tools").is_none());
+        assert_eq!(request["response_format"]["type"], "json_schema");
+        assert_eq!(request["response_format"]["json_schema"]["schema"], schema);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_structured_output_from_response() {
+        let response = json!({
+            "choices": [{"message": {"role": "assistant", "content": "{\"answer\": \"4\"}"}}]
+        });
+        assert_eq!(
+            structured_output_from_response(&response).unwrap(),
+            json!({"answer": "4"})
+        );
+
+        let response = json!({
+            "choices": [{"message": {"role": "assistant", "content": "four"}}]
+        });
+        assert!(matches!(
+            structured_output_from_response(&response),
+            Err(ProviderError::RequestFailed(_))
+        ));
+    }
 }
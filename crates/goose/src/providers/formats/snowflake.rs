@@ -70,6 +70,12 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                     // Skip redacted thinking for now
                 }
                 MessageContent::Image(_) => continue, // Snowflake doesn't support image content yet
+                MessageContent::Document(document) => {
+                    if !text_content.is_empty() {
+                        text_content.push('\n');
+                    }
+                    text_content.push_str(&document.as_fallback_text());
+                }
                 MessageContent::FrontendToolRequest(_tool_request) => {
                     // Skip frontend tool requests
                 }
@@ -4,7 +4,6 @@ use anyhow::Result;
 use async_trait::async_trait;
 use reqwest::{Client, StatusCode};
 use serde_json::Value;
-use tokio::time::sleep;
 use url::Url;
 
 use crate::message::Message;
@@ -19,22 +18,14 @@ use crate::providers::formats::gcpvertexai::{
 
 use crate::providers::formats::gcpvertexai::GcpLocation::Iowa;
 use crate::providers::gcpauth::GcpAuth;
-use crate::providers::utils::emit_debug_trace;
+use crate::providers::retry::RetryConfig;
+use crate::providers::utils::{emit_debug_trace, retry_after};
 use mcp_core::tool::Tool;
 
 /// Base URL for GCP Vertex AI documentation
 const GCP_VERTEX_AI_DOC_URL: &str = "https://cloud.google.com/vertex-ai";
 /// Default timeout for API requests in seconds
 const DEFAULT_TIMEOUT_SECS: u64 = 600;
-/// Default initial interval for retry (in milliseconds)
-const DEFAULT_INITIAL_RETRY_INTERVAL_MS: u64 = 5000;
-/// Default maximum number of retries
-const DEFAULT_MAX_RETRIES: usize = 6;
-/// Default retry backoff multiplier
-const DEFAULT_BACKOFF_MULTIPLIER: f64 = 2.0;
-/// Default maximum interval for retry (in milliseconds)
-const DEFAULT_MAX_RETRY_INTERVAL_MS: u64 = 320_000;
-
 /// Represents errors specific to GCP Vertex AI operations.
 #[derive(Debug, thiserror::Error)]
 enum GcpVertexAIError {
@@ -47,53 +38,6 @@ enum GcpVertexAIError {
     AuthError(String),
 }
 
-/// Retry configuration for handling rate limit errors
-#[derive(Debug, Clone)]
-struct RetryConfig {
-    /// Maximum number of retry attempts
-    max_retries: usize,
-    /// Initial interval between retries in milliseconds
-    initial_interval_ms: u64,
-    /// Multiplier for backoff (exponential)
-    backoff_multiplier: f64,
-    /// Maximum interval between retries in milliseconds
-    max_interval_ms: u64,
-}
-
-impl Default for RetryConfig {
-    fn default() -> Self {
-        Self {
-            max_retries: DEFAULT_MAX_RETRIES,
-            initial_interval_ms: DEFAULT_INITIAL_RETRY_INTERVAL_MS,
-            backoff_multiplier: DEFAULT_BACKOFF_MULTIPLIER,
-            max_interval_ms: DEFAULT_MAX_RETRY_INTERVAL_MS,
-        }
-    }
-}
-
-impl RetryConfig {
-    /// Calculate the delay for a specific retry attempt (with jitter)
-    fn delay_for_attempt(&self, attempt: usize) -> Duration {
-        if attempt == 0 {
-            return Duration::from_millis(0);
-        }
-
-        // Calculate exponential backoff
-        let exponent = (attempt - 1) as u32;
-        let base_delay_ms = (self.initial_interval_ms as f64
-            * self.backoff_multiplier.powi(exponent as i32)) as u64;
-
-        // Apply max limit
-        let capped_delay_ms = std::cmp::min(base_delay_ms, self.max_interval_ms);
-
-        // Add jitter (+/-20% randomness) to avoid thundering herd problem
-        let jitter_factor = 0.8 + (rand::random::<f64>() * 0.4); // Between 0.8 and 1.2
-        let jittered_delay_ms = (capped_delay_ms as f64 * jitter_factor) as u64;
-
-        Duration::from_millis(jittered_delay_ms)
-    }
-}
-
 /// Provider implementation for Google Cloud Platform's Vertex AI service.
 ///
 /// This provider enables interaction with various AI models hosted on GCP Vertex AI,
@@ -115,9 +59,6 @@ pub struct GcpVertexAIProvider {
     location: String,
     /// Configuration for the specific model being used
     model: ModelConfig,
-    /// Retry configuration for handling rate limit errors
-    #[serde(skip)]
-    retry_config: RetryConfig,
 }
 
 impl GcpVertexAIProvider {
@@ -156,9 +97,6 @@ impl GcpVertexAIProvider {
 
         let auth = GcpAuth::new().await?;
 
-        // Load optional retry configuration from environment
-        let retry_config = Self::load_retry_config(config);
-
         Ok(Self {
             client,
             auth,
@@ -166,44 +104,9 @@ impl GcpVertexAIProvider {
             project_id,
             location,
             model,
-            retry_config,
         })
     }
 
-    /// Loads retry configuration from environment variables or uses defaults.
-    fn load_retry_config(config: &crate::config::Config) -> RetryConfig {
-        let max_retries = config
-            .get_param("GCP_MAX_RETRIES")
-            .ok()
-            .and_then(|v: String| v.parse::<usize>().ok())
-            .unwrap_or(DEFAULT_MAX_RETRIES);
-
-        let initial_interval_ms = config
-            .get_param("GCP_INITIAL_RETRY_INTERVAL_MS")
-            .ok()
-            .and_then(|v: String| v.parse::<u64>().ok())
-            .unwrap_or(DEFAULT_INITIAL_RETRY_INTERVAL_MS);
-
-        let backoff_multiplier = config
-            .get_param("GCP_BACKOFF_MULTIPLIER")
-            .ok()
-            .and_then(|v: String| v.parse::<f64>().ok())
-            .unwrap_or(DEFAULT_BACKOFF_MULTIPLIER);
-
-        let max_interval_ms = config
-            .get_param("GCP_MAX_RETRY_INTERVAL_MS")
-            .ok()
-            .and_then(|v: String| v.parse::<u64>().ok())
-            .unwrap_or(DEFAULT_MAX_RETRY_INTERVAL_MS);
-
-        RetryConfig {
-            max_retries,
-            initial_interval_ms,
-            backoff_multiplier,
-            max_interval_ms,
-        }
-    }
-
     /// Determines the appropriate GCP location for model deployment.
     ///
     /// Location is determined in the following order:
@@ -269,7 +172,7 @@ impl GcpVertexAIProvider {
     }
 
     /// Makes an authenticated POST request to the Vertex AI API at a specific location.
-    /// Includes retry logic for 429 Too Many Requests errors.
+    /// Rate limit errors are retried by the shared retry layer.
     ///
     /// # Arguments
     /// * `payload` - The request payload to send
@@ -285,70 +188,24 @@ impl GcpVertexAIProvider {
             .build_request_url(context.provider(), location)
             .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
 
-        // Initialize retry counter
-        let mut attempts = 0;
-        let mut last_error = None;
-
-        loop {
-            // Check if we've exceeded max retries
-            if attempts > 0 && attempts > self.retry_config.max_retries {
-                let error_msg = format!(
-                    "Exceeded maximum retry attempts ({}) for rate limiting (429)",
-                    self.retry_config.max_retries
-                );
-                tracing::error!("{}", error_msg);
-                return Err(last_error.unwrap_or(ProviderError::RateLimitExceeded(error_msg)));
-            }
-
-            // Get a fresh auth token for each attempt
-            let auth_header = self
-                .get_auth_header()
-                .await
-                .map_err(|e| ProviderError::Authentication(e.to_string()))?;
-
-            // Make the request
-            let response = self
-                .client
-                .post(url.clone())
-                .json(payload)
-                .header("Authorization", auth_header)
-                .send()
-                .await
-                .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
-
-            let status = response.status();
-
-            // If not a 429, process normally
-            if status != StatusCode::TOO_MANY_REQUESTS {
-                let response_json = response.json::<Value>().await.map_err(|e| {
-                    ProviderError::RequestFailed(format!("Failed to parse response: {e}"))
-                })?;
-
-                return match status {
-                    StatusCode::OK => Ok(response_json),
-                    StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
-                        tracing::debug!(
-                            "Authentication failed. Status: {status}, Payload: {payload:?}"
-                        );
-                        Err(ProviderError::Authentication(format!(
-                            "Authentication failed: {response_json:?}"
-                        )))
-                    }
-                    _ => {
-                        tracing::debug!(
-                            "Request failed. Status: {status}, Response: {response_json:?}"
-                        );
-                        Err(ProviderError::RequestFailed(format!(
-                            "Request failed with status {status}: {response_json:?}"
-                        )))
-                    }
-                };
-            }
+        let auth_header = self
+            .get_auth_header()
+            .await
+            .map_err(|e| ProviderError::Authentication(e.to_string()))?;
+
+        let response = self
+            .client
+            .post(url)
+            .json(payload)
+            .header("Authorization", auth_header)
+            .send()
+            .await
+            .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
 
-            // Handle 429 Too Many Requests
-            attempts += 1;
+        let status = response.status();
 
-            // Try to parse response for more detailed error info
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_delay = retry_after(response.headers());
             let cite_gcp_vertex_429 =
                 "See https://cloud.google.com/vertex-ai/generative-ai/docs/error-code-429";
             let response_text = response.text().await.unwrap_or_default();
@@ -357,21 +214,28 @@ impl GcpVertexAIProvider {
             } else {
                 format!("Pay-as-you-go resource exhausted: {cite_gcp_vertex_429}.")
             };
+            return Err(ProviderError::rate_limited(quota_error).with_retry_after(retry_delay));
+        }
 
-            tracing::warn!(
-                "Rate limit exceeded (attempt {}/{}): {}. Retrying after backoff...",
-                attempts,
-                self.retry_config.max_retries,
-                quota_error
-            );
-
-            // Store the error in case we need to return it after max retries
-            last_error = Some(ProviderError::RateLimitExceeded(quota_error));
-
-            // Calculate and apply the backoff delay
-            let delay = self.retry_config.delay_for_attempt(attempts);
-            tracing::info!("Backing off for {:?} before retry", delay);
-            sleep(delay).await;
+        let response_json = response
+            .json::<Value>()
+            .await
+            .map_err(|e| ProviderError::RequestFailed(format!("Failed to parse response: {e}")))?;
+
+        match status {
+            StatusCode::OK => Ok(response_json),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                tracing::debug!("Authentication failed. Status: {status}, Payload: {payload:?}");
+                Err(ProviderError::Authentication(format!(
+                    "Authentication failed: {response_json:?}"
+                )))
+            }
+            _ => {
+                tracing::debug!("Request failed. Status: {status}, Response: {response_json:?}");
+                Err(ProviderError::RequestFailed(format!(
+                    "Request failed with status {status}: {response_json:?}"
+                )))
+            }
         }
     }
 
@@ -443,6 +307,7 @@ impl Provider for GcpVertexAIProvider {
         .collect();
 
         let known_models: Vec<&str> = model_strings.iter().map(|s| s.as_str()).collect();
+        let retry_defaults = RetryConfig::defaults_for("gcp_vertex_ai");
 
         ProviderMetadata::new(
             "gcp_vertex_ai",
@@ -460,25 +325,25 @@ impl Provider for GcpVertexAIProvider {
                     "GCP_MAX_RETRIES",
                     false,
                     false,
-                    Some(&DEFAULT_MAX_RETRIES.to_string()),
+                    Some(&retry_defaults.max_retries.to_string()),
                 ),
                 ConfigKey::new(
                     "GCP_INITIAL_RETRY_INTERVAL_MS",
                     false,
                     false,
-                    Some(&DEFAULT_INITIAL_RETRY_INTERVAL_MS.to_string()),
+                    Some(&retry_defaults.initial_interval_ms.to_string()),
                 ),
                 ConfigKey::new(
                     "GCP_BACKOFF_MULTIPLIER",
                     false,
                     false,
-                    Some(&DEFAULT_BACKOFF_MULTIPLIER.to_string()),
+                    Some(&retry_defaults.backoff_multiplier.to_string()),
                 ),
                 ConfigKey::new(
                     "GCP_MAX_RETRY_INTERVAL_MS",
                     false,
                     false,
-                    Some(&DEFAULT_MAX_RETRY_INTERVAL_MS.to_string()),
+                    Some(&retry_defaults.max_interval_ms.to_string()),
                 ),
             ],
         )
@@ -526,32 +391,6 @@ impl Provider for GcpVertexAIProvider {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_retry_config_delay_calculation() {
-        let config = RetryConfig {
-            max_retries: 5,
-            initial_interval_ms: 1000,
-            backoff_multiplier: 2.0,
-            max_interval_ms: 32000,
-        };
-
-        // First attempt has no delay
-        let delay0 = config.delay_for_attempt(0);
-        assert_eq!(delay0.as_millis(), 0);
-
-        // First retry should be around initial_interval with jitter
-        let delay1 = config.delay_for_attempt(1);
-        assert!(delay1.as_millis() >= 800 && delay1.as_millis() <= 1200);
-
-        // Second retry should be around initial_interval * multiplier^1 with jitter
-        let delay2 = config.delay_for_attempt(2);
-        assert!(delay2.as_millis() >= 1600 && delay2.as_millis() <= 2400);
-
-        // Check that max interval is respected
-        let delay10 = config.delay_for_attempt(10);
-        assert!(delay10.as_millis() <= 38400); // max_interval_ms * 1.2 (max jitter)
-    }
-
     #[test]
     fn test_model_provider_conversion() {
         assert_eq!(ModelProvider::Anthropic.as_str(), "anthropic");
@@ -15,6 +15,7 @@ use super::base::{Provider, ProviderMetadata, ProviderUsage, Usage};
 use super::errors::ProviderError;
 use super::formats::openai::{create_request, get_usage, response_to_message};
 use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
+use super::utils_universal_openai_stream::collect_stream;
 
 use crate::config::{Config, ConfigError};
 use crate::message::Message;
@@ -137,8 +138,6 @@ impl GithubCopilotProvider {
     }
 
     async fn post(&self, mut payload: Value) -> Result<Value, ProviderError> {
-        use crate::providers::utils_universal_openai_stream::{OAIStreamChunk, OAIStreamCollector};
-        use futures_util::StreamExt;
         // Detect gpt-4.1 and stream
         let model_name = payload.get("model").and_then(|v| v.as_str()).unwrap_or("");
         let stream_only_model = GITHUB_COPILOT_STREAM_MODELS
@@ -162,30 +161,7 @@ impl GithubCopilotProvider {
             .send()
             .await?;
         if stream_only_model {
-            let mut collector = OAIStreamCollector::new();
-            let mut stream = response.bytes_stream();
-            while let Some(chunk) = stream.next().await {
-                let chunk = chunk.map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
-                let text = String::from_utf8_lossy(&chunk);
-                for line in text.lines() {
-                    let tline = line.trim();
-                    if !tline.starts_with("data: ") {
-                        continue;
-                    }
-                    let payload = &tline[6..];
-                    if payload == "[DONE]" {
-                        break;
-                    }
-                    match serde_json::from_str::<OAIStreamChunk>(payload) {
-                        Ok(ch) => collector.add_chunk(&ch),
-                        Err(_) => continue,
-                    }
-                }
-            }
-            let final_response = collector.build_response();
-            let value = serde_json::to_value(final_response)
-                .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
-            Ok(value)
+            collect_stream(response).await
         } else {
             handle_response_openai_compat(response).await
         }
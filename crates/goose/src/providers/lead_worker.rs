@@ -1,5 +1,6 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use serde_json::Value;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -291,6 +292,10 @@ impl LeadWorkerProviderTrait for LeadWorkerProvider {
         let worker_model = self.worker_provider.get_model_config().model_name;
         (lead_model, worker_model)
     }
+
+    fn worker_provider(&self) -> Arc<dyn Provider> {
+        Arc::clone(&self.worker_provider)
+    }
 }
 
 #[async_trait]
@@ -421,6 +426,30 @@ impl Provider for LeadWorkerProvider {
         }
     }
 
+    fn supports_structured_output(&self) -> bool {
+        // Either model may be the active one when `extract` is called
+        self.lead_provider.supports_structured_output()
+            || self.worker_provider.supports_structured_output()
+    }
+
+    async fn extract(
+        &self,
+        system: &str,
+        messages: &[Message],
+        schema: &Value,
+    ) -> Result<(Value, ProviderUsage), ProviderError> {
+        // Use the active provider, or the other one if only it supports structured output
+        let active = self.get_active_provider().await;
+        let provider = if active.supports_structured_output() {
+            active
+        } else if self.lead_provider.supports_structured_output() {
+            Arc::clone(&self.lead_provider)
+        } else {
+            Arc::clone(&self.worker_provider)
+        };
+        provider.extract(system, messages, schema).await
+    }
+
     /// Check if this provider is a LeadWorkerProvider
     fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
         Some(self)
@@ -469,6 +498,46 @@ mod tests {
                 ProviderUsage::new(self.name.clone(), Usage::default()),
             ))
         }
+
+        fn supports_structured_output(&self) -> bool {
+            true
+        }
+
+        async fn extract(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _schema: &Value,
+        ) -> Result<(Value, ProviderUsage), ProviderError> {
+            Ok((
+                serde_json::json!({"name": self.name}),
+                ProviderUsage::new(self.name.clone(), Usage::default()),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extract_uses_active_provider() {
+        let lead_provider = Arc::new(MockProvider {
+            name: "lead".to_string(),
+            model_config: ModelConfig::new("lead-model".to_string()),
+        });
+
+        let worker_provider = Arc::new(MockProvider {
+            name: "worker".to_string(),
+            model_config: ModelConfig::new("worker-model".to_string()),
+        });
+
+        let provider = LeadWorkerProvider::new(lead_provider, worker_provider, Some(1));
+        let schema = serde_json::json!({"type": "object"});
+
+        assert!(provider.supports_structured_output());
+        let (_value, usage) = provider.extract("system", &[], &schema).await.unwrap();
+        assert_eq!(usage.model, "lead");
+
+        provider.complete("system", &[], &[]).await.unwrap();
+        let (_value, usage) = provider.extract("system", &[], &schema).await.unwrap();
+        assert_eq!(usage.model, "worker");
     }
 
     #[tokio::test]
@@ -3,6 +3,7 @@ pub mod azure;
 pub mod azureauth;
 pub mod base;
 pub mod bedrock;
+pub mod cache;
 pub mod databricks;
 pub mod embedding;
 pub mod errors;
@@ -17,7 +18,11 @@ pub mod lead_worker;
 pub mod oauth;
 pub mod ollama;
 pub mod openai;
+pub mod openai_compatible;
 pub mod openrouter;
+pub mod replay;
+pub mod retry;
+pub mod router;
 pub mod snowflake;
 pub mod toolshim;
 pub mod utils;
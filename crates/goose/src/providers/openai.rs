@@ -8,7 +8,10 @@ use std::time::Duration;
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
 use super::embedding::{EmbeddingCapable, EmbeddingRequest, EmbeddingResponse};
 use super::errors::ProviderError;
-use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::formats::openai::{
+    create_request, create_structured_request, get_usage, response_to_message,
+    structured_output_from_response,
+};
 use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -179,6 +182,30 @@ impl Provider for OpenAiProvider {
         Ok((message, ProviderUsage::new(model, usage)))
     }
 
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
+
+    async fn extract(
+        &self,
+        system: &str,
+        messages: &[Message],
+        schema: &Value,
+    ) -> Result<(Value, ProviderUsage), ProviderError> {
+        let payload =
+            create_structured_request(&self.model, system, messages, schema, &ImageFormat::OpenAi)?;
+        let response = self.post(payload.clone()).await?;
+
+        let output = structured_output_from_response(&response)?;
+        let usage = get_usage(&response).unwrap_or_else(|e| {
+            tracing::debug!("Failed to get usage data: {}", e);
+            Usage::default()
+        });
+        let model = get_model(&response);
+        emit_debug_trace(&self.model, &payload, &response, &usage);
+        Ok((output, ProviderUsage::new(model, usage)))
+    }
+
     /// Fetch supported models from OpenAI; returns Err on any failure, Ok(None) if no data
     async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
         // List available models via OpenAI API
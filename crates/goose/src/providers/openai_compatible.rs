@@ -0,0 +1,664 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::errors::ProviderError;
+use super::formats::openai::{
+    create_request, create_structured_request, get_usage, response_to_message,
+    structured_output_from_response,
+};
+use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
+use super::utils_universal_openai_stream::collect_stream;
+use crate::message::{Message, MessageContent};
+use crate::model::ModelConfig;
+use mcp_core::content::Content;
+use mcp_core::tool::Tool;
+
+pub const OPENAI_COMPATIBLE_DEFAULT_HOST: &str = "http://localhost:8000";
+pub const OPENAI_COMPATIBLE_DEFAULT_BASE_PATH: &str = "v1/chat/completions";
+pub const OPENAI_COMPATIBLE_DEFAULT_MODEL: &str = "local-model";
+pub const OPENAI_COMPATIBLE_DOC_URL: &str =
+    "https://platform.openai.com/docs/api-reference/chat/create";
+
+/// What a model behind the endpoint can do. Models without tool support get toolshim
+/// enabled, and images are replaced with a note for models without vision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    #[serde(default = "default_true")]
+    pub tools: bool,
+    #[serde(default)]
+    pub vision: bool,
+    #[serde(default)]
+    pub json_mode: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ModelCapabilities {
+    fn default() -> Self {
+        Self {
+            tools: true,
+            vision: false,
+            json_mode: false,
+        }
+    }
+}
+
+impl ModelCapabilities {
+    /// Reads capability hints from an entry of a `/v1/models` listing. Servers describe this
+    /// in different ways (a `capabilities` list, OpenRouter style `supported_parameters` and
+    /// `architecture.input_modalities`); returns None when the entry has none of them.
+    fn from_model_entry(entry: &Value) -> Option<Self> {
+        let strings = |value: Option<&Value>| -> Option<Vec<String>> {
+            value.and_then(|v| v.as_array()).map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(str::to_lowercase))
+                    .collect()
+            })
+        };
+
+        let capabilities = strings(entry.get("capabilities"));
+        let parameters = strings(entry.get("supported_parameters"));
+        let modalities = strings(
+            entry
+                .get("architecture")
+                .and_then(|a| a.get("input_modalities")),
+        );
+        if capabilities.is_none() && parameters.is_none() && modalities.is_none() {
+            return None;
+        }
+
+        let flags: Vec<String> = [capabilities, parameters, modalities]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .collect();
+        let has = |names: &[&str]| flags.iter().any(|f| names.contains(&f.as_str()));
+        Some(Self {
+            tools: has(&["tools", "tool_use", "function_calling"]),
+            vision: has(&["vision", "image"]),
+            json_mode: has(&["json_mode", "response_format", "structured_outputs"]),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpenAiCompatibleProvider {
+    #[serde(skip)]
+    client: Client,
+    host: String,
+    base_path: String,
+    #[serde(skip)]
+    api_key: Option<String>,
+    #[serde(skip)]
+    custom_headers: HashMap<String, String>,
+    stream: bool,
+    model: ModelConfig,
+    /// Capabilities from config, these take precedence over discovered ones
+    capabilities: HashMap<String, ModelCapabilities>,
+    /// Capabilities reported by the server's model listing
+    #[serde(skip)]
+    discovered: Mutex<HashMap<String, ModelCapabilities>>,
+    /// Set once the model listing has been looked at for the first request
+    #[serde(skip)]
+    discovery: OnceCell<()>,
+}
+
+impl Default for OpenAiCompatibleProvider {
+    fn default() -> Self {
+        let model = ModelConfig::new(OpenAiCompatibleProvider::metadata().default_model);
+        OpenAiCompatibleProvider::from_env(model)
+            .expect("Failed to initialize OpenAI compatible provider")
+    }
+}
+
+impl OpenAiCompatibleProvider {
+    pub fn from_env(model: ModelConfig) -> Result<Self> {
+        let config = crate::config::Config::global();
+        let host: String = config
+            .get_param("OPENAI_COMPATIBLE_HOST")
+            .unwrap_or_else(|_| OPENAI_COMPATIBLE_DEFAULT_HOST.to_string());
+        let base_path: String = config
+            .get_param("OPENAI_COMPATIBLE_BASE_PATH")
+            .unwrap_or_else(|_| OPENAI_COMPATIBLE_DEFAULT_BASE_PATH.to_string());
+        // Most local servers don't check keys, so auth is only sent when one is configured
+        let api_key: Option<String> = config
+            .get_secret("OPENAI_COMPATIBLE_API_KEY")
+            .ok()
+            .filter(|key: &String| !key.is_empty());
+        let custom_headers: HashMap<String, String> = config
+            .get_secret("OPENAI_COMPATIBLE_CUSTOM_HEADERS")
+            .or_else(|_| config.get_param("OPENAI_COMPATIBLE_CUSTOM_HEADERS"))
+            .map(parse_custom_headers)
+            .unwrap_or_default();
+        let stream: bool = config
+            .get_param("OPENAI_COMPATIBLE_STREAM")
+            .unwrap_or(false);
+        let capabilities: HashMap<String, ModelCapabilities> = config
+            .get_param("OPENAI_COMPATIBLE_MODEL_CAPABILITIES")
+            .unwrap_or_default();
+        let timeout_secs: u64 = config.get_param("OPENAI_COMPATIBLE_TIMEOUT").unwrap_or(600);
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()?;
+
+        Ok(Self {
+            client,
+            host,
+            base_path,
+            api_key,
+            custom_headers,
+            stream,
+            model,
+            capabilities,
+            discovered: Mutex::new(HashMap::new()),
+            discovery: OnceCell::new(),
+        })
+    }
+
+    /// Looks up what the model can do in the server's model listing, once, unless its
+    /// capabilities are configured. Servers without a listing keep the defaults.
+    async fn discover_capabilities(&self) {
+        if self.capabilities.contains_key(&self.model.model_name) {
+            return;
+        }
+        self.discovery
+            .get_or_init(|| async {
+                if let Err(e) = self.fetch_supported_models_async().await {
+                    tracing::debug!("Could not discover model capabilities: {}", e);
+                }
+            })
+            .await;
+    }
+
+    /// Capabilities of the configured model: from config if set, otherwise as reported by
+    /// the server, otherwise the defaults
+    pub fn model_capabilities(&self) -> ModelCapabilities {
+        let name = &self.model.model_name;
+        self.capabilities
+            .get(name)
+            .copied()
+            .or_else(|| self.discovered.lock().unwrap().get(name).copied())
+            .unwrap_or_default()
+    }
+
+    fn url(&self, path: &str) -> Result<url::Url, ProviderError> {
+        let base_url = url::Url::parse(&self.host)
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+        base_url.join(path).map_err(|e| {
+            ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
+        })
+    }
+
+    /// The model listing lives next to the chat endpoint, e.g. `v1/models` for
+    /// `v1/chat/completions`
+    fn models_path(&self) -> String {
+        match self.base_path.strip_suffix("chat/completions") {
+            Some(prefix) => format!("{prefix}models"),
+            None => "v1/models".to_string(),
+        }
+    }
+
+    fn add_headers(&self, mut request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        for (key, value) in &self.custom_headers {
+            request = request.header(key, value);
+        }
+        request
+    }
+
+    async fn post(&self, mut payload: Value) -> Result<Value, ProviderError> {
+        if self.stream {
+            payload
+                .as_object_mut()
+                .unwrap()
+                .insert("stream".to_string(), Value::Bool(true));
+        }
+
+        let request = self.add_headers(self.client.post(self.url(&self.base_path)?));
+        let response = request.json(&payload).send().await?;
+
+        if self.stream {
+            collect_stream(response).await
+        } else {
+            handle_response_openai_compat(response).await
+        }
+    }
+}
+
+/// Replaces images with a short note, for models that only accept text
+fn strip_images(messages: &[Message]) -> Vec<Message> {
+    const NOTE: &str = "[image omitted: the model does not support image input]";
+    messages
+        .iter()
+        .map(|message| {
+            let mut message = message.clone();
+            for content in message.content.iter_mut() {
+                match content {
+                    MessageContent::Image(_) => *content = MessageContent::text(NOTE),
+                    MessageContent::ToolResponse(response) => {
+                        if let Ok(result) = response.tool_result.as_mut() {
+                            for item in result.iter_mut() {
+                                if matches!(item, Content::Image(_)) {
+                                    *item = Content::text(NOTE);
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            message
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Provider for OpenAiCompatibleProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "openai_compatible",
+            "OpenAI Compatible",
+            "Any server with an OpenAI compatible API, such as vLLM, LM Studio, llama.cpp or LocalAI",
+            OPENAI_COMPATIBLE_DEFAULT_MODEL,
+            vec![],
+            OPENAI_COMPATIBLE_DOC_URL,
+            vec![
+                ConfigKey::new(
+                    "OPENAI_COMPATIBLE_HOST",
+                    true,
+                    false,
+                    Some(OPENAI_COMPATIBLE_DEFAULT_HOST),
+                ),
+                ConfigKey::new(
+                    "OPENAI_COMPATIBLE_BASE_PATH",
+                    false,
+                    false,
+                    Some(OPENAI_COMPATIBLE_DEFAULT_BASE_PATH),
+                ),
+                ConfigKey::new("OPENAI_COMPATIBLE_API_KEY", false, true, None),
+                ConfigKey::new("OPENAI_COMPATIBLE_CUSTOM_HEADERS", false, true, None),
+                ConfigKey::new("OPENAI_COMPATIBLE_STREAM", false, false, Some("false")),
+                ConfigKey::new("OPENAI_COMPATIBLE_TIMEOUT", false, false, Some("600")),
+            ],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        let mut model = self.model.clone();
+        if !self.model_capabilities().tools {
+            model.toolshim = true;
+        }
+        model
+    }
+
+    #[tracing::instrument(
+        skip(self, system, messages, tools),
+        fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
+    )]
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.discover_capabilities().await;
+        let capabilities = self.model_capabilities();
+        let messages = if capabilities.vision {
+            messages.to_vec()
+        } else {
+            strip_images(messages)
+        };
+        // Models without tool support get toolshim from the next turn, and no tools until then
+        let tools = if capabilities.tools { tools } else { &[] };
+        let payload = create_request(&self.model, system, &messages, tools, &ImageFormat::OpenAi)?;
+
+        let response = self.post(payload.clone()).await?;
+
+        let message = response_to_message(response.clone())?;
+        let usage = match get_usage(&response) {
+            Ok(usage) => usage,
+            Err(ProviderError::UsageError(e)) => {
+                tracing::debug!("Failed to get usage data: {}", e);
+                Usage::default()
+            }
+            Err(e) => return Err(e),
+        };
+        let model = get_model(&response);
+        emit_debug_trace(&self.model, &payload, &response, &usage);
+        Ok((message, ProviderUsage::new(model, usage)))
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.model_capabilities().json_mode
+    }
+
+    async fn extract(
+        &self,
+        system: &str,
+        messages: &[Message],
+        schema: &Value,
+    ) -> Result<(Value, ProviderUsage), ProviderError> {
+        self.discover_capabilities().await;
+        let messages = if self.model_capabilities().vision {
+            messages.to_vec()
+        } else {
+            strip_images(messages)
+        };
+        let payload = create_structured_request(
+            &self.model,
+            system,
+            &messages,
+            schema,
+            &ImageFormat::OpenAi,
+        )?;
+        let response = self.post(payload.clone()).await?;
+
+        let output = structured_output_from_response(&response)?;
+        let usage = get_usage(&response).unwrap_or_else(|e| {
+            tracing::debug!("Failed to get usage data: {}", e);
+            Usage::default()
+        });
+        let model = get_model(&response);
+        emit_debug_trace(&self.model, &payload, &response, &usage);
+        Ok((output, ProviderUsage::new(model, usage)))
+    }
+
+    /// Lists models from the server's `/models` endpoint, and remembers any capabilities
+    /// the server reports for them
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        let request = self.add_headers(self.client.get(self.url(&self.models_path())?));
+        let json = handle_response_openai_compat(request.send().await?).await?;
+
+        let data = json.get("data").and_then(|v| v.as_array()).ok_or_else(|| {
+            ProviderError::UsageError("Missing data field in JSON response".into())
+        })?;
+
+        let mut models = Vec::new();
+        let mut discovered = self.discovered.lock().unwrap();
+        for entry in data {
+            let Some(id) = entry.get("id").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            if let Some(capabilities) = ModelCapabilities::from_model_entry(entry) {
+                discovered.insert(id.to_string(), capabilities);
+            }
+            models.push(id.to_string());
+        }
+        models.sort();
+        Ok(Some(models))
+    }
+}
+
+fn parse_custom_headers(s: String) -> HashMap<String, String> {
+    s.split(',')
+        .filter_map(|header| {
+            let mut parts = header.splitn(2, '=');
+            let key = parts.next().map(|s| s.trim().to_string())?;
+            let value = parts.next().map(|s| s.trim().to_string())?;
+            Some((key, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::content::ImageContent;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn provider(server: &MockServer, model: &str) -> OpenAiCompatibleProvider {
+        OpenAiCompatibleProvider {
+            client: Client::new(),
+            host: server.uri(),
+            base_path: OPENAI_COMPATIBLE_DEFAULT_BASE_PATH.to_string(),
+            api_key: None,
+            custom_headers: HashMap::new(),
+            stream: false,
+            model: ModelConfig::new(model.to_string()),
+            capabilities: HashMap::new(),
+            discovered: Mutex::new(HashMap::new()),
+            discovery: OnceCell::new(),
+        }
+    }
+
+    fn chat_response(text: &str) -> Value {
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "local-model",
+            "choices": [{
+                "index": 0,
+                "finish_reason": "stop",
+                "message": {"role": "assistant", "content": text}
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 2, "total_tokens": 12}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_complete_with_custom_path_and_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/chat/completions"))
+            .and(header("x-team", "goose"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chat_response("Hello!")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut provider = provider(&server, "local-model");
+        provider.base_path = "api/v1/chat/completions".to_string();
+        provider.api_key = Some("secret".to_string());
+        provider.custom_headers = parse_custom_headers("x-team=goose".to_string());
+
+        let (message, usage) = provider
+            .complete("system", &[Message::user().with_text("hi")], &[])
+            .await
+            .unwrap();
+        assert_eq!(message.as_concat_text(), "Hello!");
+        assert_eq!(usage.usage.total_tokens, Some(12));
+        assert_eq!(provider.models_path(), "api/v1/models");
+    }
+
+    #[tokio::test]
+    async fn test_complete_streaming() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"stop\"}],\"model\":\"local-model\"}\n\n",
+            "data: [DONE]\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .mount(&server)
+            .await;
+
+        let mut provider = provider(&server, "local-model");
+        provider.stream = true;
+
+        let (message, _) = provider
+            .complete("system", &[Message::user().with_text("hi")], &[])
+            .await
+            .unwrap();
+        assert_eq!(message.as_concat_text(), "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_error_status_is_mapped() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .set_body_json(json!({"error": {"message": "slow down"}})),
+            )
+            .mount(&server)
+            .await;
+
+        let err = provider(&server, "local-model")
+            .complete("system", &[Message::user().with_text("hi")], &[])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::RateLimitExceeded { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_model_discovery_sets_toolshim() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [
+                    {"id": "qwen2.5-coder", "capabilities": ["tool_use"]},
+                    {"id": "phi-2", "capabilities": []},
+                    {"id": "llava", "architecture": {"input_modalities": ["text", "image"]}},
+                    {"id": "plain"}
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let phi = provider(&server, "phi-2");
+        assert!(!phi.get_model_config().toolshim);
+        let models = phi.fetch_supported_models_async().await.unwrap().unwrap();
+        assert_eq!(models, vec!["llava", "phi-2", "plain", "qwen2.5-coder"]);
+        assert!(phi.get_model_config().toolshim);
+
+        let qwen = provider(&server, "qwen2.5-coder");
+        qwen.fetch_supported_models_async().await.unwrap();
+        assert!(qwen.model_capabilities().tools);
+        assert!(!qwen.get_model_config().toolshim);
+
+        let plain = provider(&server, "plain");
+        plain.fetch_supported_models_async().await.unwrap();
+        assert_eq!(plain.model_capabilities(), ModelCapabilities::default());
+
+        // Configured capabilities win over discovered ones
+        let mut configured = provider(&server, "phi-2");
+        configured.capabilities.insert(
+            "phi-2".to_string(),
+            serde_json::from_value(json!({"vision": true})).unwrap(),
+        );
+        configured.fetch_supported_models_async().await.unwrap();
+        assert!(!configured.get_model_config().toolshim);
+        assert!(configured.model_capabilities().vision);
+    }
+
+    #[tokio::test]
+    async fn test_first_request_discovers_capabilities() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [{"id": "phi-2", "capabilities": []}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(chat_response("Hello!")))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let provider = provider(&server, "phi-2");
+        assert!(!provider.get_model_config().toolshim);
+        let tool = Tool::new("shell", "Run a command", json!({"type": "object"}), None);
+        for _ in 0..2 {
+            provider
+                .complete(
+                    "system",
+                    &[Message::user().with_text("hi")],
+                    std::slice::from_ref(&tool),
+                )
+                .await
+                .unwrap();
+        }
+
+        // The listing is only read once, and the model is never sent tools it can't use
+        assert!(provider.get_model_config().toolshim);
+        let requests = server.received_requests().await.unwrap();
+        let chat: Vec<Value> = requests
+            .iter()
+            .filter(|request| request.method.as_str() == "POST")
+            .map(|request| serde_json::from_slice(&request.body).unwrap())
+            .collect();
+        assert!(chat.iter().all(|body| body.get("tools").is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_extract_with_json_mode() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(chat_response("{\"answer\": 4}")),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut provider = provider(&server, "local-model");
+        assert!(!provider.supports_structured_output());
+        provider.capabilities.insert(
+            "local-model".to_string(),
+            serde_json::from_value(json!({"json_mode": true})).unwrap(),
+        );
+        assert!(provider.supports_structured_output());
+
+        let schema = json!({"type": "object", "properties": {"answer": {"type": "integer"}}});
+        let (output, _) = provider
+            .extract("system", &[Message::user().with_text("2+2?")], &schema)
+            .await
+            .unwrap();
+        assert_eq!(output, json!({"answer": 4}));
+
+        let requests = server.received_requests().await.unwrap();
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["response_format"]["json_schema"]["schema"], schema);
+    }
+
+    #[test]
+    fn test_strip_images_without_vision() {
+        let messages =
+            vec![Message::user()
+                .with_text("what is this?")
+                .with_content(MessageContent::Image(ImageContent {
+                    data: "aGVsbG8=".to_string(),
+                    mime_type: "image/png".to_string(),
+                    annotations: None,
+                }))];
+
+        let stripped = strip_images(&messages);
+        assert!(stripped[0]
+            .content
+            .iter()
+            .all(|c| !matches!(c, MessageContent::Image(_))));
+        assert!(stripped[0].as_concat_text().contains("image omitted"));
+    }
+}
@@ -113,8 +113,8 @@ impl OpenRouterProvider {
             // Return appropriate error based on the OpenRouter error code
             match error_code {
                 401 | 403 => return Err(ProviderError::Authentication(error_message.to_string())),
-                429 => return Err(ProviderError::RateLimitExceeded(error_message.to_string())),
-                500 | 503 => return Err(ProviderError::ServerError(error_message.to_string())),
+                429 => return Err(ProviderError::rate_limited(error_message)),
+                500 | 503 => return Err(ProviderError::server_error(error_message)),
                 _ => return Err(ProviderError::RequestFailed(error_message.to_string())),
             }
         }
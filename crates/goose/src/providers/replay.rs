@@ -0,0 +1,274 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::base::{LeadWorkerProviderTrait, Provider, ProviderMetadata, ProviderUsage};
+use super::cache::{is_cache_entry, request_fingerprint, CacheConfig, CacheMode, CachingProvider};
+use super::errors::ProviderError;
+use crate::message::Message;
+use crate::model::ModelConfig;
+use mcp_core::tool::Tool;
+
+/// Wraps a provider and records every response it gives into `dir`, where a
+/// [`ReplayProvider`] can play them back later. Recordings are response cache entries, so
+/// `GOOSE_PROVIDER_CACHE=record` captures the same fixtures from a normal session.
+pub struct RecordingProvider {
+    cache: CachingProvider,
+}
+
+impl RecordingProvider {
+    /// Records the responses of `inner`, the provider registered as `name`
+    pub fn new(name: &str, inner: Arc<dyn Provider>, dir: impl Into<PathBuf>) -> Self {
+        let config = CacheConfig {
+            mode: CacheMode::Record,
+            dir: dir.into(),
+            ttl: Duration::MAX,
+            max_size_bytes: u64::MAX,
+        };
+        Self {
+            cache: CachingProvider::new(name, inner, config),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for RecordingProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "recording",
+            "Recording Provider",
+            "Records the responses of another provider for replay",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.cache.get_model_config()
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.cache.complete(system, messages, tools).await
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.cache.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.cache.supports_embeddings()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.cache.create_embeddings(texts).await
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.cache.supports_structured_output()
+    }
+
+    async fn extract(
+        &self,
+        system: &str,
+        messages: &[Message],
+        schema: &Value,
+    ) -> Result<(Value, ProviderUsage), ProviderError> {
+        self.cache.extract(system, messages, schema).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.cache.as_lead_worker()
+    }
+}
+
+/// A provider that answers from the responses recorded in a directory and never touches
+/// the network.
+///
+/// Requests are matched by [`request_fingerprint`]. A request that was not recorded fails
+/// with a [`ProviderError`] naming it, rather than being answered with a guess.
+pub struct ReplayProvider {
+    name: String,
+    dir: PathBuf,
+    cache: CachingProvider,
+    used: Mutex<HashSet<String>>,
+}
+
+impl ReplayProvider {
+    /// Replays the responses recorded in `dir` for the provider registered as `name`
+    pub fn new(name: &str, model: ModelConfig, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        Self {
+            name: name.to_string(),
+            cache: CachingProvider::replay(name, model, dir.clone()),
+            dir,
+            used: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Recorded responses that no request has matched yet, useful to assert that a test
+    /// went through the whole recording
+    pub fn unused(&self) -> Vec<PathBuf> {
+        let used = self.used.lock().unwrap();
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut unused: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| is_cache_entry(path))
+            .filter(|path| {
+                path.file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(|key| !used.contains(key))
+            })
+            .collect();
+        unused.sort();
+        unused
+    }
+}
+
+#[async_trait]
+impl Provider for ReplayProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "replay",
+            "Replay",
+            "Answers from recorded responses, for offline tests",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.cache.get_model_config()
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let key = request_fingerprint(
+            &self.name,
+            &self.cache.get_model_config(),
+            system,
+            messages,
+            tools,
+        );
+        match self.cache.complete(system, messages, tools).await {
+            Ok(response) => {
+                self.used.lock().unwrap().insert(key);
+                Ok(response)
+            }
+            Err(e) => {
+                tracing::error!("{}", e);
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    struct EchoProvider;
+
+    #[async_trait]
+    impl Provider for EchoProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("echo".to_string())
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            let last = messages
+                .last()
+                .map(|message| message.as_concat_text())
+                .unwrap_or_default();
+            Ok((
+                Message::assistant().with_text(format!("echo: {}", last)),
+                ProviderUsage::new("echo".to_string(), Usage::new(Some(3), Some(2), Some(5))),
+            ))
+        }
+    }
+
+    fn replay(dir: &TempDir) -> ReplayProvider {
+        ReplayProvider::new("echo", ModelConfig::new("echo".to_string()), dir.path())
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = TempDir::new().unwrap();
+        let tool = Tool::new("shell", "Run a command", json!({"type": "object"}), None);
+        let first = vec![Message::user().with_text("hello")];
+        let second = vec![
+            Message::user().with_text("hello"),
+            Message::assistant().with_text("echo: hello"),
+            Message::user().with_text("again"),
+        ];
+
+        let recorder = RecordingProvider::new("echo", Arc::new(EchoProvider), dir.path());
+        recorder.complete("system", &first, &[]).await.unwrap();
+        recorder
+            .complete("system", &second, std::slice::from_ref(&tool))
+            .await
+            .unwrap();
+
+        let replay = replay(&dir);
+        assert_eq!(replay.unused().len(), 2);
+
+        let (message, usage) = replay.complete("system", &first, &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "echo: hello");
+        assert_eq!(usage.usage.total_tokens, Some(5));
+
+        let (message, _) = replay.complete("system", &second, &[tool]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "echo: again");
+        assert!(replay.unused().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_request_fails() {
+        let dir = TempDir::new().unwrap();
+        let messages = vec![Message::user().with_text("hello")];
+        let recorder = RecordingProvider::new("echo", Arc::new(EchoProvider), dir.path());
+        recorder.complete("system", &messages, &[]).await.unwrap();
+
+        let replay = replay(&dir);
+        let err = replay
+            .complete("system", &[Message::user().with_text("surprise")], &[])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::ExecutionError(_)));
+        assert!(err.to_string().contains("surprise"));
+
+        // Offering different tools is a different request
+        let tool = Tool::new("shell", "Run a command", json!({"type": "object"}), None);
+        assert!(replay.complete("system", &messages, &[tool]).await.is_err());
+        assert_eq!(replay.unused().len(), 1);
+    }
+}
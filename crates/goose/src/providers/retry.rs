@@ -0,0 +1,555 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+use super::base::{LeadWorkerProviderTrait, Provider, ProviderMetadata, ProviderUsage};
+use super::errors::ProviderError;
+use crate::config::Config;
+use crate::message::Message;
+use crate::model::ModelConfig;
+use mcp_core::tool::Tool;
+
+/// Prefix for settings that apply to every provider without its own override
+const GLOBAL_PREFIX: &str = "GOOSE_PROVIDER";
+
+const DEFAULT_MAX_RETRIES: usize = 3;
+const DEFAULT_INITIAL_RETRY_INTERVAL_MS: u64 = 1000;
+const DEFAULT_BACKOFF_MULTIPLIER: f64 = 2.0;
+const DEFAULT_MAX_RETRY_INTERVAL_MS: u64 = 30_000;
+const DEFAULT_CIRCUIT_BREAKER_THRESHOLD: usize = 3;
+const DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SECS: u64 = 60;
+
+/// Retry, backoff and circuit breaker settings for one provider
+///
+/// Every field can be set per provider with `<PROVIDER>_<SETTING>` (e.g. `DATABRICKS_MAX_RETRIES`)
+/// or for all providers with `GOOSE_PROVIDER_<SETTING>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryConfig {
+    /// Maximum number of retries after the first attempt
+    pub max_retries: usize,
+    /// Delay before the first retry in milliseconds
+    pub initial_interval_ms: u64,
+    /// Multiplier applied to the delay after each retry
+    pub backoff_multiplier: f64,
+    /// Upper bound for a single delay in milliseconds. A Retry-After longer than this is not
+    /// waited for, the error is returned instead.
+    pub max_interval_ms: u64,
+    /// Consecutive failed requests after which calls fail fast; 0 disables the breaker
+    pub circuit_breaker_threshold: usize,
+    /// How long the breaker stays open before a request is let through again
+    pub circuit_breaker_cooldown_secs: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_interval_ms: DEFAULT_INITIAL_RETRY_INTERVAL_MS,
+            backoff_multiplier: DEFAULT_BACKOFF_MULTIPLIER,
+            max_interval_ms: DEFAULT_MAX_RETRY_INTERVAL_MS,
+            circuit_breaker_threshold: DEFAULT_CIRCUIT_BREAKER_THRESHOLD,
+            circuit_breaker_cooldown_secs: DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SECS,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Built-in defaults for a provider. Providers behind shared gateways get more patience,
+    /// matching what they used before retries were handled here.
+    pub fn defaults_for(provider: &str) -> Self {
+        let base = Self::default();
+        match provider {
+            "databricks" | "gcp_vertex_ai" => Self {
+                max_retries: 6,
+                initial_interval_ms: 5000,
+                max_interval_ms: 320_000,
+                ..base
+            },
+            "azure_openai" => Self {
+                max_retries: 5,
+                max_interval_ms: 32_000,
+                ..base
+            },
+            "aws_bedrock" => Self {
+                max_retries: 10,
+                initial_interval_ms: 20_000,
+                max_interval_ms: 120_000,
+                ..base
+            },
+            _ => base,
+        }
+    }
+
+    /// Load the settings for `provider`, falling back to the global settings and then to
+    /// [`RetryConfig::defaults_for`]
+    pub fn from_config(provider: &str) -> Self {
+        let defaults = Self::defaults_for(provider);
+        let prefix = config_prefix(provider);
+
+        Self {
+            max_retries: lookup(&prefix, "MAX_RETRIES").unwrap_or(defaults.max_retries),
+            initial_interval_ms: lookup(&prefix, "INITIAL_RETRY_INTERVAL_MS")
+                .unwrap_or(defaults.initial_interval_ms),
+            backoff_multiplier: lookup(&prefix, "BACKOFF_MULTIPLIER")
+                .unwrap_or(defaults.backoff_multiplier),
+            max_interval_ms: lookup(&prefix, "MAX_RETRY_INTERVAL_MS")
+                .unwrap_or(defaults.max_interval_ms),
+            circuit_breaker_threshold: lookup(&prefix, "CIRCUIT_BREAKER_THRESHOLD")
+                .unwrap_or(defaults.circuit_breaker_threshold),
+            circuit_breaker_cooldown_secs: lookup(&prefix, "CIRCUIT_BREAKER_COOLDOWN_SECS")
+                .unwrap_or(defaults.circuit_breaker_cooldown_secs),
+        }
+    }
+
+    /// Calculate the delay for a specific retry attempt (with jitter)
+    pub fn delay_for_attempt(&self, attempt: usize) -> Duration {
+        if attempt == 0 {
+            return Duration::from_millis(0);
+        }
+
+        // Calculate exponential backoff
+        let exponent = (attempt - 1) as i32;
+        let base_delay_ms =
+            (self.initial_interval_ms as f64 * self.backoff_multiplier.powi(exponent)) as u64;
+
+        // Apply max limit
+        let capped_delay_ms = std::cmp::min(base_delay_ms, self.max_interval_ms);
+
+        // Add jitter (+/-20% randomness) to avoid thundering herd problem
+        let jitter_factor = 0.8 + (rand::random::<f64>() * 0.4); // Between 0.8 and 1.2
+        let jittered_delay_ms = (capped_delay_ms as f64 * jitter_factor) as u64;
+
+        Duration::from_millis(jittered_delay_ms)
+    }
+
+    /// The delay before retrying `error`, or None if it should not be retried
+    fn delay_for_error(&self, error: &ProviderError, attempt: usize) -> Option<Duration> {
+        let retry = error.retry_info()?;
+        if attempt > self.max_retries {
+            return None;
+        }
+        match retry.retry_after {
+            Some(delay) if delay > Duration::from_millis(self.max_interval_ms) => None,
+            Some(delay) => Some(delay),
+            None => Some(self.delay_for_attempt(attempt)),
+        }
+    }
+}
+
+/// Config key prefix for a provider's retry settings. Vertex AI keeps its historical `GCP_` prefix.
+fn config_prefix(provider: &str) -> String {
+    match provider {
+        "gcp_vertex_ai" => "GCP".to_string(),
+        _ => provider.to_uppercase(),
+    }
+}
+
+/// Read `<prefix>_<setting>`, then `GOOSE_PROVIDER_<setting>`. Values may be numbers or strings.
+fn lookup<T>(prefix: &str, setting: &str) -> Option<T>
+where
+    T: FromStr + DeserializeOwned,
+{
+    let config = Config::global();
+    [prefix, GLOBAL_PREFIX]
+        .iter()
+        .map(|prefix| format!("{}_{}", prefix, setting))
+        .find_map(|key| {
+            config.get_param::<T>(&key).ok().or_else(|| {
+                config
+                    .get_param::<String>(&key)
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+            })
+        })
+}
+
+#[derive(Debug, Default)]
+struct CircuitState {
+    consecutive_failures: usize,
+    open_until: Option<Instant>,
+}
+
+/// Wraps a provider with retries on rate limit and server errors, exponential backoff with
+/// jitter that honours Retry-After, and a circuit breaker that fails fast while the provider
+/// keeps failing
+pub struct RetryProvider {
+    name: String,
+    inner: Arc<dyn Provider>,
+    config: RetryConfig,
+    circuit: Mutex<CircuitState>,
+}
+
+impl RetryProvider {
+    pub fn new(name: &str, inner: Arc<dyn Provider>, config: RetryConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            inner,
+            config,
+            circuit: Mutex::new(CircuitState::default()),
+        }
+    }
+
+    /// Wrap `inner` with the configured settings for provider `name`
+    pub fn from_config(name: &str, inner: Arc<dyn Provider>) -> Self {
+        Self::new(name, inner, RetryConfig::from_config(name))
+    }
+
+    /// Fail fast while the breaker is open. Once the cooldown has passed requests go through
+    /// again, and a single further failure reopens it.
+    fn check_circuit(&self) -> Result<(), ProviderError> {
+        let mut circuit = self.circuit.lock().unwrap();
+        if let Some(open_until) = circuit.open_until {
+            let now = Instant::now();
+            if now < open_until {
+                return Err(ProviderError::CircuitOpen {
+                    provider: self.name.clone(),
+                    retry_in: open_until - now,
+                });
+            }
+            circuit.open_until = None;
+        }
+        Ok(())
+    }
+
+    fn record_success(&self) {
+        let mut circuit = self.circuit.lock().unwrap();
+        circuit.consecutive_failures = 0;
+        circuit.open_until = None;
+    }
+
+    fn record_failure(&self) {
+        let threshold = self.config.circuit_breaker_threshold;
+        if threshold == 0 {
+            return;
+        }
+        let mut circuit = self.circuit.lock().unwrap();
+        circuit.consecutive_failures += 1;
+        if circuit.consecutive_failures >= threshold {
+            tracing::warn!(
+                "{} failed {} requests in a row, pausing requests for {}s",
+                self.name,
+                circuit.consecutive_failures,
+                self.config.circuit_breaker_cooldown_secs
+            );
+            circuit.open_until = Some(
+                Instant::now() + Duration::from_secs(self.config.circuit_breaker_cooldown_secs),
+            );
+        }
+    }
+
+    async fn with_retry<T, F, Fut>(&self, mut request: F) -> Result<T, ProviderError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ProviderError>>,
+    {
+        self.check_circuit()?;
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let error = match request().await {
+                Ok(result) => {
+                    self.record_success();
+                    return Ok(result);
+                }
+                Err(error) => error,
+            };
+
+            let Some(delay) = self.config.delay_for_error(&error, attempt) else {
+                if error.is_retryable() {
+                    self.record_failure();
+                    tracing::error!(
+                        "{} request failed after {} attempts: {}",
+                        self.name,
+                        attempt,
+                        error
+                    );
+                }
+                return Err(error.with_attempts(attempt));
+            };
+
+            tracing::warn!(
+                "{} request failed (attempt {}/{}), retrying in {:?}: {}",
+                self.name,
+                attempt,
+                self.config.max_retries + 1,
+                delay,
+                error
+            );
+            sleep(delay).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for RetryProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "retry",
+            "Retry Provider",
+            "Retries and circuit breaking around another provider",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.with_retry(|| self.inner.complete(system, messages, tools))
+            .await
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.with_retry(|| self.inner.create_embeddings(texts.clone()))
+            .await
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.inner.supports_structured_output()
+    }
+
+    async fn extract(
+        &self,
+        system: &str,
+        messages: &[Message],
+        schema: &Value,
+    ) -> Result<(Value, ProviderUsage), ProviderError> {
+        self.with_retry(|| self.inner.extract(system, messages, schema))
+            .await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use serial_test::serial;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct ScriptedProvider {
+        results: Mutex<VecDeque<Result<(), ProviderError>>>,
+        calls: AtomicUsize,
+    }
+
+    impl ScriptedProvider {
+        fn new(results: Vec<Result<(), ProviderError>>) -> Arc<Self> {
+            Arc::new(Self {
+                results: Mutex::new(results.into()),
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl Provider for ScriptedProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("mock".to_string())
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let next = self.results.lock().unwrap().pop_front().unwrap_or(Ok(()));
+            next.map(|_| {
+                (
+                    Message::assistant().with_text("done"),
+                    ProviderUsage::new("mock".to_string(), Usage::default()),
+                )
+            })
+        }
+    }
+
+    fn fast_config() -> RetryConfig {
+        RetryConfig {
+            max_retries: 2,
+            initial_interval_ms: 1,
+            backoff_multiplier: 2.0,
+            max_interval_ms: 50,
+            circuit_breaker_threshold: 2,
+            circuit_breaker_cooldown_secs: 60,
+        }
+    }
+
+    async fn call(provider: &RetryProvider) -> Result<(Message, ProviderUsage), ProviderError> {
+        provider.complete("system", &[], &[]).await
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors_until_success() {
+        let inner = ScriptedProvider::new(vec![
+            Err(ProviderError::rate_limited("slow down")),
+            Err(ProviderError::server_error("oops")),
+        ]);
+        let provider = RetryProvider::new("mock", inner.clone(), fast_config());
+
+        assert!(call(&provider).await.is_ok());
+        assert_eq!(inner.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_permanent_errors() {
+        let inner = ScriptedProvider::new(vec![Err(ProviderError::Authentication(
+            "bad key".to_string(),
+        ))]);
+        let provider = RetryProvider::new("mock", inner.clone(), fast_config());
+
+        let err = call(&provider).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Authentication(_)));
+        assert_eq!(inner.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_with_attempt_count() {
+        let inner = ScriptedProvider::new(vec![
+            Err(ProviderError::rate_limited("1")),
+            Err(ProviderError::rate_limited("2")),
+            Err(ProviderError::rate_limited("3")),
+        ]);
+        let provider = RetryProvider::new("mock", inner.clone(), fast_config());
+
+        let err = call(&provider).await.unwrap_err();
+        assert_eq!(inner.calls(), 3);
+        assert_eq!(err.retry_info().unwrap().attempts, 3);
+        assert!(err.to_string().contains("gave up after 3 attempts"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_beyond_max_interval_is_not_waited_for() {
+        let inner =
+            ScriptedProvider::new(vec![Err(ProviderError::rate_limited("later")
+                .with_retry_after(Some(Duration::from_secs(3600))))]);
+        let provider = RetryProvider::new("mock", inner.clone(), fast_config());
+
+        let err = call(&provider).await.unwrap_err();
+        assert_eq!(inner.calls(), 1);
+        assert_eq!(
+            err.retry_info().unwrap().retry_after,
+            Some(Duration::from_secs(3600))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_honoured() {
+        let inner =
+            ScriptedProvider::new(vec![Err(ProviderError::rate_limited("soon")
+                .with_retry_after(Some(Duration::from_millis(30))))]);
+        let provider = RetryProvider::new("mock", inner.clone(), fast_config());
+
+        let start = Instant::now();
+        assert!(call(&provider).await.is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(30));
+        assert_eq!(inner.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_after_repeated_failures() {
+        let failures = (0..6)
+            .map(|_| Err(ProviderError::server_error("down")))
+            .collect();
+        let inner = ScriptedProvider::new(failures);
+        let provider = RetryProvider::new("mock", inner.clone(), fast_config());
+
+        assert!(call(&provider).await.is_err());
+        assert!(call(&provider).await.is_err());
+        assert_eq!(inner.calls(), 6);
+
+        let err = call(&provider).await.unwrap_err();
+        assert!(matches!(err, ProviderError::CircuitOpen { .. }));
+        assert_eq!(inner.calls(), 6);
+
+        // Once the cooldown is over a request is let through again
+        provider.circuit.lock().unwrap().open_until = Some(Instant::now());
+        assert!(call(&provider).await.is_ok());
+        assert_eq!(provider.circuit.lock().unwrap().consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_retry_config_delay_calculation() {
+        let config = RetryConfig {
+            max_retries: 5,
+            initial_interval_ms: 1000,
+            backoff_multiplier: 2.0,
+            max_interval_ms: 32000,
+            ..Default::default()
+        };
+
+        // First attempt has no delay
+        let delay0 = config.delay_for_attempt(0);
+        assert_eq!(delay0.as_millis(), 0);
+
+        // First retry should be around initial_interval with jitter
+        let delay1 = config.delay_for_attempt(1);
+        assert!(delay1.as_millis() >= 800 && delay1.as_millis() <= 1200);
+
+        // Second retry should be around initial_interval * multiplier^1 with jitter
+        let delay2 = config.delay_for_attempt(2);
+        assert!(delay2.as_millis() >= 1600 && delay2.as_millis() <= 2400);
+
+        // Check that max interval is respected
+        let delay10 = config.delay_for_attempt(10);
+        assert!(delay10.as_millis() <= 38400); // max_interval_ms * 1.2 (max jitter)
+    }
+
+    #[test]
+    #[serial]
+    fn test_provider_specific_settings() {
+        std::env::set_var("RETRY_TEST_MAX_RETRIES", "9");
+        std::env::set_var("GOOSE_PROVIDER_CIRCUIT_BREAKER_THRESHOLD", "0");
+
+        let config = RetryConfig::from_config("retry_test");
+        assert_eq!(config.max_retries, 9);
+        assert_eq!(config.circuit_breaker_threshold, 0);
+        assert_eq!(
+            config.initial_interval_ms,
+            DEFAULT_INITIAL_RETRY_INTERVAL_MS
+        );
+
+        std::env::remove_var("RETRY_TEST_MAX_RETRIES");
+        std::env::remove_var("GOOSE_PROVIDER_CIRCUIT_BREAKER_THRESHOLD");
+
+        assert_eq!(RetryConfig::defaults_for("databricks").max_retries, 6);
+        assert_eq!(config_prefix("gcp_vertex_ai"), "GCP");
+    }
+}
@@ -0,0 +1,660 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+use super::base::{Provider, ProviderMetadata, ProviderUsage};
+use super::errors::ProviderError;
+use crate::message::Message;
+use crate::model::ModelConfig;
+use mcp_core::tool::Tool;
+
+/// Config key holding the routing setup, see [`RouterConfig`]
+pub const ROUTER_CONFIG_KEY: &str = "GOOSE_ROUTER";
+
+/// Errors that make the router move on to the next route
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FallbackTrigger {
+    /// Server errors, failed requests and providers whose circuit breaker is open. Other
+    /// execution errors are not outages and are returned as they are.
+    Outage,
+    RateLimit,
+    /// The prompt did not fit; only routes with a larger context limit are tried next
+    ContextLength,
+}
+
+impl FallbackTrigger {
+    fn for_error(error: &ProviderError) -> Option<Self> {
+        match error {
+            ProviderError::ServerError { .. }
+            | ProviderError::CircuitOpen { .. }
+            | ProviderError::RequestFailed(_) => Some(FallbackTrigger::Outage),
+            ProviderError::RateLimitExceeded { .. } => Some(FallbackTrigger::RateLimit),
+            ProviderError::ContextLengthExceeded(_) => Some(FallbackTrigger::ContextLength),
+            _ => None,
+        }
+    }
+}
+
+fn default_fallback_on() -> Vec<FallbackTrigger> {
+    vec![
+        FallbackTrigger::Outage,
+        FallbackTrigger::RateLimit,
+        FallbackTrigger::ContextLength,
+    ]
+}
+
+/// When a route may be used. Unset conditions always match.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RouteRule {
+    /// Only use this route when the estimated prompt is at least this many tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_prompt_tokens: Option<usize>,
+    /// Only use this route when the estimated prompt is at most this many tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_prompt_tokens: Option<usize>,
+    /// Only use this route when tools are (true) or are not (false) offered to the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<bool>,
+}
+
+impl RouteRule {
+    fn matches(&self, prompt_tokens: usize, has_tools: bool) -> bool {
+        self.min_prompt_tokens
+            .is_none_or(|min| prompt_tokens >= min)
+            && self
+                .max_prompt_tokens
+                .is_none_or(|max| prompt_tokens <= max)
+            && self.tools.is_none_or(|tools| tools == has_tools)
+    }
+}
+
+/// One provider/model pair in the routing chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteConfig {
+    pub provider: String,
+    pub model: String,
+    /// Overrides the model's known context limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_limit: Option<usize>,
+    #[serde(default)]
+    pub when: RouteRule,
+}
+
+/// Ordered provider/model pairs, tried from first to last. Set under `GOOSE_ROUTER` in
+/// config.yaml:
+///
+/// ```yaml
+/// GOOSE_ROUTER:
+///   fallback_on: [outage, rate_limit, context_length]
+///   routes:
+///     - provider: groq
+///       model: llama-3.3-70b-versatile
+///       when:
+///         max_prompt_tokens: 8000
+///         tools: false
+///     - provider: anthropic
+///       model: claude-3-5-sonnet-latest
+///     - provider: google
+///       model: gemini-1.5-pro
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouterConfig {
+    pub routes: Vec<RouteConfig>,
+    #[serde(default = "default_fallback_on")]
+    pub fallback_on: Vec<FallbackTrigger>,
+}
+
+/// A provider ready to be routed to
+pub struct Route {
+    pub provider: Arc<dyn Provider>,
+    pub rule: RouteRule,
+    label: String,
+}
+
+impl Route {
+    pub fn new(provider_name: &str, provider: Arc<dyn Provider>, rule: RouteRule) -> Self {
+        let label = format!(
+            "{}/{}",
+            provider_name,
+            provider.get_model_config().model_name
+        );
+        Self {
+            provider,
+            rule,
+            label,
+        }
+    }
+
+    fn context_limit(&self) -> usize {
+        self.provider.get_model_config().context_limit()
+    }
+}
+
+/// A provider that picks from an ordered list of providers by rule, and falls over to the
+/// next one on outages, rate limits or context overflow
+pub struct RouterProvider {
+    routes: Vec<Route>,
+    fallback_on: Vec<FallbackTrigger>,
+}
+
+impl RouterProvider {
+    pub fn new(routes: Vec<Route>, fallback_on: Vec<FallbackTrigger>) -> Result<Self> {
+        if routes.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{} needs at least one route",
+                ROUTER_CONFIG_KEY
+            ));
+        }
+        Ok(Self {
+            routes,
+            fallback_on,
+        })
+    }
+
+    /// Routes whose rules match this request, in configured order. If no rule matches, all
+    /// routes are candidates so a request is never dropped for lack of a rule.
+    fn candidates(&self, system: &str, messages: &[Message], tools: &[Tool]) -> Vec<&Route> {
+        let prompt_tokens = estimate_prompt_tokens(system, messages, tools);
+        let has_tools = !tools.is_empty();
+        let matching: Vec<&Route> = self
+            .routes
+            .iter()
+            .filter(|route| route.rule.matches(prompt_tokens, has_tools))
+            .collect();
+
+        if matching.is_empty() {
+            tracing::debug!(
+                "No route matches a prompt of ~{} tokens (tools: {}), trying all routes",
+                prompt_tokens,
+                has_tools
+            );
+            self.routes.iter().collect()
+        } else {
+            matching
+        }
+    }
+}
+
+/// Rough token estimate, about four characters per token
+fn estimate_prompt_tokens(system: &str, messages: &[Message], tools: &[Tool]) -> usize {
+    let message_chars: usize = messages
+        .iter()
+        .map(|m| serde_json::to_string(m).map(|s| s.len()).unwrap_or(0))
+        .sum();
+    let tool_chars: usize = tools
+        .iter()
+        .map(|t| serde_json::to_string(t).map(|s| s.len()).unwrap_or(0))
+        .sum();
+    (system.len() + message_chars + tool_chars) / 4
+}
+
+#[async_trait]
+impl Provider for RouterProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "router",
+            "Router Provider",
+            "Routes requests across several providers with fallback",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        // The first route is the primary one, but any route may answer, so report the
+        // smallest context limit to keep the conversation within reach of all of them
+        let context_limit = self.routes.iter().map(Route::context_limit).min();
+        self.routes[0]
+            .provider
+            .get_model_config()
+            .with_context_limit(context_limit)
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let candidates = self.candidates(system, messages, tools);
+        let mut last_error = None;
+        let mut min_context = 0;
+
+        for route in candidates {
+            if route.context_limit() <= min_context {
+                tracing::info!(
+                    "Router: skipping {}, its context limit is not larger than the last route's",
+                    route.label
+                );
+                continue;
+            }
+
+            tracing::info!("Router: sending request to {}", route.label);
+            let error = match route.provider.complete(system, messages, tools).await {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+
+            match FallbackTrigger::for_error(&error) {
+                Some(trigger) if self.fallback_on.contains(&trigger) => {
+                    tracing::warn!(
+                        "Router: {} failed ({:?}), falling over to the next route: {}",
+                        route.label,
+                        trigger,
+                        error
+                    );
+                    if trigger == FallbackTrigger::ContextLength {
+                        min_context = route.context_limit();
+                    }
+                    last_error = Some(error);
+                }
+                _ => return Err(error),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            ProviderError::ContextLengthExceeded("No route has a larger context limit".to_string())
+        }))
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.routes[0].provider.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.routes.iter().any(|r| r.provider.supports_embeddings())
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let mut last_error = None;
+        for route in self
+            .routes
+            .iter()
+            .filter(|r| r.provider.supports_embeddings())
+        {
+            match route.provider.create_embeddings(texts.clone()).await {
+                Ok(embeddings) => return Ok(embeddings),
+                Err(error) => {
+                    tracing::warn!("Router: embeddings failed on {}: {}", route.label, error);
+                    last_error = Some(error);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            ProviderError::ExecutionError("No route supports embeddings".to_string())
+        }))
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        self.routes
+            .iter()
+            .any(|r| r.provider.supports_structured_output())
+    }
+
+    async fn extract(
+        &self,
+        system: &str,
+        messages: &[Message],
+        schema: &Value,
+    ) -> Result<(Value, ProviderUsage), ProviderError> {
+        let mut last_error = None;
+        for route in self
+            .candidates(system, messages, &[])
+            .into_iter()
+            .filter(|r| r.provider.supports_structured_output())
+        {
+            tracing::info!("Router: sending extraction to {}", route.label);
+            let error = match route.provider.extract(system, messages, schema).await {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+
+            match FallbackTrigger::for_error(&error) {
+                Some(trigger) if self.fallback_on.contains(&trigger) => {
+                    tracing::warn!(
+                        "Router: extraction failed on {} ({:?}), falling over to the next route: {}",
+                        route.label,
+                        trigger,
+                        error
+                    );
+                    last_error = Some(error);
+                }
+                _ => return Err(error),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            ProviderError::ExecutionError("No route supports structured output".to_string())
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct MockProvider {
+        model: ModelConfig,
+        error: Option<fn() -> ProviderError>,
+        calls: AtomicUsize,
+        structured: bool,
+    }
+
+    impl MockProvider {
+        fn new(
+            model: &str,
+            context_limit: usize,
+            error: Option<fn() -> ProviderError>,
+        ) -> Arc<Self> {
+            Arc::new(Self {
+                model: ModelConfig::new(model.to_string()).with_context_limit(Some(context_limit)),
+                error,
+                calls: AtomicUsize::new(0),
+                structured: false,
+            })
+        }
+
+        fn structured(model: &str, error: Option<fn() -> ProviderError>) -> Arc<Self> {
+            Arc::new(Self {
+                model: ModelConfig::new(model.to_string()),
+                error,
+                calls: AtomicUsize::new(0),
+                structured: true,
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl Provider for MockProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            self.model.clone()
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.error {
+                Some(error) => Err(error()),
+                None => Ok((
+                    Message::assistant().with_text(&self.model.model_name),
+                    ProviderUsage::new(self.model.model_name.clone(), Usage::default()),
+                )),
+            }
+        }
+
+        fn supports_structured_output(&self) -> bool {
+            self.structured
+        }
+
+        async fn extract(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _schema: &Value,
+        ) -> Result<(Value, ProviderUsage), ProviderError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.error {
+                Some(error) => Err(error()),
+                None => Ok((
+                    json!({"model": self.model.model_name}),
+                    ProviderUsage::new(self.model.model_name.clone(), Usage::default()),
+                )),
+            }
+        }
+    }
+
+    fn route(provider: Arc<MockProvider>, rule: RouteRule) -> Route {
+        Route::new("mock", provider, rule)
+    }
+
+    fn answered_by(result: Result<(Message, ProviderUsage), ProviderError>) -> String {
+        result.unwrap().1.model
+    }
+
+    #[tokio::test]
+    async fn test_falls_over_on_outage_and_rate_limit() {
+        let down = MockProvider::new("down", 100_000, Some(|| ProviderError::server_error("503")));
+        let limited = MockProvider::new(
+            "limited",
+            100_000,
+            Some(|| ProviderError::rate_limited("429")),
+        );
+        let unreachable = MockProvider::new(
+            "unreachable",
+            100_000,
+            Some(|| ProviderError::RequestFailed("connection refused".to_string())),
+        );
+        let healthy = MockProvider::new("healthy", 100_000, None);
+        let router = RouterProvider::new(
+            vec![
+                route(down.clone(), RouteRule::default()),
+                route(limited.clone(), RouteRule::default()),
+                route(unreachable.clone(), RouteRule::default()),
+                route(healthy.clone(), RouteRule::default()),
+            ],
+            default_fallback_on(),
+        )
+        .unwrap();
+
+        assert_eq!(answered_by(router.complete("", &[], &[]).await), "healthy");
+        assert_eq!(
+            (
+                down.calls(),
+                limited.calls(),
+                unreachable.calls(),
+                healthy.calls()
+            ),
+            (1, 1, 1, 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_does_not_fall_over_on_other_errors() {
+        let bad_key = MockProvider::new(
+            "bad_key",
+            100_000,
+            Some(|| ProviderError::Authentication("invalid".to_string())),
+        );
+        let healthy = MockProvider::new("healthy", 100_000, None);
+        let router = RouterProvider::new(
+            vec![
+                route(bad_key, RouteRule::default()),
+                route(healthy.clone(), RouteRule::default()),
+            ],
+            default_fallback_on(),
+        )
+        .unwrap();
+
+        let err = router.complete("", &[], &[]).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Authentication(_)));
+        assert_eq!(healthy.calls(), 0);
+
+        let broken = MockProvider::new(
+            "broken",
+            100_000,
+            Some(|| ProviderError::ExecutionError("bad tool schema".to_string())),
+        );
+        let router = RouterProvider::new(
+            vec![
+                route(broken, RouteRule::default()),
+                route(healthy.clone(), RouteRule::default()),
+            ],
+            default_fallback_on(),
+        )
+        .unwrap();
+
+        let err = router.complete("", &[], &[]).await.unwrap_err();
+        assert!(matches!(err, ProviderError::ExecutionError(_)));
+        assert_eq!(healthy.calls(), 0);
+    }
+
+    #[test]
+    fn test_model_config_reports_smallest_context_limit() {
+        let router = RouterProvider::new(
+            vec![
+                route(
+                    MockProvider::new("large", 1_000_000, None),
+                    RouteRule::default(),
+                ),
+                route(
+                    MockProvider::new("small", 32_000, None),
+                    RouteRule::default(),
+                ),
+            ],
+            default_fallback_on(),
+        )
+        .unwrap();
+
+        let config = router.get_model_config();
+        assert_eq!(config.model_name, "large");
+        assert_eq!(config.context_limit(), 32_000);
+    }
+
+    #[tokio::test]
+    async fn test_context_overflow_moves_to_larger_context() {
+        let overflow = || ProviderError::ContextLengthExceeded("too long".to_string());
+        let small = MockProvider::new("small", 32_000, Some(overflow));
+        let same = MockProvider::new("same", 32_000, None);
+        let large = MockProvider::new("large", 1_000_000, None);
+        let router = RouterProvider::new(
+            vec![
+                route(small, RouteRule::default()),
+                route(same.clone(), RouteRule::default()),
+                route(large, RouteRule::default()),
+            ],
+            default_fallback_on(),
+        )
+        .unwrap();
+
+        assert_eq!(answered_by(router.complete("", &[], &[]).await), "large");
+        assert_eq!(same.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_trigger_returns_error() {
+        let limited = MockProvider::new(
+            "limited",
+            100_000,
+            Some(|| ProviderError::rate_limited("429")),
+        );
+        let healthy = MockProvider::new("healthy", 100_000, None);
+        let router = RouterProvider::new(
+            vec![
+                route(limited, RouteRule::default()),
+                route(healthy, RouteRule::default()),
+            ],
+            vec![FallbackTrigger::Outage],
+        )
+        .unwrap();
+
+        let err = router.complete("", &[], &[]).await.unwrap_err();
+        assert!(matches!(err, ProviderError::RateLimitExceeded { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_rules_by_prompt_size_and_tools() {
+        let small_chat = MockProvider::new("small_chat", 8_000, None);
+        let tools = MockProvider::new("tools", 200_000, None);
+        let router = RouterProvider::new(
+            vec![
+                route(
+                    small_chat,
+                    RouteRule {
+                        max_prompt_tokens: Some(1_000),
+                        tools: Some(false),
+                        ..Default::default()
+                    },
+                ),
+                route(tools, RouteRule::default()),
+            ],
+            default_fallback_on(),
+        )
+        .unwrap();
+
+        let short = vec![Message::user().with_text("hi")];
+        let long = vec![Message::user().with_text("word ".repeat(2_000))];
+        let tool = Tool::new("echo", "Echo", json!({"type": "object"}), None);
+
+        assert_eq!(
+            answered_by(router.complete("", &short, &[]).await),
+            "small_chat"
+        );
+        assert_eq!(answered_by(router.complete("", &long, &[]).await), "tools");
+        assert_eq!(
+            answered_by(router.complete("", &short, &[tool]).await),
+            "tools"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extract_uses_routes_with_structured_output() {
+        let plain = MockProvider::new("plain", 100_000, None);
+        let down = MockProvider::structured("down", Some(|| ProviderError::server_error("503")));
+        let structured = MockProvider::structured("structured", None);
+        let router = RouterProvider::new(
+            vec![
+                route(plain.clone(), RouteRule::default()),
+                route(down.clone(), RouteRule::default()),
+                route(structured.clone(), RouteRule::default()),
+            ],
+            default_fallback_on(),
+        )
+        .unwrap();
+
+        assert!(router.supports_structured_output());
+        let messages = vec![Message::user().with_text("hi")];
+        let (value, usage) = router
+            .extract("", &messages, &json!({"type": "object"}))
+            .await
+            .unwrap();
+        assert_eq!(value, json!({"model": "structured"}));
+        assert_eq!(usage.model, "structured");
+        assert_eq!(plain.calls(), 0);
+        assert_eq!(down.calls(), 1);
+    }
+
+    #[test]
+    fn test_router_config_from_yaml() {
+        let config: RouterConfig = serde_yaml::from_str(
+            r#"
+routes:
+  - provider: groq
+    model: llama-3.3-70b-versatile
+    when:
+      max_prompt_tokens: 8000
+      tools: false
+  - provider: anthropic
+    model: claude-3-5-sonnet-latest
+    context_limit: 200000
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.routes.len(), 2);
+        assert_eq!(config.routes[0].when.max_prompt_tokens, Some(8000));
+        assert_eq!(config.routes[0].when.tools, Some(false));
+        assert_eq!(config.routes[1].when, RouteRule::default());
+        assert_eq!(config.routes[1].context_limit, Some(200_000));
+        assert_eq!(config.fallback_on, default_fallback_on());
+    }
+}
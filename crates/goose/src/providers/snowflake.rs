@@ -8,7 +8,7 @@ use std::time::Duration;
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
 use super::errors::ProviderError;
 use super::formats::snowflake::{create_request, get_usage, response_to_message};
-use super::utils::{get_model, ImageFormat};
+use super::utils::{get_model, retry_after, ImageFormat};
 use crate::config::ConfigError;
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -133,6 +133,7 @@ impl SnowflakeProvider {
             .await?;
 
         let status = response.status();
+        let retry_delay = retry_after(response.headers());
 
         let payload_text: String = response.text().await.ok().unwrap_or_default();
 
@@ -369,14 +370,15 @@ impl SnowflakeProvider {
                     error_msg
                 )))
             }
-            StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::RateLimitExceeded(
-                "Rate limit exceeded. Please try again later.".to_string(),
-            )),
+            StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::rate_limited(
+                "Rate limit exceeded. Please try again later.",
+            )
+            .with_retry_after(retry_delay)),
             StatusCode::INTERNAL_SERVER_ERROR | StatusCode::SERVICE_UNAVAILABLE => {
-                Err(ProviderError::ServerError(
-                    "Snowflake service is temporarily unavailable. Please try again later."
-                        .to_string(),
-                ))
+                Err(ProviderError::server_error(
+                    "Snowflake service is temporarily unavailable. Please try again later.",
+                )
+                .with_retry_after(retry_delay))
             }
             _ => {
                 tracing::debug!(
@@ -4,11 +4,13 @@ use crate::model::ModelConfig;
 use anyhow::Result;
 use base64::Engine;
 use regex::Regex;
+use reqwest::header::HeaderMap;
 use reqwest::{Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::{from_value, json, Map, Value};
 use std::io::Read;
 use std::path::Path;
+use std::time::Duration;
 
 use crate::providers::errors::{OpenAIError, ProviderError};
 use mcp_core::content::ImageContent;
@@ -44,11 +46,36 @@ pub fn convert_image(image: &ImageContent, image_format: &ImageFormat) -> Value
     }
 }
 
+/// Read how long the server asked us to wait before retrying. Supports the millisecond
+/// `retry-after-ms` header sent by OpenAI and the standard `Retry-After` header in either
+/// its seconds or HTTP date form.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+    };
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        return Some(Duration::from_millis(ms.max(0.0) as u64));
+    }
+
+    let value = header("retry-after")?;
+    if let Ok(secs) = value.parse::<f64>() {
+        return Some(Duration::from_millis((secs.max(0.0) * 1000.0) as u64));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
 /// Handle response from OpenAI compatible endpoints
 /// Error codes: https://platform.openai.com/docs/guides/error-codes
 /// Context window exceeded: https://community.openai.com/t/help-needed-tackling-context-length-limits-in-openai-models/617543
 pub async fn handle_response_openai_compat(response: Response) -> Result<Value, ProviderError> {
     let status = response.status();
+    let retry_delay = retry_after(response.headers());
     // Try to parse the response body as JSON (if applicable)
     let payload = match response.json::<Value>().await {
         Ok(json) => json,
@@ -75,10 +102,10 @@ pub async fn handle_response_openai_compat(response: Response) -> Result<Value,
             Err(ProviderError::RequestFailed(format!("Unknown error (status {})", status)))
         }
         StatusCode::TOO_MANY_REQUESTS => {
-            Err(ProviderError::RateLimitExceeded(format!("{:?}", payload)))
+            Err(ProviderError::rate_limited(format!("{:?}", payload)).with_retry_after(retry_delay))
         }
         StatusCode::INTERNAL_SERVER_ERROR | StatusCode::SERVICE_UNAVAILABLE => {
-            Err(ProviderError::ServerError(format!("{:?}", payload)))
+            Err(ProviderError::server_error(format!("{:?}", payload)).with_retry_after(retry_delay))
         }
         _ => {
             tracing::debug!(
@@ -140,6 +167,7 @@ fn get_google_final_status(status: StatusCode, payload: Option<&Value>) -> Statu
 /// - `Err(ProviderError)`: Describes the failure reason.
 pub async fn handle_response_google_compat(response: Response) -> Result<Value, ProviderError> {
     let status = response.status();
+    let retry_delay = retry_after(response.headers());
     let payload: Option<Value> = response.json().await.ok();
     let final_status = get_google_final_status(status, payload.as_ref());
 
@@ -166,10 +194,10 @@ pub async fn handle_response_google_compat(response: Response) -> Result<Value,
             Err(ProviderError::RequestFailed(format!("Request failed with status: {}. Message: {}", final_status, error_msg)))
         }
         StatusCode::TOO_MANY_REQUESTS => {
-            Err(ProviderError::RateLimitExceeded(format!("{:?}", payload)))
+            Err(ProviderError::rate_limited(format!("{:?}", payload)).with_retry_after(retry_delay))
         }
         StatusCode::INTERNAL_SERVER_ERROR | StatusCode::SERVICE_UNAVAILABLE => {
-            Err(ProviderError::ServerError(format!("{:?}", payload)))
+            Err(ProviderError::server_error(format!("{:?}", payload)).with_retry_after(retry_delay))
         }
         _ => {
             tracing::debug!(
@@ -338,6 +366,7 @@ pub fn emit_debug_trace(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use reqwest::header::HeaderValue;
     use serde_json::json;
 
     #[test]
@@ -560,4 +589,26 @@ mod tests {
             assert_eq!(result, expected_status);
         }
     }
+
+    #[test]
+    fn test_retry_after_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+        headers.insert("retry-after-ms", HeaderValue::from_static("250"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(250)));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "retry-after",
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert("retry-after", HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers), None);
+    }
 }
@@ -1,3 +1,5 @@
+use super::errors::ProviderError;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 
@@ -241,13 +243,63 @@ impl OAIStreamCollector {
         }
     }
 }
+
+/// Reads a `text/event-stream` chat completion to the end and returns it as a regular
+/// (non-streamed) chat completion body
+pub async fn collect_stream(
+    response: reqwest::Response,
+) -> Result<serde_json::Value, ProviderError> {
+    // Errors come back as a plain JSON body rather than a stream
+    if !response.status().is_success() {
+        return super::utils::handle_response_openai_compat(response).await;
+    }
+
+    let mut collector = OAIStreamCollector::new();
+    let mut stream = response.bytes_stream();
+    let mut buffer: Vec<u8> = Vec::new();
+    'stream: while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
+        buffer.extend_from_slice(&chunk);
+        // Events and even characters can be split across network chunks, so only decode
+        // complete lines
+        while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            if collect_line(&line, &mut collector)? {
+                break 'stream;
+            }
+        }
+    }
+    collect_line(&buffer, &mut collector)?;
+
+    serde_json::to_value(collector.build_response())
+        .map_err(|e| ProviderError::RequestFailed(e.to_string()))
+}
+
+/// Adds one line of the event stream to `collector`, returning whether it ends the stream
+fn collect_line(line: &[u8], collector: &mut OAIStreamCollector) -> Result<bool, ProviderError> {
+    let line = std::str::from_utf8(line)
+        .map_err(|e| ProviderError::RequestFailed(format!("Invalid UTF-8 in stream: {}", e)))?;
+    let Some(payload) = line.trim().strip_prefix("data: ") else {
+        return Ok(false);
+    };
+    if payload == "[DONE]" {
+        return Ok(true);
+    }
+    let chunk = serde_json::from_str::<OAIStreamChunk>(payload).map_err(|e| {
+        tracing::warn!("Unreadable stream chunk {}: {}", payload, e);
+        ProviderError::RequestFailed(format!("Invalid chunk in stream: {}", e))
+    })?;
+    collector.add_chunk(&chunk);
+    Ok(false)
+}
+
 fn null_to_empty_string<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    use serde::Deserialize;
     Ok(Option::<String>::deserialize(deserializer)?.unwrap_or_default())
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -348,6 +400,39 @@ data: [DONE]
         );
         assert_eq!(choice.finish_reason, "stop");
     }
+
+    #[tokio::test]
+    async fn test_collect_stream() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200)
+                    .set_body_raw(TEXT_STREAM.trim_start(), "text/event-stream"),
+            )
+            .mount(&server)
+            .await;
+        let response = reqwest::Client::new()
+            .post(server.uri())
+            .send()
+            .await
+            .unwrap();
+
+        let body = collect_stream(response).await.unwrap();
+        assert_eq!(
+            body["choices"][0]["message"]["content"],
+            "Hello! How can I assist you today? 🌍"
+        );
+    }
+
+    #[test]
+    fn test_collect_line_reports_bad_chunks() {
+        let mut collector = OAIStreamCollector::new();
+        assert!(!collect_line(b": keep-alive\n", &mut collector).unwrap());
+        assert!(collect_line(b"data: [DONE]\n", &mut collector).unwrap());
+        assert!(collect_line(b"data: {\"choices\": 1}\n", &mut collector).is_err());
+        assert!(collect_line(b"data: \xff\n", &mut collector).is_err());
+    }
+
     const CLAUDE_STREAM: &str = r#"
 data: {"choices":[{"index":0,"delta":{"content":"I","role":"assistant"}}],"created":1747613682,"id":"938bb8e2-6276-4a58-bca3-c675cfe7f2f5","model":"claude-3.5-sonnet"}
 data: {"choices":[{"index":0,"delta":{"content":"'ll","role":"assistant"}}],"created":1747613682,"id":"938bb8e2-6276-4a58-bca3-c675cfe7f2f5","model":"claude-3.5-sonnet"}
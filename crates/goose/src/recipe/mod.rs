@@ -1,7 +1,11 @@
 use std::fmt;
 
 use crate::agents::extension::ExtensionConfig;
+use crate::context_mgmt::truncate::TruncationStrategyKind;
+use crate::model::ReasoningConfig;
+use crate::permission::ApprovalPolicy;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 fn default_version() -> String {
     "1.0.0".to_string()
@@ -25,6 +29,10 @@ fn default_version() -> String {
 /// * `activities` - Activity labels that appear when loading the Recipe
 /// * `author` - Information about the Recipe's creator and metadata
 /// * `parameters` - Additional parameters for the Recipe
+/// * `truncation_strategy` - How the conversation is truncated when it outgrows the context window
+/// * `output_schema` - JSON schema the final result of a run must match
+/// * `reasoning` - Reasoning effort and thinking budget for the model
+/// * `approval_policy` - Rules answering tool confirmations in unattended runs; the allow rules only apply once the user approves them
 ///
 /// # Example
 ///
@@ -51,6 +59,10 @@ fn default_version() -> String {
 ///     activities: None,
 ///     author: None,
 ///     parameters: None,
+///     truncation_strategy: None,
+///     output_schema: None,
+///     reasoning: None,
+///     approval_policy: None,
 /// };
 ///
 #[derive(Serialize, Deserialize, Debug)]
@@ -85,6 +97,18 @@ pub struct Recipe {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<Vec<RecipeParameter>>, // any additional parameters for the recipe
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncation_strategy: Option<TruncationStrategyKind>, // overrides GOOSE_TRUNCATION_STRATEGY
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>, // JSON schema for the structured result of a run
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<ReasoningConfig>, // overrides GOOSE_REASONING_EFFORT and GOOSE_THINKING_BUDGET
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_policy: Option<ApprovalPolicy>, // answers tool confirmations instead of the user
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -159,6 +183,10 @@ pub struct RecipeBuilder {
     activities: Option<Vec<String>>,
     author: Option<Author>,
     parameters: Option<Vec<RecipeParameter>>,
+    truncation_strategy: Option<TruncationStrategyKind>,
+    output_schema: Option<Value>,
+    reasoning: Option<ReasoningConfig>,
+    approval_policy: Option<ApprovalPolicy>,
 }
 
 impl Recipe {
@@ -188,6 +216,10 @@ impl Recipe {
             activities: None,
             author: None,
             parameters: None,
+            truncation_strategy: None,
+            output_schema: None,
+            reasoning: None,
+            approval_policy: None,
         }
     }
 }
@@ -252,6 +284,30 @@ impl RecipeBuilder {
         self
     }
 
+    /// Sets the truncation strategy for the Recipe
+    pub fn truncation_strategy(mut self, strategy: TruncationStrategyKind) -> Self {
+        self.truncation_strategy = Some(strategy);
+        self
+    }
+
+    /// Sets the JSON schema the final result of the Recipe must match
+    pub fn output_schema(mut self, schema: Value) -> Self {
+        self.output_schema = Some(schema);
+        self
+    }
+
+    /// Sets the reasoning effort and thinking budget for the Recipe
+    pub fn reasoning(mut self, reasoning: ReasoningConfig) -> Self {
+        self.reasoning = Some(reasoning);
+        self
+    }
+
+    /// Sets the approval policy answering tool confirmations for the Recipe
+    pub fn approval_policy(mut self, policy: ApprovalPolicy) -> Self {
+        self.approval_policy = Some(policy);
+        self
+    }
+
     /// Builds the Recipe instance
     ///
     /// Returns an error if any required fields are missing
@@ -274,6 +330,10 @@ impl RecipeBuilder {
             activities: self.activities,
             author: self.author,
             parameters: self.parameters,
+            truncation_strategy: self.truncation_strategy,
+            output_schema: self.output_schema,
+            reasoning: self.reasoning,
+            approval_policy: self.approval_policy,
         })
     }
 }
@@ -3,6 +3,7 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
@@ -14,13 +15,14 @@ use tokio_cron_scheduler::{job::JobId, Job, JobScheduler as TokioJobScheduler};
 
 use crate::agents::AgentEvent;
 use crate::agents::{Agent, SessionConfig};
-use crate::config::{self, Config};
+use crate::config::{self, project_config_for, Config};
 use crate::message::Message;
 use crate::providers::base::Provider as GooseProvider; // Alias to avoid conflict in test section
 use crate::providers::create;
 use crate::recipe::Recipe;
 use crate::scheduler_trait::SchedulerTrait;
 use crate::session;
+use crate::session::retention::{cleanup_sessions, RetentionConfig};
 use crate::session::storage::SessionMetadata;
 
 // Track running tasks with their abort handles
@@ -162,6 +164,7 @@ impl Scheduler {
         });
 
         arc_self.load_jobs_from_storage().await?;
+        arc_self.add_cleanup_job().await?;
         arc_self
             .internal_scheduler
             .start()
@@ -171,6 +174,37 @@ impl Scheduler {
         Ok(arc_self)
     }
 
+    /// Apply the session retention policy every `cleanup_interval_hours`, if that is set
+    async fn add_cleanup_job(&self) -> Result<(), SchedulerError> {
+        let Some(hours) = RetentionConfig::from_config().cleanup_interval_hours else {
+            return Ok(());
+        };
+        let interval = Duration::from_secs(hours.max(1) * 60 * 60);
+        let cleanup_task = Job::new_repeated_async(interval, |_uuid, _l| {
+            Box::pin(async move {
+                // Read the policy again, it may have changed since the scheduler started
+                let config = RetentionConfig::from_config();
+                match tokio::task::spawn_blocking(move || cleanup_sessions(&config, false)).await {
+                    Ok(Ok(report)) => tracing::info!(
+                        "Session cleanup removed {} sessions, compacted {} and freed {} bytes",
+                        report.removed.len(),
+                        report.compacted.len(),
+                        report.bytes_freed()
+                    ),
+                    Ok(Err(e)) => tracing::warn!("Session cleanup failed: {:?}", e),
+                    Err(e) => tracing::warn!("Session cleanup task failed: {}", e),
+                }
+            })
+        })
+        .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+
+        self.internal_scheduler
+            .add(cleanup_task)
+            .await
+            .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        Ok(())
+    }
+
     pub async fn add_scheduled_job(
         &self,
         original_job_spec: ScheduledJob,
@@ -572,37 +606,18 @@ impl Scheduler {
         sched_id: &str,
         limit: usize,
     ) -> Result<Vec<(String, SessionMetadata)>, SchedulerError> {
-        // Changed return type
-        let all_session_files = session::storage::list_sessions()
-            .map_err(|e| SchedulerError::StorageError(io::Error::other(e)))?;
-
-        let mut schedule_sessions: Vec<(String, SessionMetadata)> = Vec::new();
-
-        for (session_name, session_path) in all_session_files {
-            match session::storage::read_metadata(&session_path) {
-                Ok(metadata) => {
-                    // metadata is not mutable here, and SessionMetadata is original
-                    if metadata.schedule_id.as_deref() == Some(sched_id) {
-                        schedule_sessions.push((session_name, metadata)); // Keep the tuple
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!(
-                        "Failed to read metadata for session file {}: {}. Skipping.",
-                        session_path.display(),
-                        e
-                    );
-                }
-            }
-        }
-
-        schedule_sessions.sort_by(|a, b| b.0.cmp(&a.0)); // Sort by session_name (timestamp string)
-
-        // Keep the tuple, just take the limit
-        let result_sessions: Vec<(String, SessionMetadata)> =
-            schedule_sessions.into_iter().take(limit).collect();
+        let page = session::query_sessions(&session::SessionQuery {
+            schedule_id: Some(sched_id.to_string()),
+            limit: Some(limit),
+            ..Default::default()
+        })
+        .map_err(|e| SchedulerError::StorageError(io::Error::other(e)))?;
 
-        Ok(result_sessions) // Return the Vec of tuples
+        Ok(page
+            .sessions
+            .into_iter()
+            .map(|info| (info.id, info.metadata))
+            .collect())
     }
 
     pub async fn run_now(&self, sched_id: &str) -> Result<String, SchedulerError> {
@@ -1041,7 +1056,8 @@ async fn run_scheduled_job_internal(
                             .to_string(),
                 }),
             };
-        let model_config = crate::model::ModelConfig::new(model_name.clone());
+        let model_config = crate::model::ModelConfig::from_config(model_name.clone())
+            .with_reasoning(recipe.reasoning.clone());
         agent_provider = create(&provider_name, model_config).map_err(|e| JobExecutionError {
             job_id: job.id.clone(),
             error: format!(
@@ -1051,6 +1067,20 @@ async fn run_scheduled_job_internal(
         })?;
     }
 
+    if let Some(strategy) = recipe.truncation_strategy {
+        agent.set_truncation_strategy(strategy).await;
+    }
+    agent.set_output_schema(recipe.output_schema.clone()).await;
+    // Nobody is there to approve a recipe's allow rules, so only its deny rules apply
+    agent
+        .set_approval_policy(
+            recipe
+                .approval_policy
+                .as_ref()
+                .map(|policy| policy.deny_only()),
+        )
+        .await;
+
     if let Err(e) = agent.update_provider(agent_provider).await {
         return Err(JobExecutionError {
             job_id: job.id.clone(),
@@ -1090,6 +1120,7 @@ async fn run_scheduled_job_internal(
         let session_config = SessionConfig {
             id: crate::session::storage::Identifier::Name(session_id_for_return.clone()),
             working_dir: current_dir.clone(),
+            project_config: project_config_for(&current_dir),
             schedule_id: Some(job.id.clone()),
         };
 
@@ -1114,6 +1145,21 @@ async fn run_scheduled_job_internal(
                         Ok(AgentEvent::McpNotification(_)) => {
                             // Handle notifications if needed
                         }
+                        Ok(AgentEvent::ContextCompacted(compaction)) => {
+                            tracing::info!(
+                                "[Job {}] Context compacted from {} to {} tokens",
+                                job.id,
+                                compaction.tokens_before,
+                                compaction.tokens_after
+                            );
+                            all_session_messages = compaction.messages;
+                        }
+                        Ok(AgentEvent::ProviderError(_)) => {
+                            // The previous message describes the error
+                        }
+                        Ok(AgentEvent::ToolDenied(_)) => {
+                            // The tool response that follows says so
+                        }
                         Err(e) => {
                             tracing::error!(
                                 "[Job {}] Error receiving message from agent: {}",
@@ -1125,6 +1171,10 @@ async fn run_scheduled_job_internal(
                     }
                 }
 
+                if let Some(output) = agent.final_output().await {
+                    tracing::info!("[Job {}] Final output: {}", job.id, output);
+                }
+
                 match crate::session::storage::read_metadata(&session_file_path) {
                     Ok(mut updated_metadata) => {
                         updated_metadata.message_count = all_session_messages.len();
@@ -1157,6 +1207,8 @@ async fn run_scheduled_job_internal(
                             accumulated_total_tokens: None,
                             accumulated_input_tokens: None,
                             accumulated_output_tokens: None,
+                            parent_session_id: None,
+                            branch_point: None,
                         };
                         if let Err(e_fb) = crate::session::storage::save_messages_with_metadata(
                             &session_file_path,
@@ -1300,6 +1352,10 @@ mod tests {
             activities: None,
             author: None,
             parameters: None,
+            truncation_strategy: None,
+            output_schema: None,
+            reasoning: None,
+            approval_policy: None,
         };
         let mut recipe_file = File::create(&recipe_filename)?;
         writeln!(
@@ -0,0 +1,347 @@
+//! Sessions packaged up to be moved to another machine
+//!
+//! A bundle holds everything in the session file, plus optionally the recipe the session was
+//! run with and the extensions it used. Extension secrets are never written to a bundle: the
+//! values of their environment variables are dropped and their names kept, so whoever imports
+//! the bundle is asked for their own.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::agents::extension::Envs;
+use crate::agents::ExtensionConfig;
+use crate::config::{Config, ExtensionConfigManager, ExtensionEntry};
+use crate::message::Message;
+use crate::recipe::Recipe;
+use crate::redaction;
+use crate::session::storage::{
+    read_messages_with_truncation, read_metadata, save_messages_with_metadata, unused_session_id,
+    SessionMetadata,
+};
+
+/// Version of the bundle format written by this build
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// A session with everything needed to pick it up somewhere else
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionBundle {
+    pub version: u32,
+    pub session_id: String,
+    pub metadata: SessionMetadata,
+    pub messages: Vec<Message>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipe: Option<Recipe>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<ExtensionConfig>,
+    /// The session file the bundle was read from, when it is on this machine
+    #[serde(skip)]
+    source: Option<PathBuf>,
+}
+
+impl SessionBundle {
+    /// Everything in a session file, without truncating any message
+    pub fn from_session(session_file: &Path) -> Result<Self> {
+        if !session_file.exists() {
+            bail!("Session file not found: {}", session_file.display());
+        }
+        let session_id = session_file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("Invalid session file {}", session_file.display()))?;
+
+        Ok(Self {
+            version: BUNDLE_VERSION,
+            session_id,
+            metadata: read_metadata(session_file)?,
+            messages: read_messages_with_truncation(session_file, None)?,
+            recipe: None,
+            extensions: Vec::new(),
+            source: Some(session_file.to_path_buf()),
+        })
+    }
+
+    /// Include a recipe, without the secrets of the extensions it lists
+    pub fn with_recipe(mut self, mut recipe: Recipe) -> Self {
+        if let Some(extensions) = recipe.extensions.as_mut() {
+            *extensions = extensions.iter().map(strip_secrets).collect();
+        }
+        self.recipe = Some(recipe);
+        self
+    }
+
+    /// Include extensions, without their secrets
+    ///
+    /// Frontend extensions are left out, since their tools only exist in the app that
+    /// registered them.
+    pub fn with_extensions<'a>(
+        mut self,
+        extensions: impl IntoIterator<Item = &'a ExtensionConfig>,
+    ) -> Self {
+        self.extensions = extensions
+            .into_iter()
+            .filter(|config| !matches!(config, ExtensionConfig::Frontend { .. }))
+            .map(strip_secrets)
+            .collect();
+        self
+    }
+
+    /// Read a bundle or a JSON export, or a session file as a bundle without extensions
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        let bundle = match serde_json::from_str::<Self>(&contents) {
+            Ok(bundle) => bundle,
+            Err(e) if path.extension().is_some_and(|ext| ext == "jsonl") => {
+                tracing::debug!("Not a bundle, reading as a session file: {}", e);
+                Self::from_session(path)?
+            }
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("{} is not a session bundle", path.display()))
+            }
+        };
+        if bundle.version > BUNDLE_VERSION {
+            bail!(
+                "The bundle was written by a newer version of goose (format {}, this build reads up to {})",
+                bundle.version,
+                BUNDLE_VERSION
+            );
+        }
+        Ok(bundle)
+    }
+
+    /// Write the session into `dir` as a new session running in `working_dir`
+    ///
+    /// The session keeps its ID unless one with that ID already exists. Returns the ID and
+    /// path of the new session.
+    pub fn import_into(&self, dir: &Path, working_dir: PathBuf) -> Result<(String, PathBuf)> {
+        // The ID names the session file, so it can't be allowed to point outside `dir`
+        if !is_file_stem(&self.session_id) {
+            bail!("The bundle has an invalid session ID {:?}", self.session_id);
+        }
+
+        let mut metadata = self.metadata.clone();
+        metadata.working_dir = working_dir;
+        metadata.message_count = self.messages.len();
+        // The schedule that started it doesn't exist on this machine
+        metadata.schedule_id = None;
+
+        let id = unused_session_id(dir, &self.session_id);
+        let path = dir.join(format!("{}.jsonl", id));
+        // The values behind redacted secrets never leave the machine, so only a session from
+        // this one brings its placeholders along
+        match &self.source {
+            Some(source) => redaction::copy_session(source, &path),
+            None => redaction::forget_session(&path),
+        }
+        save_messages_with_metadata(&path, &metadata, &self.messages)?;
+        Ok((id, path))
+    }
+
+    /// The bundled extensions that aren't configured yet
+    pub fn new_extensions(&self) -> Result<Vec<&ExtensionConfig>> {
+        let configured: HashSet<String> = ExtensionConfigManager::get_all()?
+            .iter()
+            .map(|entry| entry.config.key())
+            .collect();
+        Ok(self
+            .extensions
+            .iter()
+            .filter(|config| !configured.contains(&config.key()))
+            .collect())
+    }
+
+    /// Add the bundled extensions that aren't configured yet, disabled
+    ///
+    /// They run commands from the bundle, so the user turns them on after checking them.
+    /// Extensions that are already configured are left as they are. Returns the names of the
+    /// extensions that were added.
+    pub fn restore_extensions(&self) -> Result<Vec<String>> {
+        let mut added = Vec::new();
+        for config in self.new_extensions()? {
+            ExtensionConfigManager::set(ExtensionEntry {
+                enabled: false,
+                config: config.clone(),
+            })?;
+            added.push(config.name());
+        }
+        Ok(added)
+    }
+
+    /// Secrets the bundled extensions need that aren't set on this machine
+    pub fn missing_secrets(&self) -> Vec<String> {
+        let config = Config::global();
+        let is_set = |key: &String| matches!(config.get(key, true), Ok(value) if !value.is_null());
+        let mut missing: Vec<String> = self
+            .extensions
+            .iter()
+            .flat_map(|extension| match extension {
+                ExtensionConfig::Sse { env_keys, .. } => env_keys.clone(),
+                ExtensionConfig::Stdio { env_keys, .. } => env_keys.clone(),
+                _ => Vec::new(),
+            })
+            .filter(|key| !is_set(key))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+}
+
+/// Whether `id` is a plain file name, with no separators or parent directories in it
+fn is_file_stem(id: &str) -> bool {
+    !id.is_empty() && !id.contains("..") && !id.contains(['/', '\\', ':', '\0'])
+}
+
+/// The extensions enabled in the config
+pub fn enabled_extensions() -> Result<Vec<ExtensionConfig>> {
+    Ok(ExtensionConfigManager::get_all()?
+        .into_iter()
+        .filter(|entry| entry.enabled)
+        .map(|entry| entry.config)
+        .collect())
+}
+
+/// A copy of an extension's config that keeps the names of its environment variables but
+/// not their values
+///
+/// The names move to `env_keys`, so the extension reads them from the secret store instead.
+pub fn strip_secrets(config: &ExtensionConfig) -> ExtensionConfig {
+    let mut config = config.clone();
+    match &mut config {
+        ExtensionConfig::Sse { envs, env_keys, .. }
+        | ExtensionConfig::Stdio { envs, env_keys, .. } => {
+            let mut names: Vec<String> = envs.get_env().into_keys().collect();
+            names.sort();
+            for name in names {
+                if !env_keys.contains(&name) {
+                    env_keys.push(name);
+                }
+            }
+            *envs = Envs::default();
+        }
+        ExtensionConfig::Builtin { .. } | ExtensionConfig::Frontend { .. } => {}
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    fn stdio_extension() -> ExtensionConfig {
+        ExtensionConfig::Stdio {
+            name: "github".to_string(),
+            cmd: "github-mcp".to_string(),
+            args: vec!["--stdio".to_string()],
+            envs: Envs::new(HashMap::from([
+                ("GITHUB_TOKEN".to_string(), "ghp_secret".to_string()),
+                ("GITHUB_HOST".to_string(), "github.com".to_string()),
+            ])),
+            env_keys: vec!["GITHUB_TOKEN".to_string()],
+            timeout: Some(300),
+            description: None,
+            bundled: None,
+        }
+    }
+
+    #[test]
+    fn test_strip_secrets() {
+        match strip_secrets(&stdio_extension()) {
+            ExtensionConfig::Stdio { envs, env_keys, .. } => {
+                assert!(envs.get_env().is_empty());
+                assert_eq!(env_keys, vec!["GITHUB_TOKEN", "GITHUB_HOST"]);
+            }
+            other => panic!("Expected a stdio extension, got {:?}", other),
+        }
+
+        let bundle = SessionBundle {
+            version: BUNDLE_VERSION,
+            session_id: "s".to_string(),
+            metadata: SessionMetadata::default(),
+            messages: Vec::new(),
+            recipe: None,
+            extensions: Vec::new(),
+            source: None,
+        }
+        .with_extensions(&[stdio_extension(), ExtensionConfig::default()]);
+        let json = serde_json::to_string(&bundle).unwrap();
+        assert!(!json.contains("ghp_secret"));
+        assert_eq!(bundle.extensions.len(), 2);
+    }
+
+    #[test]
+    fn test_export_and_import() -> Result<()> {
+        let source = tempdir()?;
+        let session_file = source.path().join("handoff.jsonl");
+        let mut metadata = SessionMetadata::new(source.path().to_path_buf());
+        metadata.description = "Fix the flaky test".to_string();
+        metadata.schedule_id = Some("nightly".to_string());
+        let long_output = "x".repeat(60_000);
+        let messages = vec![
+            Message::user().with_text("Why is the test flaky?"),
+            Message::assistant().with_thinking("Look at the timing", "sig"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(mcp_core::tool::ToolCall::new(
+                    "developer__shell",
+                    serde_json::json!({"command": "cargo test"}),
+                )),
+            ),
+            Message::user()
+                .with_tool_response("1", Ok(vec![mcp_core::Content::text(long_output.clone())])),
+        ];
+        save_messages_with_metadata(&session_file, &metadata, &messages)?;
+
+        let bundle_path = source.path().join("handoff.json");
+        let bundle = SessionBundle::from_session(&session_file)?;
+        fs::write(&bundle_path, serde_json::to_string(&bundle)?)?;
+
+        let target = tempdir()?;
+        let working_dir = target.path().join("checkout");
+        let loaded = SessionBundle::load(&bundle_path)?;
+        let (id, path) = loaded.import_into(target.path(), working_dir.clone())?;
+        assert_eq!(id, "handoff");
+
+        let imported = read_metadata(&path)?;
+        assert_eq!(imported.description, "Fix the flaky test");
+        assert_eq!(imported.schedule_id, None);
+        // Nothing is lost on the way, not even output the session reader would truncate
+        assert_eq!(read_messages_with_truncation(&path, None)?, messages);
+
+        // Importing again doesn't overwrite the first copy
+        let (second_id, _) = loaded.import_into(target.path(), working_dir)?;
+        assert_eq!(second_id, "handoff_2");
+
+        // Session files can be imported directly
+        let from_jsonl = SessionBundle::load(&session_file)?;
+        assert_eq!(from_jsonl.messages.len(), messages.len());
+
+        // A session ID from the bundle never escapes the session directory
+        for id in [
+            "../../.bashrc",
+            "/etc/passwd",
+            "..",
+            "a/../b",
+            "C:\\evil",
+            "",
+        ] {
+            let mut hostile = SessionBundle::load(&session_file)?;
+            hostile.session_id = id.to_string();
+            assert!(hostile.import_into(target.path(), PathBuf::new()).is_err());
+        }
+
+        let mut newer = serde_json::to_value(&bundle)?;
+        newer["version"] = serde_json::json!(BUNDLE_VERSION + 1);
+        fs::write(&bundle_path, newer.to_string())?;
+        assert!(SessionBundle::load(&bundle_path).is_err());
+        Ok(())
+    }
+}
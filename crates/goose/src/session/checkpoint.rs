@@ -0,0 +1,595 @@
+//! Working tree checkpoints taken at the start of every agent turn.
+//!
+//! Snapshots live in a shadow git repository under the goose data directory, keyed by the
+//! working directory. The shadow repository uses the working directory as its work tree, so
+//! `.gitignore` rules are honoured, but it has its own object store, index and refs; the
+//! user's repository, index and branches are never touched. Each session keeps a chain of
+//! commits under `refs/goose/sessions/<session id>`, one per turn.
+//!
+//! The first checkpoint of a working directory copies the whole tree. After that, a turn whose
+//! files changed since the last checkpoint add up to more than the size limit gets no
+//! checkpoint, so data or dependencies that aren't ignored aren't copied every turn.
+
+use crate::message::Message;
+use crate::session::storage::{self, Identifier};
+use anyhow::{anyhow, Context, Result};
+use etcetera::{choose_app_strategy, AppStrategy, AppStrategyArgs};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Default limit, in megabytes, on the new or changed files a single checkpoint copies
+pub const DEFAULT_MAX_CHECKPOINT_MB: u64 = 100;
+
+const CHECKPOINT_SUBJECT: &str = "goose checkpoint";
+const MESSAGE_COUNT_TRAILER: &str = "Goose-Message-Count:";
+
+/// A snapshot of the working tree taken before a user turn was processed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Commit id of the snapshot in the shadow repository
+    pub commit: String,
+    /// Number of messages in the conversation before the turn started
+    pub message_count: usize,
+    /// Unix timestamp the snapshot was taken at
+    pub created_at: i64,
+}
+
+/// Checkpoints for a single session in a single working directory
+#[derive(Debug, Clone)]
+pub struct CheckpointStore {
+    git_dir: PathBuf,
+    work_tree: PathBuf,
+    session_ref: String,
+    rewound_ref: String,
+    max_bytes: u64,
+}
+
+impl CheckpointStore {
+    /// Create a store backed by the shadow repository in the goose data directory
+    pub fn new(session_id: &str, working_dir: &Path) -> Result<Self> {
+        Self::with_root(&checkpoints_root(), session_id, working_dir)
+    }
+
+    /// Create a store whose shadow repositories live under `root`
+    pub fn with_root(root: &Path, session_id: &str, working_dir: &Path) -> Result<Self> {
+        let work_tree = working_dir
+            .canonicalize()
+            .with_context(|| format!("Working directory {} not found", working_dir.display()))?;
+        let key = blake3::hash(work_tree.to_string_lossy().as_bytes()).to_hex();
+        let session = sanitize_ref_component(session_id);
+
+        Ok(Self {
+            git_dir: root.join(&key.as_str()[..16]),
+            work_tree,
+            session_ref: format!("refs/goose/sessions/{}", session),
+            rewound_ref: format!("refs/goose/rewound/{}", session),
+            max_bytes: DEFAULT_MAX_CHECKPOINT_MB * 1024 * 1024,
+        })
+    }
+
+    /// Skip checkpoints whose new or changed files add up to more than `max_bytes`
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Whether checkpoints make sense for this working directory. Snapshotting a home
+    /// directory or the filesystem root would copy far more than a project.
+    pub fn is_supported(&self) -> bool {
+        if self.work_tree.parent().is_none() {
+            return false;
+        }
+        match etcetera::home_dir()
+            .ok()
+            .and_then(|h| h.canonicalize().ok())
+        {
+            Some(home) => home != self.work_tree,
+            None => true,
+        }
+    }
+
+    async fn git<I, S>(&self, args: I) -> Result<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let output = Command::new("git")
+            .args([
+                "-c",
+                "core.autocrlf=false",
+                "-c",
+                "core.safecrlf=false",
+                "-c",
+                "advice.addEmbeddedRepo=false",
+                "-c",
+                "user.name=goose",
+                "-c",
+                "user.email=goose@localhost",
+            ])
+            .args(args)
+            .env("GIT_DIR", &self.git_dir)
+            .env("GIT_WORK_TREE", &self.work_tree)
+            .env_remove("GIT_INDEX_FILE")
+            .env_remove("GIT_OBJECT_DIRECTORY")
+            .env_remove("GIT_ALTERNATE_OBJECT_DIRECTORIES")
+            .current_dir(&self.work_tree)
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .context("Failed to run git; checkpoints require git on the PATH")?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "git failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    async fn ensure_repo(&self) -> Result<()> {
+        if self.git_dir.join("HEAD").exists() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.git_dir)?;
+        self.git(["init", "--quiet"]).await?;
+        Ok(())
+    }
+
+    async fn resolve(&self, reference: &str) -> Result<Option<String>> {
+        match self
+            .git(["rev-parse", "--verify", "--quiet", reference])
+            .await
+        {
+            Ok(commit) if !commit.is_empty() => Ok(Some(commit)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Size of the files that aren't ignored and are new or changed since the last snapshot
+    async fn pending_bytes(&self) -> Result<u64> {
+        let files = self
+            .git([
+                "ls-files",
+                "-z",
+                "--others",
+                "--modified",
+                "--exclude-standard",
+            ])
+            .await?;
+        Ok(files
+            .split('\0')
+            .filter(|file| !file.is_empty())
+            .filter_map(|file| std::fs::symlink_metadata(self.work_tree.join(file)).ok())
+            .map(|metadata| metadata.len())
+            .sum())
+    }
+
+    /// Snapshot the working tree into the shadow index and commit it on top of `parent`.
+    /// With `limited`, the snapshot is refused when the files changed since the last one
+    /// are over the size limit.
+    async fn snapshot(
+        &self,
+        parent: Option<&str>,
+        message_count: usize,
+        limited: bool,
+    ) -> Result<String> {
+        self.ensure_repo().await?;
+        // Without an index there is nothing to compare against yet, and the whole tree
+        // would count as changed on every turn, so the first snapshot is always taken
+        if limited && self.git_dir.join("index").exists() {
+            let pending = self.pending_bytes().await?;
+            if pending > self.max_bytes {
+                return Err(anyhow!(
+                    "{} MB of new or changed files is over the {} MB checkpoint limit",
+                    pending / (1024 * 1024),
+                    self.max_bytes / (1024 * 1024)
+                ));
+            }
+        }
+        self.git(["add", "--all", "."]).await?;
+        let tree = self.git(["write-tree"]).await?;
+
+        let message = format!(
+            "{}\n\n{} {}",
+            CHECKPOINT_SUBJECT, MESSAGE_COUNT_TRAILER, message_count
+        );
+        let mut args = vec!["commit-tree".to_string(), tree, "-m".to_string(), message];
+        if let Some(parent) = parent {
+            args.push("-p".to_string());
+            args.push(parent.to_string());
+        }
+        self.git(args).await
+    }
+
+    /// Record a checkpoint of the current working tree for a turn starting after
+    /// `message_count` messages
+    pub async fn create(&self, message_count: usize) -> Result<Checkpoint> {
+        let parent = self.resolve(&self.session_ref).await?;
+        let commit = self
+            .snapshot(parent.as_deref(), message_count, true)
+            .await?;
+        self.git(["update-ref", self.session_ref.as_str(), commit.as_str()])
+            .await?;
+        Ok(Checkpoint {
+            commit,
+            message_count,
+            created_at: chrono::Utc::now().timestamp(),
+        })
+    }
+
+    /// All checkpoints for the session, most recent first
+    pub async fn list(&self) -> Result<Vec<Checkpoint>> {
+        if !self.git_dir.join("HEAD").exists() || self.resolve(&self.session_ref).await?.is_none() {
+            return Ok(Vec::new());
+        }
+
+        let log = self
+            .git([
+                "log",
+                "--first-parent",
+                "--format=%H%x1f%ct%x1f%B%x1e",
+                self.session_ref.as_str(),
+            ])
+            .await?;
+
+        Ok(log
+            .split('\x1e')
+            .filter_map(|entry| {
+                let mut fields = entry.trim().splitn(3, '\x1f');
+                let commit = fields.next()?.to_string();
+                let created_at = fields.next()?.parse().ok()?;
+                let message_count = fields.next()?.lines().find_map(|line| {
+                    line.strip_prefix(MESSAGE_COUNT_TRAILER)
+                        .and_then(|count| count.trim().parse().ok())
+                })?;
+                Some(Checkpoint {
+                    commit,
+                    message_count,
+                    created_at,
+                })
+            })
+            .collect())
+    }
+
+    /// Undo the last `turns` turns: restore the working tree to the checkpoint taken before
+    /// the earliest of them and drop the checkpoints that follow it.
+    ///
+    /// The state of the working tree just before the rewind is kept under
+    /// `refs/goose/rewound/<session id>` so a rewind can itself be recovered from.
+    pub async fn rewind(&self, turns: usize) -> Result<Checkpoint> {
+        if turns == 0 {
+            return Err(anyhow!("Number of turns to rewind must be at least 1"));
+        }
+        let checkpoints = self.list().await?;
+        if checkpoints.is_empty() {
+            return Err(anyhow!(
+                "No checkpoints recorded for this session; set GOOSE_CHECKPOINTS=true to \
+                 snapshot the working directory every turn"
+            ));
+        }
+        let target = checkpoints.get(turns - 1).cloned().ok_or_else(|| {
+            anyhow!(
+                "Cannot rewind {} turn(s); only {} checkpoint(s) recorded for this session",
+                turns,
+                checkpoints.len()
+            )
+        })?;
+
+        // Refresh the shadow index to the current working tree so that read-tree can tell
+        // which files were created after the checkpoint and remove them. This runs whatever
+        // the size of the changes, since large changes are when a rewind matters most.
+        let head = self.resolve(&self.session_ref).await?;
+        let current = self
+            .snapshot(head.as_deref(), checkpoints[0].message_count, false)
+            .await?;
+        self.git(["update-ref", self.rewound_ref.as_str(), current.as_str()])
+            .await?;
+
+        self.git(["read-tree", "-u", "--reset", target.commit.as_str()])
+            .await?;
+
+        match self.resolve(&format!("{}^", target.commit)).await? {
+            Some(parent) => {
+                self.git(["update-ref", self.session_ref.as_str(), parent.as_str()])
+                    .await?;
+            }
+            None => {
+                self.git(["update-ref", "-d", self.session_ref.as_str()])
+                    .await?;
+            }
+        }
+
+        Ok(target)
+    }
+}
+
+/// Where the shadow repositories live, under the goose data directory
+fn checkpoints_root() -> PathBuf {
+    let app_strategy = AppStrategyArgs {
+        top_level_domain: "Block".to_string(),
+        author: "Block".to_string(),
+        app_name: "goose".to_string(),
+    };
+    choose_app_strategy(app_strategy)
+        .expect("goose requires a home dir")
+        .data_dir()
+        .join("checkpoints")
+}
+
+/// Drop the checkpoints of a deleted session, in whichever working directories it had them
+pub fn remove_session_checkpoints(session_id: &str) -> Result<()> {
+    remove_session_checkpoints_in(&checkpoints_root(), session_id)
+}
+
+/// Drop the checkpoints of a deleted session from the shadow repositories under `root`.
+///
+/// Snapshots that only the session used are pruned once they are an hour old, so a checkpoint
+/// another session is taking in the same repository at the time is left alone.
+pub fn remove_session_checkpoints_in(root: &Path, session_id: &str) -> Result<()> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Ok(());
+    };
+    let session = sanitize_ref_component(session_id);
+    let session_refs = [
+        format!("refs/goose/sessions/{}", session),
+        format!("refs/goose/rewound/{}", session),
+    ];
+
+    for entry in entries {
+        let git_dir = entry?.path();
+        if !git_dir.join("HEAD").is_file() {
+            continue;
+        }
+        let git = |args: &[&str]| -> Result<String> {
+            let output = std::process::Command::new("git")
+                .args(args)
+                .env("GIT_DIR", &git_dir)
+                .env_remove("GIT_WORK_TREE")
+                .env_remove("GIT_INDEX_FILE")
+                .env_remove("GIT_OBJECT_DIRECTORY")
+                .env_remove("GIT_ALTERNATE_OBJECT_DIRECTORIES")
+                .stdin(Stdio::null())
+                .output()
+                .context("Failed to run git; checkpoints require git on the PATH")?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "git {} failed: {}",
+                    args.join(" "),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        };
+
+        let mut args = vec!["for-each-ref", "--format=%(refname)"];
+        args.extend(session_refs.iter().map(String::as_str));
+        let existing = git(&args)?;
+        if existing.is_empty() {
+            continue;
+        }
+        for reference in existing.lines() {
+            git(&["update-ref", "-d", reference])?;
+        }
+        git(&["prune", "--expire=1.hour.ago"])?;
+    }
+    Ok(())
+}
+
+fn sanitize_ref_component(id: &str) -> String {
+    let sanitized: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if sanitized.is_empty() {
+        "session".to_string()
+    } else {
+        sanitized
+    }
+}
+
+/// The name a session is stored under, used to key its checkpoints
+pub fn session_id(id: &Identifier) -> String {
+    match id {
+        Identifier::Name(name) => name.clone(),
+        Identifier::Path(path) => path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Rewind a stored session by `turns` turns, restoring files in its working directory and
+/// trimming the conversation on disk. Returns the remaining messages.
+pub async fn rewind_session(session_file: &Path, turns: usize) -> Result<Vec<Message>> {
+    let mut metadata = storage::read_metadata(session_file)?;
+    let session_id = session_id(&Identifier::Path(session_file.to_path_buf()));
+    let store = CheckpointStore::new(&session_id, &metadata.working_dir)?;
+    let checkpoint = store.rewind(turns).await?;
+
+    let mut messages = storage::read_messages(session_file)?;
+    messages.truncate(checkpoint.message_count);
+    metadata.message_count = messages.len();
+    storage::save_messages_with_metadata(session_file, &metadata, &messages)?;
+
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_rewind_restores_working_tree() -> Result<()> {
+        let root = tempdir()?;
+        let work = tempdir()?;
+        let store = CheckpointStore::with_root(root.path(), "20250101_000000", work.path())?;
+
+        fs::write(work.path().join("a.txt"), "one")?;
+        store.create(0).await?;
+
+        // Turn 1 edits a file and adds another
+        fs::write(work.path().join("a.txt"), "two")?;
+        fs::create_dir(work.path().join("src"))?;
+        fs::write(work.path().join("src/b.txt"), "new")?;
+        store.create(2).await?;
+
+        // Turn 2 deletes the original file
+        fs::remove_file(work.path().join("a.txt"))?;
+
+        let checkpoints = store.list().await?;
+        assert_eq!(checkpoints.len(), 2);
+        assert_eq!(checkpoints[0].message_count, 2);
+        assert_eq!(checkpoints[1].message_count, 0);
+
+        let target = store.rewind(1).await?;
+        assert_eq!(target.message_count, 2);
+        assert_eq!(fs::read_to_string(work.path().join("a.txt"))?, "two");
+        assert!(work.path().join("src/b.txt").exists());
+
+        let target = store.rewind(1).await?;
+        assert_eq!(target.message_count, 0);
+        assert_eq!(fs::read_to_string(work.path().join("a.txt"))?, "one");
+        assert!(!work.path().join("src/b.txt").exists());
+        assert!(store.list().await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rewind_respects_gitignore_and_user_repo() -> Result<()> {
+        let root = tempdir()?;
+        let work = tempdir()?;
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(args)
+                .current_dir(work.path())
+                .output()
+        };
+        git(&["init", "--quiet"])?;
+        let head_before = fs::read_to_string(work.path().join(".git/HEAD"))?;
+
+        fs::write(work.path().join(".gitignore"), "build/\n")?;
+        fs::create_dir(work.path().join("build"))?;
+        fs::write(work.path().join("build/out.bin"), "artifact")?;
+        fs::write(work.path().join("main.rs"), "fn main() {}")?;
+
+        let store = CheckpointStore::with_root(root.path(), "session", work.path())?;
+        store.create(0).await?;
+        fs::write(work.path().join("main.rs"), "fn main() { todo!() }")?;
+        fs::remove_file(work.path().join("build/out.bin"))?;
+
+        store.rewind(1).await?;
+        assert_eq!(
+            fs::read_to_string(work.path().join("main.rs"))?,
+            "fn main() {}"
+        );
+        // Ignored files are neither snapshotted nor restored
+        assert!(!work.path().join("build/out.bin").exists());
+        // The user's repository is left alone
+        assert!(!work.path().join(".git/index").exists());
+        assert_eq!(
+            fs::read_to_string(work.path().join(".git/HEAD"))?,
+            head_before
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_removing_a_session_drops_only_its_checkpoints() -> Result<()> {
+        let root = tempdir()?;
+        let work = tempdir()?;
+        let removed = CheckpointStore::with_root(root.path(), "removed", work.path())?;
+        let kept = CheckpointStore::with_root(root.path(), "kept", work.path())?;
+
+        fs::write(work.path().join("a.txt"), "one")?;
+        removed.create(0).await?;
+        kept.create(0).await?;
+        fs::write(work.path().join("a.txt"), "two")?;
+        removed.create(2).await?;
+        removed.rewind(1).await?;
+
+        remove_session_checkpoints_in(root.path(), "removed")?;
+        assert!(removed.list().await?.is_empty());
+        assert!(removed.resolve(&removed.rewound_ref).await?.is_none());
+        assert_eq!(kept.list().await?.len(), 1);
+
+        // Nothing is left to remove the second time
+        remove_session_checkpoints_in(root.path(), "removed")?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_large_changes_are_not_checkpointed() -> Result<()> {
+        let root = tempdir()?;
+        let work = tempdir()?;
+        let store =
+            CheckpointStore::with_root(root.path(), "session", work.path())?.with_max_bytes(1024);
+
+        fs::write(work.path().join("small.txt"), "small")?;
+        store.create(0).await?;
+
+        // Only what changed since the last checkpoint counts towards the limit
+        fs::write(work.path().join("data.bin"), vec![0u8; 2048])?;
+        assert!(store.create(2).await.is_err());
+        assert_eq!(store.list().await?.len(), 1);
+
+        fs::remove_file(work.path().join("data.bin"))?;
+        fs::write(work.path().join("small.txt"), "still small")?;
+        store.create(4).await?;
+        assert_eq!(store.list().await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_large_first_checkpoint_and_rewind_ignore_limit() -> Result<()> {
+        let root = tempdir()?;
+        let work = tempdir()?;
+        let store =
+            CheckpointStore::with_root(root.path(), "session", work.path())?.with_max_bytes(1024);
+
+        // The first checkpoint is the baseline for later ones, whatever its size
+        fs::write(work.path().join("data.bin"), vec![0u8; 2048])?;
+        store.create(0).await?;
+
+        fs::write(work.path().join("small.txt"), "small")?;
+        store.create(2).await?;
+        assert_eq!(store.list().await?.len(), 2);
+
+        // A turn that changed more than the limit can still be rewound
+        fs::write(work.path().join("data.bin"), vec![1u8; 4096])?;
+        store.rewind(1).await?;
+        assert_eq!(fs::read(work.path().join("data.bin"))?, vec![0u8; 2048]);
+        assert!(work.path().join("small.txt").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rewind_too_far() -> Result<()> {
+        let root = tempdir()?;
+        let work = tempdir()?;
+        let store = CheckpointStore::with_root(root.path(), "session", work.path())?;
+        assert!(store.rewind(1).await.is_err());
+
+        store.create(0).await?;
+        assert!(store.rewind(0).await.is_err());
+        assert!(store.rewind(2).await.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sanitize_ref_component() {
+        assert_eq!(sanitize_ref_component("20250101_120000"), "20250101_120000");
+        assert_eq!(sanitize_ref_component("../my session"), "___my_session");
+        assert_eq!(sanitize_ref_component(""), "session");
+    }
+}
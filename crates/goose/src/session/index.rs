@@ -0,0 +1,678 @@
+//! SQLite index over the session files.
+//!
+//! Session files stay the source of truth and the format other tools read, but listing and
+//! filtering sessions goes through this index so it doesn't have to open every file. Files that
+//! were written before the index existed, or changed behind its back, are imported the next time
+//! the index is synced.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use mcp_core::role::Role;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::message::{Message, MessageContent};
+use crate::session::info::{SessionInfo, SortOrder};
+use crate::session::storage::{self, SessionMetadata};
+
+/// Name of the index database inside the session directory
+pub const INDEX_FILE_NAME: &str = "sessions.db";
+
+const SCHEMA_VERSION: i32 = 2;
+
+const SCHEMA: &str = "
+CREATE TABLE sessions (
+    id TEXT PRIMARY KEY,
+    path TEXT NOT NULL,
+    modified INTEGER NOT NULL,
+    working_dir TEXT NOT NULL,
+    description TEXT NOT NULL,
+    schedule_id TEXT,
+    message_count INTEGER NOT NULL,
+    metadata TEXT NOT NULL
+);
+CREATE INDEX sessions_modified ON sessions (modified);
+CREATE INDEX sessions_working_dir ON sessions (working_dir);
+CREATE INDEX sessions_schedule_id ON sessions (schedule_id);
+CREATE TABLE messages (
+    id INTEGER PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    role TEXT NOT NULL,
+    created INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    text_hash TEXT NOT NULL,
+    message TEXT NOT NULL,
+    UNIQUE (session_id, position)
+);
+CREATE VIRTUAL TABLE messages_fts USING fts5 (text, content = 'messages', content_rowid = 'id');
+CREATE TRIGGER messages_fts_insert AFTER INSERT ON messages BEGIN
+    INSERT INTO messages_fts (rowid, text) VALUES (new.id, new.text);
+END;
+CREATE TRIGGER messages_fts_delete AFTER DELETE ON messages BEGIN
+    INSERT INTO messages_fts (messages_fts, rowid, text) VALUES ('delete', old.id, old.text);
+END;
+CREATE TABLE embeddings (
+    text_hash TEXT NOT NULL,
+    model TEXT NOT NULL,
+    vector BLOB NOT NULL,
+    PRIMARY KEY (text_hash, model)
+);
+";
+
+const DROP_SCHEMA: &str = "
+DROP TABLE IF EXISTS embeddings;
+DROP TABLE IF EXISTS messages_fts;
+DROP TABLE IF EXISTS messages;
+DROP TABLE IF EXISTS sessions;
+";
+
+/// Filters for listing sessions. Every filter is optional and they are combined with AND.
+#[derive(Debug, Clone, Default)]
+pub struct SessionQuery {
+    /// Only sessions last modified at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only sessions last modified before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Only sessions started in this working directory
+    pub working_dir: Option<PathBuf>,
+    /// Only sessions created by this schedule
+    pub schedule_id: Option<String>,
+    /// Only sessions whose description contains this text, ignoring case
+    pub description: Option<String>,
+    /// Sort by modification time
+    pub order: SortOrder,
+    /// Maximum number of sessions to return
+    pub limit: Option<usize>,
+    /// Number of matching sessions to skip
+    pub offset: usize,
+}
+
+/// One page of sessions matching a [`SessionQuery`]
+#[derive(Clone, Serialize)]
+pub struct SessionPage {
+    pub sessions: Vec<SessionInfo>,
+    /// Number of sessions matching the filters, ignoring the limit and offset
+    pub total: usize,
+}
+
+pub struct SessionIndex {
+    pub(super) conn: Connection,
+    session_dir: PathBuf,
+}
+
+impl SessionIndex {
+    /// Open the index in the default session directory
+    pub fn open() -> Result<Self> {
+        Self::open_in(&storage::ensure_session_dir()?)
+    }
+
+    /// Open the index for the sessions in `session_dir`, creating it if needed
+    pub fn open_in(session_dir: &Path) -> Result<Self> {
+        let conn = Connection::open(session_dir.join(INDEX_FILE_NAME))
+            .context("Failed to open the session index")?;
+        // The CLI, the server and scheduled jobs can all write sessions at the same time
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+
+        // Everything in the index can be read back from the session files, so an index from
+        // another version is rebuilt rather than migrated
+        let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            conn.execute_batch(&format!("BEGIN; {DROP_SCHEMA} {SCHEMA} COMMIT;"))?;
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+
+        Ok(Self {
+            conn,
+            session_dir: session_dir.to_path_buf(),
+        })
+    }
+
+    /// Bring the index up to date with the session files on disk. Files that are new or were
+    /// modified since they were indexed are imported and sessions whose file is gone are
+    /// dropped. Returns the number of files imported.
+    pub fn sync(&mut self) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+
+        let indexed: HashMap<String, i64> = {
+            let mut stmt = tx.prepare("SELECT id, modified FROM sessions")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_, _>>()?
+        };
+
+        let mut seen = HashSet::new();
+        let mut imported = 0;
+        for entry in fs::read_dir(&self.session_dir)?.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "jsonl") {
+                continue;
+            }
+            let Some(id) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                continue;
+            };
+            let modified = match file_modified(&path) {
+                Ok(modified) => modified,
+                Err(e) => {
+                    tracing::warn!("Failed to read {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            seen.insert(id.clone());
+            if indexed.get(&id) == Some(&modified) {
+                continue;
+            }
+
+            let metadata = match storage::read_metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    tracing::warn!("Skipping session file {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            // Still index the metadata of a session with unreadable messages, so it is listed
+            // and not retried on every sync
+            let messages =
+                storage::read_messages_with_truncation(&path, None).unwrap_or_else(|e| {
+                    tracing::warn!("Failed to read messages from {}: {}", path.display(), e);
+                    Vec::new()
+                });
+            write_session(&tx, &id, &path, modified, &metadata, &messages)?;
+            imported += 1;
+        }
+
+        for id in indexed.keys().filter(|id| !seen.contains(*id)) {
+            delete_session(&tx, id)?;
+        }
+
+        tx.commit()?;
+        Ok(imported)
+    }
+
+    /// Record a session file that was just written
+    pub fn record(
+        &mut self,
+        session_file: &Path,
+        metadata: &SessionMetadata,
+        messages: &[Message],
+    ) -> Result<()> {
+        let id = session_id(session_file)?;
+        let modified = file_modified(session_file)?;
+        let tx = self.conn.transaction()?;
+        write_session(&tx, &id, session_file, modified, metadata, messages)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record a session file that was just written with `added` after the first `unchanged`
+    /// messages it was last recorded with. Only the added messages are indexed, unless the
+    /// index doesn't have the others, in which case the whole file is.
+    pub(crate) fn record_added(
+        &mut self,
+        session_file: &Path,
+        metadata: &SessionMetadata,
+        unchanged: usize,
+        added: &[Message],
+    ) -> Result<()> {
+        let id = session_id(session_file)?;
+        let modified = file_modified(session_file)?;
+        let tx = self.conn.transaction()?;
+        let indexed: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM messages WHERE session_id = ?1 AND position < ?2",
+            params![id, unchanged as i64],
+            |row| row.get(0),
+        )?;
+        if indexed as usize == unchanged {
+            write_metadata(&tx, &id, session_file, modified, metadata)?;
+            write_messages(&tx, &id, unchanged, added)?;
+        } else {
+            let messages = storage::read_messages_with_truncation(session_file, None)?;
+            write_session(&tx, &id, session_file, modified, metadata, &messages)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Drop a session from the index. The session file is left alone.
+    pub fn remove(&self, id: &str) -> Result<()> {
+        delete_session(&self.conn, id)
+    }
+
+    /// List the sessions matching `query`
+    pub fn query(&self, query: &SessionQuery) -> Result<SessionPage> {
+        let mut clauses = Vec::new();
+        let mut values = Vec::new();
+        if let Some(since) = query.since {
+            clauses.push("modified >= ?");
+            values.push(Value::Integer(since.timestamp_millis()));
+        }
+        if let Some(until) = query.until {
+            clauses.push("modified < ?");
+            values.push(Value::Integer(until.timestamp_millis()));
+        }
+        if let Some(working_dir) = &query.working_dir {
+            clauses.push("working_dir = ?");
+            values.push(Value::Text(working_dir.to_string_lossy().to_string()));
+        }
+        if let Some(schedule_id) = &query.schedule_id {
+            clauses.push("schedule_id = ?");
+            values.push(Value::Text(schedule_id.clone()));
+        }
+        if let Some(description) = &query.description {
+            clauses.push("instr(lower(description), lower(?)) > 0");
+            values.push(Value::Text(description.clone()));
+        }
+        let filter = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", clauses.join(" AND "))
+        };
+
+        let total: i64 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM sessions{}", filter),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let order = match query.order {
+            SortOrder::Ascending => "ASC",
+            SortOrder::Descending => "DESC",
+        };
+        // A negative limit means no limit in SQLite
+        values.push(Value::Integer(query.limit.map_or(-1, |limit| limit as i64)));
+        values.push(Value::Integer(query.offset as i64));
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, path, modified, metadata FROM sessions{} \
+             ORDER BY modified {order}, id {order} LIMIT ? OFFSET ?",
+            filter
+        ))?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+
+        let mut sessions = Vec::new();
+        for row in rows {
+            let (id, path, modified, metadata) = row?;
+            sessions.push(SessionInfo {
+                id,
+                path,
+                modified: format_modified(modified),
+                metadata: serde_json::from_str(&metadata)?,
+            });
+        }
+
+        Ok(SessionPage {
+            sessions,
+            total: total as usize,
+        })
+    }
+
+    /// Metadata of an indexed session
+    pub fn metadata(&self, id: &str) -> Result<Option<SessionMetadata>> {
+        let metadata: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT metadata FROM sessions WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        metadata
+            .map(|metadata| serde_json::from_str(&metadata).map_err(Into::into))
+            .transpose()
+    }
+
+    /// Messages of an indexed session, in order
+    pub fn messages(&self, id: &str) -> Result<Vec<Message>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT message FROM messages WHERE session_id = ?1 ORDER BY position")?;
+        let rows = stmt.query_map(params![id], |row| row.get::<_, String>(0))?;
+        let mut messages = Vec::new();
+        for row in rows {
+            messages.push(serde_json::from_str(&row?)?);
+        }
+        Ok(messages)
+    }
+
+    /// Write an indexed session in the session file format: metadata on the first line,
+    /// followed by one message per line
+    pub fn export_jsonl(&self, id: &str, mut writer: impl Write) -> Result<()> {
+        let metadata = self
+            .metadata(id)?
+            .ok_or_else(|| anyhow!("Session '{}' not found", id))?;
+        serde_json::to_writer(&mut writer, &metadata)?;
+        writeln!(writer)?;
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT message FROM messages WHERE session_id = ?1 ORDER BY position")?;
+        let rows = stmt.query_map(params![id], |row| row.get::<_, String>(0))?;
+        for row in rows {
+            writeln!(writer, "{}", row?)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Query the sessions in the default session directory, syncing the index first
+pub fn query_sessions(query: &SessionQuery) -> Result<SessionPage> {
+    let mut index = SessionIndex::open()?;
+    index.sync()?;
+    index.query(query)
+}
+
+/// Record a session file written by [`storage::save_messages_with_metadata`], which kept its
+/// first `unchanged` messages and wrote `added` after them. Files outside the session directory
+/// aren't listed, so they aren't indexed either.
+pub(crate) fn record_saved(
+    session_file: &Path,
+    metadata: &SessionMetadata,
+    unchanged: usize,
+    added: &[Message],
+) -> Result<()> {
+    let session_dir = storage::ensure_session_dir()?;
+    if session_file.parent() != Some(session_dir.as_path()) {
+        return Ok(());
+    }
+    SessionIndex::open_in(&session_dir)?.record_added(session_file, metadata, unchanged, added)
+}
+
+fn write_session(
+    conn: &Connection,
+    id: &str,
+    path: &Path,
+    modified: i64,
+    metadata: &SessionMetadata,
+    messages: &[Message],
+) -> Result<()> {
+    write_metadata(conn, id, path, modified, metadata)?;
+    write_messages(conn, id, 0, messages)
+}
+
+fn write_metadata(
+    conn: &Connection,
+    id: &str,
+    path: &Path,
+    modified: i64,
+    metadata: &SessionMetadata,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO sessions
+             (id, path, modified, working_dir, description, schedule_id, message_count, metadata)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT (id) DO UPDATE SET
+             path = excluded.path,
+             modified = excluded.modified,
+             working_dir = excluded.working_dir,
+             description = excluded.description,
+             schedule_id = excluded.schedule_id,
+             message_count = excluded.message_count,
+             metadata = excluded.metadata",
+        params![
+            id,
+            path.to_string_lossy(),
+            modified,
+            metadata.working_dir.to_string_lossy(),
+            metadata.description,
+            metadata.schedule_id,
+            metadata.message_count as i64,
+            serde_json::to_string(metadata)?,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Replace the messages of a session from position `from` on with `messages`
+fn write_messages(conn: &Connection, id: &str, from: usize, messages: &[Message]) -> Result<()> {
+    conn.execute(
+        "DELETE FROM messages WHERE session_id = ?1 AND position >= ?2",
+        params![id, from as i64],
+    )?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO messages (session_id, position, role, created, text, text_hash, message)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for (position, message) in (from..).zip(messages) {
+        let role = match message.role {
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        };
+        let text = search_text(message);
+        stmt.execute(params![
+            id,
+            position as i64,
+            role,
+            message.created,
+            text,
+            text_hash(&text),
+            serde_json::to_string(message)?,
+        ])?;
+    }
+    Ok(())
+}
+
+fn delete_session(conn: &Connection, id: &str) -> Result<()> {
+    // Delete the messages explicitly so the full-text index triggers run
+    conn.execute("DELETE FROM messages WHERE session_id = ?1", params![id])?;
+    conn.execute("DELETE FROM sessions WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// The text of a message that search looks at: what was said, which tools were called with what
+/// arguments, and what they returned
+fn search_text(message: &Message) -> String {
+    message
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            MessageContent::Text(text) => Some(text.text.clone()),
+            MessageContent::ToolRequest(request) => Some(request.to_readable_string()),
+            MessageContent::ToolResponse(response) => {
+                response.tool_result.as_ref().ok().map(|result| {
+                    result
+                        .iter()
+                        .filter_map(|c| c.as_text())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn text_hash(text: &str) -> String {
+    blake3::hash(text.as_bytes()).to_hex().to_string()
+}
+
+fn session_id(session_file: &Path) -> Result<String> {
+    session_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow!("Invalid session file {}", session_file.display()))
+}
+
+/// Modification time of a file in milliseconds since the epoch
+fn file_modified(path: &Path) -> Result<i64> {
+    let modified = fs::metadata(path)?.modified()?;
+    Ok(modified.duration_since(UNIX_EPOCH)?.as_millis() as i64)
+}
+
+pub(super) fn format_modified(millis: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(millis)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::storage::save_messages_with_metadata;
+    use std::time::SystemTime;
+    use tempfile::{tempdir, TempDir};
+
+    fn write_session_file(
+        dir: &Path,
+        id: &str,
+        metadata: &SessionMetadata,
+        messages: &[Message],
+        modified: DateTime<Utc>,
+    ) -> PathBuf {
+        let path = dir.join(format!("{}.jsonl", id));
+        save_messages_with_metadata(&path, metadata, messages).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::from(modified))
+            .unwrap();
+        path
+    }
+
+    fn day(day: u32) -> DateTime<Utc> {
+        format!("2025-03-{:02}T12:00:00Z", day).parse().unwrap()
+    }
+
+    /// Four sessions on consecutive days: two in one project, one scheduled, one elsewhere
+    fn populated_dir() -> (TempDir, TempDir, TempDir) {
+        let sessions = tempdir().unwrap();
+        let project = tempdir().unwrap();
+        let other = tempdir().unwrap();
+
+        let mut metadata = SessionMetadata::new(project.path().to_path_buf());
+        metadata.description = "Fix the Login page".to_string();
+        let messages = vec![
+            Message::user().with_text("the login page is broken"),
+            Message::assistant().with_text("Fixed it"),
+        ];
+        write_session_file(sessions.path(), "a", &metadata, &messages, day(1));
+
+        metadata.description = "Write release notes".to_string();
+        write_session_file(sessions.path(), "b", &metadata, &messages, day(2));
+
+        let mut scheduled = SessionMetadata::new(project.path().to_path_buf());
+        scheduled.description = "Nightly report".to_string();
+        scheduled.schedule_id = Some("nightly".to_string());
+        write_session_file(sessions.path(), "c", &scheduled, &[], day(3));
+
+        let mut elsewhere = SessionMetadata::new(other.path().to_path_buf());
+        elsewhere.description = "Login flow tests".to_string();
+        write_session_file(sessions.path(), "d", &elsewhere, &[], day(4));
+
+        (sessions, project, other)
+    }
+
+    fn ids(page: &SessionPage) -> Vec<&str> {
+        page.sessions.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sync_imports_and_drops_files() -> Result<()> {
+        let (sessions, _project, _other) = populated_dir();
+        let mut index = SessionIndex::open_in(sessions.path())?;
+
+        assert_eq!(index.sync()?, 4);
+        // Nothing changed, so nothing is imported again
+        assert_eq!(index.sync()?, 0);
+        assert_eq!(index.messages("a")?.len(), 2);
+
+        fs::remove_file(sessions.path().join("b.jsonl"))?;
+        index.sync()?;
+        let page = index.query(&SessionQuery::default())?;
+        assert_eq!(ids(&page), vec!["d", "c", "a"]);
+        assert_eq!(page.total, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_filters_and_pagination() -> Result<()> {
+        let (sessions, project, _other) = populated_dir();
+        let mut index = SessionIndex::open_in(sessions.path())?;
+        index.sync()?;
+
+        let query = |query: SessionQuery| index.query(&query).unwrap();
+
+        let page = query(SessionQuery {
+            working_dir: Some(project.path().to_path_buf()),
+            ..Default::default()
+        });
+        assert_eq!(ids(&page), vec!["c", "b", "a"]);
+
+        let page = query(SessionQuery {
+            schedule_id: Some("nightly".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(ids(&page), vec!["c"]);
+
+        let page = query(SessionQuery {
+            description: Some("login".to_string()),
+            order: SortOrder::Ascending,
+            ..Default::default()
+        });
+        assert_eq!(ids(&page), vec!["a", "d"]);
+
+        let page = query(SessionQuery {
+            since: Some(day(2)),
+            until: Some(day(4)),
+            ..Default::default()
+        });
+        assert_eq!(ids(&page), vec!["c", "b"]);
+        assert_eq!(page.sessions[0].modified, "2025-03-03 12:00:00 UTC");
+
+        let page = query(SessionQuery {
+            limit: Some(2),
+            offset: 1,
+            ..Default::default()
+        });
+        assert_eq!(ids(&page), vec!["c", "b"]);
+        assert_eq!(page.total, 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_and_export() -> Result<()> {
+        let (sessions, project, _other) = populated_dir();
+        let mut index = SessionIndex::open_in(sessions.path())?;
+        index.sync()?;
+
+        let mut metadata = SessionMetadata::new(project.path().to_path_buf());
+        metadata.description = "Fix the Login page".to_string();
+        metadata.message_count = 3;
+        let messages = vec![
+            Message::user().with_text("the login page is broken"),
+            Message::assistant().with_text("Fixed it"),
+            Message::user().with_text("thanks"),
+        ];
+        let path = sessions.path().join("a.jsonl");
+        save_messages_with_metadata(&path, &metadata, &messages)?;
+        index.record(&path, &metadata, &messages)?;
+        assert_eq!(index.sync()?, 0);
+        assert_eq!(index.metadata("a")?.unwrap().message_count, 3);
+
+        let mut exported = Vec::new();
+        index.export_jsonl("a", &mut exported)?;
+        assert_eq!(String::from_utf8(exported)?, fs::read_to_string(&path)?);
+
+        assert!(index.export_jsonl("missing", Vec::new()).is_err());
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::session::index::{query_sessions, SessionQuery};
 use crate::session::{self, SessionMetadata};
 use anyhow::Result;
 use serde::Serialize;
@@ -13,12 +14,29 @@ pub struct SessionInfo {
 }
 
 /// Sort order for listing sessions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum SortOrder {
     Ascending,
+    #[default]
     Descending,
 }
 
 pub fn get_session_info(sort_order: SortOrder) -> Result<Vec<SessionInfo>> {
+    let query = SessionQuery {
+        order: sort_order,
+        ..Default::default()
+    };
+    match query_sessions(&query) {
+        Ok(page) => Ok(page.sessions),
+        Err(e) => {
+            tracing::warn!("Session index unavailable, reading session files: {:?}", e);
+            scan_session_info(sort_order)
+        }
+    }
+}
+
+/// List sessions by reading the metadata of every session file
+fn scan_session_info(sort_order: SortOrder) -> Result<Vec<SessionInfo>> {
     let sessions = match session::list_sessions() {
         Ok(sessions) => sessions,
         Err(e) => {
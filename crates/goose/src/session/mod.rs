@@ -1,11 +1,23 @@
+pub mod bundle;
+pub mod checkpoint;
+pub mod index;
 pub mod info;
+pub mod retention;
+pub mod rewrite;
+pub mod search;
 pub mod storage;
 
 // Re-export common session types and functions
 pub use storage::{
-    ensure_session_dir, generate_description, generate_session_id, get_most_recent_session,
-    get_path, list_sessions, persist_messages, read_messages, read_metadata, update_metadata,
-    Identifier, SessionMetadata,
+    delete_session, ensure_session_dir, fork_session, generate_description, generate_session_id,
+    get_most_recent_session, get_path, list_sessions, persist_messages, read_messages,
+    read_metadata, update_metadata, Identifier, SessionMetadata,
 };
 
-pub use info::{get_session_info, SessionInfo};
+pub use bundle::SessionBundle;
+pub use checkpoint::{rewind_session, Checkpoint, CheckpointStore};
+pub use index::{query_sessions, SessionIndex, SessionPage, SessionQuery};
+pub use info::{get_session_info, SessionInfo, SortOrder};
+pub use retention::{cleanup_sessions, CleanupReport, RetentionConfig};
+pub use rewrite::{rewrite_session, Rewrite};
+pub use search::{search_sessions, semantic_search_sessions, SearchHit, SearchQuery};
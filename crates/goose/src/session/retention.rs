@@ -0,0 +1,616 @@
+//! Retention policy for saved sessions
+//!
+//! Sessions are removed once they are too old, too many or take too much disk space, with
+//! separate limits for sessions started by scheduled jobs. Sessions that are kept can be
+//! compacted once they are old enough: large tool outputs, which are rarely needed again, are
+//! cut down to their beginning.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::agents::large_response_handler::stale_response_stores;
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+use crate::session::index::SessionIndex;
+use crate::session::storage::{delete_session, ensure_session_dir, read_metadata};
+
+/// Config key holding the retention policy
+pub const RETENTION_CONFIG_KEY: &str = "GOOSE_SESSION_RETENTION";
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+// Sessions modified this recently may still be open somewhere, so they are never touched
+const MIN_AGE: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_COMPACT_OUTPUT_KB: u64 = 16;
+const COMPACTED_SUFFIX: &str = " bytes removed when the session was compacted ...]";
+// Records when the policy was last applied, so startup cleanup runs at most once a day
+const LAST_CLEANUP_FILE: &str = ".last_cleanup";
+
+/// Limits for one group of sessions
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RetentionRule {
+    /// Remove sessions that haven't been modified for this many days
+    pub max_age_days: Option<u64>,
+    /// Keep at most this many sessions, removing the oldest first
+    pub max_sessions: Option<usize>,
+    /// Remove the oldest sessions once together they take more than this many megabytes
+    pub max_total_mb: Option<u64>,
+}
+
+impl RetentionRule {
+    fn is_empty(&self) -> bool {
+        self.max_age_days.is_none() && self.max_sessions.is_none() && self.max_total_mb.is_none()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Limits for all sessions, or for the sessions started by hand when `scheduled` is set
+    #[serde(flatten)]
+    pub sessions: RetentionRule,
+    /// Limits for sessions started by scheduled jobs, which are then counted separately
+    pub scheduled: Option<RetentionRule>,
+    /// Shorten large tool outputs in sessions that haven't been modified for this many days
+    pub compact_after_days: Option<u64>,
+    /// Tool outputs larger than this many kilobytes are shortened to this size when compacting
+    pub compact_output_kb: u64,
+    /// Apply the policy when a new CLI session starts, at most once a day
+    pub cleanup_on_startup: bool,
+    /// Apply the policy from the scheduler every this many hours
+    pub cleanup_interval_hours: Option<u64>,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            sessions: RetentionRule::default(),
+            scheduled: None,
+            compact_after_days: None,
+            compact_output_kb: DEFAULT_COMPACT_OUTPUT_KB,
+            cleanup_on_startup: false,
+            cleanup_interval_hours: None,
+        }
+    }
+}
+
+impl RetentionConfig {
+    pub fn from_config() -> Self {
+        Config::global()
+            .get_param(RETENTION_CONFIG_KEY)
+            .unwrap_or_default()
+    }
+}
+
+/// Which limit a session was removed by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemovalReason {
+    Age,
+    Count,
+    Size,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RemovedSession {
+    pub id: String,
+    pub scheduled: bool,
+    pub reason: RemovalReason,
+    pub bytes: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CompactedSession {
+    pub id: String,
+    /// Number of tool outputs that were shortened
+    pub outputs: usize,
+    pub bytes_saved: u64,
+}
+
+/// What a cleanup did, or would do for a dry run
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CleanupReport {
+    pub dry_run: bool,
+    pub removed: Vec<RemovedSession>,
+    pub compacted: Vec<CompactedSession>,
+    /// Large tool responses left in the temp directory by agents that didn't clean up
+    pub stale_responses: usize,
+    pub stale_response_bytes: u64,
+}
+
+impl CleanupReport {
+    pub fn bytes_freed(&self) -> u64 {
+        self.removed.iter().map(|s| s.bytes).sum::<u64>()
+            + self.compacted.iter().map(|s| s.bytes_saved).sum::<u64>()
+            + self.stale_response_bytes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.compacted.is_empty() && self.stale_responses == 0
+    }
+}
+
+struct SessionFile {
+    id: String,
+    path: PathBuf,
+    age: Duration,
+    bytes: u64,
+    scheduled: bool,
+}
+
+/// Apply the retention policy to the session directory and clear out stale tool responses
+pub fn cleanup_sessions(config: &RetentionConfig, dry_run: bool) -> Result<CleanupReport> {
+    let session_dir = ensure_session_dir()?;
+    let mut report = cleanup_sessions_in(&session_dir, config, SystemTime::now(), dry_run)?;
+
+    for path in stale_response_stores() {
+        report.stale_responses += 1;
+        report.stale_response_bytes += disk_usage(&path);
+        if !dry_run {
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            if let Err(e) = removed {
+                tracing::warn!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    if !dry_run {
+        fs::write(session_dir.join(LAST_CLEANUP_FILE), "")?;
+    }
+    Ok(report)
+}
+
+/// Apply the retention policy if it asks for cleanup on startup and hasn't run today
+pub fn cleanup_on_startup() -> Result<Option<CleanupReport>> {
+    let config = RetentionConfig::from_config();
+    if !config.cleanup_on_startup {
+        return Ok(None);
+    }
+    let last_cleanup = ensure_session_dir()?.join(LAST_CLEANUP_FILE);
+    let ran_recently = fs::metadata(&last_cleanup)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|elapsed| elapsed < DAY);
+    if ran_recently {
+        return Ok(None);
+    }
+    cleanup_sessions(&config, false).map(Some)
+}
+
+/// Apply the retention policy to the sessions in `session_dir` as of `now`
+pub fn cleanup_sessions_in(
+    session_dir: &Path,
+    config: &RetentionConfig,
+    now: SystemTime,
+    dry_run: bool,
+) -> Result<CleanupReport> {
+    let mut sessions = scan_sessions(session_dir, now)?;
+    // Newest first, so the limits keep the most recent sessions
+    sessions.sort_by_key(|session| session.age);
+
+    let mut report = CleanupReport {
+        dry_run,
+        ..Default::default()
+    };
+    let mut removals = Vec::new();
+    match &config.scheduled {
+        Some(scheduled) => {
+            let (by_schedule, by_hand): (Vec<_>, Vec<_>) =
+                sessions.iter().partition(|session| session.scheduled);
+            removals.extend(select_removals(&by_hand, &config.sessions));
+            removals.extend(select_removals(&by_schedule, scheduled));
+        }
+        None => {
+            let all: Vec<_> = sessions.iter().collect();
+            removals.extend(select_removals(&all, &config.sessions));
+        }
+    }
+
+    let mut changed = Vec::new();
+    for (session, reason) in &removals {
+        if !dry_run {
+            let forks = delete_session(&session.path)
+                .with_context(|| format!("Failed to remove {}", session.path.display()))?;
+            changed.push(session.id.clone());
+            changed.extend(forks);
+        }
+        report.removed.push(RemovedSession {
+            id: session.id.clone(),
+            scheduled: session.scheduled,
+            reason: *reason,
+            bytes: session.bytes,
+        });
+    }
+
+    if let Some(days) = config.compact_after_days {
+        let max_bytes = (config.compact_output_kb * 1024) as usize;
+        for session in &sessions {
+            if session.age < DAY * days as u32 || session.age < MIN_AGE {
+                continue;
+            }
+            if removals.iter().any(|(removed, _)| removed.id == session.id) {
+                continue;
+            }
+            match compact_session(&session.path, max_bytes, dry_run) {
+                Ok(Some(compacted)) => {
+                    if !dry_run {
+                        changed.push(session.id.clone());
+                    }
+                    report.compacted.push(compacted);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to compact session {}: {:?}", session.id, e),
+            }
+        }
+    }
+
+    if !changed.is_empty() {
+        if let Err(e) = update_index(session_dir, &changed) {
+            tracing::warn!("Failed to update the session index: {:?}", e);
+        }
+    }
+    Ok(report)
+}
+
+fn scan_sessions(session_dir: &Path, now: SystemTime) -> Result<Vec<SessionFile>> {
+    let mut sessions = Vec::new();
+    for entry in fs::read_dir(session_dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "jsonl") {
+            continue;
+        }
+        let Some(id) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let file_metadata = fs::metadata(&path)?;
+        let age = file_metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or_default();
+        let scheduled = read_metadata(&path)
+            .map(|metadata| metadata.schedule_id.is_some())
+            .unwrap_or(false);
+        sessions.push(SessionFile {
+            id,
+            path,
+            age,
+            bytes: file_metadata.len(),
+            scheduled,
+        });
+    }
+    Ok(sessions)
+}
+
+/// Pick the sessions to remove from a group sorted newest first
+fn select_removals<'a>(
+    sessions: &[&'a SessionFile],
+    rule: &RetentionRule,
+) -> Vec<(&'a SessionFile, RemovalReason)> {
+    if rule.is_empty() {
+        return Vec::new();
+    }
+    let max_age = rule.max_age_days.map(|days| DAY * days as u32);
+    let max_bytes = rule.max_total_mb.map(|mb| mb * 1024 * 1024);
+
+    let mut removals = Vec::new();
+    let mut kept_count = 0;
+    let mut kept_bytes = 0;
+    for session in sessions {
+        let reason = if session.age < MIN_AGE {
+            None
+        } else if max_age.is_some_and(|max_age| session.age > max_age) {
+            Some(RemovalReason::Age)
+        } else if rule.max_sessions.is_some_and(|max| kept_count >= max) {
+            Some(RemovalReason::Count)
+        } else if max_bytes.is_some_and(|max| kept_bytes + session.bytes > max) {
+            Some(RemovalReason::Size)
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => removals.push((*session, reason)),
+            None => {
+                kept_count += 1;
+                kept_bytes += session.bytes;
+            }
+        }
+    }
+    removals
+}
+
+/// Shorten the tool outputs in a session file that are longer than `max_bytes`
+///
+/// Only the lines that change are rewritten, and the file keeps its modification time so that
+/// compaction doesn't make a session look recent.
+fn compact_session(
+    path: &Path,
+    max_bytes: usize,
+    dry_run: bool,
+) -> Result<Option<CompactedSession>> {
+    let modified = fs::metadata(path)?.modified()?;
+    let reader = io::BufReader::new(File::open(path)?);
+
+    let mut lines = Vec::new();
+    let mut outputs = 0;
+    let mut bytes_saved = 0;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        // The first line is the metadata
+        if i > 0 {
+            if let Ok(mut message) = serde_json::from_str::<Message>(&line) {
+                let (count, saved) = compact_message(&mut message, max_bytes);
+                if count > 0 {
+                    outputs += count;
+                    bytes_saved += saved;
+                    lines.push(serde_json::to_string(&message)?);
+                    continue;
+                }
+            }
+        }
+        lines.push(line);
+    }
+    if outputs == 0 {
+        return Ok(None);
+    }
+
+    if !dry_run {
+        let temp_path = path.with_extension("jsonl.tmp");
+        let mut writer = io::BufWriter::new(File::create(&temp_path)?);
+        for line in &lines {
+            writeln!(writer, "{}", line)?;
+        }
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.set_modified(modified)?;
+        drop(file);
+        fs::rename(&temp_path, path)?;
+    }
+
+    Ok(Some(CompactedSession {
+        id: path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        outputs,
+        bytes_saved: bytes_saved as u64,
+    }))
+}
+
+/// Cut tool output text down to `max_bytes`, returning how many outputs were cut and by how
+/// many bytes
+fn compact_message(message: &mut Message, max_bytes: usize) -> (usize, usize) {
+    let mut outputs = 0;
+    let mut bytes_saved = 0;
+    for content in &mut message.content {
+        let MessageContent::ToolResponse(response) = content else {
+            continue;
+        };
+        let Ok(contents) = &mut response.tool_result else {
+            continue;
+        };
+        for item in contents {
+            let mcp_core::Content::Text(text) = item else {
+                continue;
+            };
+            if text.text.len() <= max_bytes || text.text.ends_with(COMPACTED_SUFFIX) {
+                continue;
+            }
+            let mut end = max_bytes;
+            while !text.text.is_char_boundary(end) {
+                end -= 1;
+            }
+            let removed = text.text.len() - end;
+            text.text.truncate(end);
+            text.text
+                .push_str(&format!("\n[... {}{}", removed, COMPACTED_SUFFIX));
+            outputs += 1;
+            bytes_saved += removed;
+        }
+    }
+    (outputs, bytes_saved)
+}
+
+/// Drop changed sessions from the index so the next sync reads them again
+fn update_index(session_dir: &Path, changed: &[String]) -> Result<()> {
+    let mut index = SessionIndex::open_in(session_dir)?;
+    for id in changed {
+        index.remove(id)?;
+    }
+    index.sync()?;
+    Ok(())
+}
+
+fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = fs::metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| disk_usage(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::storage::{
+        read_messages_with_truncation, save_messages_with_metadata, SessionMetadata,
+    };
+    use tempfile::tempdir;
+
+    fn write_session(
+        dir: &Path,
+        id: &str,
+        age_days: u64,
+        schedule_id: Option<&str>,
+        messages: &[Message],
+    ) -> PathBuf {
+        let path = dir.join(format!("{}.jsonl", id));
+        let mut metadata = SessionMetadata::new(dir.to_path_buf());
+        metadata.schedule_id = schedule_id.map(str::to_string);
+        save_messages_with_metadata(&path, &metadata, messages).unwrap();
+        let modified = SystemTime::now() - DAY * age_days as u32;
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        path
+    }
+
+    fn removed_ids(report: &CleanupReport) -> Vec<&str> {
+        let mut ids: Vec<&str> = report.removed.iter().map(|s| s.id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_age_and_count_limits() -> Result<()> {
+        let dir = tempdir()?;
+        let hello = [Message::user().with_text("hello")];
+        write_session(dir.path(), "today", 0, None, &hello);
+        write_session(dir.path(), "last_week", 7, None, &hello);
+        write_session(dir.path(), "last_month", 30, None, &hello);
+        write_session(dir.path(), "last_year", 365, None, &hello);
+
+        let config = RetentionConfig {
+            sessions: RetentionRule {
+                max_age_days: Some(90),
+                max_sessions: Some(2),
+                max_total_mb: None,
+            },
+            ..Default::default()
+        };
+
+        let dry_run = cleanup_sessions_in(dir.path(), &config, SystemTime::now(), true)?;
+        assert_eq!(removed_ids(&dry_run), vec!["last_month", "last_year"]);
+        assert!(dir.path().join("last_year.jsonl").exists());
+
+        let report = cleanup_sessions_in(dir.path(), &config, SystemTime::now(), false)?;
+        let reasons: Vec<_> = report
+            .removed
+            .iter()
+            .map(|s| (s.id.as_str(), s.reason))
+            .collect();
+        assert!(reasons.contains(&("last_month", RemovalReason::Count)));
+        assert!(reasons.contains(&("last_year", RemovalReason::Age)));
+        assert!(!dir.path().join("last_year.jsonl").exists());
+        assert!(dir.path().join("today.jsonl").exists());
+        assert!(dir.path().join("last_week.jsonl").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scheduled_sessions_have_their_own_limits() -> Result<()> {
+        let dir = tempdir()?;
+        let hello = [Message::user().with_text("hello")];
+        write_session(dir.path(), "manual", 20, None, &hello);
+        write_session(dir.path(), "nightly_1", 2, Some("nightly"), &hello);
+        write_session(dir.path(), "nightly_2", 3, Some("nightly"), &hello);
+        write_session(dir.path(), "nightly_3", 20, Some("nightly"), &hello);
+
+        let config = RetentionConfig {
+            sessions: RetentionRule {
+                max_age_days: Some(90),
+                ..Default::default()
+            },
+            scheduled: Some(RetentionRule {
+                max_age_days: Some(14),
+                max_sessions: Some(1),
+                max_total_mb: None,
+            }),
+            ..Default::default()
+        };
+        let report = cleanup_sessions_in(dir.path(), &config, SystemTime::now(), false)?;
+        assert_eq!(removed_ids(&report), vec!["nightly_2", "nightly_3"]);
+        assert!(report.removed.iter().all(|s| s.scheduled));
+        assert!(dir.path().join("manual.jsonl").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_limit_keeps_recent_sessions() -> Result<()> {
+        let dir = tempdir()?;
+        let big = [Message::user().with_text("x".repeat(600 * 1024))];
+        write_session(dir.path(), "newer", 1, None, &big);
+        write_session(dir.path(), "older", 2, None, &big);
+        // Too recent to touch, but it still counts towards the limit
+        write_session(dir.path(), "active", 0, None, &big);
+
+        let config = RetentionConfig {
+            sessions: RetentionRule {
+                max_total_mb: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let report = cleanup_sessions_in(dir.path(), &config, SystemTime::now(), false)?;
+        assert_eq!(removed_ids(&report), vec!["newer", "older"]);
+        assert_eq!(report.removed[0].reason, RemovalReason::Size);
+        assert!(dir.path().join("active.jsonl").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compaction_shortens_old_tool_outputs() -> Result<()> {
+        let dir = tempdir()?;
+        let output = "é".repeat(10_000);
+        let messages = [
+            Message::user().with_text("Show me the log"),
+            Message::user().with_tool_response("1", Ok(vec![mcp_core::Content::text(output)])),
+        ];
+        let old = write_session(dir.path(), "old", 10, None, &messages);
+        let recent = write_session(dir.path(), "recent", 1, None, &messages);
+        let modified = fs::metadata(&old)?.modified()?;
+
+        let config = RetentionConfig {
+            compact_after_days: Some(7),
+            compact_output_kb: 1,
+            ..Default::default()
+        };
+        let report = cleanup_sessions_in(dir.path(), &config, SystemTime::now(), false)?;
+        assert!(report.removed.is_empty());
+        assert_eq!(report.compacted.len(), 1);
+        assert_eq!(report.compacted[0].id, "old");
+        assert_eq!(report.compacted[0].outputs, 1);
+        assert_eq!(report.compacted[0].bytes_saved, 20_000 - 1024);
+
+        let compacted = read_messages_with_truncation(&old, None)?;
+        assert_eq!(compacted[0], messages[0]);
+        let MessageContent::ToolResponse(response) = &compacted[1].content[0] else {
+            panic!("Expected a tool response");
+        };
+        let text = response.tool_result.as_ref().unwrap()[0]
+            .as_text()
+            .unwrap()
+            .to_string();
+        assert!(text.starts_with(&"é".repeat(512)));
+        assert!(text.ends_with(COMPACTED_SUFFIX));
+        // Compacting doesn't make the session look recent
+        assert_eq!(fs::metadata(&old)?.modified()?, modified);
+        assert_eq!(read_messages_with_truncation(&recent, None)?, messages);
+
+        // A second pass has nothing left to do
+        let again = cleanup_sessions_in(dir.path(), &config, SystemTime::now(), false)?;
+        assert!(again.is_empty());
+        Ok(())
+    }
+}
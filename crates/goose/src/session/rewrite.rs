@@ -0,0 +1,168 @@
+//! Rewriting a conversation from an earlier point, for editing a prompt or regenerating a reply
+//!
+//! Nothing is thrown away: before a session is cut back, the conversation as it was is saved
+//! as a branch of the session (see [`fork_session`](super::fork_session)), so the replaced
+//! replies can still be found with `goose session list --tree`.
+
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use mcp_core::role::Role;
+
+use crate::message::{Message, MessageContent};
+use crate::session::storage::{
+    branch_session, read_messages_with_truncation, read_metadata, save_messages_with_metadata,
+};
+
+/// A session after it was cut back
+#[derive(Debug, Clone)]
+pub struct Rewrite {
+    /// The messages left in the session
+    pub messages: Vec<Message>,
+    /// ID of the branch holding the conversation as it was, if anything was cut
+    pub branch_id: Option<String>,
+}
+
+/// Whether a message is a prompt typed by the user, rather than tool results sent back as a
+/// user message
+pub fn is_prompt(message: &Message) -> bool {
+    message.role == Role::User && !message.is_tool_response()
+}
+
+/// Index of the prompt `back` prompts from the end of the conversation, where 1 is the latest
+pub fn prompt_index(messages: &[Message], back: usize) -> Option<usize> {
+    if back == 0 {
+        return None;
+    }
+    messages
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, message)| is_prompt(message))
+        .nth(back - 1)
+        .map(|(index, _)| index)
+}
+
+/// A copy of a prompt with its text replaced, keeping any images or documents attached to it
+pub fn edit_prompt(original: &Message, text: &str) -> Message {
+    let mut edited = Message::user().with_text(text);
+    edited.content.extend(
+        original
+            .content
+            .iter()
+            .filter(|content| !matches!(content, MessageContent::Text(_)))
+            .cloned(),
+    );
+    edited.created = Utc::now().timestamp();
+    edited
+}
+
+/// Cut a stored session back to its first `keep` messages
+///
+/// If that drops any messages, the whole conversation is first saved as a branch of the
+/// session, branching at `keep`.
+pub fn rewrite_session(session_file: &Path, keep: usize) -> Result<Rewrite> {
+    let mut messages = read_messages_with_truncation(session_file, None)?;
+    if keep > messages.len() {
+        return Err(anyhow!(
+            "Can't keep {} messages, the session has {}",
+            keep,
+            messages.len()
+        ));
+    }
+    if keep == messages.len() {
+        return Ok(Rewrite {
+            messages,
+            branch_id: None,
+        });
+    }
+
+    let (branch_id, _) = branch_session(session_file, &messages, keep)?;
+
+    messages.truncate(keep);
+    let mut metadata = read_metadata(session_file)?;
+    metadata.message_count = messages.len();
+    save_messages_with_metadata(session_file, &metadata, &messages)?;
+
+    Ok(Rewrite {
+        messages,
+        branch_id: Some(branch_id),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::storage::SessionMetadata;
+    use mcp_core::tool::ToolCall;
+    use tempfile::tempdir;
+
+    fn conversation() -> Vec<Message> {
+        vec![
+            Message::user().with_text("List the files"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    serde_json::json!({"command": "ls"}),
+                )),
+            ),
+            Message::user().with_tool_response("1", Ok(vec![mcp_core::Content::text("a.rs")])),
+            Message::assistant().with_text("There is one file, a.rs"),
+            Message::user().with_text("Open it"),
+            Message::assistant().with_text("It is empty"),
+        ]
+    }
+
+    #[test]
+    fn test_prompt_index() {
+        let messages = conversation();
+        assert_eq!(prompt_index(&messages, 1), Some(4));
+        // Tool results aren't prompts
+        assert_eq!(prompt_index(&messages, 2), Some(0));
+        assert_eq!(prompt_index(&messages, 3), None);
+        assert_eq!(prompt_index(&messages, 0), None);
+    }
+
+    #[test]
+    fn test_edit_prompt_keeps_attachments() {
+        let original = Message::user()
+            .with_text("What is in this picture?")
+            .with_image("aGVsbG8=", "image/png");
+        let edited = edit_prompt(&original, "Describe the colours");
+        assert_eq!(edited.as_concat_text(), "Describe the colours");
+        assert_eq!(edited.content.len(), 2);
+        assert!(matches!(edited.content[1], MessageContent::Image(_)));
+    }
+
+    #[test]
+    fn test_rewrite_session_keeps_a_branch() -> Result<()> {
+        let dir = tempdir()?;
+        let session_file = dir.path().join("main.jsonl");
+        let messages = conversation();
+        save_messages_with_metadata(
+            &session_file,
+            &SessionMetadata::new(dir.path().to_path_buf()),
+            &messages,
+        )?;
+
+        let rewrite = rewrite_session(&session_file, 4)?;
+        assert_eq!(rewrite.messages, messages[..4]);
+        assert_eq!(read_messages_with_truncation(&session_file, None)?.len(), 4);
+        assert_eq!(read_metadata(&session_file)?.message_count, 4);
+
+        let branch_id = rewrite.branch_id.expect("a branch should be saved");
+        let branch_file = dir.path().join(format!("{}.jsonl", branch_id));
+        let branch = read_metadata(&branch_file)?;
+        assert_eq!(branch.parent_session_id.as_deref(), Some("main"));
+        assert_eq!(branch.branch_point, Some(4));
+        assert_eq!(read_messages_with_truncation(&branch_file, None)?, messages);
+
+        // Nothing to cut, nothing to save
+        let unchanged = rewrite_session(&session_file, 4)?;
+        assert!(unchanged.branch_id.is_none());
+        assert!(rewrite_session(&session_file, 5).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,474 @@
+//! Search over the messages of past sessions.
+//!
+//! Full-text search uses the SQLite FTS5 index kept alongside the session index. Semantic search
+//! embeds messages with the provider, caching the embeddings in the index so each message is only
+//! embedded once per model.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use rusqlite::params;
+use rusqlite::types::Value;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::context_mgmt::truncate::cosine_similarity;
+use crate::model::ModelConfig;
+use crate::providers::{self, base::Provider};
+use crate::session::index::{format_modified, SessionIndex};
+
+/// Marks the start and end of the matched words in a snippet
+pub const HIGHLIGHT: &str = "**";
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// Longest text sent to the provider for a single embedding
+const MAX_EMBEDDING_TEXT_SIZE: usize = 2000;
+const EMBEDDING_BATCH_SIZE: usize = 64;
+const SNIPPET_LENGTH: usize = 160;
+
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub text: String,
+    /// Only search sessions started in this working directory
+    pub working_dir: Option<PathBuf>,
+    /// Maximum number of matching messages to return
+    pub limit: usize,
+}
+
+impl SearchQuery {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            working_dir: None,
+            limit: DEFAULT_SEARCH_LIMIT,
+        }
+    }
+}
+
+/// A message that matched a search, best matches first
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SearchHit {
+    pub session_id: String,
+    pub description: String,
+    pub working_dir: String,
+    /// When the session was last modified
+    pub modified: String,
+    /// Position of the message in the session, counting from zero
+    pub message_index: usize,
+    pub role: String,
+    /// When the message was created, in seconds since the epoch
+    pub created: i64,
+    /// Part of the message around the match, with matched words wrapped in [`HIGHLIGHT`]
+    pub snippet: String,
+    /// Higher is better; only comparable between hits of the same search
+    pub score: f32,
+}
+
+impl SessionIndex {
+    /// Find messages containing every word of the query, in their text, tool calls or tool
+    /// results
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHit>> {
+        let Some(match_expression) = match_expression(&query.text) else {
+            return Ok(Vec::new());
+        };
+
+        let mut sql = "SELECT m.session_id, s.description, s.working_dir, s.modified, m.position,
+                    m.role, m.created, snippet(messages_fts, 0, ?, ?, '…', 16),
+                    bm25(messages_fts)
+             FROM messages_fts
+             JOIN messages m ON m.id = messages_fts.rowid
+             JOIN sessions s ON s.id = m.session_id
+             WHERE messages_fts MATCH ?"
+            .to_string();
+        let mut values = vec![
+            Value::Text(HIGHLIGHT.to_string()),
+            Value::Text(HIGHLIGHT.to_string()),
+            Value::Text(match_expression),
+        ];
+        if let Some(working_dir) = &query.working_dir {
+            sql.push_str(" AND s.working_dir = ?");
+            values.push(Value::Text(working_dir.to_string_lossy().to_string()));
+        }
+        sql.push_str(" ORDER BY bm25(messages_fts) LIMIT ?");
+        values.push(Value::Integer(query.limit as i64));
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values.iter()), |row| {
+            Ok(SearchHit {
+                session_id: row.get(0)?,
+                description: row.get(1)?,
+                working_dir: row.get(2)?,
+                modified: format_modified(row.get(3)?),
+                message_index: row.get::<_, i64>(4)? as usize,
+                role: row.get(5)?,
+                created: row.get(6)?,
+                snippet: row.get(7)?,
+                // bm25 is lower for better matches
+                score: -row.get::<_, f64>(8)? as f32,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Find the messages closest in meaning to the query. Messages that have not been embedded
+    /// with the provider's model yet are embedded first.
+    pub async fn semantic_search(
+        &mut self,
+        query: &SearchQuery,
+        provider: &Arc<dyn Provider>,
+    ) -> Result<Vec<SearchHit>> {
+        if !provider.supports_embeddings() {
+            bail!("The provider does not support embeddings");
+        }
+        let model = provider.get_model_config().model_name;
+
+        let missing = self.missing_embeddings(query, &model)?;
+        for batch in missing.chunks(EMBEDDING_BATCH_SIZE) {
+            let texts = batch.iter().map(|(_, text)| text.clone()).collect();
+            let embeddings = provider.create_embeddings(texts).await?;
+            if embeddings.len() != batch.len() {
+                bail!(
+                    "Expected {} embeddings, got {}",
+                    batch.len(),
+                    embeddings.len()
+                );
+            }
+            self.store_embeddings(&model, batch, &embeddings)?;
+        }
+
+        let query_embedding = provider
+            .create_embeddings(vec![query.text.clone()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("The provider returned no embeddings"))?;
+
+        let mut hits = self.score_embeddings(query, &model, &query_embedding)?;
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(query.limit);
+        Ok(hits)
+    }
+
+    /// Texts in the searched sessions without an embedding for `model`, by hash
+    fn missing_embeddings(
+        &self,
+        query: &SearchQuery,
+        model: &str,
+    ) -> Result<Vec<(String, String)>> {
+        let mut sql = "SELECT DISTINCT m.text_hash, m.text
+             FROM messages m
+             JOIN sessions s ON s.id = m.session_id
+             LEFT JOIN embeddings e ON e.text_hash = m.text_hash AND e.model = ?
+             WHERE e.text_hash IS NULL AND m.text != ''"
+            .to_string();
+        let mut values = vec![Value::Text(model.to_string())];
+        if let Some(working_dir) = &query.working_dir {
+            sql.push_str(" AND s.working_dir = ?");
+            values.push(Value::Text(working_dir.to_string_lossy().to_string()));
+        }
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values.iter()), |row| {
+            let text: String = row.get(1)?;
+            Ok((
+                row.get(0)?,
+                text.chars().take(MAX_EMBEDDING_TEXT_SIZE).collect(),
+            ))
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn store_embeddings(
+        &mut self,
+        model: &str,
+        texts: &[(String, String)],
+        embeddings: &[Vec<f32>],
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO embeddings (text_hash, model, vector) VALUES (?1, ?2, ?3)",
+            )?;
+            for ((hash, _), embedding) in texts.iter().zip(embeddings) {
+                let vector: Vec<u8> = embedding.iter().flat_map(|x| x.to_le_bytes()).collect();
+                stmt.execute(params![hash, model, vector])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn score_embeddings(
+        &self,
+        query: &SearchQuery,
+        model: &str,
+        query_embedding: &[f32],
+    ) -> Result<Vec<SearchHit>> {
+        let mut sql = "SELECT m.session_id, s.description, s.working_dir, s.modified, m.position,
+                    m.role, m.created, m.text, e.vector
+             FROM messages m
+             JOIN sessions s ON s.id = m.session_id
+             JOIN embeddings e ON e.text_hash = m.text_hash AND e.model = ?"
+            .to_string();
+        let mut values = vec![Value::Text(model.to_string())];
+        if let Some(working_dir) = &query.working_dir {
+            sql.push_str(" WHERE s.working_dir = ?");
+            values.push(Value::Text(working_dir.to_string_lossy().to_string()));
+        }
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values.iter()), |row| {
+            let text: String = row.get(7)?;
+            let vector: Vec<u8> = row.get(8)?;
+            let embedding: Vec<f32> = vector
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect();
+            Ok(SearchHit {
+                session_id: row.get(0)?,
+                description: row.get(1)?,
+                working_dir: row.get(2)?,
+                modified: format_modified(row.get(3)?),
+                message_index: row.get::<_, i64>(4)? as usize,
+                role: row.get(5)?,
+                created: row.get(6)?,
+                snippet: leading_snippet(&text),
+                score: cosine_similarity(&embedding, query_embedding),
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+/// Full-text search over the sessions in the default session directory
+pub fn search_sessions(query: &SearchQuery) -> Result<Vec<SearchHit>> {
+    let mut index = SessionIndex::open()?;
+    index.sync()?;
+    index.search(query)
+}
+
+/// Semantic search over the sessions in the default session directory
+pub async fn semantic_search_sessions(
+    query: &SearchQuery,
+    provider: &Arc<dyn Provider>,
+) -> Result<Vec<SearchHit>> {
+    let mut index = SessionIndex::open()?;
+    index.sync()?;
+    index.semantic_search(query, provider).await
+}
+
+/// The provider used to embed sessions for semantic search: the embedding model when one is
+/// configured with `GOOSE_EMBEDDING_MODEL_PROVIDER`, otherwise the main provider
+pub fn embedding_provider() -> Result<Arc<dyn Provider>> {
+    let config = Config::global();
+    if let Ok(provider_name) = config.get_param::<String>("GOOSE_EMBEDDING_MODEL_PROVIDER") {
+        let model = config
+            .get_param::<String>("GOOSE_EMBEDDING_MODEL")
+            .unwrap_or_else(|_| "text-embedding-3-small".to_string());
+        return providers::create(&provider_name, ModelConfig::new(model)).with_context(|| {
+            format!("Failed to create {} provider for embeddings", provider_name)
+        });
+    }
+
+    let provider_name: String = config
+        .get_param("GOOSE_PROVIDER")
+        .context("No provider configured. Run 'goose configure' first")?;
+    let model: String = config
+        .get_param("GOOSE_MODEL")
+        .context("No model configured. Run 'goose configure' first")?;
+    providers::create(&provider_name, ModelConfig::new(model))
+}
+
+/// Turn what the user typed into an FTS5 query matching every word, so punctuation and FTS
+/// operators in the query are searched for literally
+fn match_expression(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn leading_snippet(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > SNIPPET_LENGTH {
+        format!("{}…", text.chars().take(SNIPPET_LENGTH).collect::<String>())
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Message;
+    use crate::providers::base::{ProviderMetadata, ProviderUsage};
+    use crate::providers::errors::ProviderError;
+    use crate::session::storage::{save_messages_with_metadata, SessionMetadata};
+    use async_trait::async_trait;
+    use mcp_core::tool::{Tool, ToolCall};
+    use serde_json::json;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::{tempdir, TempDir};
+
+    /// Embeds text as counts of a few known words
+    struct WordCountProvider {
+        embedded: AtomicUsize,
+    }
+
+    const WORDS: [&str; 4] = ["ci", "flaky", "login", "page"];
+
+    #[async_trait]
+    impl Provider for WordCountProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("words".to_string())
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            unimplemented!()
+        }
+
+        fn supports_embeddings(&self) -> bool {
+            true
+        }
+
+        async fn create_embeddings(
+            &self,
+            texts: Vec<String>,
+        ) -> Result<Vec<Vec<f32>>, ProviderError> {
+            self.embedded.fetch_add(texts.len(), Ordering::SeqCst);
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let text = text.to_lowercase();
+                    WORDS
+                        .iter()
+                        .map(|word| text.matches(word).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    fn write(dir: &Path, id: &str, working_dir: &Path, description: &str, messages: &[Message]) {
+        let mut metadata = SessionMetadata::new(working_dir.to_path_buf());
+        metadata.description = description.to_string();
+        save_messages_with_metadata(&dir.join(format!("{}.jsonl", id)), &metadata, messages)
+            .unwrap();
+    }
+
+    fn populated_index() -> (TempDir, TempDir, TempDir, SessionIndex) {
+        let sessions = tempdir().unwrap();
+        let project = tempdir().unwrap();
+        let other = tempdir().unwrap();
+
+        write(
+            sessions.path(),
+            "ci",
+            project.path(),
+            "Fix flaky CI",
+            &[
+                Message::user().with_text("The CI job is flaky again"),
+                Message::assistant().with_tool_request(
+                    "1",
+                    Ok(ToolCall::new(
+                        "developer__shell",
+                        json!({"command": "cargo nextest run --retries 2"}),
+                    )),
+                ),
+                Message::assistant().with_text("Retries are enabled now"),
+            ],
+        );
+        write(
+            sessions.path(),
+            "login",
+            other.path(),
+            "Login page",
+            &[
+                Message::user().with_text("Make the login page responsive"),
+                Message::assistant().with_text("Done, the login page now works on phones"),
+            ],
+        );
+
+        let mut index = SessionIndex::open_in(sessions.path()).unwrap();
+        index.sync().unwrap();
+        (sessions, project, other, index)
+    }
+
+    #[test]
+    fn test_full_text_search() -> Result<()> {
+        let (_sessions, project, _other, index) = populated_index();
+
+        let hits = index.search(&SearchQuery::new("flaky ci"))?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "ci");
+        assert_eq!(hits[0].message_index, 0);
+        assert_eq!(hits[0].role, "user");
+        assert_eq!(hits[0].snippet, "The **CI** job is **flaky** again");
+
+        // Tool calls are searchable too
+        let hits = index.search(&SearchQuery::new("nextest"))?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_index, 1);
+
+        let hits = index.search(&SearchQuery::new("login page"))?;
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|hit| hit.session_id == "login"));
+
+        let mut query = SearchQuery::new("login page");
+        query.working_dir = Some(project.path().to_path_buf());
+        assert!(index.search(&query)?.is_empty());
+
+        // FTS syntax in the query is searched for literally instead of failing
+        assert!(index.search(&SearchQuery::new("\"unbalanced OR (")).is_ok());
+        assert!(index.search(&SearchQuery::new("   "))?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search() -> Result<()> {
+        let (_sessions, _project, _other, mut index) = populated_index();
+        let words = Arc::new(WordCountProvider {
+            embedded: AtomicUsize::new(0),
+        });
+        let provider: Arc<dyn Provider> = words.clone();
+
+        let mut query = SearchQuery::new("flaky pipeline in CI");
+        query.limit = 1;
+        let hits = index.semantic_search(&query, &provider).await?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "ci");
+        assert_eq!(hits[0].message_index, 0);
+        assert_eq!(hits[0].snippet, "The CI job is flaky again");
+
+        // Five messages and the query; the second search only embeds its query
+        assert_eq!(words.embedded.load(Ordering::SeqCst), 6);
+        index
+            .semantic_search(&SearchQuery::new("login"), &provider)
+            .await?;
+        assert_eq!(words.embedded.load(Ordering::SeqCst), 7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_expression() {
+        assert_eq!(
+            match_expression("flaky \"CI\"").as_deref(),
+            Some("\"flaky\" \"\"\"CI\"\"\"")
+        );
+        assert_eq!(match_expression(" \t"), None);
+    }
+}
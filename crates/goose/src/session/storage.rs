@@ -1,13 +1,19 @@
+use crate::agents::large_response_handler;
 use crate::message::Message;
 use crate::providers::base::Provider;
+use crate::redaction::{self, Redactor};
+use crate::session::{checkpoint, index};
 use anyhow::Result;
 use chrono::Local;
 use etcetera::{choose_app_strategy, AppStrategy, AppStrategyArgs};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{self, BufRead, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 use utoipa::ToSchema;
 
 fn get_home_dir() -> PathBuf {
@@ -41,6 +47,10 @@ pub struct SessionMetadata {
     pub accumulated_input_tokens: Option<i32>,
     /// The number of output tokens used in the session. Accumulated across all messages.
     pub accumulated_output_tokens: Option<i32>,
+    /// ID of the session this one was forked from, if any
+    pub parent_session_id: Option<String>,
+    /// Number of messages copied from the parent session when this one was forked
+    pub branch_point: Option<usize>,
 }
 
 // Custom deserializer to handle old sessions without working_dir
@@ -61,6 +71,8 @@ impl<'de> Deserialize<'de> for SessionMetadata {
             accumulated_input_tokens: Option<i32>,
             accumulated_output_tokens: Option<i32>,
             working_dir: Option<PathBuf>,
+            parent_session_id: Option<String>,
+            branch_point: Option<usize>,
         }
 
         let helper = Helper::deserialize(deserializer)?;
@@ -82,6 +94,8 @@ impl<'de> Deserialize<'de> for SessionMetadata {
             accumulated_input_tokens: helper.accumulated_input_tokens,
             accumulated_output_tokens: helper.accumulated_output_tokens,
             working_dir,
+            parent_session_id: helper.parent_session_id,
+            branch_point: helper.branch_point,
         })
     }
 }
@@ -106,6 +120,8 @@ impl SessionMetadata {
             accumulated_total_tokens: None,
             accumulated_input_tokens: None,
             accumulated_output_tokens: None,
+            parent_session_id: None,
+            branch_point: None,
         }
     }
 }
@@ -459,14 +475,98 @@ pub async fn persist_messages(
     }
 }
 
+/// Modification time and length of a file, to notice when something else writes it
+type FileStamp = (SystemTime, u64);
+
+/// What this process last saved to a session file: the file as it was left and a hash of each
+/// message as it was passed in, before redaction
+struct SavedMessages {
+    stamp: FileStamp,
+    hashes: Vec<blake3::Hash>,
+}
+
+/// The messages last saved to each session file, so a save only redacts and indexes the
+/// messages that changed since
+static SAVED_MESSAGES: Lazy<Mutex<HashMap<PathBuf, SavedMessages>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn message_hash(message: &Message) -> Result<blake3::Hash> {
+    let mut hasher = blake3::Hasher::new();
+    serde_json::to_writer(&mut hasher, message)?;
+    Ok(hasher.finalize())
+}
+
+/// The lines of `session_file` holding the leading messages that are the same as when this
+/// process last saved it, or none if the file was written by something else since
+fn unchanged_lines(session_file: &Path, hashes: &[blake3::Hash]) -> Vec<String> {
+    let saved = SAVED_MESSAGES.lock().unwrap();
+    let Some(saved) = saved
+        .get(session_file)
+        .filter(|saved| Some(saved.stamp) == file_stamp(session_file))
+    else {
+        return Vec::new();
+    };
+    let unchanged = saved
+        .hashes
+        .iter()
+        .zip(hashes)
+        .take_while(|(saved, hash)| saved == hash)
+        .count();
+    if unchanged == 0 {
+        return Vec::new();
+    }
+
+    let lines: Vec<String> = File::open(session_file)
+        .and_then(|file| {
+            io::BufReader::new(file)
+                .lines()
+                .skip(1)
+                .take(unchanged)
+                .collect()
+        })
+        .unwrap_or_default();
+    if lines.len() == unchanged {
+        lines
+    } else {
+        Vec::new()
+    }
+}
+
 /// Write messages to a session file with the provided metadata
 ///
 /// Overwrites the file with metadata as the first line, followed by all messages in JSONL format.
+/// Messages that were saved unchanged before are copied from the file as they are, and only the
+/// others are redacted and indexed.
 pub fn save_messages_with_metadata(
     session_file: &Path,
     metadata: &SessionMetadata,
     messages: &[Message],
 ) -> Result<()> {
+    let hashes = messages
+        .iter()
+        .map(message_hash)
+        .collect::<Result<Vec<_>>>()?;
+    let mut lines = unchanged_lines(session_file, &hashes);
+    let unchanged = lines.len();
+
+    // Keep secrets out of the file when redaction is enabled
+    let redactor = Redactor::for_session(Some(session_file));
+    let added: Vec<Message> = messages[unchanged..]
+        .iter()
+        .map(|message| match &redactor {
+            Some(redactor) => redactor.redact_message(message),
+            None => message.clone(),
+        })
+        .collect();
+    for message in &added {
+        lines.push(serde_json::to_string(message)?);
+    }
+
     let file = File::create(session_file).expect("The path specified does not exist");
     let mut writer = io::BufWriter::new(file);
 
@@ -474,13 +574,188 @@ pub fn save_messages_with_metadata(
     serde_json::to_writer(&mut writer, &metadata)?;
     writeln!(writer)?;
 
-    // Write all messages
-    for message in messages {
-        serde_json::to_writer(&mut writer, &message)?;
-        writeln!(writer)?;
+    for line in &lines {
+        writeln!(writer, "{}", line)?;
     }
 
     writer.flush()?;
+    drop(writer);
+
+    let mut saved = SAVED_MESSAGES.lock().unwrap();
+    match file_stamp(session_file) {
+        Some(stamp) => saved.insert(session_file.to_path_buf(), SavedMessages { stamp, hashes }),
+        None => saved.remove(session_file),
+    };
+    drop(saved);
+
+    // The file is what matters; a stale index is caught up on the next sync
+    if let Err(e) = index::record_saved(session_file, metadata, unchanged, &added) {
+        tracing::warn!("Failed to update the session index: {:?}", e);
+    }
+    Ok(())
+}
+
+/// Start a new session from the first `at` messages of an existing one
+///
+/// The new session is written next to the original, which is left untouched, and records the
+/// original as its parent. Returns the ID and path of the new session.
+pub fn fork_session(session_file: &Path, at: usize) -> Result<(String, PathBuf)> {
+    if !session_file.exists() {
+        return Err(anyhow::anyhow!(
+            "Session file not found: {}",
+            session_file.display()
+        ));
+    }
+
+    let mut messages = read_messages_with_truncation(session_file, None)?;
+    if at == 0 || at > messages.len() {
+        return Err(anyhow::anyhow!(
+            "Can't fork at message {}, the session has {} messages",
+            at,
+            messages.len()
+        ));
+    }
+    messages.truncate(at);
+    // A tool call without its result can't be sent to the provider again
+    if messages
+        .last()
+        .is_some_and(|message| message.is_tool_call())
+    {
+        return Err(anyhow::anyhow!(
+            "Message {} is a tool call; fork after its result instead (--at {})",
+            at,
+            at + 1
+        ));
+    }
+
+    branch_session(session_file, &messages, at)
+}
+
+/// Save `messages` as a new session next to `session_file`, recording that session as its
+/// parent and the first `branch_point` messages as shared with it
+pub(crate) fn branch_session(
+    session_file: &Path,
+    messages: &[Message],
+    branch_point: usize,
+) -> Result<(String, PathBuf)> {
+    let parent_id = session_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow::anyhow!("Invalid session file {}", session_file.display()))?;
+
+    let mut metadata = read_metadata(session_file)?;
+    metadata.message_count = messages.len();
+    metadata.schedule_id = None;
+    metadata.parent_session_id = Some(parent_id);
+    metadata.branch_point = Some(branch_point);
+    // Token counts describe the conversation the parent went on to have
+    metadata.total_tokens = None;
+    metadata.input_tokens = None;
+    metadata.output_tokens = None;
+    metadata.accumulated_total_tokens = None;
+    metadata.accumulated_input_tokens = None;
+    metadata.accumulated_output_tokens = None;
+
+    let dir = session_file.parent().unwrap_or_else(|| Path::new("."));
+    let id = unused_session_id(dir, &generate_session_id());
+    let path = dir.join(format!("{}.jsonl", id));
+
+    // The branch keeps the parent's placeholders, so they still stand for the same values
+    redaction::copy_session(session_file, &path);
+    save_messages_with_metadata(&path, &metadata, messages)?;
+    Ok((id, path))
+}
+
+/// `base_id`, with a numeric suffix if a session with that ID already exists in `dir`
+pub(crate) fn unused_session_id(dir: &Path, base_id: &str) -> String {
+    let mut id = base_id.to_string();
+    let mut suffix = 1;
+    while dir.join(format!("{}.jsonl", id)).exists() {
+        suffix += 1;
+        id = format!("{}_{}", base_id, suffix);
+    }
+    id
+}
+
+/// Delete a session along with what is kept for it elsewhere: the recipe it was imported
+/// with, its checkpoints and its redacted values. Its forks take over its parent. Returns the
+/// IDs of the forks whose metadata changed.
+pub fn delete_session(session_file: &Path) -> Result<Vec<String>> {
+    let id = session_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow::anyhow!("Invalid session file {}", session_file.display()))?;
+    let metadata = read_metadata(session_file).ok();
+
+    fs::remove_file(session_file)?;
+    let dir = session_file.parent().unwrap_or_else(|| Path::new("."));
+    // Recipes saved by `goose session import` go with their session
+    let _ = fs::remove_file(dir.join(format!("{}.recipe.yaml", id)));
+    if let Err(e) = checkpoint::remove_session_checkpoints(&id) {
+        tracing::warn!(
+            "Failed to remove the checkpoints of session {}: {:?}",
+            id,
+            e
+        );
+    }
+    redaction::forget_session(session_file);
+    large_response_handler::remove_session_responses(&id);
+    SAVED_MESSAGES.lock().unwrap().remove(session_file);
+
+    let (parent, branch_point) = metadata
+        .map(|metadata| (metadata.parent_session_id, metadata.branch_point))
+        .unwrap_or_default();
+    reparent_forks(dir, &id, parent, branch_point)
+}
+
+/// Point the forks of the deleted session `id` at its own parent. A fork shares with the new
+/// parent only what both branches shared, and has no parent once the first session is gone.
+fn reparent_forks(
+    dir: &Path,
+    id: &str,
+    parent: Option<String>,
+    branch_point: Option<usize>,
+) -> Result<Vec<String>> {
+    let mut updated = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "jsonl") {
+            continue;
+        }
+        let Ok(mut metadata) = read_metadata(&path) else {
+            continue;
+        };
+        if metadata.parent_session_id.as_deref() != Some(id) {
+            continue;
+        }
+        metadata.branch_point = match (&parent, metadata.branch_point, branch_point) {
+            (None, _, _) => None,
+            (Some(_), Some(own), Some(inherited)) => Some(own.min(inherited)),
+            (Some(_), own, inherited) => own.or(inherited),
+        };
+        metadata.parent_session_id = parent.clone();
+        rewrite_metadata_line(&path, &metadata)?;
+        if let Some(stem) = path.file_stem() {
+            updated.push(stem.to_string_lossy().to_string());
+        }
+    }
+    Ok(updated)
+}
+
+/// Replace the metadata line of a session file, keeping its messages as they are and its
+/// modification time, so the session doesn't look recently used
+fn rewrite_metadata_line(session_file: &Path, metadata: &SessionMetadata) -> Result<()> {
+    let modified = fs::metadata(session_file)?.modified()?;
+    let contents = fs::read_to_string(session_file)?;
+    let messages = contents.split_once('\n').map_or("", |(_, rest)| rest);
+
+    let temp_path = session_file.with_extension("jsonl.tmp");
+    let mut file = File::create(&temp_path)?;
+    writeln!(file, "{}", serde_json::to_string(metadata)?)?;
+    file.write_all(messages.as_bytes())?;
+    file.set_modified(modified)?;
+    drop(file);
+    fs::rename(&temp_path, session_file)?;
     Ok(())
 }
 
@@ -585,6 +860,49 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_saving_again_only_changes_what_changed() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("test.jsonl");
+        let metadata = SessionMetadata::default();
+        let texts = |path: &Path| -> Result<Vec<String>> {
+            Ok(read_messages(path)?
+                .iter()
+                .map(|message| message.as_concat_text())
+                .collect())
+        };
+        let first = Message::user().with_text("first");
+
+        save_messages_with_metadata(
+            &file_path,
+            &metadata,
+            &[first.clone(), Message::assistant().with_text("second")],
+        )?;
+        save_messages_with_metadata(
+            &file_path,
+            &metadata,
+            &[first.clone(), Message::assistant().with_text("replaced")],
+        )?;
+        assert_eq!(texts(&file_path)?, vec!["first", "replaced"]);
+
+        // A file written by something else is saved in full again
+        let other = dir.path().join("other.jsonl");
+        save_messages_with_metadata(
+            &other,
+            &metadata,
+            &[Message::user().with_text("edited elsewhere")],
+        )?;
+        fs::copy(&other, &file_path)?;
+        save_messages_with_metadata(
+            &file_path,
+            &metadata,
+            &[first, Message::assistant().with_text("third")],
+        )?;
+        assert_eq!(texts(&file_path)?, vec!["first", "third"]);
+
+        Ok(())
+    }
+
     #[test]
     fn test_empty_file() -> Result<()> {
         let dir = tempdir()?;
@@ -791,6 +1109,88 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_forks_take_over_the_parent_of_a_deleted_session() -> Result<()> {
+        let dir = tempdir()?;
+        let mut metadata = SessionMetadata::new(dir.path().to_path_buf());
+        let messages = vec![Message::user().with_text("hello")];
+
+        metadata.parent_session_id = Some("root".to_string());
+        metadata.branch_point = Some(3);
+        save_messages_with_metadata(&dir.path().join("grandchild.jsonl"), &metadata, &messages)?;
+        metadata.parent_session_id = Some("other".to_string());
+        save_messages_with_metadata(&dir.path().join("unrelated.jsonl"), &metadata, &messages)?;
+
+        // "root" was forked from "top" at message 2 and is being deleted
+        let updated = reparent_forks(dir.path(), "root", Some("top".to_string()), Some(2))?;
+        assert_eq!(updated, vec!["grandchild".to_string()]);
+        let grandchild = read_metadata(&dir.path().join("grandchild.jsonl"))?;
+        assert_eq!(grandchild.parent_session_id.as_deref(), Some("top"));
+        assert_eq!(grandchild.branch_point, Some(2));
+        assert_eq!(
+            read_messages(&dir.path().join("grandchild.jsonl"))?.len(),
+            1
+        );
+
+        // Once the first session is gone, the fork stands on its own
+        reparent_forks(dir.path(), "top", None, None)?;
+        let grandchild = read_metadata(&dir.path().join("grandchild.jsonl"))?;
+        assert_eq!(grandchild.parent_session_id, None);
+        assert_eq!(grandchild.branch_point, None);
+        let unrelated = read_metadata(&dir.path().join("unrelated.jsonl"))?;
+        assert_eq!(unrelated.parent_session_id.as_deref(), Some("other"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fork_session() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("original.jsonl");
+
+        let mut metadata = SessionMetadata::new(dir.path().to_path_buf());
+        metadata.description = "Investigate the outage".to_string();
+        metadata.schedule_id = Some("nightly".to_string());
+        metadata.accumulated_total_tokens = Some(1234);
+        let messages = vec![
+            Message::user().with_text("Why did the deploy fail?"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(mcp_core::tool::ToolCall::new(
+                    "developer__shell",
+                    serde_json::json!({"command": "kubectl get pods"}),
+                )),
+            ),
+            Message::user().with_tool_response("1", Ok(vec![])),
+            Message::assistant().with_text("The pods are crash looping"),
+        ];
+        save_messages_with_metadata(&file_path, &metadata, &messages)?;
+
+        let (id, fork_path) = fork_session(&file_path, 3)?;
+        assert_eq!(fork_path, dir.path().join(format!("{}.jsonl", id)));
+        assert_eq!(read_messages(&fork_path)?.len(), 3);
+        let fork = read_metadata(&fork_path)?;
+        assert_eq!(fork.description, "Investigate the outage");
+        assert_eq!(fork.parent_session_id.as_deref(), Some("original"));
+        assert_eq!(fork.branch_point, Some(3));
+        assert_eq!(fork.message_count, 3);
+        assert_eq!(fork.schedule_id, None);
+        assert_eq!(fork.accumulated_total_tokens, None);
+
+        // The original is untouched
+        assert_eq!(read_messages(&file_path)?.len(), 4);
+
+        // A second fork in the same second gets its own file
+        let (second_id, _) = fork_session(&file_path, 1)?;
+        assert_ne!(second_id, id);
+
+        // Forking right after a tool call would leave it without a result
+        assert!(fork_session(&file_path, 2).is_err());
+        assert!(fork_session(&file_path, 0).is_err());
+        assert!(fork_session(&file_path, 5).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_invalid_working_dir() -> Result<()> {
         let dir = tempdir()?;
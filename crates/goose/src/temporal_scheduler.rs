@@ -673,39 +673,20 @@ impl TemporalScheduler {
         sched_id: &str,
         limit: usize,
     ) -> Result<Vec<(String, SessionMetadata)>, SchedulerError> {
-        use crate::session::storage;
-
-        // Get all session files
-        let all_session_files = storage::list_sessions().map_err(|e| {
+        let page = crate::session::query_sessions(&crate::session::SessionQuery {
+            schedule_id: Some(sched_id.to_string()),
+            limit: Some(limit),
+            ..Default::default()
+        })
+        .map_err(|e| {
             SchedulerError::SchedulerInternalError(format!("Failed to list sessions: {}", e))
         })?;
 
-        let mut schedule_sessions: Vec<(String, SessionMetadata)> = Vec::new();
-
-        for (session_name, session_path) in all_session_files {
-            match storage::read_metadata(&session_path) {
-                Ok(metadata) => {
-                    // Check if this session belongs to the requested schedule
-                    if metadata.schedule_id.as_deref() == Some(sched_id) {
-                        schedule_sessions.push((session_name, metadata));
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!(
-                        "Failed to read metadata for session file {}: {}. Skipping.",
-                        session_path.display(),
-                        e
-                    );
-                }
-            }
-        }
-
-        // Sort by session_name (timestamp string) in descending order (newest first)
-        schedule_sessions.sort_by(|a, b| b.0.cmp(&a.0));
-
-        // Take only the requested limit
-        let result_sessions: Vec<(String, SessionMetadata)> =
-            schedule_sessions.into_iter().take(limit).collect();
+        let result_sessions: Vec<(String, SessionMetadata)> = page
+            .sessions
+            .into_iter()
+            .map(|info| (info.id, info.metadata))
+            .collect();
 
         tracing::info!(
             "Found {} sessions for schedule '{}'",
@@ -136,6 +136,15 @@ async fn run_truncate_test(
             Ok(AgentEvent::McpNotification(n)) => {
                 println!("MCP Notification: {n:?}");
             }
+            Ok(AgentEvent::ContextCompacted(compaction)) => {
+                println!("Context compacted: {:?}", compaction.strategy);
+            }
+            Ok(AgentEvent::ProviderError(e)) => {
+                println!("Provider error: {}", e);
+            }
+            Ok(AgentEvent::ToolDenied(id)) => {
+                println!("Tool call denied: {}", id);
+            }
             Err(e) => {
                 println!("Error: {:?}", e);
                 return Err(e);
@@ -327,3 +336,364 @@ mod tests {
         .await
     }
 }
+
+/// Runs the whole agent loop against recorded provider responses, with no network access
+#[cfg(test)]
+mod replay_tests {
+    use super::*;
+    use goose::agents::ExtensionConfig;
+    use goose::message::MessageContent;
+    use goose::providers::base::{ProviderMetadata, ProviderUsage, Usage};
+    use goose::providers::errors::ProviderError;
+    use goose::providers::replay::{RecordingProvider, ReplayProvider};
+    use mcp_core::content::Content;
+    use mcp_core::tool::{Tool, ToolCall};
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    /// Stands in for a real provider while recording. Questions about the weather get a
+    /// call to the weather tool and then its result, anything else gets a fixed answer.
+    struct ScriptedProvider;
+
+    #[async_trait::async_trait]
+    impl Provider for ScriptedProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("scripted".to_string())
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            let tool_output = messages.last().and_then(|message| {
+                message.content.iter().find_map(|content| match content {
+                    MessageContent::ToolResponse(response) => response
+                        .tool_result
+                        .as_ref()
+                        .ok()
+                        .and_then(|output| output.first())
+                        .and_then(|content| content.as_text())
+                        .map(str::to_string),
+                    _ => None,
+                })
+            });
+            let message = match tool_output {
+                Some(output) => Message::assistant().with_text(format!("The weather: {}", output)),
+                None if messages
+                    .last()
+                    .is_some_and(|m| m.as_concat_text().contains("weather")) =>
+                {
+                    Message::assistant().with_tool_request(
+                        "call_weather",
+                        Ok(ToolCall::new("get_weather", json!({"location": "Paris"}))),
+                    )
+                }
+                None => Message::assistant().with_text("2 + 2 is 4"),
+            };
+            Ok((
+                message,
+                ProviderUsage::new("scripted".to_string(), Usage::default()),
+            ))
+        }
+    }
+
+    /// What a reply produced: the messages, and the provider errors that ended it
+    struct Run {
+        messages: Vec<Message>,
+        provider_errors: Vec<String>,
+    }
+
+    fn weather_tool() -> ExtensionConfig {
+        ExtensionConfig::Frontend {
+            name: "weather".to_string(),
+            tools: vec![Tool::new(
+                "get_weather",
+                "Get the weather for a location",
+                json!({
+                    "type": "object",
+                    "required": ["location"],
+                    "properties": {"location": {"type": "string"}}
+                }),
+                None,
+            )],
+            instructions: None,
+            bundled: None,
+        }
+    }
+
+    /// Runs one reply with the weather tool installed, answering its calls the way a
+    /// frontend would
+    async fn run_agent(provider: Arc<dyn Provider>, prompt: &str) -> Result<Run> {
+        let agent = Agent::new();
+        agent.update_provider(provider).await?;
+        agent.add_extension(weather_tool()).await?;
+
+        let reply_stream = agent
+            .reply(&[Message::user().with_text(prompt)], None)
+            .await?;
+        tokio::pin!(reply_stream);
+
+        let mut run = Run {
+            messages: Vec::new(),
+            provider_errors: Vec::new(),
+        };
+        while let Some(event) = reply_stream.next().await {
+            match event? {
+                AgentEvent::Message(message) => {
+                    for content in &message.content {
+                        if let MessageContent::FrontendToolRequest(request) = content {
+                            agent
+                                .handle_tool_result(
+                                    request.id.clone(),
+                                    Ok(vec![Content::text("sunny, 21°C")]),
+                                )
+                                .await;
+                        }
+                    }
+                    run.messages.push(message);
+                }
+                AgentEvent::ProviderError(error) => run.provider_errors.push(error),
+                _ => {}
+            }
+        }
+        Ok(run)
+    }
+
+    fn replay(dir: &TempDir) -> Arc<ReplayProvider> {
+        Arc::new(ReplayProvider::new(
+            "scripted",
+            ModelConfig::new("scripted".to_string()),
+            dir.path(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_agent_replays_recorded_session() -> Result<()> {
+        let dir = TempDir::new()?;
+        let recorder = Arc::new(RecordingProvider::new(
+            "scripted",
+            Arc::new(ScriptedProvider),
+            dir.path(),
+        ));
+        let recorded = run_agent(recorder, "what is 2 + 2?").await?;
+
+        let replay = replay(&dir);
+        let replayed = run_agent(replay.clone(), "what is 2 + 2?").await?;
+        assert!(replayed.provider_errors.is_empty());
+        assert_eq!(replayed.messages.len(), recorded.messages.len());
+        assert_eq!(replayed.messages[0].as_concat_text(), "2 + 2 is 4");
+        assert!(replay.unused().is_empty());
+
+        // Anything that was not recorded fails with a provider error instead of being answered
+        let unexpected = run_agent(replay, "what is 3 + 3?").await?;
+        assert_eq!(unexpected.provider_errors.len(), 1);
+        assert!(unexpected.provider_errors[0].contains("No recorded response"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_agent_replays_tool_round_trip() -> Result<()> {
+        let dir = TempDir::new()?;
+        let recorder = Arc::new(RecordingProvider::new(
+            "scripted",
+            Arc::new(ScriptedProvider),
+            dir.path(),
+        ));
+        let recorded = run_agent(recorder, "what's the weather in Paris?").await?;
+        assert!(recorded.provider_errors.is_empty());
+
+        let replay = replay(&dir);
+        let replayed = run_agent(replay.clone(), "what's the weather in Paris?").await?;
+        assert!(replayed.provider_errors.is_empty());
+        // Both requests were answered from the recording: the one that asked for the tool,
+        // and the one carrying the tool's result back
+        assert!(replay.unused().is_empty());
+
+        let texts = |run: &Run| {
+            run.messages
+                .iter()
+                .map(|message| message.as_concat_text())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts(&replayed), texts(&recorded));
+        assert!(replayed.messages.iter().any(|message| message
+            .content
+            .iter()
+            .any(|content| matches!(content, MessageContent::FrontendToolRequest(_)))));
+        assert_eq!(
+            replayed.messages.last().unwrap().as_concat_text(),
+            "The weather: sunny, 21°C"
+        );
+        Ok(())
+    }
+}
+
+/// Runs replies that must end with a result matching an output schema
+#[cfg(test)]
+mod structured_output_tests {
+    use super::*;
+    use goose::providers::base::{ProviderMetadata, ProviderUsage, Usage};
+    use goose::providers::errors::ProviderError;
+    use mcp_core::tool::{Tool, ToolCall};
+    use serde_json::{json, Value};
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// Answers with canned messages, and canned JSON when asked for structured output
+    struct ScriptedProvider {
+        native: bool,
+        replies: Mutex<VecDeque<Message>>,
+        outputs: Mutex<VecDeque<Value>>,
+    }
+
+    impl ScriptedProvider {
+        fn new(native: bool, replies: Vec<Message>, outputs: Vec<Value>) -> Arc<Self> {
+            Arc::new(Self {
+                native,
+                replies: Mutex::new(replies.into()),
+                outputs: Mutex::new(outputs.into()),
+            })
+        }
+    }
+
+    fn usage() -> ProviderUsage {
+        ProviderUsage::new("scripted".to_string(), Usage::default())
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for ScriptedProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("scripted".to_string())
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            let reply = self.replies.lock().unwrap().pop_front();
+            reply
+                .map(|message| (message, usage()))
+                .ok_or_else(|| ProviderError::ExecutionError("No more replies".to_string()))
+        }
+
+        fn supports_structured_output(&self) -> bool {
+            self.native
+        }
+
+        async fn extract(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _schema: &Value,
+        ) -> Result<(Value, ProviderUsage), ProviderError> {
+            let output = self.outputs.lock().unwrap().pop_front();
+            output
+                .map(|output| (output, usage()))
+                .ok_or_else(|| ProviderError::ExecutionError("No more outputs".to_string()))
+        }
+    }
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["answer"],
+            "properties": {"answer": {"type": "integer"}}
+        })
+    }
+
+    fn final_output_call(id: &str, arguments: Value) -> Message {
+        Message::assistant()
+            .with_tool_request(id, Ok(ToolCall::new("platform__final_output", arguments)))
+    }
+
+    async fn run_agent(agent: &Agent) -> Result<Vec<Message>> {
+        let reply_stream = agent
+            .reply(&[Message::user().with_text("what is 2 + 2?")], None)
+            .await?;
+        tokio::pin!(reply_stream);
+
+        let mut responses = Vec::new();
+        while let Some(event) = reply_stream.next().await {
+            if let AgentEvent::Message(message) = event? {
+                responses.push(message);
+            }
+        }
+        Ok(responses)
+    }
+
+    #[tokio::test]
+    async fn test_final_output_tool_with_reminder_and_retry() -> Result<()> {
+        let provider = ScriptedProvider::new(
+            false,
+            vec![
+                Message::assistant().with_text("It is 4."),
+                final_output_call("1", json!({"answer": "four"})),
+                final_output_call("2", json!({"answer": 4})),
+            ],
+            vec![],
+        );
+        let agent = Agent::new();
+        agent.update_provider(provider.clone()).await?;
+        agent.set_output_schema(Some(schema())).await;
+
+        let responses = run_agent(&agent).await?;
+        assert_eq!(agent.final_output().await, Some(json!({"answer": 4})));
+        assert!(provider.replies.lock().unwrap().is_empty());
+        assert!(responses.iter().any(|message| message
+            .as_concat_text()
+            .contains("platform__final_output yet")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_without_valid_output() -> Result<()> {
+        let provider = ScriptedProvider::new(
+            false,
+            (0..5)
+                .map(|_| Message::assistant().with_text("It is 4."))
+                .collect(),
+            vec![],
+        );
+        let agent = Agent::new();
+        agent.update_provider(provider).await?;
+        agent.set_output_schema(Some(schema())).await;
+
+        let responses = run_agent(&agent).await?;
+        assert_eq!(agent.final_output().await, None);
+        assert!(responses
+            .last()
+            .unwrap()
+            .as_concat_text()
+            .contains("Could not produce output matching the schema"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_native_structured_output_is_validated() -> Result<()> {
+        let provider = ScriptedProvider::new(
+            true,
+            vec![Message::assistant().with_text("It is 4.")],
+            vec![json!({"answer": 4.5}), json!({"answer": 4})],
+        );
+        let agent = Agent::new();
+        agent.update_provider(provider.clone()).await?;
+        agent.set_output_schema(Some(schema())).await;
+
+        run_agent(&agent).await?;
+        assert_eq!(agent.final_output().await, Some(json!({"answer": 4})));
+        assert!(provider.outputs.lock().unwrap().is_empty());
+        Ok(())
+    }
+}
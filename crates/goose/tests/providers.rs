@@ -1,21 +1,25 @@
 use anyhow::Result;
 use dotenv::dotenv;
 use goose::message::{Message, MessageContent};
+use goose::model::ModelConfig;
 use goose::providers::base::Provider;
+use goose::providers::cache::{CacheConfig, CacheMode, CachingProvider};
 use goose::providers::errors::ProviderError;
+use goose::providers::replay::ReplayProvider;
 use goose::providers::{
     anthropic, azure, bedrock, databricks, google, groq, ollama, openai, openrouter, snowflake,
 };
 use mcp_core::content::Content;
 use mcp_core::tool::Tool;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 
 #[derive(Debug, Clone, Copy)]
 enum TestStatus {
     Passed,
-    Skipped,
+    Replayed,
     Failed,
 }
 
@@ -23,7 +27,7 @@ impl std::fmt::Display for TestStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             TestStatus::Passed => write!(f, "✅"),
-            TestStatus::Skipped => write!(f, "⏭️"),
+            TestStatus::Replayed => write!(f, "🔁"),
             TestStatus::Failed => write!(f, "❌"),
         }
     }
@@ -49,8 +53,9 @@ impl TestReport {
         self.record_status(provider, TestStatus::Passed);
     }
 
-    fn record_skip(&self, provider: &str) {
-        self.record_status(provider, TestStatus::Skipped);
+    /// Passed against recorded responses rather than the live provider
+    fn record_replay(&self, provider: &str) {
+        self.record_status(provider, TestStatus::Replayed);
     }
 
     fn record_fail(&self, provider: &str) {
@@ -79,13 +84,16 @@ lazy_static::lazy_static! {
 struct ProviderTester {
     provider: Arc<dyn Provider>,
     name: String,
+    /// Whether responses come from the recorded fixtures instead of the provider
+    replaying: bool,
 }
 
 impl ProviderTester {
-    fn new<T: Provider + Send + Sync + 'static>(provider: T, name: String) -> Self {
+    fn new(provider: Arc<dyn Provider>, name: String, replaying: bool) -> Self {
         Self {
-            provider: Arc::new(provider),
+            provider,
             name,
+            replaying,
         }
     }
 
@@ -258,11 +266,34 @@ impl ProviderTester {
     async fn run_test_suite(&self) -> Result<()> {
         self.test_basic_response().await?;
         self.test_tool_usage().await?;
-        self.test_context_length_exceeded_error().await?;
+        // Errors are never recorded, so there is nothing to replay for this one
+        if !self.replaying {
+            self.test_context_length_exceeded_error().await?;
+        }
         Ok(())
     }
 }
 
+/// Responses recorded from each provider, replayed when its credentials aren't configured.
+/// Record them again with GOOSE_PROVIDER_CACHE=record and real credentials.
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/providers")
+}
+
+/// The live provider, wrapped in the response cache when GOOSE_PROVIDER_CACHE is set. Unless
+/// GOOSE_PROVIDER_CACHE_DIR says otherwise, responses are recorded to the fixtures.
+fn live_provider<T: Provider + Send + Sync + 'static>(provider: T) -> Arc<dyn Provider> {
+    let provider: Arc<dyn Provider> = Arc::new(provider);
+    let mut config = CacheConfig::from_config();
+    if config.mode == CacheMode::Off {
+        return provider;
+    }
+    if std::env::var("GOOSE_PROVIDER_CACHE_DIR").is_err() {
+        config.dir = fixtures_dir();
+    }
+    Arc::new(CachingProvider::new(&T::metadata().name, provider, config))
+}
+
 fn load_env() {
     if let Ok(path) = dotenv() {
         println!("Loaded environment from {:?}", path);
@@ -313,15 +344,22 @@ where
         }
     }
 
-    // Setup the provider
+    // Setup the provider, or replay its recorded responses when there are no credentials
     let missing_vars = required_vars.iter().any(|var| std::env::var(var).is_err());
-    if missing_vars {
-        println!("Skipping {} tests - credentials not configured", name);
-        TEST_REPORT.record_skip(name);
-        return Ok(());
-    }
-
-    let provider = provider_fn();
+    let provider: Arc<dyn Provider> = if missing_vars {
+        println!(
+            "Replaying recorded {} responses - credentials not configured",
+            name
+        );
+        let metadata = T::metadata();
+        Arc::new(ReplayProvider::new(
+            &metadata.name,
+            ModelConfig::new(metadata.default_model),
+            fixtures_dir(),
+        ))
+    } else {
+        live_provider(provider_fn())
+    };
 
     // Restore original environment
     for (&var, value) in original_env.iter() {
@@ -337,8 +375,12 @@ where
 
     std::mem::drop(lock);
 
-    let tester = ProviderTester::new(provider, name.to_string());
+    let tester = ProviderTester::new(provider, name.to_string(), missing_vars);
     match tester.run_test_suite().await {
+        Ok(_) if missing_vars => {
+            TEST_REPORT.record_replay(name);
+            Ok(())
+        }
         Ok(_) => {
             TEST_REPORT.record_pass(name);
             Ok(())
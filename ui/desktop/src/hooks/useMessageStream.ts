@@ -19,12 +19,29 @@ export interface NotificationEvent {
   };
 }
 
+// Sent when the agent compacts the conversation; `messages` replaces the history so far
+export interface ContextCompactedEvent {
+  type: 'ContextCompacted';
+  strategy: 'summarize' | 'truncate';
+  tokens_before: number;
+  tokens_after: number;
+  messages: Message[];
+}
+
 // Event types for SSE stream
 type MessageEvent =
   | { type: 'Message'; message: Message }
   | { type: 'Error'; error: string }
   | { type: 'Finish'; reason: string }
-  | NotificationEvent;
+  | NotificationEvent
+  | ContextCompactedEvent;
+
+// Show and send a message unless it says otherwise
+const withDefaultFlags = (message: Message): Message => ({
+  ...message,
+  display: message.display === undefined ? true : message.display,
+  sendToLLM: message.sendToLLM === undefined ? true : message.sendToLLM,
+});
 
 export interface UseMessageStreamOptions {
   /**
@@ -245,19 +262,8 @@ export function useMessageStream({
 
                 switch (parsedEvent.type) {
                   case 'Message': {
-                    // Create a new message object with the properties preserved or defaulted
-                    const newMessage = {
-                      ...parsedEvent.message,
-                      // Only set to true if it's undefined (preserve false values)
-                      display:
-                        parsedEvent.message.display === undefined
-                          ? true
-                          : parsedEvent.message.display,
-                      sendToLLM:
-                        parsedEvent.message.sendToLLM === undefined
-                          ? true
-                          : parsedEvent.message.sendToLLM,
-                    };
+                    // Only set the flags to true if they're undefined (preserve false values)
+                    const newMessage = withDefaultFlags(parsedEvent.message);
 
                     // Update messages with the new message
                     currentMessages = [...currentMessages, newMessage];
@@ -265,6 +271,14 @@ export function useMessageStream({
                     break;
                   }
 
+                  case 'ContextCompacted': {
+                    // The agent carries on from the compacted conversation, so later messages
+                    // and requests have to build on it rather than on the old history
+                    currentMessages = parsedEvent.messages.map(withDefaultFlags);
+                    mutate(currentMessages, false);
+                    break;
+                  }
+
                   case 'Notification': {
                     const newNotification = {
                       ...parsedEvent,